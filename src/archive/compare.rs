@@ -0,0 +1,163 @@
+use super::{Archive, ArchiveEntries, ArchiveEntry, EntryProperties, NodeID};
+use crate::util::stream;
+use anyhow::{anyhow, Context, Result};
+use smallvec::SmallVec;
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU32, Ordering},
+    sync::Arc,
+};
+use zip::ZipArchive;
+
+/// Compares entries of an archive against a directory they were previously extracted to.
+pub struct Comparer {
+    archive: Arc<Archive>,
+    files: Arc<ArchiveEntries>,
+    base_nodes: SmallVec<[NodeID; 4]>,
+    pub compared: AtomicU32,
+    pub total_to_compare: u32,
+}
+
+impl Comparer {
+    pub fn prepare(archive: Arc<Archive>, base_nodes: SmallVec<[NodeID; 4]>) -> Self {
+        let files = archive.entries();
+        let total_to_compare = files.children_count(&base_nodes) as u32;
+
+        Self {
+            archive,
+            files,
+            base_nodes,
+            compared: AtomicU32::new(0),
+            total_to_compare,
+        }
+    }
+
+    /// Compare every entry against the given `dir_path`.
+    ///
+    /// Returns every difference found, sorted by path.
+    pub fn compare<P>(&self, dir_path: P) -> Result<Vec<Difference>>
+    where
+        P: AsRef<Path>,
+    {
+        let dir_path = dir_path.as_ref();
+
+        if !dir_path.is_dir() {
+            return Err(anyhow!("{} is not a directory", dir_path.display()));
+        }
+
+        let mut archive = self.archive.open_reader()?;
+        let mut differences = Vec::new();
+
+        let valid_files = self
+            .files
+            .children_iter(&self.base_nodes)
+            .filter(|(id, _, _)| *id != NodeID::first());
+
+        for (_, node, path) in valid_files {
+            let disk_path = dir_path.join(&path);
+            self.compare_entry(&mut archive, node, &path, &disk_path, &mut differences)?;
+            self.compared.fetch_add(1, Ordering::Relaxed);
+        }
+
+        differences.sort_unstable_by(|x, y| x.path().cmp(y.path()));
+
+        Ok(differences)
+    }
+
+    fn compare_entry(
+        &self,
+        archive: &mut ZipArchive<File>,
+        entry: &ArchiveEntry,
+        path: &Path,
+        disk_path: &Path,
+        differences: &mut Vec<Difference>,
+    ) -> Result<()> {
+        let metadata = match fs::metadata(disk_path) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                differences.push(Difference::Missing(path.into()));
+                return Ok(());
+            }
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| anyhow!("failed to inspect {}", disk_path.display()))
+            }
+        };
+
+        match &entry.props {
+            EntryProperties::Directory => {
+                if !metadata.is_dir() {
+                    differences.push(Difference::Changed(path.into()));
+                    return Ok(());
+                }
+
+                let names = entry
+                    .children
+                    .iter()
+                    .map(|&id| self.files[id].name.as_str())
+                    .collect::<HashSet<_>>();
+
+                let dir = fs::read_dir(disk_path)
+                    .with_context(|| anyhow!("failed to read directory {}", disk_path.display()))?;
+
+                for disk_entry in dir {
+                    let disk_entry = disk_entry.with_context(|| {
+                        anyhow!("failed to read directory {}", disk_path.display())
+                    })?;
+
+                    let name = disk_entry.file_name();
+
+                    if !names.contains(name.to_string_lossy().as_ref()) {
+                        differences.push(Difference::Extra(path.join(name)));
+                    }
+                }
+            }
+            EntryProperties::File(props) => {
+                if !metadata.is_file()
+                    || metadata.len() != props.raw_size_bytes
+                    || !Self::contents_match(archive, entry, disk_path)?
+                {
+                    differences.push(Difference::Changed(path.into()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn contents_match(
+        archive: &mut ZipArchive<File>,
+        entry: &ArchiveEntry,
+        disk_path: &Path,
+    ) -> Result<bool> {
+        let mut disk_file = File::open(disk_path)
+            .with_context(|| anyhow!("failed to open {}", disk_path.display()))?;
+
+        let mut archive_file = archive
+            .by_index(entry.entry_num)
+            .with_context(|| anyhow!("failed to get {} from archive", entry.name))?;
+
+        stream::equal(&mut archive_file, &mut disk_file)
+            .with_context(|| anyhow!("failed to compare {}", disk_path.display()))
+    }
+}
+
+pub enum Difference {
+    /// The entry exists in the archive, but not on disk.
+    Missing(PathBuf),
+    /// The entry exists on disk, but not in the archive.
+    Extra(PathBuf),
+    /// The entry exists in both places, but its type or content differs.
+    Changed(PathBuf),
+}
+
+impl Difference {
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Missing(path) | Self::Extra(path) | Self::Changed(path) => path,
+        }
+    }
+}
@@ -0,0 +1,630 @@
+use super::{nested, Archive, ArchiveEntry};
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDate};
+use flate2::read::ZlibDecoder;
+use regex::bytes::Regex;
+use std::io::Read;
+use zip::read::ZipFile;
+
+/// The details of a PDF or office document that are stored in its metadata, like its title and how many pages it
+/// has.
+pub struct DocumentInfo {
+    /// What kind of document it is, like `PDF document`.
+    pub kind: &'static str,
+    /// Each detail that could be read, as its name and value.
+    pub details: Vec<(&'static str, String)>,
+}
+
+impl DocumentInfo {
+    /// The most of a PDF that is read to find its metadata, which is usually at the end of it.
+    const MAX_PDF_BYTES: u64 = 16 * 1024 * 1024;
+
+    /// The most that is read of each metadata file inside of an office document.
+    const MAX_PART_BYTES: u64 = 1024 * 1024;
+
+    /// Returns true if the file that starts with the given `content` is a document that can be read.
+    pub fn is_document(content: &[u8]) -> bool {
+        content.starts_with(b"%PDF-") || office_format(content).is_some()
+    }
+
+    /// Read the metadata of the document that's the given file `entry` in the `archive`, which starts with the
+    /// given `content`.
+    pub fn read(archive: &Archive, entry: &ArchiveEntry, content: &[u8]) -> Result<Self> {
+        let mut info = match office_format(content) {
+            Some(OfficeFormat::OpenDocument(kind)) => Self::open_document(archive, entry, kind)?,
+            Some(OfficeFormat::OfficeOpenXml) => Self::office_open_xml(archive, entry)?,
+            None => Self::pdf(archive, entry, content)?,
+        };
+
+        info.details.retain(|(_, value)| !value.is_empty());
+        Ok(info)
+    }
+
+    fn pdf(archive: &Archive, entry: &ArchiveEntry, content: &[u8]) -> Result<Self> {
+        // The cross-reference table and trailer are written at the end, so the rest of the file is needed
+        let content = if entry.total_size_bytes > content.len() as u64 {
+            archive.read_start(entry, Self::MAX_PDF_BYTES)?
+        } else {
+            content.to_vec()
+        };
+
+        let pdf = Pdf::new(&content);
+        let info = pdf.trailer_ref("Info").and_then(|id| pdf.object(id));
+
+        let field = |key: &str| {
+            info.as_ref()
+                .and_then(|info| pdf.string(info, key))
+                .unwrap_or_default()
+        };
+
+        let mut title = field("Title");
+        let mut author = field("Author");
+        let mut created = pdf_date(&field("CreationDate")).unwrap_or_default();
+
+        // Newer documents can keep their metadata in XMP instead, which is XML embedded in the file
+        if let Some(xmp) = pdf.xmp() {
+            if title.is_empty() {
+                title = xml_list_item(&xmp, "dc:title").unwrap_or_default();
+            }
+
+            if author.is_empty() {
+                author = xml_list_item(&xmp, "dc:creator").unwrap_or_default();
+            }
+
+            if created.is_empty() {
+                created = xml_text(&xmp, "xmp:CreateDate")
+                    .map(|date| iso_date(&date))
+                    .unwrap_or_default();
+            }
+        }
+
+        let version = content
+            .get(5..8)
+            .map(|version| String::from_utf8_lossy(version).into_owned())
+            .unwrap_or_default();
+
+        let pages = pdf.page_count().map(|count| count.to_string());
+
+        Ok(Self {
+            kind: "PDF document",
+            details: vec![
+                ("Pages", pages.unwrap_or_default()),
+                ("Title", title),
+                ("Author", author),
+                ("Created", created),
+                ("Version", version),
+            ],
+        })
+    }
+
+    fn office_open_xml(archive: &Archive, entry: &ArchiveEntry) -> Result<Self> {
+        let parts = nested::visit_entries(archive, entry, OfficeParts::visit_open_xml)?;
+
+        let kind = match parts.app {
+            Some(App::Word) => "Word document",
+            Some(App::Excel) => "Excel spreadsheet",
+            Some(App::PowerPoint) => "PowerPoint presentation",
+            None => "Office document",
+        };
+
+        let field = |xml: &str, tag| xml_text(xml, tag).unwrap_or_default();
+
+        // Word only knows how many pages there are when it lays the document out, so it's saved with the rest of
+        // the statistics about the document instead of being something that can be counted
+        let count = match parts.app {
+            Some(App::Word) => ("Pages", field(&parts.stats, "Pages")),
+            Some(App::Excel) => ("Sheets", count_text(parts.sheets)),
+            Some(App::PowerPoint) => ("Slides", count_text(parts.slides)),
+            None => ("Pages", String::new()),
+        };
+
+        let created = xml_text(&parts.meta, "dcterms:created").map(|date| iso_date(&date));
+
+        Ok(Self {
+            kind,
+            details: vec![
+                count,
+                ("Title", field(&parts.meta, "dc:title")),
+                ("Author", field(&parts.meta, "dc:creator")),
+                ("Created", created.unwrap_or_default()),
+            ],
+        })
+    }
+
+    fn open_document(archive: &Archive, entry: &ArchiveEntry, kind: &'static str) -> Result<Self> {
+        let parts = nested::visit_entries(archive, entry, OfficeParts::visit_open_document)?;
+        let meta = &parts.meta;
+
+        let count = match xml_attr(meta, "meta:document-statistic", "meta:page-count") {
+            Some(pages) => ("Pages", pages),
+            None => (
+                "Sheets",
+                xml_attr(meta, "meta:document-statistic", "meta:table-count").unwrap_or_default(),
+            ),
+        };
+
+        // The creator is whoever saved the document last, so the one who first made it is preferred
+        let author =
+            xml_text(meta, "meta:initial-creator").or_else(|| xml_text(meta, "dc:creator"));
+        let created = xml_text(meta, "meta:creation-date").map(|date| iso_date(&date));
+
+        Ok(Self {
+            kind,
+            details: vec![
+                count,
+                ("Title", xml_text(meta, "dc:title").unwrap_or_default()),
+                ("Author", author.unwrap_or_default()),
+                ("Created", created.unwrap_or_default()),
+            ],
+        })
+    }
+}
+
+/// The formats of office documents, which are both zip archives of XML files.
+enum OfficeFormat {
+    /// The ODF format that most office programs use, along with the kind of document it is.
+    OpenDocument(&'static str),
+    /// The format of Microsoft Office.
+    OfficeOpenXml,
+}
+
+/// Returns the format of the office document that starts with the given `content`, or None if it isn't one.
+///
+/// Both formats are recognized by the name of the first file in them, which office programs always write first.
+fn office_format(content: &[u8]) -> Option<OfficeFormat> {
+    if !content.starts_with(b"PK\x03\x04") {
+        return None;
+    }
+
+    let name_len = usize::from(u16::from_le_bytes([*content.get(26)?, *content.get(27)?]));
+    let extra_len = usize::from(u16::from_le_bytes([*content.get(28)?, *content.get(29)?]));
+    let name = content.get(30..30 + name_len)?;
+
+    match name {
+        b"[Content_Types].xml" => Some(OfficeFormat::OfficeOpenXml),
+        // The mimetype file is always stored without compression so it can be read like this
+        b"mimetype" => {
+            let start = 30 + name_len + extra_len;
+            let mimetype = content.get(start..)?;
+
+            let kind = match mimetype.strip_prefix(b"application/vnd.oasis.opendocument.")? {
+                rest if rest.starts_with(b"text") => "OpenDocument text",
+                rest if rest.starts_with(b"spreadsheet") => "OpenDocument spreadsheet",
+                rest if rest.starts_with(b"presentation") => "OpenDocument presentation",
+                _ => "OpenDocument file",
+            };
+
+            Some(OfficeFormat::OpenDocument(kind))
+        }
+        _ => None,
+    }
+}
+
+/// The files inside of an office document that hold its metadata.
+#[derive(Default)]
+struct OfficeParts {
+    /// The file with the title, author, and creation date.
+    meta: String,
+    /// The file with statistics about the document, like how many pages it has.
+    stats: String,
+    app: Option<App>,
+    sheets: usize,
+    slides: usize,
+}
+
+#[derive(Copy, Clone)]
+enum App {
+    Word,
+    Excel,
+    PowerPoint,
+}
+
+impl OfficeParts {
+    fn visit_open_xml(&mut self, file: &mut ZipFile) {
+        let name = file.name().to_string();
+
+        match name.as_str() {
+            "docProps/core.xml" => self.meta = read_part(file),
+            "docProps/app.xml" => self.stats = read_part(file),
+            _ if name.starts_with("word/") => self.app = Some(App::Word),
+            _ if name.starts_with("xl/") => {
+                self.app = Some(App::Excel);
+
+                if name.starts_with("xl/worksheets/sheet") {
+                    self.sheets += 1;
+                }
+            }
+            _ if name.starts_with("ppt/") => {
+                self.app = Some(App::PowerPoint);
+
+                if name.starts_with("ppt/slides/slide") {
+                    self.slides += 1;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn visit_open_document(&mut self, file: &mut ZipFile) {
+        if file.name() == "meta.xml" {
+            self.meta = read_part(file);
+        }
+    }
+}
+
+/// Read a metadata file from inside of an office document, or nothing if it can't be read.
+fn read_part(file: &mut ZipFile) -> String {
+    let mut part = String::new();
+
+    file.take(DocumentInfo::MAX_PART_BYTES)
+        .read_to_string(&mut part)
+        .ok();
+
+    part
+}
+
+fn count_text(count: usize) -> String {
+    if count > 0 {
+        count.to_string()
+    } else {
+        String::new()
+    }
+}
+
+/// Finds objects in the raw content of a PDF.
+///
+/// Objects can be stored on their own or packed into compressed object streams, so both are searched.
+struct Pdf<'a> {
+    content: &'a [u8],
+    streams: Vec<ObjectStream>,
+}
+
+impl<'a> Pdf<'a> {
+    /// The most that an object stream is decompressed to.
+    const MAX_STREAM_BYTES: u64 = 16 * 1024 * 1024;
+
+    fn new(content: &'a [u8]) -> Self {
+        let streams = regex(r"/Type\s*/ObjStm\b")
+            .find_iter(content)
+            .filter_map(|found| Self::object_stream(content, found.start()))
+            .collect();
+
+        Self { content, streams }
+    }
+
+    /// Decompress the object stream whose dictionary contains the given position in the `content`, and read the
+    /// header at the start of it that says where each object is.
+    fn object_stream(content: &[u8], pos: usize) -> Option<ObjectStream> {
+        let dict_start = rfind(&content[..pos], b"obj")?;
+        let stream_start = pos + find(&content[pos..], b"stream")?;
+        let dict = &content[dict_start..stream_start];
+
+        if !dict.windows(12).any(|window| window == b"/FlateDecode") {
+            return None;
+        }
+
+        let first = capture_number(dict, r"/First\s+(\d+)")? as usize;
+
+        // The data starts on the line after the keyword
+        let data_start = stream_start + 6;
+        let data_start = data_start
+            + content[data_start..]
+                .iter()
+                .take_while(|&&byte| byte == b'\r' || byte == b'\n')
+                .count();
+
+        let mut data = Vec::new();
+
+        ZlibDecoder::new(&content[data_start..])
+            .take(Self::MAX_STREAM_BYTES)
+            .read_to_end(&mut data)
+            .ok()?;
+
+        let header = String::from_utf8_lossy(data.get(..first)?).into_owned();
+        let numbers = header
+            .split_ascii_whitespace()
+            .filter_map(|num| num.parse::<usize>().ok())
+            .collect::<Vec<_>>();
+
+        let objects = numbers
+            .chunks_exact(2)
+            .map(|pair| (pair[0] as u32, first + pair[1]))
+            .collect();
+
+        Some(ObjectStream { data, objects })
+    }
+
+    /// Returns the ID of the object that the given `key` of the trailer refers to.
+    ///
+    /// Files that have been edited have a trailer for each edit, and the last one is the newest.
+    fn trailer_ref(&self, key: &str) -> Option<u32> {
+        let pattern = format!(r"/{}\s+(\d+)\s+\d+\s+R", key);
+
+        regex(&pattern)
+            .captures_iter(self.content)
+            .last()
+            .and_then(|captures| parse_number(&captures[1]))
+            .map(|id| id as u32)
+    }
+
+    /// Returns the content of the object with the given `id`.
+    fn object(&self, id: u32) -> Option<Vec<u8>> {
+        let pattern = format!(r"(?:^|\D){}\s+\d+\s+obj\b", id);
+
+        if let Some(found) = regex(&pattern).find_iter(self.content).last() {
+            let body = &self.content[found.end()..];
+            let end = find(body, b"endobj").unwrap_or(body.len());
+            return Some(body[..end].to_vec());
+        }
+
+        self.streams.iter().find_map(|stream| {
+            let objects = &stream.objects;
+            let pos = objects.iter().position(|&(object, _)| object == id)?;
+            let start = objects[pos].1;
+            let end = objects
+                .get(pos + 1)
+                .map_or(stream.data.len(), |&(_, end)| end);
+
+            stream.data.get(start..end).map(<[u8]>::to_vec)
+        })
+    }
+
+    /// Returns the string that the given `key` of the given `dict` is set to, following it to the object it's in
+    /// if it's stored separately.
+    fn string(&self, dict: &[u8], key: &str) -> Option<String> {
+        let pattern = format!(r"/{}\s*(\d+\s+\d+\s+R|[(<])", key);
+        let captures = regex(&pattern).captures(dict)?;
+        let value = captures.get(1)?;
+
+        if value.as_bytes().ends_with(b"R") {
+            let id = parse_number(value.as_bytes().split(u8::is_ascii_whitespace).next()?)?;
+            let object = self.object(id as u32)?;
+            let start = object
+                .iter()
+                .position(|&byte| byte == b'(' || byte == b'<')?;
+            return pdf_string(&object[start..]);
+        }
+
+        pdf_string(&dict[value.start()..])
+    }
+
+    /// Returns how many pages there are, which is stored in the object at the top of the tree of pages.
+    fn page_count(&self) -> Option<u64> {
+        let catalog = self.object(self.trailer_ref("Root")?)?;
+        let pages = capture_number(&catalog, r"/Pages\s+(\d+)\s+\d+\s+R")?;
+        let pages = self.object(pages as u32)?;
+
+        capture_number(&pages, r"/Count\s+(\d+)")
+    }
+
+    /// Returns the XMP metadata of the document, if it's stored without compression.
+    fn xmp(&self) -> Option<String> {
+        let start = find(self.content, b"<x:xmpmeta")?;
+        let end = start + find(&self.content[start..], b"</x:xmpmeta>")?;
+
+        Some(String::from_utf8_lossy(&self.content[start..end]).into_owned())
+    }
+}
+
+/// A stream that packs several objects together so they can be compressed.
+struct ObjectStream {
+    /// The decompressed content of the stream.
+    data: Vec<u8>,
+    /// The ID of each object in the stream and where it starts in the content.
+    objects: Vec<(u32, usize)>,
+}
+
+fn regex(pattern: &str) -> Regex {
+    // Unicode has to be turned off to match against bytes that aren't valid UTF-8
+    Regex::new(&format!("(?-u){}", pattern)).expect("invalid PDF pattern")
+}
+
+fn parse_number(bytes: &[u8]) -> Option<u64> {
+    std::str::from_utf8(bytes).ok()?.parse().ok()
+}
+
+/// Returns the number in the first capture group of the given `pattern` in the `content`.
+fn capture_number(content: &[u8], pattern: &str) -> Option<u64> {
+    let captures = regex(pattern).captures(content)?;
+    parse_number(&captures[1])
+}
+
+/// Read the string at the start of the given `bytes`, which is either text in parentheses or hex in angle brackets.
+fn pdf_string(bytes: &[u8]) -> Option<String> {
+    let raw = match bytes.first()? {
+        b'(' => pdf_literal(&bytes[1..]),
+        b'<' => {
+            let end = bytes.iter().position(|&byte| byte == b'>')?;
+
+            let digits = bytes[1..end]
+                .iter()
+                .filter_map(|&byte| char::from(byte).to_digit(16))
+                .collect::<Vec<_>>();
+
+            digits
+                .chunks(2)
+                .map(|pair| (pair[0] * 16 + pair.get(1).unwrap_or(&0)) as u8)
+                .collect()
+        }
+        _ => return None,
+    };
+
+    // Strings are either UTF-16 with a byte order mark in front, or a superset of Latin-1 that's close enough
+    let text = match raw.as_slice() {
+        [0xFE, 0xFF, rest @ ..] => {
+            let units = rest
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect::<Vec<_>>();
+
+            String::from_utf16_lossy(&units)
+        }
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        raw => raw.iter().map(|&byte| char::from(byte)).collect(),
+    };
+
+    Some(text.trim().into())
+}
+
+/// Read the text in parentheses that starts at the given `bytes`, which can have parentheses nested inside of it
+/// and escape sequences.
+fn pdf_literal(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut depth = 0;
+    let mut iter = bytes.iter().copied().peekable();
+
+    while let Some(byte) = iter.next() {
+        match byte {
+            b'(' => depth += 1,
+            b')' if depth == 0 => break,
+            b')' => depth -= 1,
+            b'\\' => {
+                let escaped = match iter.next() {
+                    Some(escaped) => escaped,
+                    None => break,
+                };
+
+                let byte = match escaped {
+                    b'n' => b'\n',
+                    b'r' => b'\r',
+                    b't' => b'\t',
+                    b'b' => 0x08,
+                    b'f' => 0x0C,
+                    // A backslash at the end of a line continues the string on the next one
+                    b'\r' | b'\n' => continue,
+                    b'0'..=b'7' => {
+                        let mut value = u32::from(escaped - b'0');
+
+                        for _ in 0..2 {
+                            match iter.peek() {
+                                Some(&digit @ b'0'..=b'7') => {
+                                    value = value * 8 + u32::from(digit - b'0');
+                                    iter.next();
+                                }
+                                _ => break,
+                            }
+                        }
+
+                        value as u8
+                    }
+                    other => other,
+                };
+
+                out.push(byte);
+                continue;
+            }
+            _ => (),
+        }
+
+        out.push(byte);
+    }
+
+    out
+}
+
+/// Format a PDF date, which looks like `D:20200102150405+01'00'`, the same way dates are shown elsewhere.
+fn pdf_date(date: &str) -> Option<String> {
+    let digits = date.strip_prefix("D:").unwrap_or(date);
+    let field = |range: std::ops::Range<usize>, default: u32| {
+        digits
+            .get(range)
+            .and_then(|field| field.parse().ok())
+            .unwrap_or(default)
+    };
+
+    let year = digits.get(..4)?.parse().ok()?;
+    let date = NaiveDate::from_ymd_opt(year, field(4..6, 1), field(6..8, 1))?;
+    let time = date.and_hms_opt(field(8..10, 0), field(10..12, 0), field(12..14, 0))?;
+
+    // Dates without a time zone are shown as they are, since there's no telling where they were written
+    let zone = digits.get(14..).unwrap_or_default().replace('\'', "");
+
+    // The zone can be any text at all, so it's only sliced where that lands between characters
+    let offset = match (zone.as_bytes().first(), zone.get(..3), zone.get(3..5)) {
+        (Some(b'Z'), ..) => "+00:00".into(),
+        (Some(b'+' | b'-'), Some(hours), Some(mins)) => format!("{}:{}", hours, mins),
+        _ => return Some(time.format("%Y-%m-%d %H:%M").to_string()),
+    };
+
+    Some(iso_date(&format!(
+        "{}{}",
+        time.format("%Y-%m-%dT%H:%M:%S"),
+        offset
+    )))
+}
+
+/// Format a date like `2020-01-02T15:04:05Z` in local time, the same way dates are shown elsewhere.
+fn iso_date(date: &str) -> String {
+    let date = date.trim();
+
+    match DateTime::parse_from_rfc3339(date) {
+        Ok(date) => date
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string(),
+        // Dates without a time zone are shown as they are
+        Err(_) => date.get(..16).unwrap_or(date).replace('T', " "),
+    }
+}
+
+/// Returns the text inside of the first element with the given `tag` in the `xml`.
+fn xml_text(xml: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+
+    let mut rest = xml;
+
+    // Skip past elements whose name only starts with the tag, like `dc:creatorTool` when looking for `dc:creator`
+    let start = loop {
+        let pos = rest.find(&open)?;
+        rest = &rest[pos + open.len()..];
+
+        if let Some('>' | ' ' | '\t' | '\r' | '\n') = rest.chars().next() {
+            break rest.find('>')? + 1;
+        }
+    };
+
+    let end = rest.find(&close)?;
+    let text = unescape_xml(rest.get(start..end)?.trim());
+
+    Some(text).filter(|text| !text.is_empty())
+}
+
+/// Returns the text of the first item of the list inside of the element with the given `tag` in the `xml`, which
+/// is how XMP stores values that can have more than one of.
+fn xml_list_item(xml: &str, tag: &str) -> Option<String> {
+    let start = xml.find(&format!("<{}", tag))?;
+    xml_text(&xml[start..], "rdf:li")
+}
+
+/// Returns the value of the given `attr` of the first element with the given `tag` in the `xml`.
+fn xml_attr(xml: &str, tag: &str, attr: &str) -> Option<String> {
+    let start = xml.find(&format!("<{}", tag))?;
+    let element = &xml[start..start + xml[start..].find('>')?];
+
+    let pattern = format!("{}=\"", attr);
+    let value_start = element.find(&pattern)? + pattern.len();
+    let value_end = value_start + element[value_start..].find('"')?;
+
+    Some(unescape_xml(&element[value_start..value_end]))
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .rposition(|window| window == needle)
+}
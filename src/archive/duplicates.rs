@@ -0,0 +1,238 @@
+use super::{Archive, ArchiveEntries, EntryProperties, FileProperties, NodeID};
+use anyhow::{anyhow, Context, Result};
+use parking_lot::{Mutex, MutexGuard};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::Read,
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+    sync::Arc,
+};
+use zip::ZipArchive;
+
+/// Finds sets of files in an archive that have identical content.
+///
+/// Files are first grouped by their size and the CRC32 checksum stored in the archive, which doesn't need any of
+/// them to be read. Different content can still share a checksum, so the files in each group are then compared
+/// byte for byte, and only files that really are identical end up in the same set.
+pub struct DuplicateFinder {
+    archive: Arc<Archive>,
+    files: Arc<ArchiveEntries>,
+    /// Files that share their size and checksum with at least one other file, grouped together.
+    candidates: Vec<Vec<NodeID>>,
+    cancelled: AtomicBool,
+    finished: AtomicBool,
+    sets: Mutex<Vec<DuplicateSet>>,
+    pub checked: AtomicU32,
+    pub total_to_check: u32,
+}
+
+impl DuplicateFinder {
+    const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+    pub fn prepare(archive: Arc<Archive>) -> Self {
+        let files = archive.entries();
+        let mut groups: HashMap<(u64, u32), Vec<NodeID>> = HashMap::new();
+
+        for (id, entry, _) in files.children_iter(&[NodeID::first()]) {
+            match &entry.props {
+                EntryProperties::File(props) if Self::is_comparable(props) => groups
+                    .entry((props.raw_size_bytes, props.crc32))
+                    .or_default()
+                    .push(id),
+                _ => (),
+            }
+        }
+
+        let candidates = groups
+            .into_values()
+            .filter(|ids| ids.len() > 1)
+            .collect::<Vec<_>>();
+
+        let total_to_check = candidates.iter().map(Vec::len).sum::<usize>() as u32;
+
+        Self {
+            archive,
+            files,
+            candidates,
+            cancelled: AtomicBool::new(false),
+            finished: AtomicBool::new(false),
+            sets: Mutex::new(Vec::new()),
+            checked: AtomicU32::new(0),
+            total_to_check,
+        }
+    }
+
+    /// Returns true if the content of a file with the given `props` can be compared with other files.
+    ///
+    /// Empty files don't waste any space by being duplicated, and the content of encrypted files can't be read.
+    /// Symlinks and special files don't have content in the usual sense, so they're left out too.
+    fn is_comparable(props: &FileProperties) -> bool {
+        props.raw_size_bytes > 0 && !props.encrypted && !props.is_symlink && props.special.is_none()
+    }
+
+    #[inline(always)]
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    #[inline(always)]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Returns true once every file has been compared, or comparing stopped early.
+    #[inline(always)]
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Acquire)
+    }
+
+    /// Returns the sets found so far, from the one that wastes the most space to the least.
+    #[inline(always)]
+    pub fn sets(&self) -> MutexGuard<Vec<DuplicateSet>> {
+        self.sets.lock()
+    }
+
+    /// Compare every file that may have a duplicate, recording each set of identical files as it's found.
+    pub fn find(&self) -> Result<()> {
+        let result = self.find_sets();
+        self.finished.store(true, Ordering::Release);
+        result
+    }
+
+    fn find_sets(&self) -> Result<()> {
+        let mut readers = ContentReaders::open(&self.archive)?;
+
+        for group in &self.candidates {
+            let mut remaining = group.clone();
+
+            // Every file that's identical to the first one is taken out of the group, and the files left over are
+            // compared with each other the same way
+            while let Some((&first, rest)) = remaining.split_first() {
+                let mut same = vec![first];
+                let mut different = Vec::new();
+
+                for &id in rest {
+                    if self.is_cancelled() {
+                        return Ok(());
+                    }
+
+                    // A file that can't be read is treated as different so it doesn't stop the rest from being
+                    // compared
+                    match readers.same_content(&self.files, first, id) {
+                        Ok(true) => same.push(id),
+                        Ok(false) | Err(_) => different.push(id),
+                    }
+                }
+
+                self.checked.fetch_add(same.len() as u32, Ordering::Relaxed);
+
+                if same.len() > 1 {
+                    self.add_set(DuplicateSet::new(&self.files, same));
+                }
+
+                remaining = different;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add the given `set` to the ones found so far, keeping them ordered by how much space they waste.
+    fn add_set(&self, set: DuplicateSet) {
+        let mut sets = self.sets.lock();
+
+        let pos = sets.partition_point(|existing| {
+            existing.wasted_bytes() > set.wasted_bytes()
+                || (existing.wasted_bytes() == set.wasted_bytes()
+                    && existing.files[0].1 <= set.files[0].1)
+        });
+
+        sets.insert(pos, set);
+    }
+}
+
+/// Files with identical content.
+pub struct DuplicateSet {
+    /// The uncompressed size of each file.
+    pub size: u64,
+    /// The ID and path of each file, ordered by path.
+    pub files: Vec<(NodeID, String)>,
+}
+
+impl DuplicateSet {
+    fn new(files: &ArchiveEntries, ids: Vec<NodeID>) -> Self {
+        let size = files[ids[0]].total_size_bytes;
+
+        let mut files = ids
+            .into_iter()
+            .map(|id| (id, files.path_of(id).to_string_lossy().into_owned()))
+            .collect::<Vec<_>>();
+
+        files.sort_unstable_by(|(_, x), (_, y)| x.cmp(y));
+
+        Self { size, files }
+    }
+
+    /// Returns the space taken up by every copy of the file but one.
+    pub fn wasted_bytes(&self) -> u64 {
+        self.size * (self.files.len() as u64 - 1)
+    }
+}
+
+/// Two handles to an archive, so a pair of its files can be read at the same time and compared as they're read.
+struct ContentReaders {
+    archives: [ZipArchive<File>; 2],
+    buffers: [Vec<u8>; 2],
+}
+
+impl ContentReaders {
+    fn open(archive: &Archive) -> Result<Self> {
+        Ok(Self {
+            archives: [archive.open_reader()?, archive.open_reader()?],
+            buffers: [
+                vec![0; DuplicateFinder::READ_BUFFER_SIZE],
+                vec![0; DuplicateFinder::READ_BUFFER_SIZE],
+            ],
+        })
+    }
+
+    /// Returns true if the files with the given ID's have the same content.
+    ///
+    /// Both files should be the same size.
+    fn same_content(&mut self, files: &ArchiveEntries, x: NodeID, y: NodeID) -> Result<bool> {
+        let [x_archive, y_archive] = &mut self.archives;
+        let [x_buffer, y_buffer] = &mut self.buffers;
+        let (x, y) = (&files[x], &files[y]);
+
+        let mut x_file = x_archive
+            .by_index(x.entry_num)
+            .with_context(|| anyhow!("failed to get {} from archive", x.name))?;
+
+        let mut y_file = y_archive
+            .by_index(y.entry_num)
+            .with_context(|| anyhow!("failed to get {} from archive", y.name))?;
+
+        let mut remaining = x.total_size_bytes;
+
+        while remaining > 0 {
+            let len = remaining.min(x_buffer.len() as u64) as usize;
+
+            x_file
+                .read_exact(&mut x_buffer[..len])
+                .with_context(|| anyhow!("failed to read {}", x.name))?;
+
+            y_file
+                .read_exact(&mut y_buffer[..len])
+                .with_context(|| anyhow!("failed to read {}", y.name))?;
+
+            if x_buffer[..len] != y_buffer[..len] {
+                return Ok(false);
+            }
+
+            remaining -= len as u64;
+        }
+
+        Ok(true)
+    }
+}
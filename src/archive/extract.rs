@@ -1,91 +1,1508 @@
-use super::{Archive, ArchiveEntry, EntryProperties, NodeID};
+use super::{
+    filter::{self, PathFilter},
+    is_valid_filename,
+    mount::SpaceEstimate,
+    rename::RenameRule,
+    Archive, ArchiveEntries, ArchiveEntry, EntryProperties, NodeID,
+};
+use crate::util::{disk::DiskStats, size, stream};
 use anyhow::{anyhow, Context, Result};
 use parking_lot::Mutex;
 use smallvec::SmallVec;
 use std::{
-    fs::{self, File},
-    io,
-    sync::atomic::Ordering,
+    collections::{HashMap, HashSet},
+    ffi::{OsStr, OsString},
+    fs::{self, File, Permissions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    mem,
+    num::{NonZeroU64, NonZeroUsize},
+    os::unix::{ffi::OsStrExt, fs::PermissionsExt},
+    str::FromStr,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
     sync::Arc,
+    thread,
+    time::{Duration, Instant},
 };
 use std::{
-    path::{Path, PathBuf},
-    sync::atomic::AtomicU32,
+    path::{Component, Path, PathBuf},
+    sync::atomic::{AtomicU32, AtomicU64},
 };
 use zip::ZipArchive;
 
+type ExtractedFile<'a> = (NodeID, &'a ArchiveEntry, PathBuf);
+
+/// A file with the same content as an earlier file, along with the path of that earlier file.
+type DuplicateFile<'a> = (ExtractedFile<'a>, PathBuf);
+
+/// Settings that change how entries are extracted.
+#[derive(Clone, Default)]
+// Each of these is an independent switch from the command line, so they don't form a state machine
+#[allow(clippy::struct_excessive_bools)]
+pub struct ExtractOptions {
+    /// Keep the setuid and setgid bits of extracted files.
+    ///
+    /// These are stripped by default so an archive can't sneak in a program that runs with elevated privileges.
+    pub allow_setuid: bool,
+    /// Write symlinks as regular files that contain the path they point to.
+    pub symlinks_as_files: bool,
+    /// Change the path of each entry before it's extracted.
+    pub rename: Option<Arc<RenameRule>>,
+    /// Only extract files that pass this filter, along with the directories that lead to them.
+    pub filter: Option<Arc<PathFilter>>,
+    /// Leave out dotfiles and operating system junk inside of the extracted entries, along with everything in them.
+    ///
+    /// Entries that are extracted directly are never left out, even if they're hidden themselves.
+    pub skip_hidden: bool,
+    /// The number of leading components to remove from the path of each entry, like `tar --strip-components`.
+    pub strip_components: usize,
+    /// Leave files that already exist with the same content alone, which lets an interrupted
+    /// extraction pick up where it left off.
+    pub skip_existing: bool,
+    /// Keep going when an entry can't be extracted and record why instead of stopping.
+    pub continue_on_error: bool,
+    /// Read every written file back after extracting and record the ones that don't match the archive's checksum.
+    pub verify: bool,
+    /// Extract into a hidden directory next to the destination and only move everything into place once
+    /// the extraction succeeds, so a failed or cancelled extraction never leaves a partial tree behind.
+    pub atomic: bool,
+    /// Write files with the same content as a file that was already extracted as hard links to it to save space.
+    pub hardlink_duplicates: bool,
+    /// Skip over long runs of zeros instead of writing them so files like disk images take up less space.
+    pub sparse: bool,
+    /// The most bytes to write each second.
+    pub rate_limit: Option<RateLimit>,
+    /// Write files with the lowest CPU and I/O priority so the rest of the system stays responsive.
+    pub low_priority: bool,
+}
+
+/// A cap on how many bytes can be written each second.
+///
+/// Rates are written as a number of bytes with an optional `K`, `M`, or `G` suffix, like `500K` or `20M`.
+#[derive(Copy, Clone)]
+pub struct RateLimit {
+    pub bytes_per_sec: NonZeroU64,
+}
+
+impl FromStr for RateLimit {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let bytes_per_sec = size::parse(value)
+            .and_then(NonZeroU64::new)
+            .ok_or_else(|| {
+                anyhow!(
+                    "invalid rate limit: {} (expected a size like 500K or 20M)",
+                    value.trim()
+                )
+            })?;
+
+        Ok(Self { bytes_per_sec })
+    }
+}
+
 pub struct Extractor {
     archive: Arc<Archive>,
+    files: Arc<ArchiveEntries>,
     base_nodes: SmallVec<[NodeID; 4]>,
+    options: ExtractOptions,
+    cancelled: AtomicBool,
     pub extracted: AtomicU32,
     pub total_to_extract: u32,
+    pub extracted_bytes: AtomicU64,
+    pub total_bytes: u64,
+    /// Set once extraction starts, since a prepared extraction may wait for the user to confirm it.
+    started_at: Mutex<Option<Instant>>,
+    failures: Mutex<Vec<Failure>>,
 }
 
 impl Extractor {
-    pub fn prepare(archive: Arc<Archive>, base_nodes: SmallVec<[NodeID; 4]>) -> Self {
-        let total_to_extract = if base_nodes.contains(&NodeID::first()) {
-            archive.files.len() as u32
-        } else {
-            archive
-                .files
-                .children_iter(&base_nodes)
-                .count()
-                .min(archive.files.len()) as u32
-        };
+    const COPY_BUFFER_SIZE: usize = 64 * 1024;
 
-        Self {
+    /// The smallest run of zeros that is turned into a hole in a sparse file, which is the usual size of a filesystem block.
+    const SPARSE_BLOCK_SIZE: usize = 4096;
+
+    const SETUID_BITS: u32 = 0o6000;
+
+    /// Prepare to extract the given `base_nodes` from the `archive`.
+    ///
+    /// Fails if more than one file would be written to the same path, which renaming and stripping components can do.
+    pub fn prepare(
+        archive: Arc<Archive>,
+        base_nodes: SmallVec<[NodeID; 4]>,
+        options: ExtractOptions,
+    ) -> Result<Self> {
+        let files = archive.entries();
+        let entries = renamed_entries(entries_to_extract(&files, &base_nodes, &options), &options);
+        let total_to_extract = entries.len() as u32;
+
+        check_unique_paths(&files, &entries)?;
+
+        let total_bytes = entries
+            .iter()
+            .map(|(_, entry, _)| match &entry.props {
+                EntryProperties::File(props) => props.raw_size_bytes,
+                EntryProperties::Directory => 0,
+            })
+            .sum();
+
+        Ok(Self {
             archive,
+            files,
             base_nodes,
+            options,
+            cancelled: AtomicBool::new(false),
             extracted: AtomicU32::new(0),
             total_to_extract,
+            extracted_bytes: AtomicU64::new(0),
+            total_bytes,
+            started_at: Mutex::new(None),
+            failures: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Wait until writing more data would no longer go over the rate limit, if there is one.
+    fn throttle(&self) {
+        // Sleeping in short steps keeps cancelling responsive with low limits
+        const MAX_SLEEP: Duration = Duration::from_millis(100);
+
+        let limit = match self.options.rate_limit {
+            Some(limit) => limit.bytes_per_sec.get(),
+            None => return,
+        };
+
+        let started_at = match *self.started_at.lock() {
+            Some(started_at) => started_at,
+            None => return,
+        };
+
+        let written = self.extracted_bytes.load(Ordering::Relaxed);
+        let allowed_at = Duration::from_secs_f64(written as f64 / limit as f64);
+
+        while let Some(remaining) = allowed_at.checked_sub(started_at.elapsed()) {
+            if remaining.is_zero() || self.is_cancelled() {
+                break;
+            }
+
+            thread::sleep(remaining.min(MAX_SLEEP));
+        }
+    }
+
+    /// Returns how fast data is being written and how long it should take to write the rest.
+    ///
+    /// Returns None until enough time has passed to get a useful estimate.
+    pub fn throughput(&self) -> Option<Throughput> {
+        const MIN_ELAPSED_SECS: f64 = 1.0;
+
+        let elapsed = (*self.started_at.lock())?.elapsed().as_secs_f64();
+        let extracted = self.extracted_bytes.load(Ordering::Relaxed);
+
+        if elapsed < MIN_ELAPSED_SECS || extracted == 0 {
+            return None;
         }
+
+        let bytes_per_sec = extracted as f64 / elapsed;
+        let remaining = self.total_bytes.saturating_sub(extracted) as f64 / bytes_per_sec;
+
+        Some(Throughput {
+            bytes_per_sec: bytes_per_sec.round() as u64,
+            remaining: Duration::from_secs_f64(remaining),
+        })
+    }
+
+    /// Check if the filesystem at the given `path` has enough free space for every entry that will be extracted.
+    ///
+    /// The `path` doesn't have to exist yet, in which case the closest directory above it that does is checked.
+    /// Returns None if everything fits.
+    pub fn space_check<P>(&self, path: P) -> Result<Option<SpaceEstimate>>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+
+        let existing = path
+            .ancestors()
+            .find(|ancestor| ancestor.exists())
+            .filter(|ancestor| !ancestor.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+
+        let stats = DiskStats::read(existing)
+            .with_context(|| anyhow!("failed to get filesystem info for {}", existing.display()))?;
+
+        if self.total_bytes <= stats.available_bytes {
+            return Ok(None);
+        }
+
+        Ok(Some(SpaceEstimate {
+            needed_bytes: self.total_bytes,
+            available_bytes: stats.available_bytes,
+        }))
+    }
+
+    /// Stop the extraction as soon as possible.
+    ///
+    /// Entries that were already extracted are kept, but any file that was in the middle of
+    /// being written will be removed.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    #[inline(always)]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
     }
 
     pub fn extract<P>(&self, out_path: P) -> Result<()>
     where
         P: AsRef<Path> + Into<PathBuf>,
     {
-        fs::create_dir_all(&out_path).context("failed to create base output path")?;
+        *self.started_at.lock() = Some(Instant::now());
 
         let out_path = out_path.into();
 
-        let valid_files = self
-            .archive
-            .files
-            .children_iter(&self.base_nodes)
-            .filter(|(id, _, _)| *id != NodeID::first());
+        if !self.options.low_priority {
+            return self.extract_to(&out_path);
+        }
+
+        // A lowered priority can't be raised again without privileges, so the extraction gets a thread of its own
+        // rather than lowering the one it was started from
+        thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    lower_thread_priority();
+                    self.extract_to(&out_path)
+                })
+                .join()
+                .unwrap_or_else(|_| Err(anyhow!("extraction thread panicked")))
+        })
+    }
+
+    fn extract_to(&self, out_path: &Path) -> Result<()> {
+        if !self.options.atomic {
+            return self.extract_into(out_path);
+        }
+
+        // An existing destination can't be swapped out as a whole, so its top-level entries are moved in one by one instead
+        let nested = out_path.exists();
+        let partial_path = partial_path(out_path, nested);
+
+        if let Some(parent) = partial_path.parent() {
+            fs::create_dir_all(parent).context("failed to create base output path")?;
+        }
+
+        let result = self.extract_into(&partial_path).and_then(|()| {
+            if self.is_cancelled() {
+                return Ok(());
+            }
+
+            move_into_place(&partial_path, out_path, nested)
+        });
+
+        // An error from the extraction itself is more important to report
+        match fs::remove_dir_all(&partial_path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound && result.is_ok() => Err(err)
+                .with_context(|| {
+                    anyhow!(
+                        "failed to remove temporary directory: {}",
+                        partial_path.display()
+                    )
+                }),
+            _ => result,
+        }
+    }
+
+    fn extract_into(&self, out_path: &Path) -> Result<()> {
+        fs::create_dir_all(out_path).context("failed to create base output path")?;
+
+        let entries = renamed_entries(
+            entries_to_extract(&self.files, &self.base_nodes, &self.options),
+            &self.options,
+        );
+
+        let (unsafe_entries, entries): (Vec<_>, Vec<_>) = entries
+            .into_iter()
+            .partition(|&(id, _, _)| unsafe_path(&self.files, id).is_some());
+
+        // These are refused before anything is written, so a refused archive leaves nothing behind
+        for (id, _, path) in unsafe_entries {
+            let unsafe_path = unsafe_path(&self.files, id).unwrap_or_default();
+            let result = Err(anyhow!(
+                "refusing to extract entry with unsafe path: {}",
+                unsafe_path
+            ));
+
+            self.tolerate_failure::<()>(&path, result)?;
+        }
+
+        self.create_base_parents(out_path, &entries)?;
+
+        let (dirs, files): (Vec<_>, Vec<_>) = entries
+            .into_iter()
+            .partition(|(_, node, _)| node.props.is_dir());
+
+        let (symlinks, files): (Vec<_>, Vec<_>) = files
+            .into_iter()
+            .partition(|(_, node, _)| !self.options.symlinks_as_files && node.props.is_symlink());
+
+        let (files, duplicates) = if self.options.hardlink_duplicates {
+            split_duplicates(files)
+        } else {
+            (files, Vec::new())
+        };
+
+        // Every directory is created before any file is written so that a file can never
+        // be placed in a directory that hasn't been checked yet
+        for (_, _, path) in dirs {
+            if self.is_cancelled() {
+                return Ok(());
+            }
+
+            let result = self.extract_dir(out_path, &path);
+            self.tolerate_failure(&path, result)?;
 
-        for (_, node, path) in valid_files {
-            Self::extract_file(&self.archive.inner, node, &out_path.join(path))?;
             self.extracted.fetch_add(1, Ordering::Relaxed);
         }
 
+        self.extract_files(out_path, &files)?;
+
+        // Duplicates need the files they're linked to to be fully written first
+        if !self.is_cancelled() {
+            self.link_duplicates(out_path, &duplicates)?;
+        }
+
+        // Symlinks are created last so that nothing else in the archive can be written through them
+        self.extract_symlinks(out_path, &symlinks)?;
+
+        if self.options.verify && !self.is_cancelled() {
+            self.verify_files(out_path, &files)?;
+        }
+
         Ok(())
     }
 
-    fn extract_file(
-        archive: &Mutex<ZipArchive<File>>,
+    /// Create the directories above each of the base nodes in the given `entries`.
+    ///
+    /// Base nodes from different directories keep the directories that set them apart, which
+    /// don't have entries of their own to be created from.
+    fn create_base_parents(&self, out_path: &Path, entries: &[ExtractedFile]) -> Result<()> {
+        let parents = entries
+            .iter()
+            .filter(|(id, _, _)| self.base_nodes.contains(id))
+            .filter_map(|(_, _, path)| path.parent())
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .collect::<HashSet<_>>();
+
+        for parent in parents {
+            let parent = checked_out_path(out_path, parent)?;
+
+            fs::create_dir_all(&parent)
+                .with_context(|| anyhow!("failed to create directory: {}", parent.display()))?;
+        }
+
+        Ok(())
+    }
+
+    fn extract_dir(&self, out_path: &Path, path: &Path) -> Result<()> {
+        let entry_path = checked_out_path(out_path, path)?;
+
+        // Renamed and stripped directories can end up being merged together or stripped away entirely,
+        // and resumed extractions will have already created some of them
+        let created = if self.options.rename.is_some()
+            || self.options.strip_components > 0
+            || self.options.skip_existing
+        {
+            fs::create_dir_all(&entry_path)
+        } else {
+            fs::create_dir(&entry_path)
+        };
+
+        created.with_context(|| anyhow!("failed to create directory: {}", entry_path.display()))
+    }
+
+    /// Record the error of the given `result` and carry on if failures should be tolerated.
+    ///
+    /// Returns None if the `result` was a failure that was recorded.
+    fn tolerate_failure<T>(&self, path: &Path, result: Result<T>) -> Result<Option<T>> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(error) if self.options.continue_on_error => {
+                self.failures.lock().push(Failure {
+                    path: path.to_path_buf(),
+                    error,
+                });
+
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Take every failure that was recorded while extracting.
+    ///
+    /// Failures are only recorded when [`ExtractOptions::continue_on_error`] or [`ExtractOptions::verify`] is set.
+    pub fn take_failures(&self) -> Vec<Failure> {
+        let mut failures = mem::take(&mut *self.failures.lock());
+        failures.sort_unstable_by(|x, y| x.path.cmp(&y.path));
+        failures
+    }
+
+    /// Read back each of the given `files` and record every one that doesn't match the checksum in the archive.
+    ///
+    /// Files that already failed to extract are skipped.
+    fn verify_files(&self, out_path: &Path, files: &[ExtractedFile]) -> Result<()> {
+        if files.is_empty() {
+            return Ok(());
+        }
+
+        let mut archive = self.archive.open_reader()?;
+
+        let already_failed = self
+            .failures
+            .lock()
+            .iter()
+            .map(|failure| failure.path.clone())
+            .collect::<HashSet<_>>();
+
+        for (_, entry, path) in files {
+            if self.is_cancelled() {
+                break;
+            }
+
+            if already_failed.contains(path) {
+                continue;
+            }
+
+            let result = checked_out_path(out_path, path).and_then(|entry_path| {
+                let archive_file = archive
+                    .by_index(entry.entry_num)
+                    .with_context(|| anyhow!("failed to get {} from archive", path.display()))?;
+
+                is_same_file(&entry_path, archive_file.size(), archive_file.crc32())
+            });
+
+            let error = match result {
+                Ok(true) => continue,
+                Ok(false) => anyhow!("written file does not match the checksum in the archive"),
+                Err(err) => err.context("failed to verify file"),
+            };
+
+            self.failures.lock().push(Failure {
+                path: path.clone(),
+                error,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Create each of the given `duplicates` as a hard link to the file that has the same content.
+    ///
+    /// Files are only linked when their content really matches, as the checksums they were paired up with can collide.
+    /// Otherwise, they're extracted as usual.
+    fn link_duplicates(&self, out_path: &Path, duplicates: &[DuplicateFile]) -> Result<()> {
+        if duplicates.is_empty() {
+            return Ok(());
+        }
+
+        let mut archive = self.archive.open_reader()?;
+
+        for ((_, entry, path), original) in duplicates {
+            if self.is_cancelled() {
+                break;
+            }
+
+            let result = self.link_duplicate(&mut archive, out_path, entry, path, original);
+
+            if let Some(false) = self.tolerate_failure(path, result)? {
+                break;
+            }
+
+            self.extracted.fetch_add(1, Ordering::Relaxed);
+        }
+
+        Ok(())
+    }
+
+    /// Returns false if the extraction was cancelled before the file could be fully written.
+    fn link_duplicate(
+        &self,
+        archive: &mut ZipArchive<File>,
+        out_path: &Path,
         entry: &ArchiveEntry,
+        path: &Path,
+        original: &Path,
+    ) -> Result<bool> {
+        let original = checked_out_path(out_path, original)?;
+        let entry_path = checked_out_path(out_path, path)?;
+        self.create_renamed_parent(&entry_path)?;
+
+        let mut archive_file = archive
+            .by_index(entry.entry_num)
+            .with_context(|| anyhow!("failed to get {} from archive", path.display()))?;
+
+        let size = archive_file.size();
+
+        // The original may have failed to extract when failures are tolerated
+        if !file_matches(&mut archive_file, size, &original)? {
+            drop(archive_file);
+            return self.extract_file(archive, entry, &entry_path);
+        }
+
+        // A file left behind by an earlier extraction would otherwise make the link fail
+        match fs::symlink_metadata(&entry_path) {
+            Ok(metadata) if metadata.is_file() => fs::remove_file(&entry_path)
+                .with_context(|| anyhow!("failed to replace {}", entry_path.display()))?,
+            Ok(_) => (),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| anyhow!("failed to inspect {}", entry_path.display()))
+            }
+        }
+
+        fs::hard_link(&original, &entry_path).with_context(|| {
+            anyhow!(
+                "failed to link {} to {}",
+                entry_path.display(),
+                original.display()
+            )
+        })?;
+
+        self.extracted_bytes.fetch_add(size, Ordering::Relaxed);
+        Ok(true)
+    }
+
+    /// Create the given `symlinks`.
+    ///
+    /// The path each symlink points to is kept exactly as it is in the archive, so relative
+    /// targets stay relative to the directory the symlink is in.
+    fn extract_symlinks(&self, out_path: &Path, symlinks: &[ExtractedFile]) -> Result<()> {
+        if symlinks.is_empty() {
+            return Ok(());
+        }
+
+        let mut archive = self.archive.open_reader()?;
+
+        for (_, entry, path) in symlinks {
+            if self.is_cancelled() {
+                break;
+            }
+
+            let result = self.extract_symlink(&mut archive, out_path, entry, path);
+            self.tolerate_failure(path, result)?;
+
+            self.extracted.fetch_add(1, Ordering::Relaxed);
+        }
+
+        Ok(())
+    }
+
+    fn extract_symlink(
+        &self,
+        archive: &mut ZipArchive<File>,
         out_path: &Path,
+        entry: &ArchiveEntry,
+        path: &Path,
     ) -> Result<()> {
-        match &entry.props {
-            EntryProperties::Directory => fs::create_dir(&out_path)
-                .with_context(|| anyhow!("failed to create directory: {}", out_path.display()))?,
-            EntryProperties::File(_) => {
-                let mut file = File::create(&out_path)
-                    .with_context(|| anyhow!("failed to create file: {}", out_path.display()))?;
+        let mut archive_file = archive
+            .by_index(entry.entry_num)
+            .with_context(|| anyhow!("failed to get {} from archive", path.display()))?;
 
-                let mut archive = archive.lock();
+        let mut target = Vec::new();
 
-                let mut archive_file = archive.by_index(entry.entry_num).with_context(|| {
-                    anyhow!("failed to get {} from archive", out_path.display())
-                })?;
+        archive_file
+            .read_to_end(&mut target)
+            .with_context(|| anyhow!("failed to read symlink: {}", path.display()))?;
+
+        let target = OsStr::from_bytes(&target);
 
-                io::copy(&mut archive_file, &mut file)
-                    .with_context(|| anyhow!("failed to extract file: {}", out_path.display()))?;
+        // Checking the path without the usual protections is fine since nothing is written if it matches
+        if self.options.skip_existing
+            && matches!(fs::read_link(out_path.join(path)), Ok(existing) if existing == target)
+        {
+            return Ok(());
+        }
+
+        let link_path = checked_out_path(out_path, path)?;
+        self.create_renamed_parent(&link_path)?;
+
+        std::os::unix::fs::symlink(target, &link_path)
+            .with_context(|| anyhow!("failed to create symlink: {}", link_path.display()))?;
+
+        self.extracted_bytes
+            .fetch_add(target.len() as u64, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    /// Extract the given `files` across as many workers as there are available CPU's.
+    ///
+    /// Each worker opens its own handle to the archive so they never have to wait on each other.
+    fn extract_files(&self, out_path: &Path, files: &[ExtractedFile]) -> Result<()> {
+        let num_workers = thread::available_parallelism()
+            .map_or(1, NonZeroUsize::get)
+            .min(files.len());
+
+        let next_file = AtomicUsize::new(0);
+        let failed = AtomicBool::new(false);
+
+        thread::scope(|scope| {
+            let workers = (0..num_workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let result = self.extract_worker(out_path, files, &next_file, &failed);
+
+                        if result.is_err() {
+                            failed.store(true, Ordering::Relaxed);
+                        }
+
+                        result
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            // Any workers left running will be joined at the end of the scope
+            workers.into_iter().try_for_each(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|_| Err(anyhow!("extraction worker panicked")))
+            })
+        })
+    }
+
+    fn extract_worker(
+        &self,
+        out_path: &Path,
+        files: &[ExtractedFile],
+        next_file: &AtomicUsize,
+        failed: &AtomicBool,
+    ) -> Result<()> {
+        let mut archive = self.archive.open_reader()?;
+
+        // New threads don't always inherit the priority of the thread that spawned them
+        if self.options.low_priority {
+            lower_thread_priority();
+        }
+
+        // Stop as soon as any other worker runs into an error
+        while !failed.load(Ordering::Relaxed) && !self.is_cancelled() {
+            let (_, entry, path) = match files.get(next_file.fetch_add(1, Ordering::Relaxed)) {
+                Some(file) => file,
+                None => break,
+            };
+
+            let result = checked_out_path(out_path, path).and_then(|entry_path| {
+                self.create_renamed_parent(&entry_path)?;
+                self.extract_file(&mut archive, entry, &entry_path)
+            });
+
+            if let Some(false) = self.tolerate_failure(path, result)? {
+                break;
             }
+
+            self.extracted.fetch_add(1, Ordering::Relaxed);
         }
 
         Ok(())
     }
+
+    /// Returns false if the extraction was cancelled before the file could be fully written.
+    fn extract_file(
+        &self,
+        archive: &mut ZipArchive<File>,
+        entry: &ArchiveEntry,
+        out_path: &Path,
+    ) -> Result<bool> {
+        if self.options.skip_existing {
+            let mut archive_file = archive
+                .by_index(entry.entry_num)
+                .with_context(|| anyhow!("failed to get {} from archive", out_path.display()))?;
+
+            let size = archive_file.size();
+
+            if file_matches(&mut archive_file, size, out_path)? {
+                self.extracted_bytes.fetch_add(size, Ordering::Relaxed);
+                return Ok(true);
+            }
+        }
+
+        let mut archive_file = archive
+            .by_index(entry.entry_num)
+            .with_context(|| anyhow!("failed to get {} from archive", out_path.display()))?;
+
+        let mut file = File::create(&out_path)
+            .with_context(|| anyhow!("failed to create file: {}", out_path.display()))?;
+
+        let mut buffer = vec![0; Self::COPY_BUFFER_SIZE];
+
+        loop {
+            if self.is_cancelled() {
+                drop(file);
+
+                fs::remove_file(out_path).with_context(|| {
+                    anyhow!("failed to remove partial file: {}", out_path.display())
+                })?;
+
+                return Ok(false);
+            }
+
+            let read = match archive_file.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| anyhow!("failed to extract file: {}", out_path.display()))
+                }
+            };
+
+            let written = if self.options.sparse {
+                write_sparse(&mut file, &buffer[..read])
+            } else {
+                file.write_all(&buffer[..read])
+            };
+
+            written.with_context(|| anyhow!("failed to extract file: {}", out_path.display()))?;
+
+            self.extracted_bytes
+                .fetch_add(read as u64, Ordering::Relaxed);
+
+            self.throttle();
+        }
+
+        // A hole at the end of the file only becomes part of it once the length is set
+        if self.options.sparse {
+            file.stream_position()
+                .and_then(|len| file.set_len(len))
+                .with_context(|| anyhow!("failed to extract file: {}", out_path.display()))?;
+        }
+
+        self.apply_permissions(entry, out_path)?;
+        Ok(true)
+    }
+
+    /// Create the directory the given `out_path` will be placed in if a rename rule moved it somewhere new.
+    ///
+    /// Without a rename rule, the directory is always created beforehand from its own archive entry.
+    fn create_renamed_parent(&self, out_path: &Path) -> Result<()> {
+        if self.options.rename.is_none() {
+            return Ok(());
+        }
+
+        match out_path.parent() {
+            Some(parent) => fs::create_dir_all(parent)
+                .with_context(|| anyhow!("failed to create directory: {}", parent.display())),
+            None => Ok(()),
+        }
+    }
+
+    fn apply_permissions(&self, entry: &ArchiveEntry, out_path: &Path) -> Result<()> {
+        let mode = match &entry.props {
+            // The permissions of a symlink are meaningless for the file written in its place
+            EntryProperties::File(props) if !props.is_symlink => props.unix_mode,
+            EntryProperties::File(_) | EntryProperties::Directory => None,
+        };
+
+        let mode = match mode {
+            Some(mode) if self.options.allow_setuid => mode,
+            Some(mode) => mode & !Self::SETUID_BITS,
+            None => return Ok(()),
+        };
+
+        fs::set_permissions(out_path, Permissions::from_mode(mode))
+            .with_context(|| anyhow!("failed to set permissions of {}", out_path.display()))
+    }
+}
+
+/// An entry that couldn't be extracted.
+pub struct Failure {
+    pub path: PathBuf,
+    pub error: anyhow::Error,
+}
+
+/// The rate an [`Extractor`] is writing data at.
+pub struct Throughput {
+    pub bytes_per_sec: u64,
+    /// The estimated time left until every file is written.
+    pub remaining: Duration,
+}
+
+/// Returns true if there's a file at the given `path` that has the given `size` and exactly the same content as what's
+/// left to read from `reader`.
+fn file_matches<R>(reader: &mut R, size: u64, path: &Path) -> Result<bool>
+where
+    R: Read,
+{
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err).with_context(|| anyhow!("failed to open {}", path.display())),
+    };
+
+    let metadata = file
+        .metadata()
+        .with_context(|| anyhow!("failed to inspect {}", path.display()))?;
+
+    if !metadata.is_file() || metadata.len() != size {
+        return Ok(false);
+    }
+
+    stream::equal(reader, &mut file)
+        .with_context(|| anyhow!("failed to compare {}", path.display()))
+}
+
+/// Give the calling thread the lowest CPU priority, along with the idle I/O scheduling class on Linux.
+///
+/// Other systems apply the CPU priority to the whole process rather than the calling thread.
+///
+/// This is best-effort, so any errors are ignored.
+fn lower_thread_priority() {
+    const LOWEST_NICE: libc::c_int = 19;
+
+    // This only applies to the calling thread on Linux when given an ID of 0
+    unsafe {
+        libc::setpriority(libc::PRIO_PROCESS, 0, LOWEST_NICE);
+    }
+
+    #[cfg(target_os = "linux")]
+    lower_thread_io_priority();
+}
+
+/// Give the calling thread the idle I/O scheduling class.
+#[cfg(target_os = "linux")]
+fn lower_thread_io_priority() {
+    // From linux/ioprio.h
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+    const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+
+    // This only applies to the calling thread when given an ID of 0
+    unsafe {
+        libc::syscall(
+            libc::SYS_ioprio_set,
+            IOPRIO_WHO_PROCESS,
+            0,
+            IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+        );
+    }
+}
+
+/// Write the given `data` to `file`, but seek over every block that only contains zeros to leave a hole instead.
+fn write_sparse(file: &mut File, data: &[u8]) -> io::Result<()> {
+    for block in data.chunks(Extractor::SPARSE_BLOCK_SIZE) {
+        if block.iter().all(|&byte| byte == 0) {
+            // Blocks are never bigger than SPARSE_BLOCK_SIZE, so this can't wrap
+            #[allow(clippy::cast_possible_wrap)]
+            file.seek(SeekFrom::Current(block.len() as i64))?;
+        } else {
+            file.write_all(block)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Split the given `files` into the ones that need to be written and the ones that look like they have the same content
+/// and permissions as one of those.
+///
+/// Empty files are never treated as duplicates, as linking them wouldn't save any space.
+fn split_duplicates(files: Vec<ExtractedFile>) -> (Vec<ExtractedFile>, Vec<DuplicateFile>) {
+    let mut originals = HashMap::new();
+    let mut unique = Vec::with_capacity(files.len());
+    let mut duplicates = Vec::new();
+
+    for file in files {
+        let props = match &file.1.props {
+            EntryProperties::File(props) if props.raw_size_bytes > 0 => props,
+            EntryProperties::File(_) | EntryProperties::Directory => {
+                unique.push(file);
+                continue;
+            }
+        };
+
+        let key = (props.raw_size_bytes, props.crc32, props.unix_mode);
+
+        if let Some(original) = originals.get(&key) {
+            let original = PathBuf::clone(original);
+            duplicates.push((file, original));
+            continue;
+        }
+
+        originals.insert(key, file.2.clone());
+        unique.push(file);
+    }
+
+    (unique, duplicates)
+}
+
+/// Returns true if the file at the given `path` already has the given `size` and `crc32` checksum.
+fn is_same_file(path: &Path, size: u64, crc32: u32) -> Result<bool> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err).with_context(|| anyhow!("failed to open {}", path.display())),
+    };
+
+    let metadata = file
+        .metadata()
+        .with_context(|| anyhow!("failed to inspect {}", path.display()))?;
+
+    if !metadata.is_file() || metadata.len() != size {
+        return Ok(false);
+    }
+
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buffer = vec![0; Extractor::COPY_BUFFER_SIZE];
+
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => hasher.update(&buffer[..read]),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+            Err(err) => {
+                return Err(err).with_context(|| anyhow!("failed to read {}", path.display()))
+            }
+        }
+    }
+
+    Ok(hasher.finalize() == crc32)
+}
+
+/// Returns the full path of the entry with the given `id` as it's stored in the archive if it or any directory above
+/// it has a name like `..` that can't be a filename.
+///
+/// Those names are left out of the paths entries are extracted to, which would otherwise quietly put the entry
+/// somewhere other than where the archive says, or leave it clashing with another entry.
+fn unsafe_path(files: &ArchiveEntries, id: NodeID) -> Option<String> {
+    let mut names = Vec::new();
+    let mut cur_node = id;
+
+    while cur_node != NodeID::first() {
+        names.push(files[cur_node].name.as_str());
+
+        match files[cur_node].parent {
+            Some(parent) => cur_node = parent,
+            None => break,
+        }
+    }
+
+    if names.iter().all(|name| is_valid_filename(name)) {
+        return None;
+    }
+
+    names.reverse();
+    Some(names.join("/"))
+}
+
+/// Returns the given `entries` with the rename rule from the `options` applied to their paths, if there is one.
+fn renamed_entries<'a>(
+    entries: Vec<ExtractedFile<'a>>,
+    options: &ExtractOptions,
+) -> Vec<ExtractedFile<'a>> {
+    let rule = match &options.rename {
+        Some(rule) => rule,
+        None => return entries,
+    };
+
+    entries
+        .into_iter()
+        .map(|(id, node, path)| {
+            let path = rule.apply(&path, node.props.is_dir());
+            (id, node, path)
+        })
+        .collect()
+}
+
+/// Returns an error if more than one of the given `entries` would be extracted to the same path, unless they're all
+/// directories, which are merged together.
+///
+/// Files are written by several workers at once, so two of them at the same path would end up mixed together.
+/// Entries with unsafe paths are left to be refused on their own.
+fn check_unique_paths(files: &ArchiveEntries, entries: &[ExtractedFile]) -> Result<()> {
+    let mut is_dir_by_path = HashMap::with_capacity(entries.len());
+
+    for (id, entry, path) in entries {
+        if unsafe_path(files, *id).is_some() {
+            continue;
+        }
+
+        let is_dir = entry.props.is_dir();
+
+        match is_dir_by_path.insert(path.as_path(), is_dir) {
+            Some(true) if is_dir => (),
+            Some(_) => {
+                return Err(anyhow!(
+                    "more than one entry would be extracted to {}",
+                    path.display()
+                ))
+            }
+            None => (),
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns every entry under the given `base_nodes` that should be extracted with the given `options`.
+///
+/// Leading components are already stripped from the returned paths.
+fn entries_to_extract<'a>(
+    files: &'a ArchiveEntries,
+    base_nodes: &[NodeID],
+    options: &ExtractOptions,
+) -> Vec<ExtractedFile<'a>> {
+    let mut entries = filtered_entries(files, base_nodes, options.filter.as_deref());
+
+    if options.skip_hidden {
+        entries.retain(|&(id, _, _)| !is_hidden_below(files, id, base_nodes));
+    }
+
+    if options.strip_components == 0 {
+        return entries;
+    }
+
+    entries
+        .into_iter()
+        .filter_map(|(id, entry, path)| {
+            let stripped = path
+                .components()
+                .skip(options.strip_components)
+                .collect::<PathBuf>();
+
+            // Entries that are stripped away entirely are skipped just like tar does
+            if stripped.as_os_str().is_empty() {
+                None
+            } else {
+                Some((id, entry, stripped))
+            }
+        })
+        .collect()
+}
+
+/// Returns true if the entry with the given `id` or any directory between it and the closest of the `base_nodes` is
+/// hidden by [`filter::is_hidden`].
+fn is_hidden_below(files: &ArchiveEntries, id: NodeID, base_nodes: &[NodeID]) -> bool {
+    let mut cur_node = Some(id);
+
+    while let Some(node) = cur_node {
+        if base_nodes.contains(&node) {
+            return false;
+        }
+
+        if filter::is_hidden(&files[node].name) {
+            return true;
+        }
+
+        cur_node = files[node].parent;
+    }
+
+    false
+}
+
+/// Returns every entry under the given `base_nodes` that passes the given `filter`.
+fn filtered_entries<'a>(
+    files: &'a ArchiveEntries,
+    base_nodes: &[NodeID],
+    filter: Option<&PathFilter>,
+) -> Vec<ExtractedFile<'a>> {
+    let entries = files
+        .children_iter(base_nodes)
+        .filter(|(id, _, _)| *id != NodeID::first());
+
+    let filter = match filter {
+        Some(filter) => filter,
+        None => return entries.collect(),
+    };
+
+    let entries = entries.collect::<Vec<_>>();
+
+    // Directories are only worth creating if a file that passes the filter will be placed in them
+    let needed_dirs = entries
+        .iter()
+        .filter(|(_, entry, path)| !entry.props.is_dir() && filter.matches(path))
+        .flat_map(|(_, _, path)| path.ancestors().skip(1))
+        .map(Path::to_path_buf)
+        .collect::<HashSet<_>>();
+
+    entries
+        .into_iter()
+        .filter(|(_, entry, path)| {
+            if entry.props.is_dir() {
+                needed_dirs.contains(path)
+            } else {
+                filter.matches(path)
+            }
+        })
+        .collect()
+}
+
+/// Returns the hidden directory an atomic extraction to `out_path` should be written to first.
+///
+/// The directory is always on the same filesystem as `out_path` so it can be renamed into place.
+/// If `nested` is true, the directory is placed inside of `out_path` instead of next to it.
+fn partial_path(out_path: &Path, nested: bool) -> PathBuf {
+    let pid = std::process::id();
+
+    match out_path.file_name() {
+        Some(name) if !nested => {
+            let mut partial_name = OsString::from(".");
+            partial_name.push(name);
+            partial_name.push(format!(".vear-partial-{}", pid));
+
+            out_path.with_file_name(partial_name)
+        }
+        _ => out_path.join(format!(".vear-partial-{}", pid)),
+    }
+}
+
+/// Move the contents of a finished atomic extraction at `partial_path` to `out_path`.
+///
+/// If `nested` is true, the entries are merged into `out_path` separately, as `out_path` already exists.
+fn move_into_place(partial_path: &Path, out_path: &Path, nested: bool) -> Result<()> {
+    if !nested {
+        return fs::rename(partial_path, out_path)
+            .with_context(|| anyhow!("failed to move extracted files to {}", out_path.display()));
+    }
+
+    merge_dir(partial_path, out_path)
+}
+
+/// Move every entry in the directory at `from` into the existing directory at `to`.
+///
+/// Directories that exist in both are merged the same way, since a directory can't be renamed over one that isn't
+/// empty, which is what extracting to the same place again runs into. Files replace the ones already there.
+fn merge_dir(from: &Path, to: &Path) -> Result<()> {
+    let entries =
+        fs::read_dir(from).with_context(|| anyhow!("failed to read {}", from.display()))?;
+
+    for entry in entries {
+        let entry = entry.with_context(|| anyhow!("failed to read {}", from.display()))?;
+        let dest = to.join(entry.file_name());
+
+        let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+
+        // Symlinks that are already there aren't followed, so nothing can be moved outside of `to` through them
+        let exists_as_dir = fs::symlink_metadata(&dest).is_ok_and(|metadata| metadata.is_dir());
+
+        if is_dir && exists_as_dir {
+            merge_dir(&entry.path(), &dest)?;
+            continue;
+        }
+
+        fs::rename(entry.path(), &dest)
+            .with_context(|| anyhow!("failed to move extracted entry to {}", dest.display()))?;
+    }
+
+    Ok(())
+}
+
+/// Join the given `path` onto `base` while making sure that no existing component after `base` is a symlink.
+///
+/// This prevents an entry from being written outside of `base` by following a symlink that already exists
+/// in the output directory.
+fn checked_out_path(base: &Path, path: &Path) -> Result<PathBuf> {
+    let mut out_path = base.to_path_buf();
+
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => out_path.push(name),
+            _ => {
+                return Err(anyhow!(
+                    "refusing to extract entry with unsafe path: {}",
+                    path.display()
+                ))
+            }
+        }
+
+        match fs::symlink_metadata(&out_path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                return Err(anyhow!(
+                    "refusing to extract through symlink: {}",
+                    out_path.display()
+                ))
+            }
+            Ok(_) => (),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| anyhow!("failed to inspect {}", out_path.display()))
+            }
+        }
+    }
+
+    Ok(out_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smallvec::smallvec;
+    use zip::{write::FileOptions, ZipWriter};
+
+    /// A directory that's removed along with everything in it when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("vear-extract-{}-{}", name, std::process::id()));
+
+            fs::remove_dir_all(&path).ok();
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            fs::remove_dir_all(&self.0).ok();
+        }
+    }
+
+    /// Write an archive to `path` with a file for each of the given `(name, content)` pairs, with the names
+    /// stored exactly as they're given.
+    fn write_archive(path: &Path, files: &[(&str, &[u8])]) {
+        let mut zip = ZipWriter::new(File::create(path).unwrap());
+
+        for (name, content) in files {
+            zip.start_file(*name, FileOptions::default()).unwrap();
+            zip.write_all(content).unwrap();
+        }
+
+        zip.finish().unwrap();
+    }
+
+    /// Extract every entry of the archive at `archive_path` to `out_path`.
+    fn extract_all(archive_path: &Path, out_path: &Path) -> Result<()> {
+        extract_all_with(archive_path, out_path, ExtractOptions::default())
+    }
+
+    fn extract_all_with(
+        archive_path: &Path,
+        out_path: &Path,
+        options: ExtractOptions,
+    ) -> Result<()> {
+        let archive = Arc::new(Archive::read(archive_path).unwrap());
+        Extractor::prepare(archive, smallvec![NodeID::first()], options)?.extract(out_path)
+    }
+
+    /// Returns the path of every file under `dir`.
+    fn files_under(dir: &Path) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+
+            if path.is_dir() && !path.is_symlink() {
+                files.extend(files_under(&path));
+            } else {
+                files.push(path);
+            }
+        }
+
+        files
+    }
+
+    /// Assert that extracting the archive at `archive_path` to `out_path` is refused because of an unsafe path,
+    /// and that nothing was written anywhere in `dir` besides the archive.
+    fn assert_refused(dir: &Path, archive_path: &Path, out_path: &Path) {
+        let err = extract_all(archive_path, out_path).unwrap_err();
+
+        assert!(
+            format!("{:#}", err).contains("unsafe path"),
+            "unexpected error: {:#}",
+            err
+        );
+
+        assert_eq!(files_under(dir), [archive_path]);
+        assert_eq!(fs::read_dir(out_path).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn refuses_parent_dir_entries() {
+        let dir = TempDir::new("parent");
+        let (archive_path, out_path) = (dir.0.join("attack.zip"), dir.0.join("out"));
+
+        write_archive(&archive_path, &[("../escaped.txt", b"parent")]);
+        assert_refused(&dir.0, &archive_path, &out_path);
+
+        write_archive(
+            &archive_path,
+            &[
+                ("inner/safe.txt", b"safe"),
+                ("inner/../../escaped.txt", b"nested"),
+            ],
+        );
+
+        assert_refused(&dir.0, &archive_path, &out_path);
+    }
+
+    #[test]
+    fn refuses_absolute_entries() {
+        let dir = TempDir::new("absolute");
+        let (archive_path, out_path) = (dir.0.join("attack.zip"), dir.0.join("out"));
+        let target = dir.0.join("absolute.txt");
+
+        write_archive(&archive_path, &[(target.to_str().unwrap(), b"absolute")]);
+        assert_refused(&dir.0, &archive_path, &out_path);
+    }
+
+    #[test]
+    fn refuses_to_extract_through_symlinked_dir() {
+        let dir = TempDir::new("symlink");
+        let (archive_path, out_path) = (dir.0.join("attack.zip"), dir.0.join("out"));
+        let outside = dir.0.join("outside");
+
+        fs::create_dir_all(&out_path).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, out_path.join("link")).unwrap();
+
+        write_archive(&archive_path, &[("link/escaped.txt", b"through the link")]);
+
+        let err = extract_all(&archive_path, &out_path).unwrap_err();
+
+        assert!(
+            format!("{:#}", err).contains("symlink"),
+            "unexpected error: {:#}",
+            err
+        );
+
+        assert_eq!(fs::read_dir(&outside).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn checked_out_path_refuses_unsafe_paths() {
+        let dir = TempDir::new("checked");
+
+        for path in [
+            "../escaped",
+            "inner/../../escaped",
+            "/etc/passwd",
+            "./inner",
+        ] {
+            assert!(
+                checked_out_path(&dir.0, Path::new(path)).is_err(),
+                "{} was allowed",
+                path
+            );
+        }
+
+        assert_eq!(
+            checked_out_path(&dir.0, Path::new("inner/file")).unwrap(),
+            dir.0.join("inner/file")
+        );
+    }
+
+    #[test]
+    fn atomic_extraction_merges_existing_dirs() {
+        let dir = TempDir::new("merge");
+        let (archive_path, out_path) = (dir.0.join("archive.zip"), dir.0.join("out"));
+
+        write_archive(
+            &archive_path,
+            &[("dir/inner/file.txt", b"new"), ("top.txt", b"top")],
+        );
+
+        fs::create_dir_all(out_path.join("dir/inner")).unwrap();
+        fs::write(out_path.join("dir/inner/file.txt"), b"old").unwrap();
+        fs::write(out_path.join("dir/kept.txt"), b"kept").unwrap();
+
+        let options = ExtractOptions {
+            atomic: true,
+            ..ExtractOptions::default()
+        };
+
+        // Extracting to the same place twice has to merge with what the first extraction left there
+        for _ in 0..2 {
+            extract_all_with(&archive_path, &out_path, options.clone()).unwrap();
+        }
+
+        assert_eq!(
+            fs::read(out_path.join("dir/inner/file.txt")).unwrap(),
+            b"new"
+        );
+        assert_eq!(fs::read(out_path.join("dir/kept.txt")).unwrap(), b"kept");
+        assert_eq!(fs::read(out_path.join("top.txt")).unwrap(), b"top");
+
+        // The hidden directory the extraction was written to first is gone
+        assert_eq!(fs::read_dir(&out_path).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn refuses_entries_extracted_to_the_same_path() {
+        let dir = TempDir::new("same_path");
+        let (archive_path, out_path) = (dir.0.join("archive.zip"), dir.0.join("out"));
+
+        write_archive(
+            &archive_path,
+            &[("first/file.txt", b"first"), ("second/file.txt", b"second")],
+        );
+
+        let options = ExtractOptions {
+            strip_components: 1,
+            ..ExtractOptions::default()
+        };
+
+        let err = extract_all_with(&archive_path, &out_path, options).unwrap_err();
+
+        assert!(
+            format!("{:#}", err).contains("more than one entry"),
+            "unexpected error: {:#}",
+            err
+        );
+
+        assert!(!out_path.exists());
+
+        // Directories that end up at the same path are merged instead
+        write_archive(
+            &archive_path,
+            &[("first/dir/a.txt", b"a"), ("second/dir/b.txt", b"b")],
+        );
+
+        let options = ExtractOptions {
+            strip_components: 1,
+            ..ExtractOptions::default()
+        };
+
+        extract_all_with(&archive_path, &out_path, options).unwrap();
+        assert_eq!(fs::read_dir(out_path.join("dir")).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn skip_existing_only_skips_files_with_the_same_content() {
+        let dir = TempDir::new("skip");
+        let (archive_path, out_path) = (dir.0.join("archive.zip"), dir.0.join("out"));
+
+        write_archive(
+            &archive_path,
+            &[("same.txt", b"unchanged"), ("changed.txt", b"from archive")],
+        );
+
+        fs::create_dir_all(&out_path).unwrap();
+        fs::write(out_path.join("same.txt"), b"unchanged").unwrap();
+        fs::write(out_path.join("changed.txt"), b"from my disk").unwrap();
+
+        // Rewriting a file would also update when it was last modified
+        let untouched = std::time::SystemTime::UNIX_EPOCH;
+
+        File::options()
+            .write(true)
+            .open(out_path.join("same.txt"))
+            .and_then(|file| file.set_modified(untouched))
+            .unwrap();
+
+        let options = ExtractOptions {
+            skip_existing: true,
+            ..ExtractOptions::default()
+        };
+
+        extract_all_with(&archive_path, &out_path, options).unwrap();
+
+        let same = out_path.join("same.txt");
+        assert_eq!(fs::metadata(&same).unwrap().modified().unwrap(), untouched);
+        assert_eq!(fs::read(&same).unwrap(), b"unchanged");
+        assert_eq!(
+            fs::read(out_path.join("changed.txt")).unwrap(),
+            b"from archive"
+        );
+    }
 }
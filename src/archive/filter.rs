@@ -0,0 +1,131 @@
+use anyhow::{anyhow, Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use regex::Regex;
+use std::path::{Component, Path};
+
+/// Names of entries that operating systems leave behind in archives without anyone meaning to add them.
+const JUNK_NAMES: &[&str] = &["__MACOSX", ".DS_Store", "Thumbs.db"];
+
+/// Returns true if an entry with the given `name` is a dotfile or junk left behind by an operating system, which are
+/// the entries that get hidden when hidden entries are turned off.
+pub fn is_hidden(name: &str) -> bool {
+    name.starts_with('.') || JUNK_NAMES.contains(&name)
+}
+
+/// Returns true if the given `path` or any directory it's in is hidden by [`is_hidden`].
+pub fn is_hidden_path(path: &Path) -> bool {
+    path.components().any(|component| match component {
+        Component::Normal(name) => is_hidden(&name.to_string_lossy()),
+        _ => false,
+    })
+}
+
+/// Glob patterns that decide which files are extracted.
+///
+/// Patterns are matched against the path of each file relative to the entries being extracted,
+/// and a `*` never matches across a `/`. Use `**` to match any number of directories.
+pub struct PathFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl PathFilter {
+    /// Create a new `PathFilter` that only lets through files that match any of the `include` patterns
+    /// and none of the `exclude` patterns.
+    ///
+    /// If there are no `include` patterns, every file that isn't excluded is let through.
+    pub fn new<S>(include: &[S], exclude: &[S]) -> Result<Self>
+    where
+        S: AsRef<str>,
+    {
+        Ok(Self {
+            include: Self::build_set(include)?,
+            exclude: Self::build_set(exclude)?,
+        })
+    }
+
+    /// Parse a list of patterns separated by whitespace, where patterns that start with a `!` are excluded.
+    pub fn parse(patterns: &str) -> Result<Self> {
+        let (exclude, include): (Vec<_>, Vec<_>) = patterns
+            .split_whitespace()
+            .partition(|pattern| pattern.starts_with('!'));
+
+        let exclude = exclude
+            .iter()
+            .map(|pattern| &pattern[1..])
+            .collect::<Vec<_>>();
+
+        Self::new(&include, &exclude)
+    }
+
+    fn build_set<S>(patterns: &[S]) -> Result<Option<GlobSet>>
+    where
+        S: AsRef<str>,
+    {
+        if patterns.is_empty() {
+            return Ok(None);
+        }
+
+        let mut set = GlobSetBuilder::new();
+
+        for pattern in patterns {
+            let pattern = pattern.as_ref();
+
+            let glob = GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()
+                .with_context(|| anyhow!("invalid glob pattern: {}", pattern))?;
+
+            set.add(glob);
+        }
+
+        set.build()
+            .map(Some)
+            .context("failed to build glob patterns")
+    }
+
+    /// Returns true if the file at the given `path` should be extracted.
+    pub fn matches(&self, path: &Path) -> bool {
+        let included = match &self.include {
+            Some(include) => include.is_match(path),
+            None => true,
+        };
+
+        let excluded = match &self.exclude {
+            Some(exclude) => exclude.is_match(path),
+            None => false,
+        };
+
+        included && !excluded
+    }
+}
+
+/// A pattern that picks out entries by their path, which is a regular expression when it starts with `re:` and a
+/// list of glob patterns like a [`PathFilter`] takes otherwise.
+pub enum PathPattern {
+    Globs(PathFilter),
+    Regex(Regex),
+}
+
+impl PathPattern {
+    const REGEX_PREFIX: &'static str = "re:";
+
+    pub fn parse(pattern: &str) -> Result<Self> {
+        match pattern.strip_prefix(Self::REGEX_PREFIX) {
+            Some(regex) => Regex::new(regex)
+                .map(Self::Regex)
+                .with_context(|| anyhow!("invalid regular expression: {}", regex)),
+            None => PathFilter::parse(pattern).map(Self::Globs),
+        }
+    }
+
+    /// Returns true if the entry at the given `path` is picked out by the pattern.
+    ///
+    /// Regular expressions match anywhere in the path unless they're anchored with `^` or `$`.
+    pub fn matches(&self, path: &Path) -> bool {
+        match self {
+            Self::Globs(filter) => filter.matches(path),
+            Self::Regex(regex) => regex.is_match(&path.to_string_lossy()),
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use super::{Archive, ArchiveEntries, ArchiveEntry, EntryProperties, NodeID};
+use anyhow::{anyhow, Context, Result};
+use parking_lot::{Mutex, MutexGuard};
+use regex::Regex;
+use smallvec::SmallVec;
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+    sync::Arc,
+};
+use zip::ZipArchive;
+
+/// Searches the contents of entries in an archive for lines that match a pattern.
+pub struct Grepper {
+    archive: Arc<Archive>,
+    files: Arc<ArchiveEntries>,
+    base_nodes: SmallVec<[NodeID; 4]>,
+    pattern: Regex,
+    cancelled: AtomicBool,
+    finished: AtomicBool,
+    matches: Mutex<Vec<LineMatch>>,
+    pub searched: AtomicU32,
+    /// The number of files that were skipped for being binary, too large, or unreadable.
+    pub skipped: AtomicU32,
+    pub total_to_search: u32,
+}
+
+impl Grepper {
+    /// Files larger than this are skipped, since searching them would take too long to be useful.
+    pub const MAX_FILE_BYTES: u64 = 64 * 1024 * 1024;
+
+    /// Searching stops once this many lines have matched.
+    pub const MAX_MATCHES: usize = 10_000;
+
+    /// Files with a null byte this close to their start are treated as binary and skipped.
+    pub const BINARY_CHECK_BYTES: usize = 8000;
+
+    /// Lines are cut off at this many characters when they're recorded.
+    const MAX_LINE_CHARS: usize = 512;
+
+    const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+    pub fn prepare(
+        archive: Arc<Archive>,
+        base_nodes: SmallVec<[NodeID; 4]>,
+        pattern: Regex,
+    ) -> Self {
+        let files = archive.entries();
+
+        let total_to_search = files
+            .children_iter(&base_nodes)
+            .filter(|(_, entry, _)| Self::is_searchable(entry))
+            .count() as u32;
+
+        Self {
+            archive,
+            files,
+            base_nodes,
+            pattern,
+            cancelled: AtomicBool::new(false),
+            finished: AtomicBool::new(false),
+            matches: Mutex::new(Vec::new()),
+            searched: AtomicU32::new(0),
+            skipped: AtomicU32::new(0),
+            total_to_search,
+        }
+    }
+
+    #[inline(always)]
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    #[inline(always)]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Returns true once every file has been searched, or searching stopped early.
+    #[inline(always)]
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Acquire)
+    }
+
+    /// Returns the lines that have matched so far, in the order they were found.
+    #[inline(always)]
+    pub fn matches(&self) -> MutexGuard<Vec<LineMatch>> {
+        self.matches.lock()
+    }
+
+    /// Search every file for lines that match the pattern, recording each one as it's found.
+    pub fn grep(&self) -> Result<()> {
+        let result = self.grep_files();
+        self.finished.store(true, Ordering::Release);
+        result
+    }
+
+    fn grep_files(&self) -> Result<()> {
+        let mut archive = self.archive.open_reader()?;
+
+        let files = self
+            .files
+            .children_iter(&self.base_nodes)
+            .filter(|(_, entry, _)| Self::is_searchable(entry));
+
+        for (id, entry, path) in files {
+            if self.is_cancelled() || self.matches.lock().len() >= Self::MAX_MATCHES {
+                break;
+            }
+
+            let searched = match &entry.props {
+                EntryProperties::File(props) if props.raw_size_bytes > Self::MAX_FILE_BYTES => {
+                    false
+                }
+                // A single entry that can't be read shouldn't stop the rest from being searched
+                _ => self
+                    .grep_entry(&mut archive, id, entry, &path)
+                    .unwrap_or(false),
+            };
+
+            if !searched {
+                self.skipped.fetch_add(1, Ordering::Relaxed);
+            }
+
+            self.searched.fetch_add(1, Ordering::Relaxed);
+        }
+
+        Ok(())
+    }
+
+    /// Search the given `entry` line by line.
+    ///
+    /// Returns false if the entry looks like a binary file and was skipped.
+    fn grep_entry(
+        &self,
+        archive: &mut ZipArchive<File>,
+        id: NodeID,
+        entry: &ArchiveEntry,
+        path: &Path,
+    ) -> Result<bool> {
+        let file = archive
+            .by_index(entry.entry_num)
+            .with_context(|| anyhow!("failed to get {} from archive", entry.name))?;
+
+        let mut reader = BufReader::with_capacity(Self::READ_BUFFER_SIZE, file);
+
+        let start = reader
+            .fill_buf()
+            .with_context(|| anyhow!("failed to read {}", path.display()))?;
+
+        if is_binary(&start[..start.len().min(Self::BINARY_CHECK_BYTES)]) {
+            return Ok(false);
+        }
+
+        let mut line = Vec::new();
+        let mut line_num = 0;
+
+        loop {
+            line.clear();
+
+            let read = reader
+                .read_until(b'\n', &mut line)
+                .with_context(|| anyhow!("failed to read {}", path.display()))?;
+
+            if read == 0 || self.is_cancelled() {
+                break;
+            }
+
+            line_num += 1;
+
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim_end_matches(&['\r', '\n'][..]);
+
+            if !self.pattern.is_match(text) {
+                continue;
+            }
+
+            let mut matches = self.matches.lock();
+
+            if matches.len() >= Self::MAX_MATCHES {
+                break;
+            }
+
+            matches.push(LineMatch {
+                id,
+                path: path.to_path_buf(),
+                line_num,
+                line: text.chars().take(Self::MAX_LINE_CHARS).collect(),
+            });
+        }
+
+        Ok(true)
+    }
+
+    /// Returns true if the given `entry` has contents that can be searched.
+    fn is_searchable(entry: &ArchiveEntry) -> bool {
+        !entry.props.is_dir() && !entry.props.is_symlink()
+    }
+}
+
+/// A line in a file that matched the pattern being searched for.
+pub struct LineMatch {
+    pub id: NodeID,
+    pub path: PathBuf,
+    /// The number of the line in its file, starting from 1.
+    pub line_num: usize,
+    pub line: String,
+}
+
+/// Returns true if the given `bytes` from the start of a file look like they came from a binary file.
+///
+/// This is the same check grep uses, since text files practically never contain a null byte.
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes.contains(&0)
+}
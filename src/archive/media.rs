@@ -0,0 +1,1106 @@
+use crate::util::duration;
+use std::convert::TryInto;
+use std::time::Duration;
+
+/// Details about an audio, video, or image file that are read from the start of it.
+///
+/// Only the headers that are within the bytes given are read, so details that are stored further into a file, like
+/// the index at the end of some MP4 files, are left out.
+pub struct MediaInfo {
+    /// What kind of file it is, like `MP3 audio`.
+    pub kind: &'static str,
+    /// Each detail that could be read, as its name and value.
+    pub details: Vec<(&'static str, String)>,
+}
+
+impl MediaInfo {
+    /// Read the details of the file that starts with the given `content` and is `total_size` bytes long.
+    ///
+    /// Returns None if the file isn't a kind of media that can be read.
+    pub fn read(content: &[u8], total_size: u64) -> Option<Self> {
+        let mut info = match content {
+            [0xFF, 0xD8, 0xFF, ..] => Self::new("JPEG image").with_exif(content),
+            [b'f', b'L', b'a', b'C', ..] => Self::new("FLAC audio").with_flac(content),
+            [b'O', b'g', b'g', b'S', ..] => Self::ogg(content),
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E', ..] => {
+                Self::new("WAV audio").with_wav(content)
+            }
+            [0x1A, 0x45, 0xDF, 0xA3, ..] => Self::matroska(content),
+            [_, _, _, _, b'f', b't', b'y', b'p', ..] => Self::mp4(content),
+            _ => Self::mp3(content, total_size)?,
+        };
+
+        info.details.retain(|(_, value)| !value.is_empty());
+        Some(info)
+    }
+
+    fn new(kind: &'static str) -> Self {
+        Self {
+            kind,
+            details: Vec::new(),
+        }
+    }
+
+    fn add<S>(&mut self, name: &'static str, value: S)
+    where
+        S: Into<String>,
+    {
+        self.details.push((name, value.into()));
+    }
+
+    fn add_duration(&mut self, secs: f64) {
+        self.details.extend(duration_detail(secs));
+    }
+
+    fn add_audio_format(&mut self, sample_rate: u32, channels: u32) {
+        if sample_rate > 0 {
+            self.add("Sample rate", format!("{} Hz", sample_rate));
+        }
+
+        let channels = match channels {
+            0 => return,
+            1 => "Mono".into(),
+            2 => "Stereo".into(),
+            num => format!("{} channels", num),
+        };
+
+        self.add("Channels", channels);
+    }
+
+    /// Add the details in the EXIF data of a JPEG file, which is where cameras record how a photo was taken.
+    fn with_exif(mut self, content: &[u8]) -> Self {
+        let exif = match jpeg_exif(content) {
+            Some(exif) => exif,
+            None => return self,
+        };
+
+        let ifd0 = exif.ifd(exif.u32(4).unwrap_or(0) as usize);
+        let ascii = |ifd: &[Tag], tag| ifd.iter().find(|t| t.id == tag).and_then(|t| exif.ascii(t));
+
+        let camera = [ascii(&ifd0, 0x010F), ascii(&ifd0, 0x0110)]
+            .iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        // Most cameras repeat their make at the start of their model
+        let camera = match camera.as_slice() {
+            [make, model] if model.starts_with(make.as_str()) => model.clone(),
+            parts => parts.join(" "),
+        };
+
+        self.add("Camera", camera);
+
+        let sub_ifd = ifd0
+            .iter()
+            .find(|tag| tag.id == 0x8769)
+            .map(|tag| exif.ifd(tag.value as usize))
+            .unwrap_or_default();
+
+        let taken = ascii(&sub_ifd, 0x9003).or_else(|| ascii(&ifd0, 0x0132));
+        self.add("Taken", taken.unwrap_or_default());
+
+        for tag in &sub_ifd {
+            match tag.id {
+                0x829A => {
+                    if let Some((num, den)) = exif.rational(tag) {
+                        let exposure = if num > 0 && num < den {
+                            format!("1/{} s", (f64::from(den) / f64::from(num)).round())
+                        } else {
+                            format!("{} s", f64::from(num) / f64::from(den))
+                        };
+
+                        self.add("Exposure", exposure);
+                    }
+                }
+                0x829D => {
+                    if let Some((num, den)) = exif.rational(tag) {
+                        self.add(
+                            "Aperture",
+                            format!("f/{:.1}", f64::from(num) / f64::from(den)),
+                        );
+                    }
+                }
+                0x8827 => self.add("ISO", exif.short(tag).to_string()),
+                0x920A => {
+                    if let Some((num, den)) = exif.rational(tag) {
+                        self.add(
+                            "Focal length",
+                            format!("{} mm", f64::from(num) / f64::from(den)),
+                        );
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        self
+    }
+
+    /// Add the stream details and tags of a FLAC file, which are stored in blocks after its signature.
+    fn with_flac(mut self, content: &[u8]) -> Self {
+        let mut pos = 4;
+
+        while let Some(header) = content.get(pos..pos + 4) {
+            let is_last = header[0] & 0x80 != 0;
+            let len = be_uint(&header[1..4]) as usize;
+            let block = match content.get(pos + 4..pos + 4 + len) {
+                Some(block) => block,
+                None => break,
+            };
+
+            match header[0] & 0x7F {
+                // The stream info block packs its fields into bits instead of bytes
+                0 if block.len() >= 18 => {
+                    let packed = be_uint(&block[10..18]);
+                    let sample_rate = (packed >> 44) as u32;
+                    let channels = ((packed >> 41) & 0x7) as u32 + 1;
+                    let bit_depth = ((packed >> 36) & 0x1F) + 1;
+                    let total_samples = packed & 0xF_FFFF_FFFF;
+
+                    self.add("Codec", "FLAC");
+
+                    if sample_rate > 0 {
+                        self.add_duration(total_samples as f64 / f64::from(sample_rate));
+                    }
+
+                    self.add_audio_format(sample_rate, channels);
+                    self.add("Bit depth", format!("{} bits", bit_depth));
+                }
+                4 => self.add_vorbis_comments(block),
+                _ => (),
+            }
+
+            if is_last {
+                break;
+            }
+
+            pos += 4 + len;
+        }
+
+        self
+    }
+
+    /// Read the stream details and tags of an Ogg file from the first packets of its first stream.
+    ///
+    /// The length of an Ogg file is only stored in its last page, so its duration can't be read.
+    fn ogg(content: &[u8]) -> Self {
+        let vorbis = find(content, b"\x01vorbis");
+        let opus = find(content, b"OpusHead");
+
+        let mut info = match (vorbis, opus) {
+            (Some(pos), _) => {
+                let mut info = Self::new("Ogg audio");
+                let header = &content[pos..];
+                info.add("Codec", "Vorbis");
+
+                if let (Some(&channels), Some(sample_rate)) = (header.get(11), le_u32(header, 12)) {
+                    info.add_audio_format(sample_rate, channels.into());
+                }
+
+                info
+            }
+            (None, Some(pos)) => {
+                let mut info = Self::new("Ogg audio");
+                let header = &content[pos..];
+                info.add("Codec", "Opus");
+
+                if let (Some(&channels), Some(sample_rate)) = (header.get(9), le_u32(header, 12)) {
+                    info.add_audio_format(sample_rate, channels.into());
+                }
+
+                info
+            }
+            (None, None) => return Self::new("Ogg file"),
+        };
+
+        let comments = find(content, b"\x03vorbis")
+            .map(|pos| pos + 7)
+            .or_else(|| find(content, b"OpusTags").map(|pos| pos + 8));
+
+        if let Some(pos) = comments {
+            info.add_vorbis_comments(&content[pos..]);
+        }
+
+        info
+    }
+
+    /// Add the title, artist, album, and date from the given Vorbis comments, which FLAC and Ogg files store their
+    /// tags in.
+    fn add_vorbis_comments(&mut self, block: &[u8]) {
+        let vendor_len = le_u32(block, 0).unwrap_or(0) as usize;
+        let mut pos = 4 + vendor_len;
+        let count = le_u32(block, pos).unwrap_or(0);
+        pos += 4;
+
+        let mut tags = Tags::default();
+
+        for _ in 0..count {
+            let len = match le_u32(block, pos) {
+                Some(len) => len as usize,
+                None => break,
+            };
+
+            let comment = match block.get(pos + 4..pos + 4 + len) {
+                Some(comment) => String::from_utf8_lossy(comment),
+                None => break,
+            };
+
+            if let Some((name, value)) = comment.split_once('=') {
+                tags.set(&name.to_ascii_uppercase(), value);
+            }
+
+            pos += 4 + len;
+        }
+
+        tags.add_to(self);
+    }
+
+    /// Add the format and length of a WAV file from its format and data chunks.
+    fn with_wav(mut self, content: &[u8]) -> Self {
+        let mut pos = 12;
+        let mut byte_rate = 0;
+
+        while let Some(id) = content.get(pos..pos + 4) {
+            let len = le_u32(content, pos + 4).unwrap_or(0) as usize;
+            let chunk = &content[(pos + 8).min(content.len())..];
+
+            match id {
+                b"fmt " if chunk.len() >= 16 => {
+                    let codec = match le_u16(chunk, 0).unwrap_or(0) {
+                        0x0001 => "PCM",
+                        0x0003 => "PCM (floating point)",
+                        0x0006 => "A-law",
+                        0x0007 => "μ-law",
+                        0x0011 => "IMA ADPCM",
+                        0x0055 => "MP3",
+                        0xFFFE => "PCM (extensible)",
+                        _ => "Unknown",
+                    };
+
+                    let channels = le_u16(chunk, 2).unwrap_or(0);
+                    let sample_rate = le_u32(chunk, 4).unwrap_or(0);
+                    let bit_depth = le_u16(chunk, 14).unwrap_or(0);
+                    byte_rate = le_u32(chunk, 8).unwrap_or(0);
+
+                    self.add("Codec", codec);
+                    self.add_audio_format(sample_rate, channels.into());
+
+                    if bit_depth > 0 {
+                        self.add("Bit depth", format!("{} bits", bit_depth));
+                    }
+                }
+                // The data chunk holds the samples, so it's the last one worth reading
+                b"data" => {
+                    // The duration goes after the codec, like it does for every other kind of file
+                    if let Some(duration) = duration_detail(len as f64 / f64::from(byte_rate)) {
+                        self.details.insert(self.details.len().min(1), duration);
+                    }
+
+                    break;
+                }
+                _ => (),
+            }
+
+            pos += 8 + len + (len & 1);
+        }
+
+        self
+    }
+
+    /// Read the details of an MP4 or `QuickTime` file from its boxes, which nest inside of each other.
+    fn mp4(content: &[u8]) -> Self {
+        let brand = content.get(8..12).unwrap_or_default();
+
+        let mut info = match brand {
+            b"qt  " => Self::new("QuickTime video"),
+            b"M4A " | b"M4B " => Self::new("MPEG-4 audio"),
+            b"heic" | b"heix" | b"mif1" => Self::new("HEIF image"),
+            b"avif" => Self::new("AVIF image"),
+            _ => Self::new("MPEG-4 video"),
+        };
+
+        let moov = match mp4_box(content, &[b"moov"]) {
+            Some(moov) => moov,
+            None => return info,
+        };
+
+        if let Some(mvhd) = mp4_box(moov, &[b"mvhd"]) {
+            let (timescale, duration) = if mvhd.first() == Some(&1) {
+                (be_u32(mvhd, 20), mvhd.get(24..32).map(be_uint))
+            } else {
+                (be_u32(mvhd, 12), be_u32(mvhd, 16).map(u64::from))
+            };
+
+            if let (Some(timescale), Some(duration)) = (timescale, duration) {
+                if timescale > 0 {
+                    info.add_duration(duration as f64 / f64::from(timescale));
+                }
+            }
+        }
+
+        for trak in mp4_boxes(moov).filter(|(kind, _)| kind == b"trak") {
+            let trak = trak.1;
+            let handler = mp4_box(trak, &[b"mdia", b"hdlr"]).and_then(|hdlr| hdlr.get(8..12));
+
+            let codec = mp4_box(trak, &[b"mdia", b"minf", b"stbl", b"stsd"])
+                .and_then(|stsd| stsd.get(12..16))
+                .map(mp4_codec);
+
+            match (handler, codec) {
+                (Some(b"vide"), Some(codec)) => {
+                    info.add("Video codec", codec);
+
+                    if let Some(tkhd) = mp4_box(trak, &[b"tkhd"]) {
+                        let pos = if tkhd.first() == Some(&1) { 88 } else { 76 };
+
+                        // The width and height are fixed point numbers with 16 bits after the point
+                        if let (Some(width), Some(height)) =
+                            (be_u32(tkhd, pos), be_u32(tkhd, pos + 4))
+                        {
+                            info.add("Resolution", format!("{} x {}", width >> 16, height >> 16));
+                        }
+                    }
+                }
+                (Some(b"soun"), Some(codec)) => info.add("Audio codec", codec),
+                _ => (),
+            }
+        }
+
+        info
+    }
+
+    /// Read the details of a Matroska or `WebM` file from its segment info and tracks.
+    fn matroska(content: &[u8]) -> Self {
+        const EBML: u64 = 0x1A45_DFA3;
+        const DOC_TYPE: u64 = 0x4282;
+        const SEGMENT: u64 = 0x1853_8067;
+        const INFO: u64 = 0x1549_A966;
+        const TIMESTAMP_SCALE: u64 = 0x2A_D7B1;
+        const DURATION: u64 = 0x4489;
+        const TITLE: u64 = 0x7BA9;
+        const TRACKS: u64 = 0x1654_AE6B;
+        const TRACK_ENTRY: u64 = 0xAE;
+        const TRACK_TYPE: u64 = 0x83;
+        const CODEC_ID: u64 = 0x86;
+        const VIDEO: u64 = 0xE0;
+        const PIXEL_WIDTH: u64 = 0xB0;
+        const PIXEL_HEIGHT: u64 = 0xBA;
+
+        let elements = ebml_elements(content).collect::<Vec<_>>();
+
+        let doc_type = elements
+            .iter()
+            .find(|(id, _)| *id == EBML)
+            .and_then(|(_, header)| ebml_elements(header).find(|(id, _)| *id == DOC_TYPE))
+            .map(|(_, doc_type)| doc_type);
+
+        let mut info = if doc_type == Some(b"webm") {
+            Self::new("WebM video")
+        } else {
+            Self::new("Matroska video")
+        };
+
+        let segment = match elements.iter().find(|(id, _)| *id == SEGMENT) {
+            Some((_, segment)) => *segment,
+            None => return info,
+        };
+
+        // The title is shown after the details of the tracks, like the tags of other files
+        let mut title = String::new();
+
+        for (id, element) in ebml_elements(segment) {
+            match id {
+                INFO => {
+                    let mut scale = 1_000_000;
+                    let mut duration = None;
+
+                    for (id, value) in ebml_elements(element) {
+                        match id {
+                            TIMESTAMP_SCALE => scale = be_uint(value),
+                            DURATION => duration = be_float(value),
+                            TITLE => title = String::from_utf8_lossy(value).into_owned(),
+                            _ => (),
+                        }
+                    }
+
+                    if let Some(duration) = duration {
+                        info.add_duration(duration * scale as f64 / 1e9);
+                    }
+                }
+                TRACKS => {
+                    for (_, track) in ebml_elements(element).filter(|(id, _)| *id == TRACK_ENTRY) {
+                        let mut kind = 0;
+                        let mut codec = "";
+                        let mut size = None;
+
+                        for (id, value) in ebml_elements(track) {
+                            match id {
+                                TRACK_TYPE => kind = be_uint(value),
+                                CODEC_ID => codec = std::str::from_utf8(value).unwrap_or_default(),
+                                VIDEO => {
+                                    let dimension = |wanted| {
+                                        ebml_elements(value)
+                                            .find(|(id, _)| *id == wanted)
+                                            .map(|(_, value)| be_uint(value))
+                                    };
+
+                                    size = dimension(PIXEL_WIDTH).zip(dimension(PIXEL_HEIGHT));
+                                }
+                                _ => (),
+                            }
+                        }
+
+                        match kind {
+                            1 => {
+                                info.add("Video codec", matroska_codec(codec));
+
+                                if let Some((width, height)) = size {
+                                    info.add("Resolution", format!("{} x {}", width, height));
+                                }
+                            }
+                            2 => info.add("Audio codec", matroska_codec(codec)),
+                            _ => (),
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        info.add("Title", title);
+        info
+    }
+
+    /// Read the details of an MP3 file from its ID3 tag and the header of its first frame.
+    ///
+    /// Returns None if the content doesn't start with either of them.
+    fn mp3(content: &[u8], total_size: u64) -> Option<Self> {
+        let mut info = Self::new("MP3 audio");
+
+        let (tag_len, tags) = match content {
+            [b'I', b'D', b'3', ..] => Self::id3(content),
+            _ => (0, Tags::default()),
+        };
+
+        let frame = content.get(tag_len..).and_then(MpegFrame::parse);
+
+        let frame = match frame {
+            Some(frame) => frame,
+            // Without a tag, anything could look like it starts with a frame header by chance
+            None if tag_len > 0 => {
+                tags.add_to(&mut info);
+                return Some(info);
+            }
+            None => return None,
+        };
+
+        if tag_len == 0 {
+            // Another frame header right after the first one makes it much less likely to be a coincidence
+            let next = content.get(frame.len..).and_then(MpegFrame::parse);
+
+            if next.is_none() && content.len() > frame.len {
+                return None;
+            }
+        }
+
+        if frame.layer != 3 {
+            info.kind = "MPEG audio";
+        }
+
+        let audio_bytes = total_size.saturating_sub(tag_len as u64);
+
+        // Files with a variable bitrate record how many frames they have in their first one, since their length
+        // can't be worked out from their size
+        let secs = match frame.frame_count(&content[tag_len..]) {
+            Some(frames) => {
+                f64::from(frames) * f64::from(frame.samples) / f64::from(frame.sample_rate)
+            }
+            None => audio_bytes as f64 * 8.0 / f64::from(frame.bitrate * 1000),
+        };
+
+        info.add("Codec", frame.codec());
+        info.add_duration(secs);
+        info.add("Bitrate", format!("{} kbps", frame.bitrate));
+        info.add_audio_format(frame.sample_rate, frame.channels);
+        tags.add_to(&mut info);
+
+        Some(info)
+    }
+
+    /// Read the title, artist, album, and date from the `ID3v2` tag at the start of the given `content`, along with
+    /// how many bytes the tag takes up.
+    fn id3(content: &[u8]) -> (usize, Tags) {
+        let mut tags = Tags::default();
+
+        let header = match content.get(..10) {
+            Some(header) => header,
+            None => return (0, tags),
+        };
+
+        let version = header[3];
+        let len = 10 + syncsafe(&header[6..10]) as usize;
+        let body = &content[10..len.min(content.len())];
+
+        // ID3v2.2 uses three letter frame IDs, and is rare enough to not be worth reading
+        if version < 3 {
+            return (len, tags);
+        }
+
+        let mut pos = 0;
+
+        while let Some(frame) = body.get(pos..pos + 10) {
+            let id = &frame[..4];
+
+            if id[0] == 0 {
+                break;
+            }
+
+            let size = if version >= 4 {
+                syncsafe(&frame[4..8]) as usize
+            } else {
+                be_uint(&frame[4..8]) as usize
+            };
+
+            let value = match body.get(pos + 10..pos + 10 + size) {
+                Some(value) => value,
+                None => break,
+            };
+
+            let name = match id {
+                b"TIT2" => "TITLE",
+                b"TPE1" => "ARTIST",
+                b"TALB" => "ALBUM",
+                b"TDRC" | b"TYER" => "DATE",
+                _ => "",
+            };
+
+            if !name.is_empty() {
+                tags.set(name, &id3_text(value));
+            }
+
+            pos += 10 + size;
+        }
+
+        (len, tags)
+    }
+}
+
+/// The tags that are shown from an audio or video file.
+#[derive(Default)]
+struct Tags {
+    title: String,
+    artist: String,
+    album: String,
+    date: String,
+}
+
+impl Tags {
+    /// Set the tag with the given `name` as it's named in Vorbis comments, ignoring ones that aren't shown.
+    fn set(&mut self, name: &str, value: &str) {
+        let tag = match name {
+            "TITLE" => &mut self.title,
+            "ARTIST" => &mut self.artist,
+            "ALBUM" => &mut self.album,
+            "DATE" => &mut self.date,
+            _ => return,
+        };
+
+        // Only the first of a tag that's repeated is shown
+        if tag.is_empty() {
+            *tag = value.trim().into();
+        }
+    }
+
+    fn add_to(self, info: &mut MediaInfo) {
+        info.add("Title", self.title);
+        info.add("Artist", self.artist);
+        info.add("Album", self.album);
+        info.add("Date", self.date);
+    }
+}
+
+/// The header of a frame of MPEG audio.
+struct MpegFrame {
+    /// 1 for MPEG-1, 2 for MPEG-2, and 25 for MPEG-2.5.
+    version: u8,
+    layer: u8,
+    /// The bitrate in kilobits per second.
+    bitrate: u32,
+    sample_rate: u32,
+    channels: u32,
+    /// How many samples the frame holds.
+    samples: u32,
+    /// How many bytes the frame takes up, including its header.
+    len: usize,
+}
+
+impl MpegFrame {
+    /// The bitrate of each index in kilobits per second, for each layer of MPEG-1 and then layer I and layers II and
+    /// III of MPEG-2 and 2.5.
+    const BITRATES: [[u32; 15]; 5] = [
+        [
+            0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448,
+        ],
+        [
+            0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384,
+        ],
+        [
+            0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+        ],
+        [
+            0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256,
+        ],
+        [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+    ];
+
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        let header = be_u32(bytes, 0)?;
+
+        if header >> 21 != 0x7FF {
+            return None;
+        }
+
+        let version = match (header >> 19) & 0x3 {
+            0 => 25,
+            2 => 2,
+            3 => 1,
+            _ => return None,
+        };
+
+        let layer = match (header >> 17) & 0x3 {
+            1 => 3,
+            2 => 2,
+            3 => 1,
+            _ => return None,
+        };
+
+        let bitrate_index = ((header >> 12) & 0xF) as usize;
+        let rate_index = ((header >> 10) & 0x3) as usize;
+        let padding = (header >> 9) & 0x1;
+        let channels = if (header >> 6) & 0x3 == 3 { 1 } else { 2 };
+
+        let table = match (version, layer) {
+            (1, layer) => layer as usize - 1,
+            (_, 1) => 3,
+            _ => 4,
+        };
+
+        let bitrate = *Self::BITRATES[table].get(bitrate_index)?;
+
+        let sample_rate = [44_100, 48_000, 32_000].get(rate_index)?
+            / match version {
+                1 => 1,
+                2 => 2,
+                _ => 4,
+            };
+
+        // Frames with no bitrate use one that isn't written anywhere
+        if bitrate == 0 {
+            return None;
+        }
+
+        let samples = match (version, layer) {
+            (_, 1) => 384,
+            (1, _) | (_, 2) => 1152,
+            _ => 576,
+        };
+
+        let len = if layer == 1 {
+            (12 * bitrate * 1000 / sample_rate + padding) * 4
+        } else {
+            samples / 8 * bitrate * 1000 / sample_rate + padding
+        } as usize;
+
+        Some(Self {
+            version,
+            layer,
+            bitrate,
+            sample_rate,
+            channels,
+            samples,
+            len,
+        })
+    }
+
+    /// Returns how many frames the file has if it's recorded in this frame, which is the given `bytes`.
+    fn frame_count(&self, bytes: &[u8]) -> Option<u32> {
+        let side_info = match (self.version, self.channels) {
+            (1, 1) => 17,
+            (1, _) => 32,
+            (_, 1) => 9,
+            _ => 17,
+        };
+
+        let xing = 4 + side_info;
+
+        match bytes.get(xing..xing + 4)? {
+            b"Xing" | b"Info" if be_u32(bytes, xing + 4)? & 0x1 != 0 => be_u32(bytes, xing + 8),
+            _ => match bytes.get(36..40)? {
+                b"VBRI" => be_u32(bytes, 36 + 14),
+                _ => None,
+            },
+        }
+    }
+
+    fn codec(&self) -> String {
+        let version = match self.version {
+            1 => "1",
+            2 => "2",
+            _ => "2.5",
+        };
+
+        format!("MPEG-{} Layer {}", version, "I".repeat(self.layer.into()))
+    }
+}
+
+/// The TIFF structure EXIF data is stored in, which can be in either byte order.
+struct Exif<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+/// A field of an EXIF directory.
+struct Tag {
+    id: u16,
+    kind: u16,
+    count: u32,
+    /// The value itself if it fits in four bytes, or where it's stored otherwise.
+    value: u32,
+    /// Where the value is stored if it fits in four bytes.
+    pos: usize,
+}
+
+impl<'a> Exif<'a> {
+    fn u16(&self, pos: usize) -> Option<u16> {
+        let bytes = self.data.get(pos..pos + 2)?.try_into().ok()?;
+
+        Some(if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, pos: usize) -> Option<u32> {
+        let bytes = self.data.get(pos..pos + 4)?.try_into().ok()?;
+
+        Some(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    /// Read the tags of the directory that starts at the given `pos`.
+    fn ifd(&self, pos: usize) -> Vec<Tag> {
+        let count = self.u16(pos).unwrap_or(0) as usize;
+
+        (0..count)
+            .filter_map(|i| {
+                let entry = pos + 2 + i * 12;
+
+                Some(Tag {
+                    id: self.u16(entry)?,
+                    kind: self.u16(entry + 2)?,
+                    count: self.u32(entry + 4)?,
+                    value: self.u32(entry + 8)?,
+                    pos: entry + 8,
+                })
+            })
+            .collect()
+    }
+
+    fn ascii(&self, tag: &Tag) -> Option<String> {
+        if tag.kind != 2 {
+            return None;
+        }
+
+        let len = tag.count as usize;
+        let start = if len <= 4 {
+            tag.pos
+        } else {
+            tag.value as usize
+        };
+        let bytes = self.data.get(start..start + len)?;
+        let text = String::from_utf8_lossy(bytes);
+
+        Some(text.trim_end_matches('\0').trim().into())
+    }
+
+    fn short(&self, tag: &Tag) -> u16 {
+        self.u16(tag.pos).unwrap_or(0)
+    }
+
+    fn rational(&self, tag: &Tag) -> Option<(u32, u32)> {
+        let pos = tag.value as usize;
+        let (num, den) = (self.u32(pos)?, self.u32(pos + 4)?);
+
+        if tag.kind == 5 && den > 0 {
+            Some((num, den))
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the duration of a file that's `secs` long as a detail, unless it's too short or too long to be real.
+fn duration_detail(secs: f64) -> Option<(&'static str, String)> {
+    // The duration comes straight from the file, so a broken or crafted one can be anything
+    const MAX_SECS: f64 = 366.0 * 24.0 * 60.0 * 60.0;
+
+    if !(1.0..=MAX_SECS).contains(&secs) {
+        return None;
+    }
+
+    let duration = Duration::try_from_secs_f64(secs).ok()?;
+    Some(("Duration", duration::formatted_clock(duration)))
+}
+
+/// Returns the EXIF data in the APP1 segment of a JPEG file, which comes before the image itself.
+fn jpeg_exif(content: &[u8]) -> Option<Exif> {
+    let mut pos = 2;
+
+    loop {
+        let marker = content.get(pos..pos + 2)?;
+
+        // The start of the image data is where the metadata segments end
+        if marker[0] != 0xFF || marker[1] == 0xDA {
+            return None;
+        }
+
+        let len = be_u16(content, pos + 2)? as usize;
+        let segment = content.get(pos + 4..pos + 2 + len)?;
+
+        if marker[1] == 0xE1 && segment.starts_with(b"Exif\0\0") {
+            let data = &segment[6..];
+
+            return match data.get(..2)? {
+                b"II" => Some(Exif {
+                    data,
+                    little_endian: true,
+                }),
+                b"MM" => Some(Exif {
+                    data,
+                    little_endian: false,
+                }),
+                _ => None,
+            };
+        }
+
+        pos += 2 + len;
+    }
+}
+
+/// Returns the content of the box at the given `path` of box types inside of the given `content`.
+fn mp4_box<'a>(content: &'a [u8], path: &[&[u8; 4]]) -> Option<&'a [u8]> {
+    let (first, rest) = path.split_first()?;
+    let (_, found) = mp4_boxes(content).find(|(kind, _)| kind == *first)?;
+
+    if rest.is_empty() {
+        Some(found)
+    } else {
+        mp4_box(found, rest)
+    }
+}
+
+/// Returns the type and content of each box in the given `content`, stopping at the first one that runs past the end.
+fn mp4_boxes(content: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    let mut pos = 0;
+
+    std::iter::from_fn(move || {
+        let size = be_u32(content, pos)? as usize;
+        let kind: [u8; 4] = content.get(pos + 4..pos + 8)?.try_into().ok()?;
+
+        let (header_len, size) = match size {
+            0 => (8, content.len() - pos),
+            1 => (16, be_uint(content.get(pos + 8..pos + 16)?) as usize),
+            size => (8, size),
+        };
+
+        let body = content.get(pos + header_len..pos.checked_add(size)?)?;
+        pos += size;
+
+        Some((kind, body))
+    })
+}
+
+fn mp4_codec(fourcc: &[u8]) -> String {
+    let name = match fourcc {
+        b"avc1" | b"avc3" => "H.264",
+        b"hev1" | b"hvc1" => "H.265",
+        b"av01" => "AV1",
+        b"vp09" => "VP9",
+        b"mp4v" => "MPEG-4 Part 2",
+        b"apcn" | b"apch" | b"apcs" | b"apco" | b"ap4h" => "ProRes",
+        b"mp4a" => "AAC",
+        b"ac-3" => "AC-3",
+        b"ec-3" => "E-AC-3",
+        b"Opus" => "Opus",
+        b"alac" => "ALAC",
+        b"fLaC" => "FLAC",
+        b".mp3" => "MP3",
+        other => return String::from_utf8_lossy(other).trim().into(),
+    };
+
+    name.into()
+}
+
+fn matroska_codec(id: &str) -> String {
+    let name = match id {
+        "V_MPEG4/ISO/AVC" => "H.264",
+        "V_MPEGH/ISO/HEVC" => "H.265",
+        "V_AV1" => "AV1",
+        "V_VP8" => "VP8",
+        "V_VP9" => "VP9",
+        "A_AAC" => "AAC",
+        "A_OPUS" => "Opus",
+        "A_VORBIS" => "Vorbis",
+        "A_FLAC" => "FLAC",
+        "A_AC3" => "AC-3",
+        "A_EAC3" => "E-AC-3",
+        "A_MPEG/L3" => "MP3",
+        other => other
+            .strip_prefix("V_")
+            .or_else(|| other.strip_prefix("A_"))
+            .unwrap_or(other),
+    };
+
+    name.into()
+}
+
+/// Returns the ID and content of each element in the given EBML `content`, which Matroska files are made of.
+///
+/// Elements that run past the end of the content are cut short, since the file is usually only read in part.
+fn ebml_elements(content: &[u8]) -> impl Iterator<Item = (u64, &[u8])> {
+    let mut pos = 0;
+
+    std::iter::from_fn(move || {
+        let (id, id_len) = ebml_vint(content.get(pos..)?, false)?;
+        let (size, size_len) = ebml_vint(content.get(pos + id_len..)?, true)?;
+
+        let start = pos + id_len + size_len;
+        let end = start.saturating_add(size as usize).min(content.len());
+        pos = end;
+
+        Some((id, content.get(start..end)?))
+    })
+}
+
+/// Read a variable length integer from the start of the given `bytes`, returning it and how many bytes it took up.
+///
+/// IDs keep the bits that mark their length, while sizes have them removed.
+fn ebml_vint(bytes: &[u8], is_size: bool) -> Option<(u64, usize)> {
+    let first = *bytes.first()?;
+
+    if first == 0 {
+        return None;
+    }
+
+    let len = first.leading_zeros() as usize + 1;
+    let bytes = bytes.get(..len)?;
+    let value = be_uint(bytes);
+
+    if !is_size {
+        return Some((value, len));
+    }
+
+    let value = value & ((1 << (7 * len)) - 1);
+
+    // A size with every bit set means it isn't known, so the element lasts until the end
+    if value == (1 << (7 * len)) - 1 {
+        Some((u64::MAX, len))
+    } else {
+        Some((value, len))
+    }
+}
+
+/// Read the text of an ID3 frame, which starts with a byte that says how it's encoded.
+fn id3_text(value: &[u8]) -> String {
+    let (encoding, text) = match value.split_first() {
+        Some(split) => split,
+        None => return String::new(),
+    };
+
+    let text = match encoding {
+        0 => text.iter().map(|&byte| char::from(byte)).collect(),
+        1 | 2 => {
+            let (little_endian, text) = match text {
+                [0xFF, 0xFE, rest @ ..] => (true, rest),
+                [0xFE, 0xFF, rest @ ..] => (false, rest),
+                _ => (*encoding == 1, text),
+            };
+
+            let units = text
+                .chunks_exact(2)
+                .map(|pair| {
+                    if little_endian {
+                        u16::from_le_bytes([pair[0], pair[1]])
+                    } else {
+                        u16::from_be_bytes([pair[0], pair[1]])
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::from_utf8_lossy(text).into_owned(),
+    };
+
+    // Frames can hold several values separated by null characters, but only the first one is shown
+    text.split('\0').next().unwrap_or_default().into()
+}
+
+/// Returns the position of the first occurrence of `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Read a number that's stored with seven bits in each byte so it can't be mistaken for a frame sync.
+fn syncsafe(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(0, |value, &byte| (value << 7) | u32::from(byte & 0x7F))
+}
+
+/// Read a big endian number of up to eight bytes.
+fn be_uint(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .take(8)
+        .fold(0, |value, &byte| (value << 8) | u64::from(byte))
+}
+
+fn be_float(bytes: &[u8]) -> Option<f64> {
+    match bytes.len() {
+        4 => Some(f32::from_bits(be_uint(bytes) as u32).into()),
+        8 => Some(f64::from_bits(be_uint(bytes))),
+        _ => None,
+    }
+}
+
+fn be_u16(bytes: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        bytes.get(pos..pos + 2)?.try_into().ok()?,
+    ))
+}
+
+fn be_u32(bytes: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        bytes.get(pos..pos + 4)?.try_into().ok()?,
+    ))
+}
+
+fn le_u16(bytes: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(pos..pos + 2)?.try_into().ok()?,
+    ))
+}
+
+fn le_u32(bytes: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(pos..pos + 4)?.try_into().ok()?,
+    ))
+}
@@ -1,19 +1,38 @@
+pub mod compare;
+pub mod document;
+pub mod duplicates;
 pub mod extract;
+pub mod filter;
+pub mod grep;
+pub mod media;
 pub mod mount;
+pub mod nested;
+pub mod normalize;
+pub mod query;
+pub mod rename;
 
+use crate::util::file_type;
 use anyhow::{anyhow, Context, Result};
 use chardetng::EncodingDetector;
 use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use encoding_rs::Encoding;
-use parking_lot::Mutex;
+use parking_lot::RwLock;
+use smallvec::SmallVec;
 use std::{
     borrow::Cow,
+    collections::{HashMap, HashSet},
+    ffi::OsStr,
     ops::{Deref, Index},
     path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+    sync::Arc,
 };
 use std::{fs::File, time::SystemTime};
-use std::{io::Read, io::Seek, path::Path};
-use zip::{read::ZipFile, ZipArchive};
+use std::{
+    io::{self, Read, Seek, Write},
+    path::Path,
+};
+use zip::{read::ZipFile, result::ZipError, CompressionMethod, ZipArchive};
 
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
 pub struct NodeID(u32);
@@ -33,10 +52,14 @@ impl Deref for NodeID {
     }
 }
 
+/// An archive whose entries can be read from any thread.
+///
+/// The entries are kept behind a lock that is only held long enough to clone or replace a reference
+/// to them. Background tasks should grab a snapshot with [`Archive::entries`] and use it for the
+/// rest of their work so they always see a consistent tree, even if it's replaced in the meantime.
 pub struct Archive {
-    inner: Mutex<ZipArchive<File>>,
-    pub files: ArchiveEntries,
-    pub total_size_bytes: u64,
+    path: PathBuf,
+    files: RwLock<Arc<ArchiveEntries>>,
 }
 
 impl Archive {
@@ -44,82 +67,305 @@ impl Archive {
     where
         P: AsRef<Path>,
     {
-        let file = File::open(path).context("failed to open archive")?;
-        let mut archive = ZipArchive::new(file).context("failed to parse archive")?;
-        let (files, total_size_bytes) = ArchiveEntries::read(&mut archive)?;
+        Self::read_with_progress(path, &ReadProgress::default())
+    }
+
+    /// Read the archive at the given `path` while keeping `progress` up to date, so it can be shown from another
+    /// thread while a large archive is being read.
+    pub fn read_with_progress<P>(path: P, progress: &ReadProgress) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+        let files = Self::read_entries(&path, progress)?;
 
         Ok(Self {
-            inner: Mutex::new(archive),
-            files,
-            total_size_bytes,
+            path,
+            files: RwLock::new(Arc::new(files)),
         })
     }
-}
 
-impl Index<NodeID> for Archive {
-    type Output = ArchiveEntry;
+    fn read_entries(path: &Path, progress: &ReadProgress) -> Result<ArchiveEntries> {
+        let mut archive = Self::open_zip(path)?;
+        ArchiveEntries::read(&mut archive, progress)
+    }
 
-    fn index(&self, index: NodeID) -> &Self::Output {
-        &self.files[index]
+    fn open_zip(path: &Path) -> Result<ZipArchive<File>> {
+        let file = File::open(path).context("failed to open archive")?;
+        ZipArchive::new(file).context("failed to parse archive")
+    }
+
+    /// Returns the path of the archive's file.
+    #[inline(always)]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the name of the archive's file.
+    pub fn file_name(&self) -> Option<&OsStr> {
+        self.path.file_name()
+    }
+
+    /// Returns the name of the archive's file without its extension.
+    pub fn file_stem(&self) -> Option<&OsStr> {
+        self.path.file_stem()
+    }
+
+    /// Open a new handle to the archive that can be read from independently of any other handle.
+    pub fn open_reader(&self) -> Result<ZipArchive<File>> {
+        Self::open_zip(&self.path)
+    }
+
+    /// Returns a snapshot of the archive's current entries.
+    ///
+    /// The snapshot will not reflect any changes made to the archive after this is called.
+    #[inline(always)]
+    pub fn entries(&self) -> Arc<ArchiveEntries> {
+        Arc::clone(&self.files.read())
+    }
+
+    /// Decompress the file at the given `path` inside the archive and write its content to `writer`.
+    pub fn write_file<W>(&self, path: &str, writer: &mut W) -> Result<()>
+    where
+        W: Write,
+    {
+        let files = self.entries();
+
+        let id = files
+            .find_path(path)
+            .ok_or_else(|| anyhow!("{} does not exist in the archive", path))?;
+
+        match self.write_entry(&files[id], writer) {
+            Ok(()) => Ok(()),
+            // The reader on the other end of a pipe is allowed to stop early, like when a pager is closed
+            Err(err) if is_broken_pipe(&err) => Ok(()),
+            Err(err) => Err(err),
+        }
     }
+
+    /// Decompress the given file `entry` and write its content to `writer`.
+    pub fn write_entry<W>(&self, entry: &ArchiveEntry, writer: &mut W) -> Result<()>
+    where
+        W: Write,
+    {
+        if entry.props.is_dir() {
+            return Err(anyhow!("{} is a directory", entry.name));
+        }
+
+        let mut archive = self.open_reader()?;
+
+        let mut file = archive
+            .by_index(entry.entry_num)
+            .with_context(|| anyhow!("failed to get {} from archive", entry.name))?;
+
+        io::copy(&mut file, writer).with_context(|| anyhow!("failed to write {}", entry.name))?;
+        Ok(())
+    }
+
+    /// Decompress up to `max_bytes` from the start of the given file `entry`.
+    pub fn read_start(&self, entry: &ArchiveEntry, max_bytes: u64) -> Result<Vec<u8>> {
+        if entry.props.is_dir() {
+            return Err(anyhow!("{} is a directory", entry.name));
+        }
+
+        let mut archive = self.open_reader()?;
+
+        let file = archive
+            .by_index(entry.entry_num)
+            .with_context(|| anyhow!("failed to get {} from archive", entry.name))?;
+
+        let mut content = Vec::new();
+
+        file.take(max_bytes)
+            .read_to_end(&mut content)
+            .with_context(|| anyhow!("failed to read {}", entry.name))?;
+
+        Ok(content)
+    }
+
+    /// Read the details of the given file `entry` that aren't kept with it, since browsing doesn't need them.
+    pub fn file_details(&self, entry: &ArchiveEntry) -> Result<FileDetails> {
+        if entry.props.is_dir() {
+            return Err(anyhow!("{} is a directory", entry.name));
+        }
+
+        let mut archive = self.open_reader()?;
+        let encrypted = matches!(&entry.props, EntryProperties::File(props) if props.encrypted);
+
+        let file = if encrypted {
+            open_encrypted(&mut archive, entry.entry_num)
+        } else {
+            archive.by_index(entry.entry_num).map_err(Into::into)
+        }
+        .with_context(|| anyhow!("failed to get {} from archive", entry.name))?;
+
+        Ok(FileDetails {
+            compression: file.compression(),
+            comment: file.comment().to_string(),
+        })
+    }
+
+    /// Read the archive's entries from disk again.
+    ///
+    /// Existing snapshots are left untouched, but their node ID's should not be used with new snapshots.
+    pub fn reload(&self) -> Result<()> {
+        let files = Self::read_entries(&self.path, &ReadProgress::default())?;
+        *self.files.write() = Arc::new(files);
+        Ok(())
+    }
+}
+
+/// How far along reading the entries of an archive is.
+#[derive(Default)]
+pub struct ReadProgress {
+    /// The number of files in the archive that have been read so far.
+    pub read: AtomicUsize,
+    /// The number of files in the archive, which stays at zero until its index has been parsed.
+    pub total: AtomicUsize,
 }
 
-pub struct ArchiveEntries(Vec<ArchiveEntry>);
+pub struct ArchiveEntries {
+    entries: Vec<ArchiveEntry>,
+    pub total_size_bytes: u64,
+    /// The number of files that need a password to be read.
+    pub num_encrypted: usize,
+}
 
 impl ArchiveEntries {
     fn new(capacity: usize) -> Self {
         let mut entries = Vec::with_capacity(1 + capacity);
         entries.push(ArchiveEntry::root());
 
-        Self(entries)
+        Self {
+            entries,
+            total_size_bytes: 0,
+            num_encrypted: 0,
+        }
     }
 
     fn push_entry(&mut self, node: ArchiveEntry) -> NodeID {
         let next = NodeID(self.len() as u32);
-        self.0.push(node);
+        self.entries.push(node);
         next
     }
 
     // TODO: make generic over archive type
-    fn read<R>(archive: &mut ZipArchive<R>) -> Result<(Self, u64)>
+    fn read<R>(archive: &mut ZipArchive<R>, progress: &ReadProgress) -> Result<Self>
     where
         R: Read + Seek,
     {
         let mut entries = Self::new(archive.len());
-        let mut total_size_bytes = 0;
+        let mut dir_names: HashMap<NodeID, DirNames> = HashMap::new();
+
+        progress.total.store(archive.len(), Ordering::Relaxed);
 
         for i in 0..archive.len() {
-            let file = archive
-                .by_index(i)
-                .with_context(|| anyhow!("failed to get archive file at index {}", i))?;
+            progress.read.store(i, Ordering::Relaxed);
+
+            match archive.by_index(i) {
+                Ok(mut file) => {
+                    entries.add_file(&mut file, i, false, &mut dir_names);
+                    continue;
+                }
+                Err(err) if is_password_required(&err) => (),
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| anyhow!("failed to get archive file at index {}", i))
+                }
+            }
 
-            let (path, encoding) = Self::decode_filename(file.name_raw());
+            // Encrypted files can only be opened once the archive is no longer borrowed by the failed attempt
+            let mut file = open_encrypted(archive, i)?;
+            entries.add_file(&mut file, i, true, &mut dir_names);
+        }
 
-            let mut cur_node = NodeID::first();
+        progress.read.store(archive.len(), Ordering::Relaxed);
 
-            for component in path.split_terminator('/') {
-                let existing_pos = entries[cur_node]
-                    .children
-                    .iter()
-                    .find(|&&id| entries[id].name == component)
-                    .cloned();
+        entries.sum_sizes();
+        Ok(entries)
+    }
 
-                let next_node_pos = existing_pos.unwrap_or_else(|| {
-                    let mut entry = ArchiveEntry::from_path(component, i, &path, encoding, &file);
-                    entry.parent = Some(cur_node);
+    /// Add an entry for the given `file` and every directory in its path that hasn't been added yet.
+    fn add_file(
+        &mut self,
+        file: &mut ZipFile,
+        index: usize,
+        encrypted: bool,
+        dir_names: &mut HashMap<NodeID, DirNames>,
+    ) {
+        let (path, encoding) = Self::decode_filename(file.name_raw());
+        // The name is borrowed from the file, which has to be read from if it's a symlink
+        let path = path.into_owned();
+        let components = path.split_terminator('/').collect::<SmallVec<[_; 8]>>();
+
+        let mut cur_node = NodeID::first();
+
+        for (depth, component) in components.iter().enumerate() {
+            let is_file = depth + 1 == components.len() && !path.ends_with('/');
+            let name = normalize::compose(component);
+            let names = dir_names.entry(cur_node).or_default();
+
+            // Every entry inside of a directory shares its node, but files never do
+            let existing = if is_file {
+                None
+            } else {
+                names.dirs.get(name.as_ref()).copied()
+            };
+
+            cur_node = if let Some(existing) = existing {
+                existing
+            } else {
+                let unique_name = names.unique(&name, is_file);
+                let mut entry = ArchiveEntry::from_file(
+                    unique_name.clone(),
+                    index,
+                    is_file,
+                    encoding,
+                    file,
+                    encrypted,
+                );
+                entry.parent = Some(cur_node);
+
+                // Directories that are only implied by a file's path would otherwise count its size again
+                if let EntryProperties::File(props) = &entry.props {
+                    self.total_size_bytes += props.raw_size_bytes;
+
+                    if props.encrypted {
+                        self.num_encrypted += 1;
+                    }
+                }
+
+                let id = self.push_entry(entry);
+                self.entries[*cur_node as usize].children.push(id);
+
+                if !is_file {
+                    names.dirs.insert(name.into_owned(), id);
+                }
+
+                names.taken.insert(unique_name);
+                id
+            };
+        }
+    }
 
-                    let id = entries.push_entry(entry);
-                    entries.0[*cur_node as usize].children.push(id);
+    /// Fill in the total size of every entry.
+    ///
+    /// Entries are always added after their parent, so going backwards adds every entry to its parent's total
+    /// after the entry's own total is complete.
+    fn sum_sizes(&mut self) {
+        for i in (0..self.entries.len()).rev() {
+            let entry = &mut self.entries[i];
+
+            if let EntryProperties::File(props) = &entry.props {
+                entry.total_size_bytes = props.raw_size_bytes;
+            }
 
-                    total_size_bytes += file.size();
-                    id
-                });
+            let (size, parent) = (entry.total_size_bytes, entry.parent);
 
-                cur_node = next_node_pos;
+            if let Some(parent) = parent {
+                self.entries[*parent as usize].total_size_bytes += size;
             }
         }
-
-        Ok((entries, total_size_bytes))
     }
 
     fn decode_filename(bytes: &[u8]) -> (Cow<str>, &'static Encoding) {
@@ -132,13 +378,96 @@ impl ArchiveEntries {
     }
 
     pub fn create_node_id(&self, id: u32) -> Option<NodeID> {
-        if id < *NodeID::first() || id > self.len() as u32 {
+        if id < *NodeID::first() || id >= self.len() as u32 {
             return None;
         }
 
         Some(NodeID(id))
     }
 
+    /// Returns the ID of the entry at the given `path`, which should be relative to the root of the archive.
+    ///
+    /// The `path` is normalized the same way as the names of entries, so it doesn't matter how its accents are encoded.
+    pub fn find_path(&self, path: &str) -> Option<NodeID> {
+        path.split('/')
+            .filter(|component| !component.is_empty())
+            .try_fold(NodeID::first(), |cur_node, component| {
+                let component = normalize::compose(component);
+
+                self[cur_node]
+                    .children
+                    .iter()
+                    .find(|&&id| self[id].name == component)
+                    .copied()
+            })
+    }
+
+    /// Returns the full path of the given `id` from the root of the archive.
+    pub fn path_of(&self, id: NodeID) -> PathBuf {
+        let mut names = Vec::new();
+        let mut cur_node = Some(id);
+
+        while let Some(node) = cur_node {
+            let entry = &self[node];
+
+            if is_valid_filename(&entry.name) {
+                names.push(entry.name.as_str());
+            }
+
+            cur_node = entry.parent;
+        }
+
+        names.iter().rev().collect()
+    }
+
+    /// Returns the number of entries that `children_iter` will visit for the given `nodes`.
+    pub fn children_count(&self, nodes: &[NodeID]) -> usize {
+        if nodes.contains(&NodeID::first()) {
+            self.len()
+        } else {
+            self.children_iter(nodes).count().min(self.len())
+        }
+    }
+
+    /// Returns the closest directory that contains every one of the given `nodes`.
+    ///
+    /// Returns None if any of the `nodes` is the root itself.
+    fn common_parent(&self, nodes: &[NodeID]) -> Option<NodeID> {
+        let ancestors_of = |id: NodeID| {
+            let mut ancestors = Vec::new();
+            let mut cur_node = self[id].parent;
+
+            while let Some(node) = cur_node {
+                ancestors.push(node);
+                cur_node = self[node].parent;
+            }
+
+            ancestors.reverse();
+            ancestors
+        };
+
+        let (first, rest) = nodes.split_first()?;
+        let mut common = ancestors_of(*first);
+
+        for &node in rest {
+            let ancestors = ancestors_of(node);
+
+            let shared = common
+                .iter()
+                .zip(&ancestors)
+                .take_while(|(x, y)| x == y)
+                .count();
+
+            common.truncate(shared);
+        }
+
+        common.last().copied()
+    }
+
+    /// Returns an iterator over the given `nodes` and everything under them, along with the path of each entry.
+    ///
+    /// Paths are relative to the closest directory that contains all of the `nodes`, so entries from
+    /// different directories keep the directories that set them apart.
     #[inline(always)]
     pub fn children_iter<'a, I>(&self, nodes: I) -> ChildrenIter
     where
@@ -146,13 +475,78 @@ impl ArchiveEntries {
     {
         ChildrenIter::new(nodes, &self)
     }
+
+    /// Returns the uncompressed and compressed size of every file in the given `nodes`, including every file anywhere
+    /// inside of the directories among them.
+    ///
+    /// None of the `nodes` should be inside of another, or their files will be counted more than once.
+    pub fn file_sizes<'a, I>(&self, nodes: I) -> (u64, u64)
+    where
+        I: IntoIterator<Item = &'a NodeID>,
+    {
+        let mut pending = nodes.into_iter().copied().collect::<Vec<_>>();
+        let (mut raw, mut compressed) = (0, 0);
+
+        while let Some(id) = pending.pop() {
+            let entry = &self[id];
+
+            match &entry.props {
+                EntryProperties::File(props) => {
+                    raw += props.raw_size_bytes;
+                    compressed += props.compressed_size_bytes;
+                }
+                EntryProperties::Directory => pending.extend(&entry.children),
+            }
+        }
+
+        (raw, compressed)
+    }
 }
 
 impl Deref for ArchiveEntries {
     type Target = Vec<ArchiveEntry>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.entries
+    }
+}
+
+/// The names used inside of a directory while an archive's entries are being read.
+#[derive(Default)]
+struct DirNames {
+    /// The name of every entry in the directory.
+    taken: HashSet<String>,
+    /// The ID of each directory in the directory, keyed by its name in the archive before it was made unique.
+    dirs: HashMap<String, NodeID>,
+}
+
+impl DirNames {
+    /// Returns `name` if no entry in the directory has it yet, or `name` with the lowest number that makes it
+    /// unique added to it otherwise, like `notes (1).txt`.
+    ///
+    /// The number goes before the extension of files so they still open with the same program.
+    fn unique(&self, name: &str, is_file: bool) -> String {
+        if !self.taken.contains(name) {
+            return name.into();
+        }
+
+        let (stem, extension) = match name.rfind('.') {
+            // Names like .bashrc don't have an extension
+            Some(pos) if is_file && pos > 0 => name.split_at(pos),
+            _ => (name, ""),
+        };
+
+        let mut num = 1;
+
+        loop {
+            let candidate = format!("{} ({}){}", stem, num, extension);
+
+            if !self.taken.contains(&candidate) {
+                return candidate;
+            }
+
+            num += 1;
+        }
     }
 }
 
@@ -160,7 +554,7 @@ impl Index<NodeID> for ArchiveEntries {
     type Output = ArchiveEntry;
 
     fn index(&self, index: NodeID) -> &Self::Output {
-        &self.0[*index as usize]
+        &self.entries[*index as usize]
     }
 }
 
@@ -174,10 +568,29 @@ impl<'a> ChildrenIter<'a> {
     where
         I: IntoIterator<Item = &'b NodeID>,
     {
+        let base_nodes = base_nodes
+            .into_iter()
+            .copied()
+            .collect::<SmallVec<[_; 4]>>();
         let mut queue = Vec::with_capacity(32);
 
+        let common = files.common_parent(&base_nodes);
+
+        let common_path = common
+            .map(|parent| files.path_of(parent))
+            .unwrap_or_default();
+
         for node in base_nodes {
-            queue.push((*node, PathBuf::new()));
+            let path = match files[node].parent {
+                Some(parent) if Some(parent) != common => files
+                    .path_of(parent)
+                    .strip_prefix(&common_path)
+                    .map(Path::to_path_buf)
+                    .unwrap_or_default(),
+                _ => PathBuf::new(),
+            };
+
+            queue.push((node, path));
         }
 
         Self { queue, files }
@@ -203,8 +616,28 @@ impl<'a> Iterator for ChildrenIter<'a> {
     }
 }
 
+/// Returns true if the given `err` is the one given for files that can't be opened without a password.
+fn is_password_required(err: &ZipError) -> bool {
+    matches!(err, ZipError::UnsupportedArchive(msg) if *msg == ZipError::PASSWORD_REQUIRED)
+}
+
+/// Open the encrypted file at the given `index` in `archive` without decrypting it, which is enough to read
+/// everything about it but its content.
+fn open_encrypted<R>(archive: &mut ZipArchive<R>, index: usize) -> Result<ZipFile>
+where
+    R: Read + Seek,
+{
+    archive
+        .by_index_raw(index)
+        .with_context(|| anyhow!("failed to read encrypted archive file at index {}", index))
+}
+
+fn is_broken_pipe(err: &anyhow::Error) -> bool {
+    matches!(err.downcast_ref::<io::Error>(), Some(err) if err.kind() == io::ErrorKind::BrokenPipe)
+}
+
 fn is_valid_filename(name: &str) -> bool {
-    !matches!(name, ".." | "." | "/")
+    !matches!(name, ".." | "." | "/" | "")
 }
 
 #[derive(Clone)]
@@ -216,6 +649,8 @@ pub struct ArchiveEntry {
     pub entry_num: usize,
     pub parent: Option<NodeID>,
     pub children: Vec<NodeID>,
+    /// The uncompressed size of the entry, which for directories is the size of every file anywhere inside of them.
+    pub total_size_bytes: u64,
 }
 
 impl ArchiveEntry {
@@ -237,6 +672,7 @@ impl ArchiveEntry {
             encoding,
             parent: None,
             children: Vec::new(),
+            total_size_bytes: 0,
         }
     }
 
@@ -262,27 +698,32 @@ impl ArchiveEntry {
         Self::new_directory("/", 0, None, encoding_rs::UTF_8)
     }
 
-    /// Create a new `ArchiveEntry` from a specific file path in an archive.
+    /// Returns the part of the entry's name after the last `.`, or None if there isn't one.
+    pub fn extension(&self) -> Option<&str> {
+        match self.name.rfind('.') {
+            // Names like .bashrc don't have an extension
+            Some(0) | None => None,
+            Some(index) => Some(&self.name[index + 1..]),
+        }
+    }
+
+    /// Create a new `ArchiveEntry` called `name` for one of the components of the given `file`'s path.
     ///
-    /// The `path` should be the full path of the given `file`, and the
-    /// `name` should be a slice from the given `path`. This ensures
-    /// that directories and files are detected properly.
-    fn from_path<S, P>(
+    /// `is_file` should only be true for the last component of a file's path, since every other
+    /// component is a directory.
+    fn from_file<S>(
         name: S,
         entry_num: usize,
-        path: P,
+        is_file: bool,
         encoding: &'static Encoding,
-        file: &ZipFile,
+        file: &mut ZipFile,
+        encrypted: bool,
     ) -> Self
     where
         S: Into<String>,
-        P: AsRef<str>,
     {
-        let name = name.into();
-        let path = path.as_ref();
-
-        let props = if path.ends_with(&name) {
-            EntryProperties::file(file)
+        let props = if is_file {
+            EntryProperties::file(file, encrypted)
         } else {
             EntryProperties::Directory
         };
@@ -304,8 +745,8 @@ pub enum EntryProperties {
 }
 
 impl EntryProperties {
-    fn file(file: &ZipFile) -> Self {
-        Self::File(file.into())
+    fn file(file: &mut ZipFile, encrypted: bool) -> Self {
+        Self::File(FileProperties::read(file, encrypted))
     }
 
     pub fn is_dir(&self) -> bool {
@@ -314,24 +755,111 @@ impl EntryProperties {
             Self::File(_) => false,
         }
     }
+
+    pub fn is_symlink(&self) -> bool {
+        match self {
+            Self::Directory => false,
+            Self::File(props) => props.is_symlink,
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct FileProperties {
     pub raw_size_bytes: u64,
     pub compressed_size_bytes: u64,
+    /// The CRC32 checksum of the file's uncompressed content.
+    pub crc32: u32,
+    /// The Unix permission bits of the file, if the archive was created on a Unix system.
+    pub unix_mode: Option<u32>,
+    /// Whether the file is a symlink, in which case its content is the path it points to.
+    pub is_symlink: bool,
+    /// The path the file points to if it's a symlink, unless it couldn't be read.
+    pub link_target: Option<String>,
+    /// The kind of special file the file is, like a device node, if it isn't a regular file or symlink.
+    pub special: Option<SpecialFile>,
+    /// Whether the file needs a password to be read, which it can't be without.
+    pub encrypted: bool,
 }
 
-impl<'a> From<&ZipFile<'a>> for FileProperties {
-    fn from(file: &ZipFile<'a>) -> Self {
+impl FileProperties {
+    /// The longest symlink target that is read, which is the longest path Linux allows.
+    const MAX_LINK_TARGET: u64 = 4096;
+
+    /// Read the properties of the given `file`, which includes reading its content if it's a symlink that isn't
+    /// `encrypted`.
+    fn read(file: &mut ZipFile, encrypted: bool) -> Self {
+        let mode = file.unix_mode();
+        let is_symlink = matches!(mode, Some(mode) if mode & file_type::MASK == file_type::SYMLINK);
+
+        let link_target = if is_symlink && !encrypted && file.size() <= Self::MAX_LINK_TARGET {
+            Self::read_link_target(file)
+        } else {
+            None
+        };
+
         Self {
             raw_size_bytes: file.size(),
             compressed_size_bytes: file.compressed_size(),
+            crc32: file.crc32(),
+            unix_mode: mode.map(|mode| mode & 0o7777),
+            is_symlink,
+            link_target,
+            special: mode.and_then(SpecialFile::from_mode),
+            encrypted,
         }
     }
+
+    /// Returns the content of the given symlink `file`, or None if it couldn't be decompressed.
+    ///
+    /// A link that can't be read is still listed, it just doesn't show where it points to.
+    fn read_link_target(file: &mut ZipFile) -> Option<String> {
+        let mut target = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut target).ok()?;
+
+        Some(String::from_utf8_lossy(&target).into_owned())
+    }
 }
 
-#[derive(Clone)]
+/// A file that is neither a regular file, a directory, nor a symlink.
+#[derive(Copy, Clone)]
+pub enum SpecialFile {
+    Fifo,
+    CharDevice,
+    BlockDevice,
+    Socket,
+}
+
+impl SpecialFile {
+    /// Returns the kind of special file that the file type bits of the given Unix `mode` describe.
+    fn from_mode(mode: u32) -> Option<Self> {
+        match mode & file_type::MASK {
+            file_type::FIFO => Some(Self::Fifo),
+            file_type::CHAR_DEVICE => Some(Self::CharDevice),
+            file_type::BLOCK_DEVICE => Some(Self::BlockDevice),
+            file_type::SOCKET => Some(Self::Socket),
+            _ => None,
+        }
+    }
+
+    pub fn desc(self) -> &'static str {
+        match self {
+            Self::Fifo => "named pipe",
+            Self::CharDevice => "character device",
+            Self::BlockDevice => "block device",
+            Self::Socket => "socket",
+        }
+    }
+}
+
+/// Details about a file in an archive that are only read when they're asked for.
+pub struct FileDetails {
+    pub compression: CompressionMethod,
+    pub comment: String,
+}
+
+// The fields are ordered from most to least significant so the derived ordering is chronological
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Date {
     pub year: u16,
     pub month: u8,
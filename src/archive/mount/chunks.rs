@@ -0,0 +1,500 @@
+use super::spill::SpillDir;
+use super::{create_temp_dir, CacheLimit, MountOptions, MountStats};
+use crate::archive::{Archive, ArchiveEntry, EntryProperties, NodeID};
+use crate::util::disk::DiskStats;
+use anyhow::{anyhow, Context, Result};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::io::Read;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::Arc;
+use std::thread;
+
+/// Reads the files of an archive in chunks, keeping the decompressed chunks cached so reading the same part of
+/// a file again doesn't mean decompressing it all over again.
+pub struct ChunkReader {
+    archive: Arc<Archive>,
+    cache: ChunkCache,
+    stats: Arc<MountStats>,
+}
+
+impl ChunkReader {
+    // 8 GB
+    const DEFAULT_AVAILABLE_MEM_KB: u64 = 8 * 1024 * 1024;
+    /// How many chunks past the one being read are moved into the cache when a file is read sequentially.
+    const PREFETCH_CHUNKS: u64 = 4;
+
+    /// Create a new `ChunkReader` for the given `archive` that caches chunks within the limits of `options`.
+    ///
+    /// A new directory is created in the system's temporary directory to spill evicted chunks to, unless
+    /// no spill limit was given and the temporary directory is a tmpfs.
+    pub fn new(archive: &Arc<Archive>, options: MountOptions) -> Result<Self> {
+        let cache_bytes = options
+            .cache_limit
+            .map_or_else(Self::default_cache_bytes, |limit| limit.bytes.get());
+
+        let stats = Arc::new(MountStats::new(cache_bytes));
+        let spill = Self::create_spill(options.spill_limit, &stats)?;
+
+        Ok(Self {
+            archive: Arc::clone(archive),
+            cache: ChunkCache::new(cache_bytes, spill, Arc::clone(&stats)),
+            stats,
+        })
+    }
+
+    fn default_cache_bytes() -> u64 {
+        // Decompressing is expensive, so keep as much cached as we can without starving the rest of the system
+        let available_kb = available_memory_kb().unwrap_or(Self::DEFAULT_AVAILABLE_MEM_KB);
+
+        available_kb * 1024 / 2
+    }
+
+    /// Create a new directory in the system's temporary directory to keep chunks evicted from the cache in.
+    ///
+    /// Returns None if no `limit` was given and the temporary directory is a tmpfs, since spilling to it
+    /// would use up the memory the cache is limited to.
+    fn create_spill(
+        limit: Option<CacheLimit>,
+        stats: &Arc<MountStats>,
+    ) -> Result<Option<SpillDir>> {
+        let parent = env::temp_dir();
+
+        let disk = DiskStats::read(&parent)
+            .with_context(|| anyhow!("failed to get filesystem info for {}", parent.display()))?;
+
+        let max_bytes = match limit {
+            Some(limit) => limit.bytes.get(),
+            None if disk.is_tmpfs => return Ok(None),
+            // Leave most of the disk for everything else
+            None => disk.available_bytes / 4,
+        };
+
+        let dir = create_temp_dir(&parent, concat!(env!("CARGO_PKG_NAME"), "-spill"))
+            .with_context(|| anyhow!("failed to create spill directory in {}", parent.display()))?;
+
+        Ok(Some(SpillDir::new(dir, max_bytes, Arc::clone(stats))))
+    }
+
+    #[inline(always)]
+    pub fn stats(&self) -> &Arc<MountStats> {
+        &self.stats
+    }
+
+    /// Read up to `size` bytes of `entry` starting at `offset`, decompressing the chunks that aren't cached
+    /// yet with `stream`.
+    ///
+    /// Fewer bytes are returned once the end of the entry is reached.
+    pub fn read(
+        &mut self,
+        entry: OpenEntry,
+        stream: &mut Option<EntryStream>,
+        offset: u64,
+        size: u64,
+    ) -> Result<Vec<u8>> {
+        let end = offset.saturating_add(size).min(entry.size);
+        let mut pos = offset;
+        let mut data = Vec::with_capacity(end.saturating_sub(pos) as usize);
+
+        while pos < end {
+            let index = pos / EntryStream::CHUNK_SIZE;
+            let chunk = self.chunk(entry, stream, index)?;
+            let start = (pos - index * EntryStream::CHUNK_SIZE) as usize;
+
+            // The entry may be shorter than the archive claims
+            if start >= chunk.len() {
+                break;
+            }
+
+            let len = (chunk.len() - start).min((end - pos) as usize);
+            data.extend_from_slice(&chunk[start..start + len]);
+
+            pos += len as u64;
+        }
+
+        Ok(data)
+    }
+
+    /// Returns the chunk at the given `index` of `entry`, decompressing it with `stream` if it isn't cached yet.
+    fn chunk(
+        &mut self,
+        entry: OpenEntry,
+        stream: &mut Option<EntryStream>,
+        index: u64,
+    ) -> Result<&[u8]> {
+        let key = (entry.node_id, index);
+
+        self.stats.chunk_reads.fetch_add(1, Ordering::Relaxed);
+
+        if self.cache.touch(key) || self.cache.restore(key) {
+            self.stats.chunk_hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            let chunk = self.decompress_chunk(entry.entry_num, stream, index)?;
+            self.cache.insert(key, chunk);
+        }
+
+        Ok(self.cache.get(key))
+    }
+
+    fn decompress_chunk(
+        &self,
+        entry_num: usize,
+        stream: &mut Option<EntryStream>,
+        index: u64,
+    ) -> Result<Vec<u8>> {
+        let stream = match stream {
+            Some(stream) if stream.can_reach(index) => stream,
+            // Entries can only be decompressed from the start, so going back means starting over
+            _ => stream.insert(EntryStream::spawn(Arc::clone(&self.archive), entry_num)),
+        };
+
+        // Chunks before the one we want aren't cached so large files don't have to fit in memory
+        loop {
+            let (cur_index, chunk) = stream.next_chunk()?;
+
+            if cur_index == index {
+                return Ok(chunk);
+            }
+        }
+    }
+
+    /// Move the chunks that `stream` already decompressed into the cache, up to
+    /// [`ChunkReader::PREFETCH_CHUNKS`] past the chunk that contains `offset`.
+    ///
+    /// This lets the stream keep decompressing in the background while a file is read from start to end,
+    /// instead of only decompressing each chunk once it's asked for.
+    pub fn prefetch(&mut self, entry: OpenEntry, stream: &mut Option<EntryStream>, offset: u64) {
+        let stream = match stream {
+            Some(stream) => stream,
+            None => return,
+        };
+
+        let index = offset / EntryStream::CHUNK_SIZE;
+
+        // A stream that is behind was left there while cached chunks were read, so catching it up isn't free
+        if stream.next_index < index {
+            return;
+        }
+
+        while stream.next_index <= index + Self::PREFETCH_CHUNKS {
+            let (cur_index, chunk) = match stream.try_next_chunk() {
+                Some(Ok(chunk)) => chunk,
+                Some(Err(_)) | None => break,
+            };
+
+            let key = (entry.node_id, cur_index);
+
+            if !self.cache.contains(key) {
+                self.cache.insert(key, chunk);
+            }
+        }
+    }
+
+    /// Remove every chunk that was spilled to disk along with the spill directory.
+    ///
+    /// Evicted chunks are dropped from then on.
+    pub fn remove_spill(&mut self) {
+        self.cache.spill = None;
+        self.stats.spilled_bytes.store(0, Ordering::Relaxed);
+    }
+}
+
+/// The parts of an archive entry needed to read it.
+#[derive(Copy, Clone)]
+pub struct OpenEntry {
+    pub node_id: NodeID,
+    pub entry_num: usize,
+    pub size: u64,
+}
+
+impl OpenEntry {
+    pub fn new(node_id: NodeID, node: &ArchiveEntry) -> Self {
+        let size = match &node.props {
+            EntryProperties::File(props) => props.raw_size_bytes,
+            EntryProperties::Directory => 0,
+        };
+
+        Self {
+            node_id,
+            entry_num: node.entry_num,
+            size,
+        }
+    }
+}
+
+/// Decompresses an entry on its own thread so it can be read in chunks across separate requests.
+pub struct EntryStream {
+    chunks: Receiver<Result<Vec<u8>>>,
+    /// The index of the chunk that will be received next.
+    next_index: u64,
+}
+
+impl EntryStream {
+    // 1 MB
+    const CHUNK_SIZE: u64 = 1024 * 1024;
+    /// How many chunks may be decompressed before they're asked for.
+    const READ_AHEAD: usize = 2;
+
+    fn spawn(archive: Arc<Archive>, entry_num: usize) -> Self {
+        let (sender, chunks) = mpsc::sync_channel(Self::READ_AHEAD);
+
+        thread::spawn(move || {
+            if let Err(err) = Self::decompress(&archive, entry_num, &sender) {
+                sender.send(Err(err)).ok();
+            }
+        });
+
+        Self {
+            chunks,
+            next_index: 0,
+        }
+    }
+
+    fn decompress(
+        archive: &Archive,
+        entry_num: usize,
+        sender: &SyncSender<Result<Vec<u8>>>,
+    ) -> Result<()> {
+        let mut reader = archive.open_reader()?;
+        let mut file = reader
+            .by_index(entry_num)
+            .with_context(|| anyhow!("failed to find entry #{}", entry_num))?;
+
+        loop {
+            let mut chunk = Vec::with_capacity(Self::CHUNK_SIZE as usize);
+
+            (&mut file)
+                .take(Self::CHUNK_SIZE)
+                .read_to_end(&mut chunk)
+                .with_context(|| anyhow!("failed to decompress {}", file.name()))?;
+
+            // Sending only fails once the stream has been dropped, so there's nobody left to read it
+            if chunk.is_empty() || sender.send(Ok(chunk)).is_err() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Returns true if the chunk at the given `index` can still be received.
+    fn can_reach(&self, index: u64) -> bool {
+        self.next_index <= index
+    }
+
+    /// Wait for the next chunk and return it along with its index.
+    fn next_chunk(&mut self) -> Result<(u64, Vec<u8>)> {
+        let chunk = self
+            .chunks
+            .recv()
+            .map_err(|_| anyhow!("entry ended before chunk {}", self.next_index))??;
+
+        let index = self.next_index;
+        self.next_index += 1;
+
+        Ok((index, chunk))
+    }
+
+    /// Returns the next chunk along with its index if it's already decompressed, without waiting for it.
+    fn try_next_chunk(&mut self) -> Option<Result<(u64, Vec<u8>)>> {
+        let chunk = self.chunks.try_recv().ok()?;
+
+        let index = self.next_index;
+        self.next_index += 1;
+
+        Some(chunk.map(|chunk| (index, chunk)))
+    }
+}
+
+pub type ChunkKey = (NodeID, u64);
+
+/// Decompressed chunks of archive files, keyed by the file and the chunk's index.
+///
+/// Once the chunks take up more than the limit, the least recently read ones are evicted to the spill
+/// directory if there is one.
+struct ChunkCache {
+    // TODO: use faster hashing algorithm
+    chunks: HashMap<ChunkKey, CachedChunk>,
+    /// The key of each chunk ordered by when it was last read.
+    read_order: BTreeMap<u64, ChunkKey>,
+    next_read: u64,
+    /// The number of chunks cached for each entry.
+    entry_chunks: HashMap<NodeID, usize>,
+    used_bytes: u64,
+    max_bytes: u64,
+    spill: Option<SpillDir>,
+    stats: Arc<MountStats>,
+}
+
+struct CachedChunk {
+    data: Vec<u8>,
+    last_read: u64,
+}
+
+impl ChunkCache {
+    fn new(max_bytes: u64, spill: Option<SpillDir>, stats: Arc<MountStats>) -> Self {
+        Self {
+            chunks: HashMap::new(),
+            read_order: BTreeMap::new(),
+            next_read: 0,
+            entry_chunks: HashMap::new(),
+            used_bytes: 0,
+            max_bytes,
+            spill,
+            stats,
+        }
+    }
+
+    /// Move the chunk with the given `key` from the spill directory back into memory.
+    ///
+    /// Returns false if the chunk wasn't spilled.
+    fn restore(&mut self, key: ChunkKey) -> bool {
+        let data = match self.spill.as_mut().and_then(|spill| spill.take(key)) {
+            Some(data) => data,
+            None => return false,
+        };
+
+        self.insert(key, data);
+        true
+    }
+
+    /// Mark the chunk with the given `key` as the most recently read one.
+    ///
+    /// Returns false if the chunk isn't cached.
+    fn touch(&mut self, key: ChunkKey) -> bool {
+        let chunk = match self.chunks.get_mut(&key) {
+            Some(chunk) => chunk,
+            None => return false,
+        };
+
+        self.read_order.remove(&chunk.last_read);
+        self.read_order.insert(self.next_read, key);
+
+        chunk.last_read = self.next_read;
+        self.next_read += 1;
+
+        true
+    }
+
+    /// Cache the given `data` under `key` as the most recently read chunk.
+    ///
+    /// The chunk is always kept, even if it's bigger than the limit by itself.
+    fn insert(&mut self, key: ChunkKey, data: Vec<u8>) {
+        let size = data.len() as u64;
+
+        while self.used_bytes + size > self.max_bytes {
+            let oldest = match self.read_order.keys().next() {
+                Some(&oldest) => oldest,
+                None => break,
+            };
+
+            if let Some(key) = self.read_order.remove(&oldest) {
+                if let Some(chunk) = self.chunks.remove(&key) {
+                    self.used_bytes -= chunk.data.len() as u64;
+                    self.remove_entry_chunk(key.0);
+
+                    if let Some(spill) = &mut self.spill {
+                        spill.insert(key, &chunk.data);
+                    }
+                }
+            }
+        }
+
+        let chunk = CachedChunk {
+            data,
+            last_read: self.next_read,
+        };
+
+        self.chunks.insert(key, chunk);
+        self.read_order.insert(self.next_read, key);
+        self.next_read += 1;
+        self.used_bytes += size;
+        *self.entry_chunks.entry(key.0).or_insert(0) += 1;
+
+        self.stats
+            .cached_bytes
+            .store(self.used_bytes, Ordering::Relaxed);
+        self.stats
+            .cached_entries
+            .store(self.entry_chunks.len(), Ordering::Relaxed);
+    }
+
+    fn remove_entry_chunk(&mut self, node_id: NodeID) {
+        if let Entry::Occupied(mut entry) = self.entry_chunks.entry(node_id) {
+            *entry.get_mut() -= 1;
+
+            if *entry.get() == 0 {
+                entry.remove();
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn contains(&self, key: ChunkKey) -> bool {
+        self.chunks.contains_key(&key)
+    }
+
+    /// Returns the cached chunk with the given `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the chunk isn't cached.
+    #[inline(always)]
+    fn get(&self, key: ChunkKey) -> &[u8] {
+        &self.chunks[&key].data
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn available_memory_kb() -> Option<u64> {
+    read_meminfo_field("MemAvailable")
+}
+
+#[cfg(not(target_os = "linux"))]
+fn available_memory_kb() -> Option<u64> {
+    let (pages, page_size) = unsafe {
+        (
+            libc::sysconf(libc::_SC_PHYS_PAGES),
+            libc::sysconf(libc::_SC_PAGESIZE),
+        )
+    };
+
+    if pages <= 0 || page_size <= 0 {
+        return None;
+    }
+
+    // There's no portable way to find out how much memory is free, so assume half of it is
+    Some(pages as u64 * page_size as u64 / 1024 / 2)
+}
+
+#[cfg(target_os = "linux")]
+fn read_meminfo_field(field: &str) -> Option<u64> {
+    use std::fs::File;
+    use std::io::{BufRead, BufReader};
+    use std::str::FromStr;
+
+    let file = File::open("/proc/meminfo").ok()?;
+    let reader = BufReader::new(file);
+
+    for line in reader.lines() {
+        let line = line.ok()?;
+
+        let cur_field = match line.split(':').next() {
+            Some(cur) => cur,
+            None => continue,
+        };
+
+        if cur_field != field {
+            continue;
+        }
+
+        let value = line
+            .rsplit(' ')
+            .nth(1)
+            .and_then(|num| u64::from_str(num).ok())?;
+
+        return Some(value);
+    }
+
+    None
+}
@@ -0,0 +1,1219 @@
+use super::chunks::{ChunkReader, EntryStream, OpenEntry};
+use super::overlay::Overlay;
+use super::{
+    create_temp_dir, unmount_path, MountChanges, MountOptions, MountStats, SpaceEstimate,
+    UnmountStatus,
+};
+use crate::archive::{
+    normalize, Archive, ArchiveEntries, ArchiveEntry, EntryProperties, FileProperties, NodeID,
+    SpecialFile,
+};
+use crate::util::{disk::DiskStats, file_type};
+use anyhow::{anyhow, Context, Result};
+use fuser::{
+    FileAttr, FileType, Filesystem, ReplyAttr, ReplyCreate, ReplyData, ReplyDirectory, ReplyEmpty,
+    ReplyEntry, ReplyOpen, ReplyStatfs, ReplyWrite, ReplyXattr, Request, TimeOrNow, FUSE_ROOT_ID,
+};
+use libc::{c_int, EBADF, EINVAL, EIO, EISDIR, ENOENT, ENOTDIR, ENOTSUP, EPERM, ERANGE, EROFS};
+use parking_lot::{const_mutex, Mutex};
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::mem;
+use std::os::unix::fs::FileExt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+pub struct MountedArchive {
+    archive: Arc<Archive>,
+    files: Arc<ArchiveEntries>,
+    /// The ID of every entry inside of each directory, keyed by the entry's name.
+    children: HashMap<NodeID, HashMap<String, NodeID>>,
+    /// The number of blocks every file in the archive takes up, with each one rounded up to a whole block.
+    total_blocks: u64,
+    uid: u32,
+    gid: u32,
+    reader: ChunkReader,
+    /// What each open file and directory handle was opened on.
+    handles: HashMap<u64, Handle>,
+    next_handle: u64,
+    /// The changes made through the mount, if it's writable.
+    overlay: Option<Arc<Mutex<Overlay>>>,
+}
+
+impl MountedArchive {
+    const BLOCK_SIZE: u32 = 4096;
+    // Since our filesystem is read only, requests never need to expire
+    const REQ_TTL: Duration = Duration::from_secs(u64::MAX);
+    // Entries of a writable mount can change at any time
+    const WRITABLE_REQ_TTL: Duration = Duration::from_secs(0);
+    const XATTR_PREFIX: &'static str = "user.vear.";
+
+    /// Prepare the given `archive` to be mounted.
+    ///
+    /// The mount will always show the archive's entries as they were when this was called.
+    /// If the mount is writable, a new directory is created in the system's temporary directory to keep changes in.
+    pub fn new(archive: &Arc<Archive>, options: MountOptions) -> Result<Self> {
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+
+        // Make sure the archive can still be read before anything is mounted
+        archive.open_reader()?;
+
+        let files = archive.entries();
+        let children = Self::index_children(&files);
+        let total_blocks = Self::count_blocks(&files);
+
+        let overlay = if options.writable {
+            let parent = env::temp_dir();
+            let dir = create_temp_dir(&parent, concat!(env!("CARGO_PKG_NAME"), "-overlay"))
+                .with_context(|| {
+                    anyhow!("failed to create overlay directory in {}", parent.display())
+                })?;
+
+            let overlay = Overlay::new(dir, Arc::clone(&files))?;
+            Some(Arc::new(Mutex::new(overlay)))
+        } else {
+            None
+        };
+
+        let reader = ChunkReader::new(archive, options)?;
+
+        Ok(Self {
+            archive: Arc::clone(archive),
+            files,
+            children,
+            total_blocks,
+            uid,
+            gid,
+            reader,
+            handles: HashMap::new(),
+            next_handle: 0,
+            overlay,
+        })
+    }
+
+    /// Map the name of every entry to its ID for each directory, so looking up a name doesn't have to go
+    /// through every entry in the directory.
+    fn index_children(files: &ArchiveEntries) -> HashMap<NodeID, HashMap<String, NodeID>> {
+        files
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| !entry.children.is_empty())
+            .filter_map(|(id, entry)| {
+                let id = files.create_node_id(id as u32)?;
+
+                let names = entry
+                    .children
+                    .iter()
+                    .map(|&child| (files[child].name.clone(), child))
+                    .collect();
+
+                Some((id, names))
+            })
+            .collect()
+    }
+
+    fn count_blocks(files: &ArchiveEntries) -> u64 {
+        let block_size = Self::BLOCK_SIZE as u64;
+
+        files
+            .iter()
+            .map(|entry| match &entry.props {
+                EntryProperties::File(props) => props.raw_size_bytes.div_ceil(block_size),
+                EntryProperties::Directory => 0,
+            })
+            .sum()
+    }
+
+    pub fn mount<P>(self, path: P) -> Result<ArchiveMountSession>
+    where
+        P: AsRef<Path>,
+    {
+        self.mount_at(path.as_ref(), false)
+    }
+
+    /// Mount the archive at `path`, which is removed once the archive is unmounted if `temp_dir` is true.
+    fn mount_at(self, path: &Path, temp_dir: bool) -> Result<ArchiveMountSession> {
+        let access = if self.overlay.is_some() { "rw" } else { "ro" };
+
+        let mut options = vec!["-o", access, "-o", "exec"];
+
+        // Only fusermount on Linux can unmount the archive by itself if vear is killed
+        if cfg!(target_os = "linux") {
+            options.extend(&["-o", "auto_unmount"]);
+        }
+
+        let options = options.iter().map(|s| s.as_ref()).collect::<Vec<&OsStr>>();
+
+        let overlay = self.overlay.clone();
+        let stats = Arc::clone(self.reader.stats());
+        let handle = fuser::spawn_mount(self, path, &options)?;
+
+        let mount = ActiveMount {
+            id: NEXT_MOUNT_ID.fetch_add(1, Ordering::Relaxed),
+            handle,
+            path: path.to_path_buf(),
+            temp_dir,
+            overlay,
+        };
+
+        Ok(ArchiveMountSession::register(mount, stats))
+    }
+
+    /// Mount the archive at a new directory named `vear-XXXXXX` inside of the given `parent` directory.
+    ///
+    /// The directory is removed once the archive is unmounted.
+    pub fn mount_in_temp_dir<P>(self, parent: P) -> Result<ArchiveMountSession>
+    where
+        P: AsRef<Path>,
+    {
+        let parent = parent.as_ref();
+        let dir = create_temp_dir(parent, env!("CARGO_PKG_NAME"))
+            .with_context(|| anyhow!("failed to create mount directory in {}", parent.display()))?;
+
+        match self.mount_at(&dir, true) {
+            Ok(session) => Ok(session),
+            Err(err) => {
+                fs::remove_dir(&dir).ok();
+                Err(err)
+            }
+        }
+    }
+
+    /// Check if mounting at the given `path` is likely to fill up the memory of a tmpfs.
+    ///
+    /// Every file that is read through the mount is cached in memory, which a tmpfs shares.
+    /// Returns None if `path` isn't on a tmpfs or the archive's total uncompressed size fits in it.
+    pub fn tmpfs_space_check<P>(&self, path: P) -> Result<Option<SpaceEstimate>>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+
+        let stats = DiskStats::read(path)
+            .with_context(|| anyhow!("failed to get filesystem info for {}", path.display()))?;
+
+        let needed_bytes = self.files.total_size_bytes;
+
+        if !stats.is_tmpfs || needed_bytes <= stats.available_bytes {
+            return Ok(None);
+        }
+
+        Ok(Some(SpaceEstimate {
+            needed_bytes,
+            available_bytes: stats.available_bytes,
+        }))
+    }
+
+    /// Returns the attributes of an entry, with permissions taken from its `unix_mode` if the archive has one.
+    fn file_attr(
+        &self,
+        ino: u64,
+        size: u64,
+        kind: FileType,
+        nlink: u32,
+        unix_mode: Option<u32>,
+        modified_time: Option<SystemTime>,
+    ) -> FileAttr {
+        let modified_time = modified_time.unwrap_or(UNIX_EPOCH);
+
+        let perm = match unix_mode {
+            // Nothing can be written to a read-only mount, so it shouldn't look like it can
+            Some(mode) if self.overlay.is_none() => mode & 0o7777 & !0o222,
+            Some(mode) => mode & 0o7777,
+            // User can execute, everyone can read, and the user can write if the mount is writable
+            None if self.overlay.is_some() => 0o744,
+            None => 0o544,
+        };
+
+        FileAttr {
+            ino,
+            size,
+            // Stat always counts blocks in 512 byte units, regardless of the block size
+            blocks: size.div_ceil(512),
+            atime: SystemTime::now(),
+            mtime: modified_time,
+            ctime: modified_time,
+            crtime: modified_time,
+            kind,
+            perm: perm as u16,
+            nlink,
+            uid: self.uid,
+            gid: self.gid,
+            rdev: 0,
+            blksize: Self::BLOCK_SIZE as u32,
+            padding: 0,
+            flags: 0,
+        }
+    }
+
+    #[inline(always)]
+    fn ttl(&self) -> &'static Duration {
+        if self.overlay.is_some() {
+            &Self::WRITABLE_REQ_TTL
+        } else {
+            &Self::REQ_TTL
+        }
+    }
+
+    fn new_handle(&mut self) -> u64 {
+        let fh = self.next_handle;
+        self.next_handle += 1;
+        fh
+    }
+
+    /// Run `func` with the changes made through the mount, or None if the mount is read-only.
+    fn with_overlay<F, T>(&self, func: F) -> T
+    where
+        F: FnOnce(Option<&mut Overlay>) -> T,
+    {
+        match &self.overlay {
+            Some(overlay) => func(Some(&mut overlay.lock())),
+            None => func(None),
+        }
+    }
+
+    /// Returns the error for a change that the mount can never make, which is `EROFS` if it's read-only
+    /// and `err` if it's writable.
+    #[inline(always)]
+    fn unsupported_change(&self, err: c_int) -> c_int {
+        if self.overlay.is_some() {
+            err
+        } else {
+            EROFS
+        }
+    }
+
+    /// Create an empty file called `name` inside of the directory `parent` in the overlay and return its attributes.
+    fn create_file(&self, parent: u64, name: &OsStr) -> Result<FileAttr, c_int> {
+        let name = name.to_string_lossy();
+
+        self.with_overlay(|overlay| {
+            let overlay = overlay.ok_or(EROFS)?;
+
+            if !self.is_dir(parent) {
+                return Err(ENOTDIR);
+            }
+
+            let inode = overlay.create(parent, &name).map_err(|_| EIO)?;
+            self.attr(Some(overlay), inode).ok_or(EIO)
+        })
+    }
+
+    /// Returns the inode of the entry called `name` inside of the directory `parent`, taking the
+    /// changes in the `overlay` into account.
+    fn find_child(&self, overlay: Option<&Overlay>, parent: u64, name: &str) -> Option<u64> {
+        if let Some(inode) = overlay.and_then(|overlay| overlay.find(parent, name)) {
+            return Some(inode);
+        }
+
+        let (node_id, _) = self.get_node(parent)?;
+        let name = normalize::compose(name);
+        let inode = **self.children.get(&node_id)?.get(name.as_ref())? as u64 + FUSE_ROOT_ID;
+
+        if overlay.is_some_and(|overlay| overlay.is_hidden(inode)) {
+            return None;
+        }
+
+        Some(inode)
+    }
+
+    /// Returns the attributes of the entry with the given `inode`, taking the changes in the `overlay` into account.
+    fn attr(&self, overlay: Option<&Overlay>, inode: u64) -> Option<FileAttr> {
+        if let Some(overlay) = overlay.filter(|overlay| overlay.contains(inode)) {
+            let metadata = fs::metadata(overlay.content_path(inode)).ok()?;
+
+            return Some(self.file_attr(
+                inode,
+                metadata.len(),
+                FileType::RegularFile,
+                1,
+                None,
+                metadata.modified().ok(),
+            ));
+        }
+
+        let (node_id, node) = self.get_node(inode)?;
+        Some(self.attr_from_node(node_id, node))
+    }
+
+    /// Returns true if the entry with the given `inode` is a directory.
+    ///
+    /// Files created through the mount are never directories.
+    fn is_dir(&self, inode: u64) -> bool {
+        self.get_node(inode)
+            .is_some_and(|(_, node)| node.props.is_dir())
+    }
+
+    fn get_node(&self, inode: u64) -> Option<(NodeID, &ArchiveEntry)> {
+        let id = self.files.create_node_id((inode - FUSE_ROOT_ID) as u32)?;
+
+        let node = &self.files[id];
+
+        Some((id, node))
+    }
+
+    /// Returns the name and value of each extended attribute of the entry with the given `inode`.
+    ///
+    /// Files whose content was changed through the mount don't have any, since they no longer match the archive.
+    fn xattrs(&self, inode: u64) -> Option<Vec<(String, String)>> {
+        let in_overlay = self.with_overlay(|overlay| {
+            overlay.map(|overlay| (overlay.contains(inode), overlay.is_hidden(inode)))
+        });
+
+        match in_overlay {
+            Some((true, _)) => return Some(Vec::new()),
+            Some((false, true)) => return None,
+            _ => (),
+        }
+
+        let (_, node) = self.get_node(inode)?;
+        let name = |attr| format!("{}{}", Self::XATTR_PREFIX, attr);
+
+        let mut xattrs = vec![(name("encoding"), node.encoding.name().to_string())];
+
+        if let EntryProperties::File(props) = &node.props {
+            xattrs.push((
+                name("compressed_size"),
+                props.compressed_size_bytes.to_string(),
+            ));
+            xattrs.push((name("crc32"), format!("{:08x}", props.crc32)));
+        }
+
+        Some(xattrs)
+    }
+
+    fn attr_from_node(&self, node_id: NodeID, node: &ArchiveEntry) -> FileAttr {
+        let (size, kind, nlink, unix_mode) = match &node.props {
+            EntryProperties::File(props) => {
+                (props.raw_size_bytes, file_kind(props), 1, props.unix_mode)
+            }
+            EntryProperties::Directory => {
+                // Each subdirectory links back to its parent through its ".." entry
+                let subdirs = node
+                    .children
+                    .iter()
+                    .filter(|&&id| self.files[id].props.is_dir())
+                    .count();
+
+                (0, FileType::Directory, 2 + subdirs as u32, None)
+            }
+        };
+
+        self.file_attr(
+            *node_id as u64 + FUSE_ROOT_ID,
+            size,
+            kind,
+            nlink,
+            unix_mode,
+            node.last_modified.as_ref().map(Into::into),
+        )
+    }
+}
+
+impl Filesystem for MountedArchive {
+    fn lookup(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        if !self.is_dir(parent) {
+            reply.error(ENOTDIR);
+            return;
+        }
+
+        // Every name in the archive was decoded to UTF-8, so a name that isn't valid UTF-8 can't match any of them
+        let attr = name.to_str().and_then(|name| {
+            self.with_overlay(|overlay| {
+                let overlay = overlay.as_deref();
+                let inode = self.find_child(overlay, parent, name)?;
+                self.attr(overlay, inode)
+            })
+        });
+
+        match attr {
+            Some(attr) => reply.entry(self.ttl(), &attr, 0),
+            // Replying with inode 0 lets the kernel cache that the name doesn't exist, so programs that keep
+            // checking for files like .git don't have to ask again.
+            // Files can be created in a writable mount at any time, so misses there can't be cached.
+            None if self.overlay.is_none() => {
+                let attr = self.file_attr(0, 0, FileType::RegularFile, 1, None, None);
+                reply.entry(&Self::REQ_TTL, &attr, 0);
+            }
+            None => reply.error(ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request<'_>, inode: u64, reply: ReplyAttr) {
+        let attr = self.with_overlay(|overlay| self.attr(overlay.as_deref(), inode));
+
+        match attr {
+            Some(attr) => reply.attr(self.ttl(), &attr),
+            None => reply.error(ENOENT),
+        }
+    }
+
+    fn readlink(&mut self, _req: &Request<'_>, inode: u64, reply: ReplyData) {
+        let target = self
+            .get_node(inode)
+            .and_then(|(_, node)| match &node.props {
+                EntryProperties::File(props) if props.is_symlink => Some(&props.link_target),
+                _ => None,
+            });
+
+        match target {
+            Some(Some(target)) => reply.data(target.as_bytes()),
+            // The link is encrypted or its target couldn't be decompressed
+            Some(None) => reply.error(EIO),
+            None => reply.error(EINVAL),
+        }
+    }
+
+    fn setattr(
+        &mut self,
+        _req: &Request<'_>,
+        inode: u64,
+        _mode: Option<u32>,
+        _uid: Option<u32>,
+        _gid: Option<u32>,
+        size: Option<u64>,
+        _atime: Option<TimeOrNow>,
+        _mtime: Option<TimeOrNow>,
+        _ctime: Option<SystemTime>,
+        _fh: Option<u64>,
+        _crtime: Option<SystemTime>,
+        _chgtime: Option<SystemTime>,
+        _bkuptime: Option<SystemTime>,
+        _flags: Option<u32>,
+        reply: ReplyAttr,
+    ) {
+        let result = self.with_overlay(|mut overlay| {
+            if let Some(size) = size {
+                let overlay = overlay.as_deref_mut().ok_or(EROFS)?;
+                overlay.copy_up(&self.archive, inode).map_err(|_| EIO)?;
+
+                OpenOptions::new()
+                    .write(true)
+                    .open(overlay.content_path(inode))
+                    .and_then(|file| file.set_len(size))
+                    .map_err(|_| EIO)?;
+            }
+
+            // Other attributes like permissions aren't kept since there's nowhere to write them back to
+            self.attr(overlay.as_deref(), inode).ok_or(ENOENT)
+        });
+
+        match result {
+            Ok(attr) => reply.attr(self.ttl(), &attr),
+            Err(err) => reply.error(err),
+        }
+    }
+
+    fn open(&mut self, _req: &Request<'_>, inode: u64, flags: i32, reply: ReplyOpen) {
+        // Archive entries have to be moved into the overlay before they can be changed
+        if flags & libc::O_ACCMODE != libc::O_RDONLY {
+            let result = self.with_overlay(|overlay| match overlay {
+                Some(overlay) => overlay.copy_up(&self.archive, inode).map_err(|_| EIO),
+                None => Err(EROFS),
+            });
+
+            if let Err(err) = result {
+                reply.error(err);
+                return;
+            }
+        }
+
+        let fh = self.new_handle();
+
+        // Files created through the mount are always read from the overlay, so they don't need to be tracked
+        if let Some((node_id, node)) = self.get_node(inode) {
+            let entry = OpenEntry::new(node_id, node);
+
+            // Each handle gets its own stream so reading the same file twice at once doesn't keep restarting it
+            self.handles.insert(
+                fh,
+                Handle::File {
+                    entry,
+                    stream: None,
+                    next_offset: 0,
+                },
+            );
+        }
+
+        reply.opened(fh, 0);
+    }
+
+    fn create(
+        &mut self,
+        _req: &Request<'_>,
+        parent: u64,
+        name: &OsStr,
+        _mode: u32,
+        _umask: u32,
+        _flags: i32,
+        reply: ReplyCreate,
+    ) {
+        match self.create_file(parent, name) {
+            Ok(attr) => {
+                let fh = self.new_handle();
+                reply.created(self.ttl(), &attr, 0, fh, 0);
+            }
+            Err(err) => reply.error(err),
+        }
+    }
+
+    fn mknod(
+        &mut self,
+        _req: &Request<'_>,
+        parent: u64,
+        name: &OsStr,
+        mode: u32,
+        _umask: u32,
+        _rdev: u32,
+        reply: ReplyEntry,
+    ) {
+        // The overlay can only keep regular files
+        if mode & file_type::MASK != file_type::REGULAR {
+            reply.error(self.unsupported_change(EPERM));
+            return;
+        }
+
+        match self.create_file(parent, name) {
+            Ok(attr) => reply.entry(self.ttl(), &attr, 0),
+            Err(err) => reply.error(err),
+        }
+    }
+
+    fn mkdir(
+        &mut self,
+        _req: &Request<'_>,
+        _parent: u64,
+        _name: &OsStr,
+        _mode: u32,
+        _umask: u32,
+        reply: ReplyEntry,
+    ) {
+        reply.error(self.unsupported_change(EPERM));
+    }
+
+    fn write(
+        &mut self,
+        _req: &Request<'_>,
+        inode: u64,
+        _fh: u64,
+        offset: i64,
+        data: &[u8],
+        _write_flags: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyWrite,
+    ) {
+        let result = self.with_overlay(|overlay| {
+            let overlay = overlay.ok_or(EROFS)?;
+            overlay.copy_up(&self.archive, inode).map_err(|_| EIO)?;
+
+            OpenOptions::new()
+                .write(true)
+                .open(overlay.content_path(inode))
+                .and_then(|file| file.write_all_at(data, offset as u64))
+                .map_err(|_| EIO)
+        });
+
+        match result {
+            Ok(()) => reply.written(data.len() as u32),
+            Err(err) => reply.error(err),
+        }
+    }
+
+    fn unlink(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+        let name = name.to_string_lossy();
+
+        let result = self.with_overlay(|overlay| {
+            let overlay = overlay.ok_or(EROFS)?;
+            let inode = self
+                .find_child(Some(overlay), parent, &name)
+                .ok_or(ENOENT)?;
+
+            if self.is_dir(inode) {
+                return Err(EISDIR);
+            }
+
+            overlay.remove(inode);
+            Ok(())
+        });
+
+        match result {
+            Ok(()) => reply.ok(),
+            Err(err) => reply.error(err),
+        }
+    }
+
+    fn rmdir(&mut self, _req: &Request<'_>, _parent: u64, _name: &OsStr, reply: ReplyEmpty) {
+        reply.error(self.unsupported_change(EPERM));
+    }
+
+    fn symlink(
+        &mut self,
+        _req: &Request<'_>,
+        _parent: u64,
+        _name: &OsStr,
+        _link: &Path,
+        reply: ReplyEntry,
+    ) {
+        reply.error(self.unsupported_change(EPERM));
+    }
+
+    fn link(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        _new_parent: u64,
+        _new_name: &OsStr,
+        reply: ReplyEntry,
+    ) {
+        reply.error(self.unsupported_change(EPERM));
+    }
+
+    fn rename(
+        &mut self,
+        _req: &Request<'_>,
+        parent: u64,
+        name: &OsStr,
+        new_parent: u64,
+        new_name: &OsStr,
+        _flags: u32,
+        reply: ReplyEmpty,
+    ) {
+        let name = name.to_string_lossy();
+        let new_name = new_name.to_string_lossy();
+
+        let result = self.with_overlay(|overlay| {
+            let overlay = overlay.ok_or(EROFS)?;
+            let inode = self
+                .find_child(Some(overlay), parent, &name)
+                .ok_or(ENOENT)?;
+
+            // Directories would have to be moved into the overlay along with everything inside of them
+            if self.is_dir(inode) {
+                return Err(ENOTSUP);
+            }
+
+            if !self.is_dir(new_parent) {
+                return Err(ENOTDIR);
+            }
+
+            let existing = self.find_child(Some(overlay), new_parent, &new_name);
+
+            match existing {
+                Some(existing) if existing == inode => return Ok(()),
+                Some(existing) if self.is_dir(existing) => return Err(EISDIR),
+                _ => (),
+            }
+
+            overlay.copy_up(&self.archive, inode).map_err(|_| EIO)?;
+
+            if let Some(existing) = existing {
+                overlay.remove(existing);
+            }
+
+            overlay.rename(inode, new_parent, &new_name);
+            Ok(())
+        });
+
+        match result {
+            Ok(()) => reply.ok(),
+            Err(err) => reply.error(err),
+        }
+    }
+
+    fn release(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        fh: u64,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        _flush: bool,
+        reply: ReplyEmpty,
+    ) {
+        // Dropping the stream also stops its decompression.
+        // The file's chunks stay cached since reading them again is expensive.
+        self.handles.remove(&fh);
+        reply.ok();
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request<'_>,
+        inode: u64,
+        fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        self.reader.stats().reads.fetch_add(1, Ordering::Relaxed);
+
+        let changed = self.with_overlay(|overlay| {
+            let overlay = overlay.filter(|overlay| overlay.contains(inode))?;
+            Some(read_file_at(
+                &overlay.content_path(inode),
+                offset as u64,
+                size as usize,
+            ))
+        });
+
+        match changed {
+            Some(Ok(data)) => {
+                reply.data(&data);
+                return;
+            }
+            Some(Err(_)) => {
+                reply.error(EIO);
+                return;
+            }
+            None => (),
+        }
+
+        let (entry, stream, next_offset) = if let Some(Handle::File {
+            entry,
+            stream,
+            next_offset,
+        }) = self.handles.get_mut(&fh)
+        {
+            (*entry, stream, next_offset)
+        } else {
+            reply.error(EBADF);
+            return;
+        };
+
+        let offset = offset as u64;
+
+        let data = if let Ok(data) = self.reader.read(entry, stream, offset, size as u64) {
+            data
+        } else {
+            reply.error(EIO);
+            return;
+        };
+
+        reply.data(&data);
+
+        let end = offset + data.len() as u64;
+
+        if mem::replace(next_offset, end) == offset {
+            self.reader.prefetch(entry, stream, end);
+        }
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request<'_>,
+        inode: u64,
+        fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let node = if let Some(Handle::Directory(node_id)) = self.handles.get(&fh) {
+            &self.files[*node_id]
+        } else {
+            reply.error(EBADF);
+            return;
+        };
+
+        self.with_overlay(|overlay| {
+            let overlay = overlay.as_deref();
+
+            let archive_children = node.children.iter().filter_map(|&child_id| {
+                let child_inode = *child_id as u64 + FUSE_ROOT_ID;
+
+                // Entries that were changed where they are get listed with the rest of the overlay
+                let changed = overlay.is_some_and(|overlay| {
+                    overlay.contains(child_inode) || overlay.is_hidden(child_inode)
+                });
+
+                if changed {
+                    return None;
+                }
+
+                let child = &self.files[child_id];
+
+                let kind = match &child.props {
+                    EntryProperties::File(props) => file_kind(props),
+                    EntryProperties::Directory => FileType::Directory,
+                };
+
+                Some((child_inode, kind, child.name.as_str()))
+            });
+
+            let overlay_children = overlay
+                .into_iter()
+                .flat_map(|overlay| overlay.children(inode))
+                .map(|(child_inode, name)| (child_inode, FileType::RegularFile, name));
+
+            let children = archive_children
+                .chain(overlay_children)
+                .enumerate()
+                .skip(offset as usize);
+
+            for (i, (child_inode, kind, name)) in children {
+                if reply.add(child_inode, FUSE_ROOT_ID as i64 + i as i64, kind, name) {
+                    break;
+                }
+            }
+        });
+
+        reply.ok();
+    }
+
+    fn flush(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        _fh: u64,
+        _lock_owner: u64,
+        reply: ReplyEmpty,
+    ) {
+        reply.ok();
+    }
+
+    fn fsync(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        _fh: u64,
+        _datasync: bool,
+        reply: ReplyEmpty,
+    ) {
+        reply.ok();
+    }
+
+    fn opendir(&mut self, _req: &Request<'_>, inode: u64, _flags: i32, reply: ReplyOpen) {
+        let node_id = match self.get_node(inode) {
+            Some((node_id, node)) if node.props.is_dir() => node_id,
+            Some(_) => {
+                reply.error(ENOTDIR);
+                return;
+            }
+            None => {
+                reply.error(ENOENT);
+                return;
+            }
+        };
+
+        let fh = self.new_handle();
+        self.handles.insert(fh, Handle::Directory(node_id));
+
+        reply.opened(fh, 0);
+    }
+
+    fn releasedir(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        fh: u64,
+        _flags: i32,
+        reply: ReplyEmpty,
+    ) {
+        self.handles.remove(&fh);
+        reply.ok();
+    }
+
+    fn fsyncdir(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        _fh: u64,
+        _datasync: bool,
+        reply: ReplyEmpty,
+    ) {
+        reply.ok();
+    }
+
+    fn statfs(&mut self, _req: &Request<'_>, _ino: u64, reply: ReplyStatfs) {
+        // Only changes to a writable mount take up space, and they're kept on the disk of the overlay
+        let free_blocks = self
+            .with_overlay(|overlay| {
+                let overlay = overlay?;
+                DiskStats::read(overlay.dir()).ok()
+            })
+            .map_or(0, |stats| stats.available_bytes / Self::BLOCK_SIZE as u64);
+
+        reply.statfs(
+            self.total_blocks + free_blocks,
+            free_blocks,
+            free_blocks,
+            self.files.len() as u64,
+            0,
+            Self::BLOCK_SIZE,
+            255,
+            Self::BLOCK_SIZE,
+        );
+    }
+
+    fn access(&mut self, _req: &Request<'_>, _ino: u64, _mask: i32, reply: ReplyEmpty) {
+        reply.ok();
+    }
+
+    fn getxattr(
+        &mut self,
+        _req: &Request<'_>,
+        inode: u64,
+        name: &OsStr,
+        size: u32,
+        reply: ReplyXattr,
+    ) {
+        let xattrs = if let Some(xattrs) = self.xattrs(inode) {
+            xattrs
+        } else {
+            reply.error(ENOENT);
+            return;
+        };
+
+        match xattrs.iter().find(|(xattr, _)| name == xattr.as_str()) {
+            Some((_, value)) => reply_xattr(value.as_bytes(), size, reply),
+            None => reply.error(NO_XATTR),
+        }
+    }
+
+    fn listxattr(&mut self, _req: &Request<'_>, inode: u64, size: u32, reply: ReplyXattr) {
+        let xattrs = if let Some(xattrs) = self.xattrs(inode) {
+            xattrs
+        } else {
+            reply.error(ENOENT);
+            return;
+        };
+
+        // Each name ends with a null byte
+        let names = xattrs
+            .iter()
+            .flat_map(|(name, _)| name.bytes().chain(Some(0)))
+            .collect::<Vec<_>>();
+
+        reply_xattr(&names, size, reply);
+    }
+}
+
+/// The error to return when a file doesn't have the requested extended attribute.
+#[cfg(target_os = "macos")]
+const NO_XATTR: c_int = libc::ENOATTR;
+#[cfg(not(target_os = "macos"))]
+const NO_XATTR: c_int = libc::ENODATA;
+
+/// Reply to an extended attribute request with `data`.
+///
+/// A `size` of 0 means only the size of the data was asked for.
+fn reply_xattr(data: &[u8], size: u32, reply: ReplyXattr) {
+    let len = data.len() as u32;
+
+    if size == 0 {
+        reply.size(len);
+    } else if len > size {
+        reply.error(ERANGE);
+    } else {
+        reply.data(data);
+    }
+}
+
+/// Returns the kind of file that an archive entry with the given `props` is mounted as.
+fn file_kind(props: &FileProperties) -> FileType {
+    if props.is_symlink {
+        return FileType::Symlink;
+    }
+
+    match props.special {
+        Some(SpecialFile::Fifo) => FileType::NamedPipe,
+        Some(SpecialFile::CharDevice) => FileType::CharDevice,
+        Some(SpecialFile::BlockDevice) => FileType::BlockDevice,
+        Some(SpecialFile::Socket) => FileType::Socket,
+        None => FileType::RegularFile,
+    }
+}
+
+/// What an open handle refers to, so requests made with it don't have to look up its inode again.
+enum Handle {
+    /// An archive entry opened as a file, and the stream its chunks are decompressed with once
+    /// one of them isn't cached.
+    File {
+        entry: OpenEntry,
+        stream: Option<EntryStream>,
+        /// The offset right after the last read, which the next read starts at if the file is read sequentially.
+        next_offset: u64,
+    },
+    Directory(NodeID),
+}
+
+/// Read up to `size` bytes from the file at `path`, starting at `offset`.
+fn read_file_at(path: &Path, offset: u64, size: usize) -> io::Result<Vec<u8>> {
+    let file = File::open(path)?;
+    let mut data = vec![0; size];
+    let mut len = 0;
+
+    while len < size {
+        match file.read_at(&mut data[len..], offset + len as u64)? {
+            0 => break,
+            read => len += read,
+        }
+    }
+
+    data.truncate(len);
+    Ok(data)
+}
+
+/// Every mount that hasn't been unmounted yet, so they can all be unmounted when vear has to exit abruptly.
+static ACTIVE_MOUNTS: Mutex<Vec<ActiveMount>> = const_mutex(Vec::new());
+static NEXT_MOUNT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Unmount every archive that is still mounted.
+///
+/// This is meant to be called from a panic hook, so it gives up instead of waiting if the mounts are being changed.
+pub fn unmount_all() {
+    let mounts = match ACTIVE_MOUNTS.try_lock() {
+        Some(mut mounts) => mem::take(&mut *mounts),
+        None => return,
+    };
+
+    for mount in mounts {
+        mount.force_unmount();
+    }
+}
+
+/// A wrapper type around `fuser::BackgroundSession` that's safe to send across threads.
+///
+/// This uses unsafe impl's for `Send` and `Sync`. These should in fact be safe as the wrapper type prevents any kind of
+/// modification to the underlying `fuser::BackgroundSession`.
+struct ActiveMount {
+    id: u64,
+    handle: fuser::BackgroundSession,
+    path: PathBuf,
+    /// Whether `path` was created for this mount.
+    temp_dir: bool,
+    overlay: Option<Arc<Mutex<Overlay>>>,
+}
+
+impl ActiveMount {
+    /// Unmount the archive, or return the mount back if it's still being used.
+    fn try_unmount(self) -> Result<Option<MountChanges>, Self> {
+        // Dropping the handle unmounts the archive too, but gives up without saying why if it's busy
+        if let Ok(UnmountStatus::Busy) = unmount_path(&self.path, false) {
+            return Err(self);
+        }
+
+        Ok(self.finish())
+    }
+
+    /// Unmount the archive even if it's still being used.
+    fn force_unmount(self) -> Option<MountChanges> {
+        unmount_path(&self.path, true).ok();
+        self.finish()
+    }
+
+    fn finish(self) -> Option<MountChanges> {
+        // If unmounting failed for any other reason, dropping the handle tries it again
+        drop(self.handle);
+
+        if self.temp_dir {
+            fs::remove_dir(&self.path).ok();
+        }
+
+        let overlay = self.overlay?;
+        let changes = overlay.lock().finish();
+        changes
+    }
+}
+
+unsafe impl Send for ActiveMount {}
+unsafe impl Sync for ActiveMount {}
+
+/// A mounted archive, which is unmounted once this is dropped.
+pub struct ArchiveMountSession {
+    id: u64,
+    path: PathBuf,
+    stats: Arc<MountStats>,
+}
+
+impl ArchiveMountSession {
+    fn register(mount: ActiveMount, stats: Arc<MountStats>) -> Self {
+        let session = Self {
+            id: mount.id,
+            path: mount.path.clone(),
+            stats,
+        };
+
+        ACTIVE_MOUNTS.lock().push(mount);
+        session
+    }
+
+    /// Remove this session's mount from the active mounts, or return None if it was already unmounted.
+    fn take_mount(&self) -> Option<ActiveMount> {
+        let mut mounts = ACTIVE_MOUNTS.lock();
+        let pos = mounts.iter().position(|mount| mount.id == self.id)?;
+        Some(mounts.swap_remove(pos))
+    }
+
+    /// Unmount the archive and return what was changed through it, if it was writable and anything changed.
+    ///
+    /// Returns the session back if the mount is still being used, in which case it can be unmounted anyway
+    /// with [`ArchiveMountSession::force_unmount`].
+    pub fn unmount(self) -> Result<Option<MountChanges>, Self> {
+        let mount = match self.take_mount() {
+            Some(mount) => mount,
+            None => return Ok(None),
+        };
+
+        match mount.try_unmount() {
+            Ok(changes) => Ok(changes),
+            Err(mount) => {
+                ACTIVE_MOUNTS.lock().push(mount);
+                Err(self)
+            }
+        }
+    }
+
+    /// Unmount the archive like [`ArchiveMountSession::unmount`], even if it's still being used.
+    pub fn force_unmount(self) -> Option<MountChanges> {
+        self.take_mount()?.force_unmount()
+    }
+
+    /// Returns the directory that files changed through the mount are kept in, or None if it isn't writable or was
+    /// already unmounted.
+    pub fn overlay_dir(&self) -> Option<PathBuf> {
+        ACTIVE_MOUNTS
+            .lock()
+            .iter()
+            .find(|mount| mount.id == self.id)
+            .and_then(|mount| mount.overlay.as_ref())
+            .map(|overlay| overlay.lock().dir().to_path_buf())
+    }
+
+    /// Returns the path the archive is mounted at.
+    #[inline(always)]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns counters about how the mount is being read.
+    #[inline(always)]
+    pub fn stats(&self) -> &MountStats {
+        &self.stats
+    }
+
+    /// Returns false once the archive was unmounted from outside of vear, such as with `fusermount -u`.
+    pub fn is_mounted(&self) -> bool {
+        ACTIVE_MOUNTS
+            .lock()
+            .iter()
+            .find(|mount| mount.id == self.id)
+            .is_some_and(|mount| !mount.handle.guard.is_finished())
+    }
+}
+
+impl Drop for ArchiveMountSession {
+    fn drop(&mut self) {
+        if let Some(mount) = self.take_mount() {
+            mount.force_unmount();
+        }
+    }
+}
@@ -0,0 +1,295 @@
+#![allow(clippy::cast_lossless)]
+#![allow(clippy::cast_possible_wrap)]
+
+mod chunks;
+#[cfg(feature = "mount")]
+mod fuse;
+#[cfg(feature = "mount")]
+pub mod overlay;
+mod spill;
+#[cfg(not(feature = "mount"))]
+mod unsupported;
+pub mod webdav;
+
+#[cfg(feature = "mount")]
+pub use fuse::{unmount_all, ArchiveMountSession, MountedArchive};
+#[cfg(not(feature = "mount"))]
+pub use unsupported::{unmount_all, ArchiveMountSession, MountedArchive};
+
+use crate::util::size;
+use anyhow::{anyhow, Context, Result};
+use std::ffi::{CString, OsString};
+use std::fmt;
+use std::io;
+use std::num::NonZeroU64;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// Paths that exist when FUSE is installed.
+#[cfg(target_os = "macos")]
+const FUSE_PATHS: [&str; 2] = [
+    "/Library/Filesystems/macfuse.fs",
+    "/Library/Filesystems/osxfuse.fs",
+];
+#[cfg(not(target_os = "macos"))]
+const FUSE_PATHS: [&str; 1] = ["/dev/fuse"];
+
+/// Returns true if archives can be mounted, which needs vear to be built with the `mount` feature and FUSE
+/// (or macFUSE on macOS) to be installed.
+pub fn is_available() -> bool {
+    cfg!(feature = "mount") && FUSE_PATHS.iter().any(|path| Path::new(path).exists())
+}
+
+/// Commands that unmount a FUSE filesystem without needing root, in the order they're tried, along with
+/// the arguments to unmount normally and the arguments to unmount even if the filesystem is busy.
+#[cfg(target_os = "macos")]
+const UNMOUNT_COMMANDS: [(&str, &[&str], &[&str]); 1] = [("umount", &[], &["-f"])];
+/// Forcing fusermount to unmount is done lazily, which detaches the filesystem right away and cleans it up once nothing uses it.
+#[cfg(not(target_os = "macos"))]
+const UNMOUNT_COMMANDS: [(&str, &[&str], &[&str]); 2] = [
+    ("fusermount", &["-u"], &["-u", "-z"]),
+    ("fusermount3", &["-u"], &["-u", "-z"]),
+];
+
+/// Whether a filesystem was unmounted.
+#[derive(Copy, Clone, PartialEq)]
+pub enum UnmountStatus {
+    Unmounted,
+    /// The filesystem is still being used, so it was left mounted.
+    Busy,
+}
+
+/// Unmount the filesystem at `path`, such as an archive that a background vear process keeps mounted.
+///
+/// If `force` is true, the filesystem is unmounted even if it's still being used.
+pub fn unmount_path(path: &Path, force: bool) -> Result<UnmountStatus> {
+    for &(program, args, force_args) in &UNMOUNT_COMMANDS {
+        let args = if force { force_args } else { args };
+
+        let output = match Command::new(program)
+            .args(args)
+            .arg(path)
+            .stdin(Stdio::null())
+            .output()
+        {
+            Ok(output) => output,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err).with_context(|| anyhow!("failed to run {}", program)),
+        };
+
+        if output.status.success() {
+            return Ok(UnmountStatus::Unmounted);
+        }
+
+        let msg = String::from_utf8_lossy(&output.stderr);
+
+        // Both fusermount and umount only explain why they failed in their message
+        if msg.to_lowercase().contains("busy") {
+            return Ok(UnmountStatus::Busy);
+        }
+
+        return Err(anyhow!(
+            "failed to unmount {}: {}",
+            path.display(),
+            msg.trim()
+        ));
+    }
+
+    Err(anyhow!(
+        "failed to find a command to unmount {} with",
+        path.display()
+    ))
+}
+
+/// A process that is keeping a mount busy.
+pub struct MountUser {
+    pub pid: u32,
+    pub name: String,
+}
+
+impl fmt::Display for MountUser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.pid)
+    }
+}
+
+/// Returns every process that has a file open inside of the mount at `path` or is running inside of it.
+#[cfg(target_os = "linux")]
+pub fn processes_using(path: &Path) -> Vec<MountUser> {
+    use std::fs;
+
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+    let processes = match fs::read_dir("/proc") {
+        Ok(processes) => processes,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut users = processes
+        .filter_map(Result::ok)
+        .filter_map(|process| {
+            let pid = process.file_name().to_str()?.parse().ok()?;
+            let dir = process.path();
+
+            let links = ["cwd", "root", "exe"].iter().map(|link| dir.join(link));
+
+            // Reading the open files of other users' processes isn't allowed, so those are skipped
+            let files = fs::read_dir(dir.join("fd"))
+                .into_iter()
+                .flatten()
+                .filter_map(|file| Some(file.ok()?.path()));
+
+            let uses_mount = links
+                .chain(files)
+                .filter_map(|link| fs::read_link(link).ok())
+                .any(|target| target.starts_with(&path));
+
+            if !uses_mount {
+                return None;
+            }
+
+            let name = fs::read_to_string(dir.join("comm"))
+                .map(|name| name.trim_end().to_string())
+                .unwrap_or_default();
+
+            Some(MountUser { pid, name })
+        })
+        .collect::<Vec<_>>();
+
+    users.sort_unstable_by_key(|user| user.pid);
+    users
+}
+
+/// Returns every process that has a file open inside of the mount at `path` or is running inside of it.
+///
+/// There's no way to find out without lsof outside of Linux, so this always returns an empty list.
+#[cfg(not(target_os = "linux"))]
+pub fn processes_using(_path: &Path) -> Vec<MountUser> {
+    Vec::new()
+}
+
+/// Create a new directory named `<prefix>-XXXXXX` inside of the given `parent` directory.
+fn create_temp_dir(parent: &Path, prefix: &str) -> Result<PathBuf> {
+    let template = parent.join(format!("{}-XXXXXX", prefix));
+    let mut template = CString::new(template.as_os_str().as_bytes())?.into_bytes_with_nul();
+
+    // mkdtemp replaces the X's in place with a name that didn't exist yet
+    let result = unsafe { libc::mkdtemp(template.as_mut_ptr().cast()) };
+
+    if result.is_null() {
+        return Err(io::Error::last_os_error().into());
+    }
+
+    template.pop();
+    Ok(OsString::from_vec(template).into())
+}
+
+/// Settings for how an archive is mounted.
+#[derive(Copy, Clone, Default)]
+pub struct MountOptions {
+    /// The most memory decompressed files may be cached in, or None to use half of the available memory.
+    pub cache_limit: Option<CacheLimit>,
+    /// The most disk space chunks evicted from the cache may be kept in, or None to use a quarter of the
+    /// free space in the system's temporary directory.
+    pub spill_limit: Option<CacheLimit>,
+    /// Whether files can be changed, which keeps the changes in a directory on disk.
+    #[cfg_attr(not(feature = "mount"), allow(dead_code))]
+    pub writable: bool,
+}
+
+/// The most memory that decompressed files from a mount may be cached in.
+///
+/// Sizes are written as a number of bytes with an optional `K`, `M`, or `G` suffix, like `512M` or `2G`.
+#[derive(Copy, Clone)]
+pub struct CacheLimit {
+    pub bytes: NonZeroU64,
+}
+
+impl FromStr for CacheLimit {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let bytes = size::parse(value)
+            .and_then(NonZeroU64::new)
+            .ok_or_else(|| {
+                anyhow!(
+                    "invalid cache size: {} (expected a size like 512M or 2G)",
+                    value.trim()
+                )
+            })?;
+
+        Ok(Self { bytes })
+    }
+}
+
+/// Counters about a mounted archive that are updated as it's read from.
+pub struct MountStats {
+    /// The number of read requests made to the mount.
+    pub reads: AtomicU64,
+    /// The number of decompressed chunks the read requests needed.
+    pub chunk_reads: AtomicU64,
+    /// The number of needed chunks that were already cached.
+    pub chunk_hits: AtomicU64,
+    /// The number of entries with at least one chunk cached.
+    pub cached_entries: AtomicUsize,
+    pub cached_bytes: AtomicU64,
+    pub max_cached_bytes: u64,
+    /// The number of bytes of chunks that were evicted from the cache and kept on disk instead.
+    pub spilled_bytes: AtomicU64,
+}
+
+impl MountStats {
+    fn new(max_cached_bytes: u64) -> Self {
+        Self {
+            reads: AtomicU64::new(0),
+            chunk_reads: AtomicU64::new(0),
+            chunk_hits: AtomicU64::new(0),
+            cached_entries: AtomicUsize::new(0),
+            cached_bytes: AtomicU64::new(0),
+            max_cached_bytes,
+            spilled_bytes: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the percentage of needed chunks that were already cached, or None if no chunks were needed yet.
+    pub fn hit_rate(&self) -> Option<f64> {
+        let reads = self.chunk_reads.load(Ordering::Relaxed);
+        let hits = self.chunk_hits.load(Ordering::Relaxed);
+
+        if reads == 0 {
+            return None;
+        }
+
+        Some(hits as f64 / reads as f64 * 100.0)
+    }
+}
+
+/// The amount of memory a mount may need compared to how much is available.
+pub struct SpaceEstimate {
+    pub needed_bytes: u64,
+    pub available_bytes: u64,
+}
+
+/// Everything that was changed through a writable mount.
+pub struct MountChanges {
+    /// The directory that the content of changed and added files was kept in.
+    pub dir: PathBuf,
+    pub changes: Vec<MountChange>,
+}
+
+pub enum MountChange {
+    Added(PathBuf),
+    Modified(PathBuf),
+    Removed(PathBuf),
+}
+
+impl MountChange {
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Added(path) | Self::Modified(path) | Self::Removed(path) => path,
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use super::{MountChange, MountChanges};
+use crate::archive::{Archive, ArchiveEntries, NodeID};
+use anyhow::{anyhow, Context, Result};
+use fuser::FUSE_ROOT_ID;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Changes made to the files of a writable mount.
+///
+/// Since archives can't be written to, the content of every changed or created file is kept in a directory on disk instead.
+pub struct Overlay {
+    dir: PathBuf,
+    files: Arc<ArchiveEntries>,
+    /// Files whose content is kept in the overlay, keyed by their inode.
+    changed: HashMap<u64, OverlayFile>,
+    /// Inodes of archive entries that were removed or moved away from where they are in the archive.
+    hidden: HashSet<u64>,
+    next_inode: u64,
+    finished: bool,
+}
+
+/// Where a file in the overlay is located in the mount.
+struct OverlayFile {
+    /// The inode of the directory the file is in, which is always a directory from the archive.
+    parent: u64,
+    name: String,
+}
+
+impl Overlay {
+    /// The directory that the content of each file is kept in while mounted, named after the file's inode.
+    const CONTENT_DIR: &'static str = ".vear-content";
+
+    /// Create a new `Overlay` for the given archive `files` that keeps changes inside of `dir`.
+    ///
+    /// `dir` is removed once the overlay is finished if nothing was changed.
+    pub fn new(dir: PathBuf, files: Arc<ArchiveEntries>) -> Result<Self> {
+        let content_dir = dir.join(Self::CONTENT_DIR);
+
+        if let Err(err) = fs::create_dir(&content_dir) {
+            fs::remove_dir(&dir).ok();
+            return Err(err).with_context(|| anyhow!("failed to create {}", content_dir.display()));
+        }
+
+        let next_inode = Self::first_new_inode(&files);
+
+        Ok(Self {
+            dir,
+            files,
+            changed: HashMap::new(),
+            hidden: HashSet::new(),
+            next_inode,
+            finished: false,
+        })
+    }
+
+    /// Returns the inode given to the first file that is created through the mount.
+    ///
+    /// Every inode before it belongs to an archive entry.
+    fn first_new_inode(files: &ArchiveEntries) -> u64 {
+        files.len() as u64 + FUSE_ROOT_ID
+    }
+
+    fn node_id(&self, inode: u64) -> Option<NodeID> {
+        let id = inode.checked_sub(FUSE_ROOT_ID)?;
+        self.files.create_node_id(id as u32)
+    }
+
+    /// Returns the directory the changes are kept in.
+    #[inline(always)]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the path the content of the file with the given `inode` is kept at.
+    pub fn content_path(&self, inode: u64) -> PathBuf {
+        self.dir.join(Self::CONTENT_DIR).join(inode.to_string())
+    }
+
+    /// Returns true if the content of the file with the given `inode` is kept in the overlay.
+    #[inline(always)]
+    pub fn contains(&self, inode: u64) -> bool {
+        self.changed.contains_key(&inode)
+    }
+
+    /// Returns true if the archive entry with the given `inode` is no longer where it is in the archive.
+    #[inline(always)]
+    pub fn is_hidden(&self, inode: u64) -> bool {
+        self.hidden.contains(&inode)
+    }
+
+    /// Returns the inode of the file in the overlay called `name` inside of the directory `parent`.
+    pub fn find(&self, parent: u64, name: &str) -> Option<u64> {
+        self.changed
+            .iter()
+            .find(|(_, file)| file.parent == parent && file.name == name)
+            .map(|(&inode, _)| inode)
+    }
+
+    /// Returns the inode and name of every file in the overlay inside of the directory `parent`.
+    pub fn children(&self, parent: u64) -> impl Iterator<Item = (u64, &str)> {
+        self.changed
+            .iter()
+            .filter(move |(_, file)| file.parent == parent)
+            .map(|(&inode, file)| (inode, file.name.as_str()))
+    }
+
+    /// Move the content of the archive entry with the given `inode` into the overlay so it can be changed.
+    ///
+    /// Does nothing if the entry's content is already in the overlay.
+    pub fn copy_up(&mut self, archive: &Archive, inode: u64) -> Result<()> {
+        if self.contains(inode) {
+            return Ok(());
+        }
+
+        let id = self
+            .node_id(inode)
+            .ok_or_else(|| anyhow!("no archive entry with inode {}", inode))?;
+
+        let entry = &self.files[id];
+        let path = self.content_path(inode);
+
+        let mut file =
+            File::create(&path).with_context(|| anyhow!("failed to create {}", path.display()))?;
+
+        archive.write_entry(entry, &mut file)?;
+
+        let file = OverlayFile {
+            parent: entry
+                .parent
+                .map_or(FUSE_ROOT_ID, |parent| *parent as u64 + FUSE_ROOT_ID),
+            name: entry.name.clone(),
+        };
+
+        self.changed.insert(inode, file);
+        Ok(())
+    }
+
+    /// Create a new empty file called `name` inside of the directory `parent` and return its inode.
+    pub fn create(&mut self, parent: u64, name: &str) -> Result<u64> {
+        let inode = self.next_inode;
+        let path = self.content_path(inode);
+
+        File::create(&path).with_context(|| anyhow!("failed to create {}", path.display()))?;
+
+        let file = OverlayFile {
+            parent,
+            name: name.into(),
+        };
+
+        self.changed.insert(inode, file);
+        self.next_inode += 1;
+
+        Ok(inode)
+    }
+
+    /// Remove the file with the given `inode` from the mount.
+    pub fn remove(&mut self, inode: u64) {
+        if self.changed.remove(&inode).is_some() {
+            fs::remove_file(self.content_path(inode)).ok();
+        }
+
+        if inode < Self::first_new_inode(&self.files) {
+            self.hidden.insert(inode);
+        }
+    }
+
+    /// Move the file with the given `inode` to `name` inside of the directory `parent`.
+    ///
+    /// Archive entries have to be moved into the overlay with [`Overlay::copy_up`] first.
+    pub fn rename(&mut self, inode: u64, parent: u64, name: &str) {
+        if let Some(file) = self.changed.get_mut(&inode) {
+            file.parent = parent;
+            file.name = name.into();
+        }
+
+        if inode < Self::first_new_inode(&self.files) {
+            self.hidden.insert(inode);
+        }
+    }
+
+    /// Returns the path of the archive entry with the given `inode`.
+    fn archive_path(&self, inode: u64) -> PathBuf {
+        self.node_id(inode)
+            .map(|id| self.files.path_of(id))
+            .unwrap_or_default()
+    }
+
+    /// Move the content of every file in the overlay to its path inside of the overlay directory and
+    /// return what changed.
+    ///
+    /// Returns None if this was already called or nothing changed, in which case the overlay directory is removed.
+    pub fn finish(&mut self) -> Option<MountChanges> {
+        if mem::replace(&mut self.finished, true) {
+            return None;
+        }
+
+        let kept = mem::take(&mut self.changed);
+        let hidden = mem::take(&mut self.hidden);
+        let mut changes = Vec::with_capacity(kept.len() + hidden.len());
+
+        for &inode in &hidden {
+            changes.push(MountChange::Removed(self.archive_path(inode)));
+        }
+
+        for (inode, file) in kept {
+            let path = self.archive_path(file.parent).join(&file.name);
+
+            let dest = self.dir.join(&path);
+            let moved = dest
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|()| fs::rename(self.content_path(inode), &dest));
+
+            // The content is still kept under its inode otherwise, so it isn't lost
+            if moved.is_err() {
+                continue;
+            }
+
+            // Archive entries that moved count as being removed and added again
+            if inode < Self::first_new_inode(&self.files) && !hidden.contains(&inode) {
+                changes.push(MountChange::Modified(path));
+            } else {
+                changes.push(MountChange::Added(path));
+            }
+        }
+
+        // This only succeeds if every file was moved out of it
+        fs::remove_dir(self.dir.join(Self::CONTENT_DIR)).ok();
+
+        if changes.is_empty() {
+            fs::remove_dir(&self.dir).ok();
+            return None;
+        }
+
+        changes.sort_unstable_by(|x, y| x.path().cmp(y.path()));
+
+        Some(MountChanges {
+            dir: self.dir.clone(),
+            changes,
+        })
+    }
+}
+
+impl Drop for Overlay {
+    fn drop(&mut self) {
+        // Makes sure the content of changed files ends up at the right path even if nobody asked for the changes
+        self.finish();
+    }
+}
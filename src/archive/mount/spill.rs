@@ -0,0 +1,124 @@
+use super::chunks::ChunkKey;
+use super::MountStats;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+/// Chunks that were evicted from the memory cache of a mount, kept in files on disk so reading them again
+/// doesn't mean decompressing them all over again.
+///
+/// Once the chunks take up more than the limit, the ones that were spilled first are removed.
+pub struct SpillDir {
+    dir: PathBuf,
+    chunks: HashMap<ChunkKey, SpilledChunk>,
+    /// The key of each chunk ordered by when it was spilled.
+    spill_order: BTreeMap<u64, ChunkKey>,
+    next_spill: u64,
+    used_bytes: u64,
+    max_bytes: u64,
+    stats: Arc<MountStats>,
+}
+
+struct SpilledChunk {
+    len: u64,
+    spilled_at: u64,
+}
+
+impl SpillDir {
+    /// Create a new `SpillDir` that keeps up to `max_bytes` of chunks inside of `dir`.
+    ///
+    /// `dir` and everything inside of it is removed once this is dropped.
+    pub fn new(dir: PathBuf, max_bytes: u64, stats: Arc<MountStats>) -> Self {
+        Self {
+            dir,
+            chunks: HashMap::new(),
+            spill_order: BTreeMap::new(),
+            next_spill: 0,
+            used_bytes: 0,
+            max_bytes,
+            stats,
+        }
+    }
+
+    fn chunk_path(&self, key: ChunkKey) -> PathBuf {
+        let (node_id, index) = key;
+        self.dir.join(format!("{}-{}", *node_id, index))
+    }
+
+    /// Write the chunk with the given `key` to disk.
+    ///
+    /// Chunks that are bigger than the limit by themselves or can't be written are dropped instead.
+    pub fn insert(&mut self, key: ChunkKey, data: &[u8]) {
+        let size = data.len() as u64;
+
+        if size > self.max_bytes || self.chunks.contains_key(&key) {
+            return;
+        }
+
+        while self.used_bytes + size > self.max_bytes {
+            let oldest = match self.spill_order.keys().next() {
+                Some(&oldest) => oldest,
+                None => break,
+            };
+
+            if let Some(key) = self.spill_order.remove(&oldest) {
+                self.remove(key);
+            }
+        }
+
+        let path = self.chunk_path(key);
+
+        // A partially written chunk would be read back as if it were complete
+        if fs::write(&path, data).is_err() {
+            fs::remove_file(&path).ok();
+            return;
+        }
+
+        let chunk = SpilledChunk {
+            len: size,
+            spilled_at: self.next_spill,
+        };
+
+        self.chunks.insert(key, chunk);
+        self.spill_order.insert(self.next_spill, key);
+        self.next_spill += 1;
+        self.used_bytes += size;
+
+        self.update_stats();
+    }
+
+    /// Read the chunk with the given `key` back and remove it from disk.
+    ///
+    /// Returns None if the chunk wasn't spilled or couldn't be read back.
+    pub fn take(&mut self, key: ChunkKey) -> Option<Vec<u8>> {
+        let len = self.chunks.get(&key)?.len;
+        let data = fs::read(self.chunk_path(key)).ok();
+
+        self.remove(key);
+        self.update_stats();
+
+        data.filter(|data| data.len() as u64 == len)
+    }
+
+    fn remove(&mut self, key: ChunkKey) {
+        if let Some(chunk) = self.chunks.remove(&key) {
+            self.spill_order.remove(&chunk.spilled_at);
+            self.used_bytes -= chunk.len;
+            fs::remove_file(self.chunk_path(key)).ok();
+        }
+    }
+
+    fn update_stats(&self) {
+        self.stats
+            .spilled_bytes
+            .store(self.used_bytes, Ordering::Relaxed);
+    }
+}
+
+impl Drop for SpillDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.dir).ok();
+    }
+}
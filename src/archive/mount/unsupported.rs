@@ -0,0 +1,71 @@
+use super::{MountChanges, MountOptions, MountStats, SpaceEstimate};
+use crate::archive::Archive;
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Stands in for a mountable archive when vear is built without the `mount` feature.
+///
+/// This can never be created, so none of its methods can be called.
+pub enum MountedArchive {}
+
+impl MountedArchive {
+    pub fn new(_archive: &Arc<Archive>, _options: MountOptions) -> Result<Self> {
+        Err(anyhow!(
+            "vear was built without support for mounting archives"
+        ))
+    }
+
+    pub fn mount<P>(self, _path: P) -> Result<ArchiveMountSession>
+    where
+        P: AsRef<Path>,
+    {
+        match self {}
+    }
+
+    pub fn mount_in_temp_dir<P>(self, _parent: P) -> Result<ArchiveMountSession>
+    where
+        P: AsRef<Path>,
+    {
+        match self {}
+    }
+
+    pub fn tmpfs_space_check<P>(&self, _path: P) -> Result<Option<SpaceEstimate>>
+    where
+        P: AsRef<Path>,
+    {
+        match *self {}
+    }
+}
+
+/// Stands in for a mounted archive when vear is built without the `mount` feature.
+pub enum ArchiveMountSession {}
+
+impl ArchiveMountSession {
+    pub fn unmount(self) -> Result<Option<MountChanges>, Self> {
+        match self {}
+    }
+
+    pub fn force_unmount(self) -> Option<MountChanges> {
+        match self {}
+    }
+
+    pub fn overlay_dir(&self) -> Option<PathBuf> {
+        match *self {}
+    }
+
+    pub fn path(&self) -> &Path {
+        match *self {}
+    }
+
+    pub fn stats(&self) -> &MountStats {
+        match *self {}
+    }
+
+    pub fn is_mounted(&self) -> bool {
+        match *self {}
+    }
+}
+
+/// Does nothing, since nothing can be mounted.
+pub fn unmount_all() {}
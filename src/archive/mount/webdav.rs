@@ -0,0 +1,718 @@
+use super::chunks::{ChunkReader, OpenEntry};
+use super::MountOptions;
+use crate::archive::{Archive, ArchiveEntries, ArchiveEntry, EntryProperties, NodeID};
+use anyhow::{anyhow, Context, Result};
+use chrono::NaiveDateTime;
+use parking_lot::Mutex;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+
+/// Serves the entries of an archive read-only over HTTP, so it can be mounted by the OS or browsed with a
+/// file manager on systems where FUSE isn't available.
+///
+/// Files are read through the same chunk cache that a FUSE mount uses.
+pub struct WebDavServer {
+    listener: TcpListener,
+    files: Arc<ArchiveEntries>,
+    reader: Mutex<ChunkReader>,
+}
+
+impl WebDavServer {
+    /// The methods that can be used on every path.
+    const ALLOWED_METHODS: &'static str = "OPTIONS, GET, HEAD, PROPFIND";
+    /// How much of a file is read at a time while sending it, so other requests get a turn with the cache.
+    const SEND_SIZE: u64 = 256 * 1024;
+    /// The biggest request body that is skipped over instead of closing the connection.
+    const MAX_SKIPPED_BODY: u64 = 1024 * 1024;
+
+    /// Prepare the given `archive` to be served on `addr`, caching files read from it within the limits of
+    /// `options`.
+    ///
+    /// The server will always show the archive's entries as they were when this was called.
+    pub fn bind(archive: &Arc<Archive>, addr: SocketAddr, options: MountOptions) -> Result<Self> {
+        // Make sure the archive can still be read before anything is served
+        archive.open_reader()?;
+
+        let listener =
+            TcpListener::bind(addr).with_context(|| anyhow!("failed to listen on {}", addr))?;
+
+        Ok(Self {
+            listener,
+            files: archive.entries(),
+            reader: Mutex::new(ChunkReader::new(archive, options)?),
+        })
+    }
+
+    /// Returns the address the server is listening on, which has the port that was picked if port 0 was given.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.listener
+            .local_addr()
+            .context("failed to get the address being listened on")
+    }
+
+    /// Accept connections forever, handling each one on its own thread.
+    pub fn serve(self: &Arc<Self>) {
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+
+            let server = Arc::clone(self);
+
+            thread::spawn(move || {
+                // The client closing the connection early is the only way this fails, so there's nothing to do about it
+                server.handle_connection(stream).ok();
+            });
+        }
+    }
+
+    /// Remove the chunks that were spilled to disk, which should be done before vear exits since the server
+    /// is never dropped while its connections may still be using it.
+    pub fn close(&self) {
+        self.reader.lock().remove_spill();
+    }
+
+    fn handle_connection(&self, stream: TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = BufWriter::new(stream);
+
+        loop {
+            let request = match Request::read(&mut reader) {
+                Ok(Some(request)) => request,
+                Ok(None) => return Ok(()),
+                Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+                    let response = Response::new(400).text(err.to_string());
+                    return self.send(response, false, false, &mut writer);
+                }
+                Err(err) => return Err(err),
+            };
+
+            let keep_alive = request.keep_alive && request.skip_body(&mut reader)?;
+            let response = self.respond(&request);
+
+            self.send(response, request.method == "HEAD", keep_alive, &mut writer)?;
+
+            if !keep_alive {
+                return Ok(());
+            }
+        }
+    }
+
+    fn respond(&self, request: &Request) -> Response {
+        // Keeps websites from reading the archive through a domain that was pointed at localhost
+        if !request.header("host").is_some_and(is_local_host) {
+            return Response::new(403).text("requests must be made to localhost");
+        }
+
+        let node_id = match self.files.find_path(&request.path) {
+            Some(node_id) => node_id,
+            None => return Response::new(404).text("not found"),
+        };
+
+        match request.method.as_str() {
+            "OPTIONS" => Response::new(200)
+                .header("DAV", "1")
+                .header("Allow", Self::ALLOWED_METHODS)
+                // Lets Windows know it can mount the server
+                .header("MS-Author-Via", "DAV"),
+            "GET" | "HEAD" => self.get(request, node_id),
+            "PROPFIND" => self.propfind(request, node_id),
+            _ => Response::new(405)
+                .header("Allow", Self::ALLOWED_METHODS)
+                .text("the archive can only be read"),
+        }
+    }
+
+    fn get(&self, request: &Request, node_id: NodeID) -> Response {
+        let node = &self.files[node_id];
+
+        let props = match &node.props {
+            EntryProperties::File(props) => props,
+            EntryProperties::Directory => return self.list_directory(node_id),
+        };
+
+        let size = props.raw_size_bytes;
+        let range = request
+            .header("range")
+            .map_or(ByteRange::Whole, |range| ByteRange::parse(range, size));
+
+        let (status, start, end) = match range {
+            ByteRange::Whole => (200, 0, size),
+            ByteRange::Partial(start, end) => (206, start, end),
+            ByteRange::Unsatisfiable => {
+                return Response::new(416).header("Content-Range", format!("bytes */{}", size))
+            }
+        };
+
+        let mut response = Response::new(status)
+            .header("Accept-Ranges", "bytes")
+            .header("Content-Type", "application/octet-stream")
+            .header("ETag", etag(node));
+
+        if let Some(modified) = http_date(node) {
+            response = response.header("Last-Modified", modified);
+        }
+
+        if status == 206 {
+            response = response.header(
+                "Content-Range",
+                format!("bytes {}-{}/{}", start, end - 1, size),
+            );
+        }
+
+        response.body = Body::Entry {
+            entry: OpenEntry::new(node_id, node),
+            start,
+            end,
+        };
+
+        response
+    }
+
+    /// Returns a page that links to everything inside of the directory at `node_id`, for when it's opened
+    /// in a browser.
+    fn list_directory(&self, node_id: NodeID) -> Response {
+        let href = self.href(node_id);
+        let title = format!("Index of {}", escape(&percent_decode(&href)));
+
+        let mut page = format!(
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{0}</title></head>\n<body><h1>{0}</h1><ul>\n",
+            title
+        );
+
+        if let Some(parent) = self.files[node_id].parent {
+            writeln!(
+                page,
+                "<li><a href=\"{}\">../</a></li>",
+                escape(&self.href(parent))
+            )
+            .ok();
+        }
+
+        for &child_id in &self.files[node_id].children {
+            let child = &self.files[child_id];
+            let suffix = if child.props.is_dir() { "/" } else { "" };
+
+            writeln!(
+                page,
+                "<li><a href=\"{}\">{}{}</a></li>",
+                escape(&self.href(child_id)),
+                escape(&child.name),
+                suffix
+            )
+            .ok();
+        }
+
+        page.push_str("</ul></body></html>\n");
+
+        Response::new(200).body("text/html; charset=utf-8", page.into_bytes())
+    }
+
+    fn propfind(&self, request: &Request, node_id: NodeID) -> Response {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<D:multistatus xmlns:D=\"DAV:\">\n",
+        );
+
+        self.write_props(&mut xml, node_id);
+
+        // Listing the whole tree at once would be huge for big archives, so it's treated like a depth of 1
+        if request.header("depth") != Some("0") {
+            for &child_id in &self.files[node_id].children {
+                self.write_props(&mut xml, child_id);
+            }
+        }
+
+        xml.push_str("</D:multistatus>\n");
+
+        Response::new(207).body("application/xml; charset=utf-8", xml.into_bytes())
+    }
+
+    /// Append a response with every property of the entry at `node_id` to the multistatus `xml`.
+    fn write_props(&self, xml: &mut String, node_id: NodeID) {
+        let node = &self.files[node_id];
+
+        write!(
+            xml,
+            "<D:response><D:href>{}</D:href><D:propstat><D:prop><D:displayname>{}</D:displayname>",
+            escape(&self.href(node_id)),
+            escape(&node.name)
+        )
+        .ok();
+
+        match &node.props {
+            EntryProperties::File(props) => {
+                write!(
+                    xml,
+                    "<D:resourcetype/><D:getcontentlength>{}</D:getcontentlength>\
+                     <D:getcontenttype>application/octet-stream</D:getcontenttype>\
+                     <D:getetag>{}</D:getetag>",
+                    props.raw_size_bytes,
+                    escape(&etag(node))
+                )
+                .ok();
+            }
+            EntryProperties::Directory => {
+                xml.push_str("<D:resourcetype><D:collection/></D:resourcetype>");
+            }
+        }
+
+        if let Some(modified) = http_date(node) {
+            write!(xml, "<D:getlastmodified>{}</D:getlastmodified>", modified).ok();
+        }
+
+        xml.push_str("</D:prop><D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response>\n");
+    }
+
+    /// Returns the percent-encoded path of the entry at `node_id`, which ends with a slash for directories.
+    fn href(&self, node_id: NodeID) -> String {
+        let mut names = Vec::new();
+        let mut cur_node = node_id;
+
+        while let Some(parent) = self.files[cur_node].parent {
+            names.push(percent_encode(&self.files[cur_node].name));
+            cur_node = parent;
+        }
+
+        names.reverse();
+
+        let mut href = format!("/{}", names.join("/"));
+
+        if !names.is_empty() && self.files[node_id].props.is_dir() {
+            href.push('/');
+        }
+
+        href
+    }
+
+    fn send<W>(
+        &self,
+        response: Response,
+        head_only: bool,
+        keep_alive: bool,
+        writer: &mut W,
+    ) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let content_length = match &response.body {
+            Body::Bytes(bytes) => bytes.len() as u64,
+            Body::Entry { start, end, .. } => end - start,
+        };
+
+        write!(
+            writer,
+            "HTTP/1.1 {} {}\r\nContent-Length: {}\r\n",
+            response.status,
+            reason_phrase(response.status),
+            content_length
+        )?;
+
+        for (name, value) in &response.headers {
+            write!(writer, "{}: {}\r\n", name, value)?;
+        }
+
+        if !keep_alive {
+            writer.write_all(b"Connection: close\r\n")?;
+        }
+
+        writer.write_all(b"\r\n")?;
+
+        if !head_only {
+            match response.body {
+                Body::Bytes(bytes) => writer.write_all(&bytes)?,
+                Body::Entry { entry, start, end } => self.send_entry(entry, start, end, writer)?,
+            }
+        }
+
+        writer.flush()
+    }
+
+    /// Write the bytes of `entry` from `start` up to `end` to `writer`.
+    fn send_entry<W>(
+        &self,
+        entry: OpenEntry,
+        start: u64,
+        end: u64,
+        writer: &mut W,
+    ) -> io::Result<()>
+    where
+        W: Write,
+    {
+        // Each request gets its own stream so sending the same file twice at once doesn't keep restarting it
+        let mut stream = None;
+        let mut pos = start;
+
+        while pos < end {
+            let data = {
+                let mut reader = self.reader.lock();
+                reader.stats().reads.fetch_add(1, Ordering::Relaxed);
+
+                let data = reader
+                    .read(entry, &mut stream, pos, (end - pos).min(Self::SEND_SIZE))
+                    .map_err(|err| io::Error::other(format!("{:#}", err)))?;
+
+                reader.prefetch(entry, &mut stream, pos + data.len() as u64);
+                data
+            };
+
+            // The entry is shorter than the archive claims, so the promised length can't be sent
+            if data.is_empty() {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+
+            writer.write_all(&data)?;
+            pos += data.len() as u64;
+        }
+
+        Ok(())
+    }
+}
+
+/// The parts of an HTTP request needed to answer it.
+struct Request {
+    method: String,
+    /// The decoded path of the requested entry from the root of the archive.
+    path: String,
+    /// Every header with its name in lowercase.
+    headers: Vec<(String, String)>,
+    keep_alive: bool,
+}
+
+impl Request {
+    /// The longest request line or header that will be read.
+    const MAX_LINE_LEN: u64 = 8 * 1024;
+    const MAX_HEADERS: usize = 100;
+
+    /// Read the next request's line and headers from `reader`, leaving its body unread.
+    ///
+    /// Returns None if the connection was closed before another request was sent.
+    fn read<R>(reader: &mut R) -> io::Result<Option<Self>>
+    where
+        R: BufRead,
+    {
+        let line = match read_line(reader)? {
+            Some(line) => line,
+            None => return Ok(None),
+        };
+
+        let mut parts = line.split(' ');
+
+        let (method, target, version) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(method), Some(target), Some(version)) => (method, target, version),
+            _ => return Err(invalid_data("malformed request line")),
+        };
+
+        let mut headers = Vec::new();
+
+        loop {
+            let line = read_line(reader)?.ok_or_else(|| invalid_data("request ended early"))?;
+
+            if line.is_empty() {
+                break;
+            }
+
+            if headers.len() >= Self::MAX_HEADERS {
+                return Err(invalid_data("too many headers"));
+            }
+
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| invalid_data("malformed header"))?;
+
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+
+        let mut request = Self {
+            method: method.to_string(),
+            path: percent_decode(request_path(target)),
+            headers,
+            keep_alive: false,
+        };
+
+        // HTTP/1.1 connections stay open unless the client says otherwise
+        request.keep_alive = version == "HTTP/1.1"
+            && !request
+                .header("connection")
+                .is_some_and(|value| value.eq_ignore_ascii_case("close"));
+
+        Ok(Some(request))
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(cur_name, _)| cur_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Read past the request's body, since none of the supported methods need it.
+    ///
+    /// Returns false if the body can't be skipped, in which case the connection has to be closed after responding.
+    fn skip_body<R>(&self, reader: &mut R) -> io::Result<bool>
+    where
+        R: Read,
+    {
+        if self.header("transfer-encoding").is_some() {
+            return Ok(false);
+        }
+
+        let len = match self.header("content-length").map(str::parse::<u64>) {
+            Some(Ok(len)) => len,
+            Some(Err(_)) => return Ok(false),
+            None => return Ok(true),
+        };
+
+        if len > WebDavServer::MAX_SKIPPED_BODY {
+            return Ok(false);
+        }
+
+        let skipped = io::copy(&mut reader.take(len), &mut io::sink())?;
+        Ok(skipped == len)
+    }
+}
+
+/// Read a line without its line ending from `reader`.
+///
+/// Returns None if the end of the stream was reached before anything was read.
+fn read_line<R>(reader: &mut R) -> io::Result<Option<String>>
+where
+    R: BufRead,
+{
+    let mut line = Vec::new();
+    reader
+        .take(Request::MAX_LINE_LEN)
+        .read_until(b'\n', &mut line)?;
+
+    if line.is_empty() {
+        return Ok(None);
+    }
+
+    if line.pop() != Some(b'\n') {
+        return Err(invalid_data("line is too long"));
+    }
+
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+
+    String::from_utf8(line)
+        .map(Some)
+        .map_err(|_| invalid_data("request isn't valid UTF-8"))
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Returns the path of a request's `target`, which may also have a scheme, host, and query.
+fn request_path(target: &str) -> &str {
+    let path = match target.find("://") {
+        Some(pos) => {
+            let after_scheme = &target[pos + 3..];
+            after_scheme
+                .find('/')
+                .map_or("/", |start| &after_scheme[start..])
+        }
+        None => target,
+    };
+
+    path.split('?').next().unwrap_or(path)
+}
+
+/// Returns true if the value of a `Host` header points to the local machine.
+fn is_local_host(host: &str) -> bool {
+    let name = if host.starts_with('[') {
+        host.split(']').next().map_or(host, |name| &name[1..])
+    } else {
+        host.split(':').next().unwrap_or(host)
+    };
+
+    matches!(name, "localhost" | "127.0.0.1" | "::1")
+}
+
+/// Which part of a file a request asked for with a `Range` header.
+#[derive(Copy, Clone)]
+enum ByteRange {
+    Whole,
+    /// The start and the exclusive end of the requested bytes.
+    Partial(u64, u64),
+    Unsatisfiable,
+}
+
+impl ByteRange {
+    /// Parse the `value` of a `Range` header for a file that is `size` bytes long.
+    ///
+    /// Headers that can't be parsed or ask for several ranges are answered with the whole file.
+    fn parse(value: &str, size: u64) -> Self {
+        let spec = match value.trim().strip_prefix("bytes=") {
+            Some(spec) if !spec.contains(',') => spec.trim(),
+            _ => return Self::Whole,
+        };
+
+        let (start, end) = match spec.split_once('-') {
+            Some(bounds) => bounds,
+            None => return Self::Whole,
+        };
+
+        let (start, end) = match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(start), _) if start >= size => return Self::Unsatisfiable,
+            (Ok(start), Ok(end)) if start <= end => (start, (end + 1).min(size)),
+            (Ok(start), Err(_)) if end.is_empty() => (start, size),
+            // The last bytes of the file
+            (Err(_), Ok(len)) if start.is_empty() => {
+                if len == 0 || size == 0 {
+                    return Self::Unsatisfiable;
+                }
+
+                (size.saturating_sub(len), size)
+            }
+            _ => return Self::Whole,
+        };
+
+        Self::Partial(start, end)
+    }
+}
+
+/// A response to a request, which is sent all at once.
+struct Response {
+    status: u16,
+    headers: Vec<(&'static str, String)>,
+    body: Body,
+}
+
+impl Response {
+    fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Body::Bytes(Vec::new()),
+        }
+    }
+
+    fn header<S>(mut self, name: &'static str, value: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    fn body(self, content_type: &str, body: Vec<u8>) -> Self {
+        let mut response = self.header("Content-Type", content_type);
+        response.body = Body::Bytes(body);
+        response
+    }
+
+    fn text<S>(self, text: S) -> Self
+    where
+        S: Into<String>,
+    {
+        let mut text = text.into();
+        text.push('\n');
+
+        self.body("text/plain; charset=utf-8", text.into_bytes())
+    }
+}
+
+enum Body {
+    Bytes(Vec<u8>),
+    /// The bytes of an archive entry from `start` up to `end`, which are read from the archive as they're sent.
+    Entry {
+        entry: OpenEntry,
+        start: u64,
+        end: u64,
+    },
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        206 => "Partial Content",
+        207 => "Multi-Status",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        416 => "Range Not Satisfiable",
+        _ => "",
+    }
+}
+
+/// Returns a tag that changes whenever the content of the file `node` does.
+fn etag(node: &ArchiveEntry) -> String {
+    match &node.props {
+        EntryProperties::File(props) => {
+            format!("\"{:08x}-{:x}\"", props.crc32, props.raw_size_bytes)
+        }
+        EntryProperties::Directory => "\"dir\"".into(),
+    }
+}
+
+/// Returns when `node` was last modified, formatted like the dates in HTTP headers.
+fn http_date(node: &ArchiveEntry) -> Option<String> {
+    let date: NaiveDateTime = node.last_modified.as_ref()?.into();
+    Some(date.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+}
+
+/// Escape `text` so it can be put inside of HTML or XML.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+
+    escaped
+}
+
+/// Encode every byte of `name` that can't appear in a path segment of a URL as is.
+fn percent_encode(name: &str) -> String {
+    let mut encoded = String::with_capacity(name.len());
+
+    for &byte in name.as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            write!(encoded, "%{:02X}", byte).ok();
+        }
+    }
+
+    encoded
+}
+
+/// Decode the percent-encoded bytes of `path`, leaving invalid escapes as they are.
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|hex| bytes[i] == b'%' && hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        if let Some(byte) = escaped {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -0,0 +1,252 @@
+use super::{is_password_required, open_encrypted, Archive, ArchiveEntry};
+use anyhow::{anyhow, Context, Result};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use zip::{
+    read::{read_zipfile_from_stream, ZipFile},
+    CompressionMethod, ZipArchive,
+};
+
+/// The top-level entries of an archive that's a file inside of another archive.
+pub struct NestedListing {
+    /// The top-level entries, with directories first and each ordered by name.
+    pub entries: Vec<NestedEntry>,
+    /// How many files and directories are in the archive, at any depth.
+    pub total_entries: usize,
+}
+
+pub struct NestedEntry {
+    pub name: String,
+    /// The uncompressed size of the file, or of everything inside of the directory.
+    pub size_bytes: u64,
+    pub is_dir: bool,
+}
+
+impl NestedListing {
+    /// Returns true if the given `content` from the start of a file looks like the start of a zip archive.
+    pub fn is_archive(content: &[u8]) -> bool {
+        // The second signature is the one an empty archive starts with
+        content.starts_with(b"PK\x03\x04") || content.starts_with(b"PK\x05\x06")
+    }
+
+    /// Read the top-level entries of the archive that's the given file `entry` in the `archive`.
+    pub fn read(archive: &Archive, entry: &ArchiveEntry) -> Result<Self> {
+        let listing = visit_entries(archive, entry, |listing: &mut ListingBuilder, file| {
+            listing.add(file.name(), file.size(), file.is_dir());
+        })?;
+
+        Ok(listing.build())
+    }
+}
+
+/// The largest compressed archive that's decompressed into memory when its entries can't be read in order.
+const MAX_IN_MEMORY_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Call `visit` with each entry of the archive that's the given file `entry` in the `archive`, along with the state
+/// it's gathering, which is returned once every entry has been visited.
+///
+/// Archives that are stored as they are only have their list of entries at the end read. Compressed ones have to be
+/// decompressed to get to it, so the header in front of each entry is read along the way instead. The state starts
+/// over if that doesn't work out and the archive has to be decompressed in full after all.
+pub fn visit_entries<T, F>(archive: &Archive, entry: &ArchiveEntry, mut visit: F) -> Result<T>
+where
+    T: Default,
+    F: FnMut(&mut T, &mut ZipFile),
+{
+    let mut outer = archive.open_reader()?;
+
+    let file = outer
+        .by_index(entry.entry_num)
+        .with_context(|| anyhow!("failed to get {} from archive", entry.name))?;
+
+    if file.compression() == CompressionMethod::Stored {
+        let (start, len) = (file.data_start(), file.size());
+        drop(file);
+
+        let reader = EntryReader {
+            file: outer.into_inner(),
+            start,
+            len,
+            pos: 0,
+        };
+
+        let inner = ZipArchive::new(reader)
+            .with_context(|| anyhow!("{} isn't a valid archive", entry.name))?;
+
+        return visit_archive(inner, visit);
+    }
+
+    let size = file.size();
+    drop(file);
+
+    match visit_stream(archive, entry, &mut visit) {
+        Ok(state) => Ok(state),
+        // Entries that are written without their size in front of them can't be skipped over, so the
+        // whole archive is decompressed to read the list at the end instead
+        Err(_) if size <= MAX_IN_MEMORY_BYTES => {
+            let content = archive.read_start(entry, size)?;
+
+            let inner = ZipArchive::new(Cursor::new(content))
+                .with_context(|| anyhow!("{} isn't a valid archive", entry.name))?;
+
+            visit_archive(inner, visit)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Visit the entries from the list at the end of the given `archive`.
+fn visit_archive<R, T, F>(mut archive: ZipArchive<R>, mut visit: F) -> Result<T>
+where
+    R: Read + Seek,
+    T: Default,
+    F: FnMut(&mut T, &mut ZipFile),
+{
+    let mut state = T::default();
+
+    for index in 0..archive.len() {
+        match archive.by_index(index) {
+            Ok(mut file) => {
+                visit(&mut state, &mut file);
+                continue;
+            }
+            // The size of an encrypted file can still be read without its password
+            Err(err) if is_password_required(&err) => (),
+            Err(err) => return Err(err).context("failed to read entry"),
+        }
+
+        let mut file = open_encrypted(&mut archive, index)?;
+        visit(&mut state, &mut file);
+    }
+
+    Ok(state)
+}
+
+/// Visit the entries from the header in front of each of them, in the order they're stored in.
+fn visit_stream<T, F>(archive: &Archive, entry: &ArchiveEntry, visit: &mut F) -> Result<T>
+where
+    T: Default,
+    F: FnMut(&mut T, &mut ZipFile),
+{
+    let mut outer = archive.open_reader()?;
+
+    let mut file = outer
+        .by_index(entry.entry_num)
+        .with_context(|| anyhow!("failed to get {} from archive", entry.name))?;
+
+    let mut state = T::default();
+
+    // Each entry skips past its content when it's dropped, which leaves the next header to be read
+    while let Some(mut inner) =
+        read_zipfile_from_stream(&mut file).context("failed to read entry")?
+    {
+        visit(&mut state, &mut inner);
+    }
+
+    Ok(state)
+}
+
+/// Gathers the top-level entries of an archive from the paths of every entry in it.
+#[derive(Default)]
+struct ListingBuilder {
+    /// Each top-level entry by its name.
+    top_level: BTreeMap<String, NestedEntry>,
+    total_entries: usize,
+}
+
+impl ListingBuilder {
+    fn add(&mut self, path: &str, size_bytes: u64, is_dir: bool) {
+        let path = path.trim_start_matches('/');
+        let (name, is_dir) = match path.split_once('/') {
+            // Directories aren't always stored as entries of their own, so anything with a directory in its path
+            // counts towards it
+            Some((name, _)) => (name, true),
+            None => (path, is_dir),
+        };
+
+        if name.is_empty() {
+            return;
+        }
+
+        self.total_entries += 1;
+
+        let entry = self
+            .top_level
+            .entry(name.into())
+            .or_insert_with(|| NestedEntry {
+                name: name.into(),
+                size_bytes: 0,
+                is_dir,
+            });
+
+        entry.size_bytes += size_bytes;
+    }
+
+    fn build(self) -> NestedListing {
+        let (mut entries, files): (Vec<_>, Vec<_>) =
+            self.top_level.into_values().partition(|entry| entry.is_dir);
+
+        entries.extend(files);
+
+        NestedListing {
+            entries,
+            total_entries: self.total_entries,
+        }
+    }
+}
+
+/// Reads and seeks within the part of an archive's file that a stored entry takes up, as if it were the whole file.
+struct EntryReader {
+    file: File,
+    start: u64,
+    len: u64,
+    /// The position from the start of the entry.
+    pos: u64,
+}
+
+impl Read for EntryReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len.saturating_sub(self.pos);
+        let len = (buf.len() as u64).min(remaining) as usize;
+
+        if len == 0 {
+            return Ok(0);
+        }
+
+        self.file.seek(SeekFrom::Start(self.start + self.pos))?;
+
+        let read = self.file.read(&mut buf[..len])?;
+        self.pos += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for EntryReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => checked_offset(self.len, offset),
+            SeekFrom::Current(offset) => checked_offset(self.pos, offset),
+        };
+
+        match pos {
+            Some(pos) => {
+                self.pos = pos;
+                Ok(pos)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "seeked before the start of the entry",
+            )),
+        }
+    }
+}
+
+fn checked_offset(base: u64, offset: i64) -> Option<u64> {
+    if offset < 0 {
+        base.checked_sub(offset.unsigned_abs())
+    } else {
+        base.checked_add(offset as u64)
+    }
+}
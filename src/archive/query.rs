@@ -0,0 +1,150 @@
+use super::ArchiveEntry;
+use crate::util::size;
+
+/// A search for entries by name that can also ask for entries of a certain size or that were last modified in a
+/// certain range, like `report >10M before:2020-01-01`.
+///
+/// Words that aren't conditions are matched against the name of an entry (or its path, depending on where the query
+/// is used) as a single piece of text, ignoring case. Sizes are compared against the uncompressed size of files and
+/// the total size of directories, and entries without a date never match date conditions.
+///
+/// Words that look like conditions but can't be read as one, like a `>` on its own while a query is being typed,
+/// are matched as text instead.
+#[derive(Clone)]
+pub struct EntryQuery {
+    /// The query as it was written.
+    source: String,
+    /// The words of the query that aren't conditions, in lowercase.
+    text: String,
+    conditions: Vec<Condition>,
+}
+
+impl EntryQuery {
+    pub fn parse(query: &str) -> Self {
+        let mut words = Vec::new();
+        let mut conditions = Vec::new();
+
+        for word in query.split(' ') {
+            match Condition::parse(word) {
+                Some(condition) => conditions.push(condition),
+                None => words.push(word),
+            }
+        }
+
+        Self {
+            source: query.into(),
+            text: words.join(" ").trim().to_lowercase(),
+            conditions,
+        }
+    }
+
+    /// Returns true if the given `entry` meets every condition and `text` contains the rest of the query, ignoring
+    /// case.
+    pub fn matches(&self, entry: &ArchiveEntry, text: &str) -> bool {
+        let text_matches = self.text.is_empty() || text.to_lowercase().contains(&self.text);
+        text_matches
+            && self
+                .conditions
+                .iter()
+                .all(|condition| condition.passes(entry))
+    }
+
+    /// Returns the query as it was written.
+    #[inline(always)]
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+}
+
+/// A day, written as `YYYY-MM-DD`, `YYYY-MM`, or `YYYY` for the first day of a month or year.
+type Day = (u16, u8, u8);
+
+#[derive(Copy, Clone)]
+enum Condition {
+    Larger(u64),
+    AtLeast(u64),
+    Smaller(u64),
+    AtMost(u64),
+    /// Last modified before the start of the day.
+    Before(Day),
+    /// Last modified on the day or after it.
+    After(Day),
+}
+
+impl Condition {
+    fn parse(word: &str) -> Option<Self> {
+        if let Some(date) = word.strip_prefix("before:") {
+            return parse_day(date).map(Self::Before);
+        }
+
+        if let Some(date) = word.strip_prefix("after:") {
+            return parse_day(date).map(Self::After);
+        }
+
+        // The longer operators have to be checked first since they start with the shorter ones
+        if let Some(size) = word.strip_prefix(">=") {
+            return parse_size(size).map(Self::AtLeast);
+        }
+
+        if let Some(size) = word.strip_prefix("<=") {
+            return parse_size(size).map(Self::AtMost);
+        }
+
+        if let Some(size) = word.strip_prefix('>') {
+            return parse_size(size).map(Self::Larger);
+        }
+
+        if let Some(size) = word.strip_prefix('<') {
+            return parse_size(size).map(Self::Smaller);
+        }
+
+        None
+    }
+
+    fn passes(&self, entry: &ArchiveEntry) -> bool {
+        let size = entry.total_size_bytes;
+        let day = entry
+            .last_modified
+            .as_ref()
+            .map(|date| (date.year, date.month, date.day));
+
+        match *self {
+            Self::Larger(min) => size > min,
+            Self::AtLeast(min) => size >= min,
+            Self::Smaller(max) => size < max,
+            Self::AtMost(max) => size <= max,
+            Self::Before(limit) => matches!(day, Some(day) if day < limit),
+            Self::After(limit) => matches!(day, Some(day) if day >= limit),
+        }
+    }
+}
+
+fn parse_size(size: &str) -> Option<u64> {
+    // A size that's still being typed, like the start of `>10M`, shouldn't be read as a size of zero
+    if size.is_empty() {
+        return None;
+    }
+
+    size::parse(size)
+}
+
+fn parse_day(date: &str) -> Option<Day> {
+    let mut parts = date.splitn(3, '-');
+
+    let year = parts.next().filter(|year| year.len() == 4)?.parse().ok()?;
+
+    let month = match parts.next() {
+        Some(month) => month
+            .parse()
+            .ok()
+            .filter(|month| (1..=12).contains(month))?,
+        None => 1,
+    };
+
+    let day = match parts.next() {
+        Some(day) => day.parse().ok().filter(|day| (1..=31).contains(day))?,
+        None => 1,
+    };
+
+    Some((year, month, day))
+}
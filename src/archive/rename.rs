@@ -0,0 +1,78 @@
+use super::{ArchiveEntries, NodeID};
+use anyhow::{anyhow, Context, Error, Result};
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// A rule that changes the path of entries as they are extracted.
+///
+/// Rules are written as `PATTERN => REPLACEMENT`, where the replacement can refer to capture groups
+/// in the pattern with `$1` or `${name}`.
+pub struct RenameRule {
+    pattern: Regex,
+    replacement: String,
+}
+
+impl RenameRule {
+    const SEPARATOR: &'static str = "=>";
+
+    /// Returns the path the entry at the given `path` should be extracted to.
+    ///
+    /// Directories are matched with a trailing slash so a pattern like `^[^/]+/` can strip them away entirely,
+    /// in which case an empty path is returned.
+    pub fn apply(&self, path: &Path, is_dir: bool) -> PathBuf {
+        let mut path = path.to_string_lossy().into_owned();
+
+        if is_dir {
+            path.push('/');
+        }
+
+        let renamed = self.pattern.replace_all(&path, self.replacement.as_str());
+        PathBuf::from(renamed.trim_end_matches('/'))
+    }
+
+    /// Returns the path of every entry under the given `nodes` along with the path it would be extracted to.
+    ///
+    /// The results are sorted by their original path.
+    pub fn preview(&self, files: &ArchiveEntries, nodes: &[NodeID]) -> Vec<(PathBuf, PathBuf)> {
+        let mut renamed = files
+            .children_iter(nodes)
+            .filter(|(id, _, _)| *id != NodeID::first())
+            .map(|(_, entry, path)| {
+                let new_path = self.apply(&path, entry.props.is_dir());
+                (path, new_path)
+            })
+            .collect::<Vec<_>>();
+
+        renamed.sort_unstable_by(|(x, _), (y, _)| x.cmp(y));
+        renamed
+    }
+}
+
+impl FromStr for RenameRule {
+    type Err = Error;
+
+    fn from_str(rule: &str) -> Result<Self> {
+        let (pattern, replacement) = match rule.find(Self::SEPARATOR) {
+            Some(pos) => (&rule[..pos], &rule[pos + Self::SEPARATOR.len()..]),
+            None => {
+                return Err(anyhow!(
+                    "rename rule must be written as PATTERN {} REPLACEMENT",
+                    Self::SEPARATOR
+                ))
+            }
+        };
+
+        // Only the space next to the separator is dropped so a replacement can still be a space itself
+        let pattern = pattern.strip_suffix(' ').unwrap_or(pattern);
+        let replacement = replacement.strip_prefix(' ').unwrap_or(replacement);
+
+        let pattern =
+            Regex::new(pattern).with_context(|| anyhow!("invalid rename pattern: {}", pattern))?;
+
+        Ok(Self {
+            pattern,
+            replacement: replacement.to_string(),
+        })
+    }
+}
@@ -0,0 +1,538 @@
+use crate::archive::{
+    mount::{
+        self, webdav::WebDavServer, ArchiveMountSession, CacheLimit, MountChange, MountOptions,
+        MountedArchive, UnmountStatus,
+    },
+    Archive,
+};
+use anyhow::{anyhow, Context, Result};
+use argh::FromArgs;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::mem::MaybeUninit;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::panic;
+use std::path::Path;
+use std::process;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "mount")]
+/// Mount an archive without viewing it, until it's unmounted or vear is stopped.
+pub struct MountCommand {
+    /// the path of the archive to mount
+    #[argh(positional)]
+    archive: String,
+
+    /// the directory to mount the archive at
+    #[argh(positional)]
+    path: String,
+
+    /// keep running in the background once the archive is mounted
+    #[argh(switch)]
+    detach: bool,
+
+    /// the most memory to cache files read from the mount in, like 512M or 2G (defaults to half of the available memory)
+    #[argh(option)]
+    cache_size: Option<CacheLimit>,
+
+    /// the most disk space to keep files read from the mount in once they no longer fit in its cache (defaults to a quarter of the free space in the temporary directory)
+    #[argh(option)]
+    spill_size: Option<CacheLimit>,
+
+    /// allow changing files in the mount, keeping the changes in a directory instead of the archive
+    #[argh(switch)]
+    writable: bool,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "unmount")]
+/// Unmount an archive that vear keeps mounted in the background.
+pub struct UnmountCommand {
+    /// the directory the archive is mounted at
+    #[argh(positional)]
+    path: String,
+
+    /// unmount the archive even if something is still using it
+    #[argh(switch)]
+    force: bool,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "serve")]
+#[allow(clippy::doc_markdown)]
+/// Serve an archive read-only over WebDAV on localhost, for systems that can't mount it with FUSE.
+pub struct ServeCommand {
+    /// the path of the archive to serve
+    #[argh(positional)]
+    archive: String,
+
+    /// the port to listen on (defaults to any free port)
+    #[argh(option, default = "0")]
+    port: u16,
+
+    /// the most memory to cache files read from the server in, like 512M or 2G (defaults to half of the available memory)
+    #[argh(option)]
+    cache_size: Option<CacheLimit>,
+
+    /// the most disk space to keep files read from the server in once they no longer fit in its cache (defaults to a quarter of the free space in the temporary directory)
+    #[argh(option)]
+    spill_size: Option<CacheLimit>,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "cat")]
+/// Write the content of a file inside of an archive to stdout.
+pub struct CatCommand {
+    /// the path of the archive to read from
+    #[argh(positional)]
+    archive: String,
+
+    /// the path of the file inside of the archive
+    #[argh(positional)]
+    path: String,
+}
+
+/// Signals that stop a headless mount and unmount the archive.
+const STOP_SIGNALS: [libc::c_int; 3] = [libc::SIGINT, libc::SIGTERM, libc::SIGHUP];
+
+/// How often to check if the archive was unmounted from outside of vear.
+const UNMOUNT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Mount the archive given in `command` and wait until it's unmounted or a stop signal is received.
+pub fn mount(command: &MountCommand) -> Result<()> {
+    if !mount::is_available() {
+        return Err(anyhow!(
+            "mounting needs FUSE to be installed and vear to be built with the mount feature (vear serve can share the archive over WebDAV instead)"
+        ));
+    }
+
+    let archive = Archive::read(&command.archive)
+        .with_context(|| anyhow!("failed to read files from {}", command.archive))?;
+
+    let options = MountOptions {
+        cache_limit: command.cache_size,
+        spill_limit: command.spill_size,
+        writable: command.writable,
+    };
+
+    let path = Path::new(&command.path);
+
+    // Nothing is dropped when panicking aborts, which would otherwise leave the mount behind in a broken state
+    let default_hook = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        mount::unmount_all();
+        default_hook(info);
+    }));
+
+    if !command.detach {
+        let (session, signals) = start_mount(archive, path, options)?;
+        wait_for_unmount(session, &signals);
+        return Ok(());
+    }
+
+    // Everything after this only runs in the background process
+    let ready = detach()?;
+
+    match start_mount(archive, path, options) {
+        Ok((session, signals)) => {
+            ready.notify(Ok(()));
+            wait_for_unmount(session, &signals);
+            Ok(())
+        }
+        Err(err) => {
+            ready.notify(Err(&err));
+            Err(err)
+        }
+    }
+}
+
+/// Unmount the archive at the path given in `command`, which stops the background process keeping it mounted.
+pub fn unmount(command: &UnmountCommand) -> Result<()> {
+    let path = Path::new(&command.path);
+
+    match mount::unmount_path(path, command.force)? {
+        UnmountStatus::Unmounted => Ok(()),
+        UnmountStatus::Busy => {
+            let users = mount::processes_using(path)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+
+            let used_by = if users.is_empty() {
+                String::new()
+            } else {
+                format!(" by {}", users.join(", "))
+            };
+
+            Err(anyhow!(
+                "{} is still being used{}, so close what's using it or pass --force",
+                path.display(),
+                used_by
+            ))
+        }
+    }
+}
+
+/// Serve the archive given in `command` until a stop signal is received.
+pub fn serve(command: &ServeCommand) -> Result<()> {
+    let archive = Archive::read(&command.archive)
+        .with_context(|| anyhow!("failed to read files from {}", command.archive))?;
+
+    let options = MountOptions {
+        cache_limit: command.cache_size,
+        spill_limit: command.spill_size,
+        writable: false,
+    };
+
+    // This has to happen before the server's threads are spawned so they don't receive the signals instead
+    let signals = watch_stop_signals()?;
+
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, command.port));
+    let server = Arc::new(WebDavServer::bind(&Arc::new(archive), addr, options)?);
+
+    println!(
+        "serving {} at http://{}/",
+        command.archive,
+        server.local_addr()?
+    );
+
+    let background = Arc::clone(&server);
+    thread::spawn(move || background.serve());
+
+    signals.recv().ok();
+    server.close();
+
+    Ok(())
+}
+
+/// Write the content of the file given in `command` to stdout.
+pub fn cat(command: &CatCommand) -> Result<()> {
+    let archive = Archive::read(&command.archive)
+        .with_context(|| anyhow!("failed to read files from {}", command.archive))?;
+
+    let stdout = io::stdout();
+    archive.write_file(&command.path, &mut stdout.lock())
+}
+
+/// Lets the archives mounted while viewing stay mounted after the viewer exits.
+///
+/// The process that was started only waits for a copy of itself that shows the archives, and exits once it's closed.
+/// The copy keeps running in the background afterwards with the same mounts, so anything already using them keeps
+/// working, until they're all unmounted.
+pub struct HandOff(File);
+
+impl HandOff {
+    /// Fork vear and return in the process that shows the archives, while the other process waits for it to finish.
+    ///
+    /// This has to be called before any other threads are started, since they don't carry over to the fork.
+    pub fn start() -> Result<Self> {
+        let mut fds = [0; 2];
+
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(io::Error::last_os_error()).context("failed to create pipe");
+        }
+
+        let (reader, writer) = unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+
+        // Programs that are opened while viewing would otherwise keep the waiting process from noticing a crash
+        if unsafe { libc::fcntl(writer.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+            return Err(io::Error::last_os_error()).context("failed to configure pipe");
+        }
+
+        match unsafe { libc::fork() } {
+            -1 => Err(io::Error::last_os_error()).context("failed to fork"),
+            0 => {
+                drop(reader);
+                Ok(Self(writer))
+            }
+            _ => {
+                drop(writer);
+                process::exit(Self::wait_for_exit_code(reader))
+            }
+        }
+    }
+
+    /// Wait for the process showing the archives to say what code to exit with.
+    fn wait_for_exit_code(mut reader: File) -> i32 {
+        let mut code = [0];
+
+        match reader.read_exact(&mut code) {
+            Ok(()) => i32::from(code[0]),
+            // The other process crashed, and has already said why
+            Err(_) => 1,
+        }
+    }
+
+    /// Let the waiting process exit with the outcome of viewing given by `result`, and keep the mounts of the
+    /// `sessions` it returned running in the background until they're all unmounted or vear is told to stop.
+    pub fn finish(mut self, result: Result<Vec<ArchiveMountSession>>) {
+        let sessions = match result {
+            Ok(sessions) => sessions,
+            Err(err) => {
+                eprintln!("Error: {:?}", err);
+                self.0.write_all(&[1]).ok();
+                return;
+            }
+        };
+
+        for session in &sessions {
+            eprintln!(
+                "archive is still mounted at {} and can be unmounted with `vear unmount`",
+                session.path().display()
+            );
+
+            if let Some(dir) = session.overlay_dir() {
+                eprintln!(
+                    "files changed through it will be kept in {} once it's unmounted",
+                    dir.display()
+                );
+            }
+        }
+
+        if sessions.is_empty() {
+            self.0.write_all(&[0]).ok();
+            return;
+        }
+
+        // Keeps the process running when the terminal it was started from closes
+        unsafe {
+            libc::setsid();
+        }
+
+        let detached = handle_stop_signals().and_then(|()| redirect_std_streams());
+        let code = u8::from(detached.is_err());
+
+        if let Err(err) = &detached {
+            eprintln!("Error: failed to keep archives mounted: {:?}", err);
+        }
+
+        self.0.write_all(&[code]).ok();
+        drop(self);
+
+        if detached.is_ok() {
+            keep_mounted(sessions);
+        }
+    }
+}
+
+/// Mount the `archive` at `path` and return its session along with the signals that should stop it.
+fn start_mount(
+    archive: Archive,
+    path: &Path,
+    options: MountOptions,
+) -> Result<(ArchiveMountSession, Receiver<()>)> {
+    // This has to happen before the mount's thread is spawned so it doesn't receive the signals instead
+    let signals = watch_stop_signals()?;
+
+    let archive = Arc::new(archive);
+
+    let session = MountedArchive::new(&archive, options)
+        .and_then(|mounted| mounted.mount(path))
+        .with_context(|| anyhow!("failed to mount archive at {}", path.display()))?;
+
+    Ok((session, signals))
+}
+
+/// Block until the archive is unmounted from outside of vear or a stop signal is received through `signals`,
+/// and then list what was changed through the mount.
+fn wait_for_unmount(session: ArchiveMountSession, signals: &Receiver<()>) {
+    while session.is_mounted() {
+        match signals.recv_timeout(UNMOUNT_POLL_INTERVAL) {
+            Err(RecvTimeoutError::Timeout) => (),
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    // Whatever is still using the mount can't keep vear from stopping
+    let changes = match session.force_unmount() {
+        Some(changes) => changes,
+        None => return,
+    };
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+
+    for change in &changes.changes {
+        let symbol = match change {
+            MountChange::Added(_) => '+',
+            MountChange::Modified(_) => '~',
+            MountChange::Removed(_) => '-',
+        };
+
+        writeln!(stdout, "{} {}", symbol, change.path().display()).ok();
+    }
+
+    eprintln!("changed files were kept in {}", changes.dir.display());
+}
+
+/// Wait until every one of the `sessions` is unmounted from outside of vear or a stop signal is received, and
+/// unmount the ones that are left.
+fn keep_mounted(mut sessions: Vec<ArchiveMountSession>) {
+    while !STOP_REQUESTED.load(Ordering::SeqCst) {
+        sessions.retain(ArchiveMountSession::is_mounted);
+
+        if sessions.is_empty() {
+            return;
+        }
+
+        thread::sleep(UNMOUNT_POLL_INTERVAL);
+    }
+}
+
+/// Whether a stop signal was received by [`handle_stop_signals`].
+static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Make the stop signals set [`STOP_REQUESTED`] instead of ending vear right away, which would leave its mounts
+/// behind in a broken state.
+///
+/// This works no matter which thread receives them, unlike [`watch_stop_signals`], which only works for threads that
+/// are started afterwards.
+fn handle_stop_signals() -> Result<()> {
+    extern "C" fn request_stop(_: libc::c_int) {
+        STOP_REQUESTED.store(true, Ordering::SeqCst);
+    }
+
+    for &signal in &STOP_SIGNALS {
+        if unsafe {
+            libc::signal(
+                signal,
+                request_stop as extern "C" fn(libc::c_int) as libc::sighandler_t,
+            )
+        } == libc::SIG_ERR
+        {
+            return Err(io::Error::last_os_error()).context("failed to handle stop signals");
+        }
+    }
+
+    Ok(())
+}
+
+/// Block the stop signals for the current thread and every thread it spawns afterwards, and return a channel
+/// that receives a message once one of them is sent to vear.
+fn watch_stop_signals() -> Result<Receiver<()>> {
+    let signals = block_stop_signals()?;
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut signal = 0;
+
+        // Since the signals are blocked, they stay pending until they're waited for here
+        if unsafe { libc::sigwait(&signals, &mut signal) } == 0 {
+            sender.send(()).ok();
+        }
+    });
+
+    Ok(receiver)
+}
+
+fn block_stop_signals() -> Result<libc::sigset_t> {
+    unsafe {
+        let mut signals = MaybeUninit::<libc::sigset_t>::uninit();
+        libc::sigemptyset(signals.as_mut_ptr());
+
+        for &signal in &STOP_SIGNALS {
+            libc::sigaddset(signals.as_mut_ptr(), signal);
+        }
+
+        let signals = signals.assume_init();
+
+        match libc::pthread_sigmask(libc::SIG_BLOCK, &signals, ptr::null_mut()) {
+            0 => Ok(signals),
+            err => Err(io::Error::from_raw_os_error(err)).context("failed to block stop signals"),
+        }
+    }
+}
+
+/// Move vear into the background.
+///
+/// The current process exits once the background process notifies it through the returned [`ReadyPipe`],
+/// so this only returns in the background process.
+fn detach() -> Result<ReadyPipe> {
+    let mut fds = [0; 2];
+
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error()).context("failed to create pipe");
+    }
+
+    let (reader, writer) = unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error()).context("failed to fork"),
+        0 => {
+            drop(reader);
+
+            unsafe {
+                // Keeps the process running when the terminal it was started from closes
+                libc::setsid();
+            }
+
+            redirect_std_streams()?;
+            Ok(ReadyPipe(writer))
+        }
+        _ => {
+            drop(writer);
+            process::exit(wait_until_ready(reader))
+        }
+    }
+}
+
+/// Point stdin, stdout, and stderr to `/dev/null` so the background process doesn't write to the terminal.
+fn redirect_std_streams() -> Result<()> {
+    let null = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/null")
+        .context("failed to open /dev/null")?;
+
+    for fd in 0..=2 {
+        if unsafe { libc::dup2(null.as_raw_fd(), fd) } == -1 {
+            return Err(io::Error::last_os_error()).context("failed to redirect output");
+        }
+    }
+
+    Ok(())
+}
+
+/// Wait for the background process to report if the archive was mounted and return the exit code to use.
+fn wait_until_ready(mut reader: File) -> i32 {
+    let mut message = String::new();
+    reader.read_to_string(&mut message).ok();
+
+    if message == ReadyPipe::MOUNTED {
+        return 0;
+    }
+
+    if message.is_empty() {
+        eprintln!("Error: background process exited before the archive was mounted");
+    } else {
+        eprintln!("Error: {}", message);
+    }
+
+    1
+}
+
+/// Lets the background process tell the process that started it whether mounting succeeded.
+struct ReadyPipe(File);
+
+impl ReadyPipe {
+    const MOUNTED: &'static str = "\0";
+
+    /// Tell the waiting process the `result` of mounting, which lets it exit.
+    fn notify(mut self, result: Result<(), &anyhow::Error>) {
+        let message = match result {
+            Ok(()) => Self::MOUNTED.into(),
+            // The alternate format includes every cause on one line
+            Err(err) => format!("{:#}", err),
+        };
+
+        self.0.write_all(message.as_bytes()).ok();
+    }
+}
@@ -6,32 +6,278 @@
 #![allow(clippy::module_name_repetitions)]
 #![allow(clippy::default_trait_access)]
 #![allow(clippy::cast_sign_loss)]
+// Errors carry context that says what failed, which documenting every way a function can fail would only repeat
+#![allow(clippy::missing_errors_doc)]
 
 mod archive;
+mod headless;
 mod ui;
 mod util;
 
-use anyhow::{anyhow, Context, Result};
-use archive::Archive;
+use anyhow::{anyhow, Result};
+use archive::{
+    extract::{ExtractOptions, RateLimit},
+    filter::PathFilter,
+    mount::{ArchiveMountSession, CacheLimit, MountOptions},
+    rename::RenameRule,
+};
 use argh::FromArgs;
-use ui::{CycleResult, UI};
+use headless::{CatCommand, HandOff, MountCommand, ServeCommand, UnmountCommand};
+use std::path::PathBuf;
+use std::sync::Arc;
+use ui::notify::NotifySetting;
+use ui::{Config, CycleResult, Graphics, IconStyle, PaneRatios, UI};
 
 #[derive(FromArgs)]
+// Every switch argh parses becomes a bool
+#[allow(clippy::struct_excessive_bools)]
 /// View, extract, and mount archives in the terminal.
 struct Args {
-    /// the path of the archive to open
+    /// the paths of the archives to open, which are each opened in their own tab
     #[argh(positional)]
-    path: String,
+    paths: Vec<String>,
+
+    #[argh(subcommand)]
+    command: Option<Command>,
+
+    /// how to notify when a kind of job finishes, written as KIND=HOW where HOW is none, bell, or flash (can be used
+    /// more than once)
+    #[argh(option)]
+    notify: Vec<NotifySetting>,
+
+    /// the directory to extract the viewed directory to when using the quick extract key
+    #[argh(option, default = "PathBuf::from(\".\")")]
+    extract_dest: PathBuf,
+
+    /// keep the setuid and setgid bits of extracted files
+    #[argh(switch)]
+    allow_setuid: bool,
+
+    /// extract symlinks as regular files that contain the path they point to
+    #[argh(switch)]
+    symlinks_as_files: bool,
+
+    /// the character to separate groups of thousands with (defaults to one based on your locale)
+    #[argh(option)]
+    thousands_separator: Option<char>,
+
+    /// show a header above the viewed directory that can be clicked to sort by a column
+    #[argh(switch)]
+    column_header: bool,
+
+    /// show the date each entry was last modified in the viewed directory from the start
+    #[argh(switch)]
+    date_column: bool,
+
+    /// how much of the width the parent, viewed, and child directories each take up, like 1:2:1
+    #[argh(option)]
+    pane_ratios: Option<PaneRatios>,
+
+    /// mark the kind of each entry before its name with nerd font glyphs (nerd) or letters that work with any font (ascii)
+    #[argh(option)]
+    icons: Option<IconStyle>,
+
+    /// stop at the first and last entry of a directory instead of wrapping around to the other end
+    #[argh(switch)]
+    no_wrap: bool,
+
+    /// leave the mouse to the terminal so text can be selected instead of clicking and scrolling through entries
+    #[argh(switch)]
+    no_mouse: bool,
+
+    /// how to draw previewed images: kitty, iterm, sixel, or none (defaults to one based on your terminal)
+    #[argh(option)]
+    graphics: Option<Graphics>,
+
+    /// change the path of extracted entries with a rule written as PATTERN => REPLACEMENT
+    #[argh(option)]
+    rename: Option<RenameRule>,
+
+    /// only extract files that match the given glob pattern (can be used more than once)
+    #[argh(option)]
+    include: Vec<String>,
+
+    /// don't extract files that match the given glob pattern (can be used more than once)
+    #[argh(option)]
+    exclude: Vec<String>,
+
+    /// the number of leading path components to remove from extracted entries
+    #[argh(option, default = "0")]
+    strip_components: usize,
+
+    /// skip files that were already extracted with the same content, such as when resuming an extraction
+    #[argh(switch)]
+    skip_existing: bool,
+
+    /// keep extracting when an entry fails and show which entries failed at the end
+    #[argh(switch)]
+    continue_on_error: bool,
+
+    /// read every extracted file back and report the ones that don't match the archive's checksum
+    #[argh(switch)]
+    verify: bool,
+
+    /// extract to a hidden directory first and only move the entries into place once everything succeeds
+    #[argh(switch)]
+    atomic: bool,
+
+    /// extract files with the same content as hard links to a single copy to save space
+    #[argh(switch)]
+    hardlink_duplicates: bool,
+
+    /// write runs of zeros in extracted files as holes, which saves space with files like disk images
+    #[argh(switch)]
+    sparse: bool,
+
+    /// the most data to write each second while extracting, like 500K or 20M
+    #[argh(option)]
+    limit_rate: Option<RateLimit>,
+
+    /// the most memory to cache files read from a mount in, like 512M or 2G (defaults to half of the available memory)
+    #[argh(option)]
+    mount_cache_size: Option<CacheLimit>,
+
+    /// the most disk space to keep files read from a mount in once they no longer fit in its cache (defaults to a quarter of the free space in the temporary directory)
+    #[argh(option)]
+    mount_spill_size: Option<CacheLimit>,
+
+    /// allow changing files in the mount, keeping the changes in a directory instead of the archive
+    #[argh(switch)]
+    writable_mount: bool,
+
+    /// keep the archive mounted in the background after exiting instead of unmounting it
+    #[argh(switch)]
+    keep_mounted: bool,
+
+    /// extract with the lowest CPU and I/O priority so the rest of the system stays responsive
+    #[argh(switch)]
+    low_priority: bool,
 }
 
-#[async_std::main]
-async fn main() -> Result<()> {
-    let args: Args = argh::from_env();
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum Command {
+    Mount(MountCommand),
+    Unmount(UnmountCommand),
+    Serve(ServeCommand),
+    Cat(CatCommand),
+    View(ViewCommand),
+}
 
-    let archive = Archive::read(&args.path)
-        .with_context(|| anyhow!("failed to read files from {}", args.path))?;
+#[derive(FromArgs)]
+#[argh(subcommand, name = "view")]
+/// View archives, which works for archives named the same as a command, like `vear view mount`.
+struct ViewCommand {
+    /// the paths of the archives to open, which are each opened in their own tab
+    #[argh(positional)]
+    paths: Vec<String>,
+}
+
+impl Args {
+    /// Add the paths given to the `view` command to the paths of the archives to open.
+    fn take_view_paths(&mut self) {
+        if let Some(Command::View(command)) = &mut self.command {
+            self.paths.append(&mut command.paths);
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let mut args: Args = argh::from_env();
+
+    util::number::set_separator(
+        args.thousands_separator
+            .unwrap_or_else(util::number::locale_separator),
+    );
+
+    // Headless commands run before the async runtime starts, since its threads would otherwise receive the
+    // stop signals they wait for
+    match &args.command {
+        Some(Command::Mount(command)) => return headless::mount(command),
+        Some(Command::Unmount(command)) => return headless::unmount(command),
+        Some(Command::Serve(command)) => return headless::serve(command),
+        Some(Command::Cat(command)) => return headless::cat(command),
+        Some(Command::View(_)) | None => (),
+    }
+
+    args.take_view_paths();
+
+    // An atomic extraction writes into a new directory every time, so there would never be anything to skip
+    if args.atomic && args.skip_existing {
+        return Err(anyhow!(
+            "--atomic and --skip-existing can't be used together"
+        ));
+    }
+
+    if !args.keep_mounted {
+        return async_std::task::block_on(view(args)).map(drop);
+    }
+
+    // Forking has to happen before the async runtime starts as well, since its threads don't carry over
+    let hand_off = HandOff::start()?;
+    hand_off.finish(async_std::task::block_on(view(args)));
+    Ok(())
+}
+
+/// View the archives given in `args` until vear is closed.
+///
+/// Returns the sessions of the archives that should be kept mounted afterwards.
+async fn view(mut args: Args) -> Result<Vec<ArchiveMountSession>> {
+    if args.paths.is_empty() {
+        // Without an archive to open, one that was opened before can be picked instead
+        match ui::pick_recent_archive().await? {
+            Some(path) => args.paths.push(path),
+            None => return Ok(Vec::new()),
+        }
+    }
+
+    let archives = match ui::read_archives(&args.paths).await? {
+        Some(archives) => archives,
+        None => return Ok(Vec::new()),
+    };
+
+    let filter = if args.include.is_empty() && args.exclude.is_empty() {
+        None
+    } else {
+        Some(Arc::new(PathFilter::new(&args.include, &args.exclude)?))
+    };
 
-    let mut ui = UI::init(archive)?;
+    let config = Config {
+        notifications: args.notify.into_iter().collect(),
+        extract_options: ExtractOptions {
+            allow_setuid: args.allow_setuid,
+            symlinks_as_files: args.symlinks_as_files,
+            rename: args.rename.map(Arc::new),
+            filter,
+            // Whether hidden entries are skipped depends on whether they're hidden when each extraction starts
+            skip_hidden: false,
+            strip_components: args.strip_components,
+            skip_existing: args.skip_existing,
+            continue_on_error: args.continue_on_error,
+            verify: args.verify,
+            atomic: args.atomic,
+            hardlink_duplicates: args.hardlink_duplicates,
+            sparse: args.sparse,
+            rate_limit: args.limit_rate,
+            low_priority: args.low_priority,
+        },
+        extract_dest: args.extract_dest,
+        column_header: args.column_header,
+        date_column: args.date_column,
+        pane_ratios: args.pane_ratios.unwrap_or_default(),
+        icons: args.icons.unwrap_or_default(),
+        wrap: !args.no_wrap,
+        mouse: !args.no_mouse,
+        graphics: args.graphics.unwrap_or_else(Graphics::detect),
+        mount_options: MountOptions {
+            cache_limit: args.mount_cache_size,
+            spill_limit: args.mount_spill_size,
+            writable: args.writable_mount,
+        },
+        keep_mounted: args.keep_mounted,
+    };
+
+    let mut ui = UI::init(archives, &config)?;
 
     loop {
         match ui.next_cycle().await {
@@ -46,3 +292,19 @@ async fn main() -> Result<()> {
 
     ui.exit()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn view_command_opens_archives_named_after_commands() {
+        let mut args = Args::from_args(&["vear"], &["--date-column", "view", "mount", "cat"])
+            .expect("failed to parse arguments");
+
+        args.take_view_paths();
+
+        assert!(args.date_column);
+        assert_eq!(args.paths, ["mount", "cat"]);
+    }
+}
@@ -0,0 +1,11 @@
+use super::util::base64;
+use std::io::{self, Write};
+
+/// Copy the given `text` to the system clipboard by asking the terminal to do it with an OSC 52 escape sequence.
+///
+/// This also works over SSH, but only in terminals that support the sequence. Some of them need it to be turned on
+/// first, and the text is silently dropped otherwise.
+pub fn copy<W: Write>(out: &mut W, text: &str) -> io::Result<()> {
+    write!(out, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    out.flush()
+}
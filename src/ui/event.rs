@@ -1,14 +1,27 @@
-use crossterm::event::{Event, EventStream, KeyCode};
+use crossterm::event::{Event, EventStream, KeyCode, KeyModifiers, MouseButton, MouseEvent};
 use futures::{future::FutureExt, select, StreamExt};
 use futures_timer::Delay;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[derive(Debug)]
 pub enum EventKind {
     Key(KeyCode),
+    /// An arrow key pressed while holding shift.
+    ShiftKey(KeyCode),
+    /// Something done with the mouse at the given column and row.
+    Mouse(MouseAction, u16, u16),
     Tick,
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MouseAction {
+    Click,
+    /// A second left click at the same position shortly after the first one, which also sends a click.
+    DoubleClick,
+    ScrollUp,
+    ScrollDown,
+}
+
 pub enum ErrorKind {
     ExitRequest,
     Other(anyhow::Error),
@@ -18,26 +31,70 @@ type EventError<T> = std::result::Result<T, ErrorKind>;
 
 pub struct Events {
     reader: EventStream,
+    /// The position and time of the last left click, which is used to tell when a click is a double click.
+    last_click: Option<(u16, u16, Instant)>,
 }
 
 impl Events {
     const TICK_DURATION_MS: u64 = 1_000;
 
+    /// How often ticks are sent while something is animated, like a loading spinner.
+    pub const FAST_TICK: Duration = Duration::from_millis(100);
+
+    /// The longest time between two clicks for them to count as a double click.
+    const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
+
     pub fn new() -> Self {
         Self {
             reader: EventStream::new(),
+            last_click: None,
         }
     }
 
-    #[allow(clippy::mut_mut)]
+    fn click_action(&mut self, x: u16, y: u16) -> MouseAction {
+        let now = Instant::now();
+
+        // Taking the last click means a third click doesn't count as another double click
+        if let Some((last_x, last_y, time)) = self.last_click.take() {
+            if (last_x, last_y) == (x, y) && now - time <= Self::DOUBLE_CLICK_TIME {
+                return MouseAction::DoubleClick;
+            }
+        }
+
+        self.last_click = Some((x, y, now));
+        MouseAction::Click
+    }
+
     pub async fn next(&mut self) -> EventError<Option<EventKind>> {
-        let mut tick = Delay::new(Duration::from_millis(Self::TICK_DURATION_MS)).fuse();
+        self.next_within(Duration::from_millis(Self::TICK_DURATION_MS))
+            .await
+    }
+
+    /// Wait for the next event, or send a tick if nothing happens within the given `tick` duration.
+    #[allow(clippy::mut_mut)]
+    pub async fn next_within(&mut self, tick: Duration) -> EventError<Option<EventKind>> {
+        let mut tick = Delay::new(tick).fuse();
         let mut next_event = self.reader.next().fuse();
 
         select! {
             _ = tick => Ok(Some(EventKind::Tick)),
             event = next_event => match event {
+                Some(Ok(Event::Key(key)))
+                    if key.modifiers.contains(KeyModifiers::SHIFT)
+                        && matches!(key.code, KeyCode::Up | KeyCode::Down) =>
+                {
+                    Ok(Some(EventKind::ShiftKey(key.code)))
+                }
                 Some(Ok(Event::Key(key))) => Ok(Some(EventKind::Key(key.code))),
+                Some(Ok(Event::Mouse(MouseEvent::Down(MouseButton::Left, x, y, _)))) => {
+                    Ok(Some(EventKind::Mouse(self.click_action(x, y), x, y)))
+                }
+                Some(Ok(Event::Mouse(MouseEvent::ScrollUp(x, y, _)))) => {
+                    Ok(Some(EventKind::Mouse(MouseAction::ScrollUp, x, y)))
+                }
+                Some(Ok(Event::Mouse(MouseEvent::ScrollDown(x, y, _)))) => {
+                    Ok(Some(EventKind::Mouse(MouseAction::ScrollDown, x, y)))
+                }
                 Some(Ok(_)) => Ok(None),
                 Some(Err(err)) => Err(ErrorKind::Other(err.into())),
                 None => Err(ErrorKind::ExitRequest),
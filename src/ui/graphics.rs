@@ -0,0 +1,276 @@
+use super::util::base64;
+use anyhow::{anyhow, Result};
+use image::{codecs::png::PngEncoder, ColorType, DynamicImage, GenericImageView, RgbaImage};
+use std::env;
+use std::io::Write;
+use std::mem;
+use std::str::FromStr;
+use tui::layout::Rect;
+
+/// The escape sequences used to draw images in the terminal, since there's no single one that every terminal
+/// understands.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Graphics {
+    /// The terminal can't draw images, so only their details are shown.
+    None,
+    /// The [kitty graphics protocol](https://sw.kovidgoyal.net/kitty/graphics-protocol/).
+    Kitty,
+    /// The inline images of iTerm2, which some other terminals understand too.
+    Iterm,
+    /// Sixel graphics, which are understood by terminals like foot, mlterm, and xterm with the right options.
+    Sixel,
+}
+
+impl Graphics {
+    /// The size of each cell in pixels when the terminal doesn't say.
+    const DEFAULT_CELL_SIZE: (u32, u32) = (8, 16);
+
+    /// The most base64 that kitty accepts in a single escape sequence.
+    const KITTY_CHUNK_SIZE: usize = 4096;
+
+    /// Guess which escape sequences the terminal understands from the environment.
+    ///
+    /// Terminals don't pass escape sequences they don't know about through to the one they're running in, so
+    /// nothing is drawn from inside of tmux or screen unless it's asked for explicitly.
+    pub fn detect() -> Self {
+        let var = |name| env::var(name).unwrap_or_default();
+
+        if env::var_os("TMUX").is_some() || var("TERM").starts_with("screen") {
+            return Self::None;
+        }
+
+        let term = var("TERM");
+        let program = var("TERM_PROGRAM");
+
+        if term.contains("kitty") || env::var_os("KITTY_WINDOW_ID").is_some() {
+            Self::Kitty
+        } else if program == "iTerm.app" || program == "WezTerm" || var("LC_TERMINAL") == "iTerm2" {
+            Self::Iterm
+        } else if term.contains("sixel") || term.starts_with("foot") || term.starts_with("mlterm") {
+            Self::Sixel
+        } else {
+            Self::None
+        }
+    }
+
+    /// Returns the escape sequences that draw the given `image` in the given `area` of the screen, scaled down to
+    /// fit if it's too big.
+    ///
+    /// Returns None if the terminal can't draw images.
+    pub fn encode(self, image: &DynamicImage, area: Rect) -> Option<Vec<u8>> {
+        if self == Self::None || area.width == 0 || area.height == 0 {
+            return None;
+        }
+
+        let (cell_width, cell_height) = Self::cell_size();
+        let max_width = u32::from(area.width) * cell_width;
+        let max_height = u32::from(area.height) * cell_height;
+
+        let image = if image.width() > max_width || image.height() > max_height {
+            image.thumbnail(max_width, max_height).to_rgba8()
+        } else {
+            image.to_rgba8()
+        };
+
+        // The cursor is put back where it was so it doesn't throw off where the interface is drawn next
+        let mut out = format!("\x1b7\x1b[{};{}H", area.y + 1, area.x + 1).into_bytes();
+
+        match self {
+            Self::None => return None,
+            Self::Kitty => Self::write_kitty(&image, &mut out),
+            Self::Iterm => Self::write_iterm(&image, &mut out)?,
+            Self::Sixel => Self::write_sixel(&image, &mut out),
+        }
+
+        out.extend_from_slice(b"\x1b8");
+        Some(out)
+    }
+
+    /// Write the escape sequences that remove every image the terminal is drawing that isn't covered up by text.
+    ///
+    /// Only kitty draws images separately from text, so the others are cleared by drawing over the image.
+    pub fn clear<W: Write>(self, out: &mut W) -> std::io::Result<()> {
+        match self {
+            Self::Kitty => out.write_all(b"\x1b_Ga=d,q=2\x1b\\"),
+            Self::None | Self::Iterm | Self::Sixel => Ok(()),
+        }
+    }
+
+    /// Returns the width and height of each cell of the terminal in pixels.
+    fn cell_size() -> (u32, u32) {
+        let mut size: libc::winsize = unsafe { mem::zeroed() };
+
+        if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } != 0 {
+            return Self::DEFAULT_CELL_SIZE;
+        }
+
+        // Terminals that don't know their size in pixels leave it as zero
+        if size.ws_xpixel == 0 || size.ws_ypixel == 0 || size.ws_col == 0 || size.ws_row == 0 {
+            return Self::DEFAULT_CELL_SIZE;
+        }
+
+        (
+            u32::from(size.ws_xpixel / size.ws_col),
+            u32::from(size.ws_ypixel / size.ws_row),
+        )
+    }
+
+    fn write_kitty(image: &RgbaImage, out: &mut Vec<u8>) {
+        let encoded = base64(image.as_raw());
+        let chunks = encoded.as_bytes().chunks(Self::KITTY_CHUNK_SIZE);
+        let last = chunks.len().saturating_sub(1);
+
+        for (i, chunk) in chunks.enumerate() {
+            let more = u8::from(i != last);
+
+            // Only the first chunk describes the image
+            if i == 0 {
+                write!(
+                    out,
+                    "\x1b_Ga=T,f=32,s={},v={},q=2,m={};",
+                    image.width(),
+                    image.height(),
+                    more
+                )
+                .ok();
+            } else {
+                write!(out, "\x1b_Gm={};", more).ok();
+            }
+
+            out.extend_from_slice(chunk);
+            out.extend_from_slice(b"\x1b\\");
+        }
+    }
+
+    fn write_iterm(image: &RgbaImage, out: &mut Vec<u8>) -> Option<()> {
+        let mut png = Vec::new();
+
+        PngEncoder::new(&mut png)
+            .encode(
+                image.as_raw(),
+                image.width(),
+                image.height(),
+                ColorType::Rgba8,
+            )
+            .ok()?;
+
+        write!(
+            out,
+            "\x1b]1337;File=inline=1;size={};width={}px;height={}px:{}\x07",
+            png.len(),
+            image.width(),
+            image.height(),
+            base64(&png)
+        )
+        .ok()
+    }
+
+    /// Write the given `image` as sixels, with each of its colors rounded to one of 216 evenly spaced ones.
+    ///
+    /// Pixels that are mostly transparent are left as whatever is behind them.
+    fn write_sixel(image: &RgbaImage, out: &mut Vec<u8>) {
+        const LEVELS: usize = 6;
+        const NUM_COLORS: usize = LEVELS * LEVELS * LEVELS;
+
+        let level = |value: u8| (usize::from(value) * (LEVELS - 1) + 127) / 255;
+        let width = image.width() as usize;
+
+        write!(out, "\x1bP0;1;0q\"1;1;{};{}", image.width(), image.height()).ok();
+
+        for color in 0..NUM_COLORS {
+            let percent = |level: usize| level * 100 / (LEVELS - 1);
+            let (red, green, blue) = (color / 36, color / 6 % 6, color % 6);
+
+            write!(
+                out,
+                "#{};2;{};{};{}",
+                color,
+                percent(red),
+                percent(green),
+                percent(blue)
+            )
+            .ok();
+        }
+
+        // Sixels are drawn in bands of six rows, with a pass over the band for each color in it
+        let mut bands = vec![Vec::new(); NUM_COLORS];
+        let mut used = Vec::new();
+
+        for band_y in (0..image.height()).step_by(6) {
+            for row in 0..6 {
+                let y = band_y + row;
+
+                if y >= image.height() {
+                    break;
+                }
+
+                for (x, pixel) in (0..image.width()).map(|x| (x, image.get_pixel(x, y))) {
+                    if pixel[3] < 128 {
+                        continue;
+                    }
+
+                    let color = level(pixel[0]) * 36 + level(pixel[1]) * 6 + level(pixel[2]);
+                    let columns: &mut Vec<u8> = &mut bands[color];
+
+                    if columns.is_empty() {
+                        columns.resize(width, 0);
+                        used.push(color);
+                    }
+
+                    columns[x as usize] |= 1 << row;
+                }
+            }
+
+            for (i, &color) in used.iter().enumerate() {
+                if i > 0 {
+                    out.push(b'$');
+                }
+
+                write!(out, "#{}", color).ok();
+                Self::write_sixel_run(&bands[color], out);
+                bands[color].clear();
+            }
+
+            used.clear();
+            out.push(b'-');
+        }
+
+        out.extend_from_slice(b"\x1b\\");
+    }
+
+    /// Write the given `columns` of a band as sixel characters, with repeated ones shortened.
+    fn write_sixel_run(columns: &[u8], out: &mut Vec<u8>) {
+        let mut rest = columns;
+
+        while let Some(&bits) = rest.first() {
+            let count = rest.iter().take_while(|&&other| other == bits).count();
+            let ch = b'?' + bits;
+
+            if count > 3 {
+                write!(out, "!{}", count).ok();
+                out.push(ch);
+            } else {
+                out.extend(std::iter::repeat_n(ch, count));
+            }
+
+            rest = &rest[count..];
+        }
+    }
+}
+
+impl FromStr for Graphics {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "kitty" => Ok(Self::Kitty),
+            "iterm" => Ok(Self::Iterm),
+            "sixel" => Ok(Self::Sixel),
+            _ => Err(anyhow!(
+                "invalid graphics protocol: {} (expected kitty, iterm, sixel, or none)",
+                value.trim()
+            )),
+        }
+    }
+}
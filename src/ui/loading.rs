@@ -0,0 +1,252 @@
+use super::{
+    colors,
+    event::{EventKind, Events},
+    util::SimpleText,
+    UI,
+};
+use crate::archive::{Archive, ReadProgress};
+use crate::util::number;
+use anyhow::{anyhow, Context, Result};
+use crossterm::event::KeyCode;
+use crossterm::terminal;
+use futures::{
+    channel::oneshot,
+    future::{self, FutureExt},
+    select,
+};
+use futures_timer::Delay;
+use std::io;
+use std::path::Path;
+use std::sync::{atomic::Ordering, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+use tui::backend::CrosstermBackend;
+use tui::layout::{Alignment, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::Terminal;
+
+/// How long reading has to take before the loading screen is shown, so small archives don't flash it.
+const SHOW_AFTER: Duration = Duration::from_millis(150);
+
+/// How often the loading screen is redrawn.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Read the archive at each of the given `paths` in the background, and show how far along it is once it takes long
+/// enough to notice.
+///
+/// Returns None if the user quit before every archive was read.
+pub async fn read_archives(paths: &[String]) -> Result<Option<Vec<Archive>>> {
+    let mut screen = None;
+    let result = read_all(paths, &mut screen).await;
+
+    if let Some(screen) = screen {
+        screen.exit()?;
+    }
+
+    result
+}
+
+async fn read_all(
+    paths: &[String],
+    screen: &mut Option<LoadingScreen>,
+) -> Result<Option<Vec<Archive>>> {
+    let started_at = Instant::now();
+    let mut archives = Vec::with_capacity(paths.len());
+
+    for (index, path) in paths.iter().enumerate() {
+        let progress = Arc::new(ReadProgress::default());
+
+        let (sender, reading) = oneshot::channel();
+        let mut reading = reading.fuse();
+
+        {
+            let path = path.clone();
+            let progress = Arc::clone(&progress);
+
+            thread::spawn(move || {
+                let result = Archive::read_with_progress(&path, &progress)
+                    .with_context(|| anyhow!("failed to read files from {}", path));
+
+                // Nothing is waiting for the archive anymore if the user quit while it was being read
+                sender.send(result).ok();
+            });
+        }
+
+        loop {
+            let event = {
+                let mut redraw = Delay::new(REDRAW_INTERVAL).map(|()| None).fuse();
+
+                // Keys can only be read once the screen has taken over the terminal
+                let mut next_event = match screen {
+                    Some(screen) => screen.events.next().map(Result::ok).boxed_local(),
+                    None => future::pending().boxed_local(),
+                }
+                .fuse();
+
+                select! {
+                    archive = reading => {
+                        archives.push(received(archive, path)?);
+                        break;
+                    }
+                    event = redraw => event,
+                    event = next_event => event.flatten(),
+                }
+            };
+
+            // The archive keeps being read in the background, but nothing waits for it once we return
+            if let Some(EventKind::Key(KeyCode::Char('q') | KeyCode::Esc)) = event {
+                return Ok(None);
+            }
+
+            if screen.is_none() && started_at.elapsed() >= SHOW_AFTER {
+                *screen = Some(LoadingScreen::init()?);
+            }
+
+            if let Some(screen) = screen {
+                let status = ReadStatus {
+                    path,
+                    index,
+                    num_archives: paths.len(),
+                    progress: &progress,
+                };
+
+                screen.draw(&status)?;
+            }
+        }
+    }
+
+    Ok(Some(archives))
+}
+
+/// Returns the archive that was read from the given `path`, or an error if the thread reading it panicked.
+fn received(archive: Result<Result<Archive>, oneshot::Canceled>, path: &str) -> Result<Archive> {
+    archive.unwrap_or_else(|_| Err(anyhow!("failed to read files from {}", path)))
+}
+
+/// The archive being read and how far along it is.
+struct ReadStatus<'a> {
+    path: &'a str,
+    /// The position of the archive in the list of archives being read.
+    index: usize,
+    num_archives: usize,
+    progress: &'a ReadProgress,
+}
+
+impl<'a> ReadStatus<'a> {
+    fn title(&self) -> String {
+        let name = Path::new(self.path)
+            .file_name()
+            .map_or_else(|| self.path.into(), |name| name.to_string_lossy());
+
+        if self.num_archives < 2 {
+            return format!("Reading {}", name);
+        }
+
+        format!(
+            "Reading {} ({} of {})",
+            name,
+            self.index + 1,
+            self.num_archives
+        )
+    }
+
+    fn counts(&self) -> String {
+        let total = self.progress.total.load(Ordering::Relaxed);
+
+        // The index at the end of the archive has to be parsed before the number of entries is known
+        if total == 0 {
+            return "reading index".into();
+        }
+
+        let read = self.progress.read.load(Ordering::Relaxed);
+
+        format!(
+            "{} of {} entries",
+            number::grouped(read as u64),
+            number::grouped(total as u64)
+        )
+    }
+}
+
+/// Screen shown while archives are being read, which takes over the terminal until the interface is ready.
+struct LoadingScreen {
+    terminal: Terminal<CrosstermBackend<io::Stdout>>,
+    events: Events,
+    /// The number of times the screen has been drawn, which picks the frame of the spinner.
+    draws: usize,
+}
+
+impl LoadingScreen {
+    const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
+    fn init() -> Result<Self> {
+        terminal::enable_raw_mode().context("failed to enable raw mode")?;
+
+        let backend = CrosstermBackend::new(io::stdout());
+        let mut terminal = Terminal::new(backend).context("terminal creation failed")?;
+
+        terminal.clear().context("failed to clear terminal")?;
+
+        terminal
+            .hide_cursor()
+            .context("failed to hide mouse cursor")?;
+
+        Ok(Self {
+            terminal,
+            events: Events::new(),
+            draws: 0,
+        })
+    }
+
+    fn draw(&mut self, status: &ReadStatus) -> Result<()> {
+        let spinner = Self::SPINNER[self.draws % Self::SPINNER.len()];
+        self.draws += 1;
+
+        let title = format!("{} {}", spinner, status.title());
+        let counts = status.counts();
+
+        self.terminal.draw(|frame| {
+            let area = frame.size();
+
+            if area.height < 2 {
+                return;
+            }
+
+            let y = area.y + (area.height - 2) / 2;
+
+            let title_style = Style::default()
+                .fg(colors::WHITE)
+                .add_modifier(Modifier::BOLD);
+
+            let title = SimpleText::new(title)
+                .alignment(Alignment::Center)
+                .style(title_style);
+
+            let counts = SimpleText::new(counts)
+                .alignment(Alignment::Center)
+                .style(Style::default().fg(Color::DarkGray));
+
+            let title_area = Rect {
+                y,
+                height: 1,
+                ..area
+            };
+
+            frame.render_widget(title, title_area);
+            frame.render_widget(
+                counts,
+                Rect {
+                    y: y + 1,
+                    ..title_area
+                },
+            );
+        })?;
+
+        Ok(())
+    }
+
+    fn exit(mut self) -> Result<()> {
+        self.terminal.clear().ok();
+        UI::restore_terminal(false)
+    }
+}
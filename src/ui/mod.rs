@@ -1,18 +1,69 @@
+mod clipboard;
 mod colors;
 mod event;
+mod graphics;
+mod loading;
+pub mod notify;
 mod panel;
+mod quit_prompt;
+mod start;
+mod tab_bar;
 mod util;
 
-use crate::archive::Archive;
+use crate::archive::{
+    extract::ExtractOptions,
+    mount::{self, ArchiveMountSession, MountChanges, MountOptions},
+    Archive, NodeID,
+};
 use anyhow::{Context, Result};
-use crossterm::event::KeyCode;
-use crossterm::terminal;
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture, KeyCode};
+use crossterm::{cursor, execute, terminal};
 use event::{EventKind, Events};
+use notify::Notifications;
 use panel::{Draw, MainPanel, Panel};
-use std::io;
+
+pub use graphics::Graphics;
+pub use loading::read_archives;
+pub use panel::{IconStyle, PaneRatios};
+use quit_prompt::QuitPrompt;
+pub use start::pick_recent_archive;
+use std::io::{self, Write};
+use std::mem;
+use std::panic;
+use std::path::PathBuf;
+use tab_bar::TabBar;
 use tui::backend::CrosstermBackend;
+use tui::layout::{Constraint, Direction, Layout, Rect};
 use tui::Terminal;
 
+/// Settings that change how the interface behaves.
+#[derive(Clone)]
+// The switches are passed along from the command line as they are
+#[allow(clippy::struct_excessive_bools)]
+pub struct Config {
+    pub notifications: Notifications,
+    pub extract_options: ExtractOptions,
+    /// The directory that the viewed directory is extracted to when using the quick extract key.
+    pub extract_dest: PathBuf,
+    /// Whether to draw a clickable row with the name of each column above the viewed directory.
+    pub column_header: bool,
+    /// Whether the date each entry was last modified is drawn in the viewed directory from the start.
+    pub date_column: bool,
+    /// How much of the width the parent, viewed, and child directories each take up compared to the others.
+    pub pane_ratios: PaneRatios,
+    /// How the kind of each entry is marked before its name.
+    pub icons: IconStyle,
+    /// Whether moving past the first or last entry of a directory goes to the other end of it.
+    pub wrap: bool,
+    /// Whether clicking and scrolling should move around the archive instead of selecting text in the terminal.
+    pub mouse: bool,
+    /// How images are drawn in the terminal when they're previewed.
+    pub graphics: Graphics,
+    pub mount_options: MountOptions,
+    /// Whether the archive should be kept mounted by a background process after exiting instead of being unmounted.
+    pub keep_mounted: bool,
+}
+
 pub enum CycleResult {
     Ok,
     Exit,
@@ -22,14 +73,39 @@ pub enum CycleResult {
 pub struct UI<'a> {
     events: Events,
     terminal: Terminal<CrosstermBackend<io::Stdout>>,
-    main_panel: MainPanel<'a>,
+    /// A panel for each open tab, which can each view a different archive or the same one somewhere else.
+    tabs: Vec<MainPanel<'a>>,
+    /// The index of the tab that is shown and given keys.
+    active: usize,
+    /// Changes made through the mounts of tabs that were closed, which are reported on exit.
+    closed_changes: Vec<MountChanges>,
+    /// How far along quitting is, if the user asked to quit while extractions were running or archives were mounted.
+    quitting: Option<QuitState>,
+    mouse_captured: bool,
+    keep_mounted: bool,
+    graphics: Graphics,
+    /// The tab and previewed image the terminal is drawing and where, since it stays on the screen until it's
+    /// cleared.
+    shown_graphic: Option<(usize, NodeID, Rect)>,
 }
 
 impl<'a> UI<'a> {
-    pub fn init(archive: Archive) -> Result<Self> {
+    const QUIT_KEY: char = 'q';
+    const CLOSE_TAB_KEY: char = 'w';
+    const WAIT_FOR_JOBS_KEY: char = 'w';
+    const CANCEL_JOBS_KEY: char = 'c';
+
+    pub fn init(archives: Vec<Archive>, config: &Config) -> Result<Self> {
         // We should initialize failable panels before touching the terminal so we don't need to cleanup anything
         // if one fails
-        let main_panel = MainPanel::new(archive)?;
+        let mouse_captured = config.mouse;
+        let keep_mounted = config.keep_mounted;
+        let graphics = config.graphics;
+
+        let tabs = archives
+            .into_iter()
+            .map(|archive| MainPanel::new(archive, config.clone()))
+            .collect::<Result<Vec<_>>>()?;
 
         terminal::enable_raw_mode().context("failed to enable raw mode")?;
 
@@ -43,19 +119,46 @@ impl<'a> UI<'a> {
             .hide_cursor()
             .context("failed to hide mouse cursor")?;
 
+        // Capturing the mouse prevents text from being selected in most terminals, so it can be turned off
+        if mouse_captured {
+            execute!(io::stdout(), EnableMouseCapture).context("failed to capture mouse")?;
+        }
+
+        Self::set_panic_hook(mouse_captured);
+
         Ok(Self {
             events: Events::new(),
             terminal,
-            main_panel,
+            tabs,
+            active: 0,
+            closed_changes: Vec::new(),
+            quitting: None,
+            mouse_captured,
+            keep_mounted,
+            graphics,
+            shown_graphic: None,
         })
     }
 
     pub async fn next_cycle(&mut self) -> CycleResult {
+        if self.quitting == Some(QuitState::WaitingForJobs)
+            && !self.tabs.iter().any(MainPanel::has_jobs)
+        {
+            return CycleResult::Exit;
+        }
+
         if let Err(err) = self.draw() {
             return CycleResult::Error(err);
         }
 
-        let event = match self.events.next().await {
+        // The shown tab is drawn more often while its preview loads so the spinner keeps moving
+        let event = if self.tabs[self.active].is_loading() {
+            self.events.next_within(Events::FAST_TICK).await
+        } else {
+            self.events.next().await
+        };
+
+        let event = match event {
             Ok(Some(event)) => event,
             Ok(None) => return CycleResult::Ok,
             Err(event::ErrorKind::ExitRequest) => return CycleResult::Exit,
@@ -63,40 +166,301 @@ impl<'a> UI<'a> {
         };
 
         match event {
-            EventKind::Key(key) => self.process_key(key),
-            EventKind::Tick => CycleResult::Ok,
+            EventKind::ShiftKey(key) if self.quitting.is_none() => {
+                self.tabs[self.active].scroll_preview(key == KeyCode::Down);
+                CycleResult::Ok
+            }
+            EventKind::Key(key) | EventKind::ShiftKey(key) => self.process_key(key),
+            EventKind::Mouse(action, x, y) if self.quitting.is_none() => {
+                self.tabs[self.active].process_mouse(action, x, y);
+                CycleResult::Ok
+            }
+            EventKind::Mouse(..) | EventKind::Tick => CycleResult::Ok,
         }
     }
 
     fn draw(&mut self) -> Result<()> {
+        self.draw_frame()?;
+        self.draw_graphic()?;
+
+        // Jobs keep running in tabs that aren't shown, so they should still ring the bell when they finish
+        let mut bell = false;
+
+        for tab in &mut self.tabs {
+            bell |= tab.take_bell();
+        }
+
+        if bell {
+            let backend = self.terminal.backend_mut();
+            backend.write_all(b"\x07")?;
+            backend.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_frame(&mut self) -> Result<()> {
         // We need to remove the mutable borrow on self so we can call other mutable methods on it during our draw call.
         // This *should* be completely safe as long as nothing in the draw closure can access the terminal.
         let terminal: *mut _ = &mut self.terminal;
         let terminal: &mut _ = unsafe { &mut *terminal };
 
-        terminal
-            .draw(|frame| self.main_panel.draw(frame.size(), frame))
-            .map_err(Into::into)
+        terminal.draw(|frame| {
+            let area = frame.size();
+
+            // There's no need for a tab bar until there's more than one tab to switch between
+            if self.tabs.len() < 2 {
+                self.tabs[self.active].draw(area, frame);
+            } else {
+                let layout = Layout::default()
+                    .constraints([Constraint::Length(1), Constraint::Min(1)])
+                    .direction(Direction::Vertical)
+                    .split(area);
+
+                let titles = self.tabs.iter().map(MainPanel::title).collect::<Vec<_>>();
+
+                let tab_bar = TabBar {
+                    titles: &titles,
+                    active: self.active,
+                };
+
+                frame.render_widget(tab_bar, layout[0]);
+                self.tabs[self.active].draw(layout[1], frame);
+            }
+
+            if let Some(quitting) = self.quitting {
+                let operations = self.active_operations();
+
+                let prompt = QuitPrompt {
+                    operations: &operations,
+                    has_jobs: self.tabs.iter().any(MainPanel::has_jobs),
+                    waiting: quitting == QuitState::WaitingForJobs,
+                };
+
+                let prompt_area = prompt.area(area);
+                frame.render_widget(prompt, prompt_area);
+            }
+        })?;
+
+        Ok(())
+    }
+
+    /// Make the terminal draw the previewed image of the shown tab over the interface, if there is one.
+    ///
+    /// Images are drawn outside of the interface, so they're only drawn when they change. The interface is drawn
+    /// again from scratch first if a different image was being drawn, since it would otherwise be left behind.
+    fn draw_graphic(&mut self) -> Result<()> {
+        let placement = if self.quitting.is_none() {
+            self.tabs[self.active]
+                .graphic_placement()
+                .map(|(id, area)| (self.active, id, area))
+        } else {
+            None
+        };
+
+        if placement == self.shown_graphic {
+            return Ok(());
+        }
+
+        if self.shown_graphic.take().is_some() {
+            self.graphics.clear(self.terminal.backend_mut())?;
+            self.terminal.clear()?;
+            self.draw_frame()?;
+        }
+
+        if placement.is_none() {
+            return Ok(());
+        }
+
+        if let Some(graphic) = self.tabs[self.active].graphic() {
+            let backend = self.terminal.backend_mut();
+            backend.write_all(graphic)?;
+            backend.flush()?;
+        }
+
+        self.shown_graphic = placement;
+        Ok(())
     }
 
     fn process_key(&mut self, key: KeyCode) -> CycleResult {
-        let locked = self.main_panel.process_key(key);
+        if let Some(quitting) = self.quitting {
+            return self.process_quit_key(quitting, key);
+        }
+
+        let locked = self.tabs[self.active].process_key(key);
+
+        if let Some(text) = self.tabs[self.active].take_clipboard() {
+            if let Err(err) = clipboard::copy(self.terminal.backend_mut(), &text) {
+                return CycleResult::Error(err.into());
+            }
+        }
+
+        if let Some(tab) = self.tabs[self.active].take_new_tab() {
+            self.active += 1;
+            self.tabs.insert(self.active, tab);
+        }
 
         if locked == InputLock::Locked {
             return CycleResult::Ok;
         }
 
-        if key == KeyCode::Char('q') {
+        match key {
+            KeyCode::Char(Self::QUIT_KEY) => return self.request_quit(),
+            KeyCode::Tab => self.active = (self.active + 1) % self.tabs.len(),
+            KeyCode::BackTab => {
+                self.active = self.active.checked_sub(1).unwrap_or(self.tabs.len() - 1);
+            }
+            KeyCode::Char(Self::CLOSE_TAB_KEY) => self.close_tab(),
+            _ => (),
+        }
+
+        CycleResult::Ok
+    }
+
+    /// Returns a description of each operation in every tab that quitting would cut short.
+    fn active_operations(&self) -> Vec<String> {
+        // Archives that are kept mounted after quitting aren't cut short
+        let include_mounts = !self.keep_mounted;
+
+        if self.tabs.len() < 2 {
+            return self.tabs[self.active].active_operations(include_mounts);
+        }
+
+        self.tabs
+            .iter()
+            .flat_map(|tab| {
+                let title = tab.title();
+
+                tab.active_operations(include_mounts)
+                    .into_iter()
+                    .map(move |operation| format!("{}: {}", title, operation))
+            })
+            .collect()
+    }
+
+    /// Quit right away if nothing would be cut short by it, or ask the user what to do otherwise.
+    fn request_quit(&mut self) -> CycleResult {
+        if self.active_operations().is_empty() {
             return CycleResult::Exit;
         }
 
+        self.quitting = Some(QuitState::Confirming);
         CycleResult::Ok
     }
 
-    pub fn exit(mut self) -> Result<()> {
-        self.terminal.clear().ok();
+    /// Process a key while the user is being asked whether to quit.
+    fn process_quit_key(&mut self, quitting: QuitState, key: KeyCode) -> CycleResult {
+        let has_jobs = self.tabs.iter().any(MainPanel::has_jobs);
+
+        match key {
+            KeyCode::Char(Self::QUIT_KEY) => return CycleResult::Exit,
+            KeyCode::Char(Self::WAIT_FOR_JOBS_KEY)
+                if quitting == QuitState::Confirming && has_jobs =>
+            {
+                self.quitting = Some(QuitState::WaitingForJobs);
+            }
+            // Cancelled jobs clean up after themselves, so they still need to be waited on
+            KeyCode::Char(Self::CANCEL_JOBS_KEY)
+                if quitting == QuitState::Confirming && has_jobs =>
+            {
+                for tab in &self.tabs {
+                    tab.cancel_jobs();
+                }
+
+                self.quitting = Some(QuitState::WaitingForJobs);
+            }
+            KeyCode::Esc => self.quitting = None,
+            _ => (),
+        }
+
+        CycleResult::Ok
+    }
+
+    /// Close the active tab and unmount its archive, unless it's the only tab left.
+    fn close_tab(&mut self) {
+        if self.tabs.len() < 2 {
+            return;
+        }
+
+        let mut tab = self.tabs.remove(self.active);
+
+        // The session is only a convenience, so failing to save it shouldn't keep the tab open
+        tab.save_session().ok();
+
+        if let Some(changes) = tab.unmount() {
+            self.closed_changes.push(changes);
+        }
+
+        self.active = self.active.min(self.tabs.len() - 1);
+    }
+
+    /// Make sure mounted archives are unmounted and the terminal is usable again if vear panics.
+    ///
+    /// Nothing is dropped when panicking aborts, which would otherwise leave mounts behind in a broken state.
+    fn set_panic_hook(mouse_captured: bool) {
+        let default_hook = panic::take_hook();
+
+        panic::set_hook(Box::new(move |info| {
+            mount::unmount_all();
+            Self::restore_terminal(mouse_captured).ok();
+            default_hook(info);
+        }));
+    }
+
+    fn restore_terminal(mouse_captured: bool) -> Result<()> {
+        if mouse_captured {
+            execute!(io::stdout(), DisableMouseCapture).ok();
+        }
+
+        execute!(io::stdout(), cursor::Show).ok();
         terminal::disable_raw_mode().map_err(Into::into)
     }
+
+    /// Restore the terminal and unmount every archive, unless archives should be kept mounted after exiting.
+    ///
+    /// Returns the sessions of the archives that should be kept mounted.
+    pub fn exit(mut self) -> Result<Vec<ArchiveMountSession>> {
+        self.terminal.clear().ok();
+
+        let result = Self::restore_terminal(self.mouse_captured);
+
+        for changes in mem::take(&mut self.closed_changes) {
+            Self::report_changes(&changes);
+        }
+
+        let mut kept_mounted = Vec::new();
+
+        for tab in &mut self.tabs {
+            tab.save_session().ok();
+
+            if self.keep_mounted {
+                kept_mounted.extend(tab.take_mount_session());
+            } else if let Some(changes) = tab.unmount() {
+                Self::report_changes(&changes);
+            }
+        }
+
+        result.map(|()| kept_mounted)
+    }
+
+    /// Say where the `changes` made through a writable mount were kept, since they'd otherwise be kept without
+    /// saying where.
+    fn report_changes(changes: &MountChanges) {
+        eprintln!(
+            "{} entries changed while mounted were kept in {}",
+            changes.changes.len(),
+            changes.dir.display()
+        );
+    }
+}
+
+/// How far along quitting is after the user asked to quit while operations were still in progress.
+#[derive(Copy, Clone, PartialEq)]
+enum QuitState {
+    /// The user is being asked whether to wait for jobs, cancel them, or quit right away.
+    Confirming,
+    /// Quit once every job has finished.
+    WaitingForJobs,
 }
 
 #[derive(Copy, Clone, PartialEq)]
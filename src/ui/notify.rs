@@ -0,0 +1,253 @@
+use super::{colors, util::fill_area};
+use anyhow::{anyhow, Error};
+use std::collections::{HashMap, VecDeque};
+use std::iter::FromIterator;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::Widget,
+};
+
+/// The way the user is notified when a background job finishes.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Notify {
+    None,
+    Bell,
+    Flash,
+}
+
+impl Default for Notify {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl FromStr for Notify {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "none" => Ok(Self::None),
+            "bell" => Ok(Self::Bell),
+            "flash" => Ok(Self::Flash),
+            _ => Err(anyhow!(
+                "unknown notification kind: {} (expected none, bell, or flash)",
+                value
+            )),
+        }
+    }
+}
+
+/// The kinds of jobs that the user can be notified about once they finish.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub enum JobKind {
+    Extract,
+    Verify,
+    Compare,
+    Grep,
+    Duplicates,
+    Mount,
+    Find,
+}
+
+impl FromStr for JobKind {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "extract" => Ok(Self::Extract),
+            "verify" => Ok(Self::Verify),
+            "compare" => Ok(Self::Compare),
+            "grep" => Ok(Self::Grep),
+            "duplicates" => Ok(Self::Duplicates),
+            "mount" => Ok(Self::Mount),
+            "find" => Ok(Self::Find),
+            _ => Err(anyhow!(
+                "unknown job kind: {} (expected extract, verify, compare, grep, duplicates, mount, or find)",
+                value
+            )),
+        }
+    }
+}
+
+/// How to notify the user once a job of a certain kind finishes, written as `KIND=HOW`.
+pub struct NotifySetting {
+    pub kind: JobKind,
+    pub notify: Notify,
+}
+
+impl FromStr for NotifySetting {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (kind, notify) = value.split_once('=').ok_or_else(|| {
+            anyhow!(
+                "notification setting must be written as KIND=HOW: {}",
+                value
+            )
+        })?;
+
+        Ok(Self {
+            kind: kind.parse()?,
+            notify: notify.parse()?,
+        })
+    }
+}
+
+/// Notification settings for each kind of background job.
+#[derive(Clone, Default)]
+pub struct Notifications(HashMap<JobKind, Notify>);
+
+impl Notifications {
+    /// Returns how to notify the user once a job of the given `kind` finishes.
+    pub fn get(&self, kind: JobKind) -> Notify {
+        self.0.get(&kind).copied().unwrap_or_default()
+    }
+}
+
+impl FromIterator<NotifySetting> for Notifications {
+    /// Collect the given settings, where later settings for the same kind of job replace earlier ones.
+    fn from_iter<I>(settings: I) -> Self
+    where
+        I: IntoIterator<Item = NotifySetting>,
+    {
+        Self(
+            settings
+                .into_iter()
+                .map(|setting| (setting.kind, setting.notify))
+                .collect(),
+        )
+    }
+}
+
+/// Tracks notifications that still need to be shown to the user.
+#[derive(Default)]
+pub struct PendingNotify {
+    ring_bell: bool,
+    flash_until: Option<Instant>,
+}
+
+impl PendingNotify {
+    const FLASH_DURATION: Duration = Duration::from_millis(750);
+
+    pub fn push(&mut self, notify: Notify) {
+        match notify {
+            Notify::None => (),
+            Notify::Bell => self.ring_bell = true,
+            Notify::Flash => self.flash_until = Some(Instant::now() + Self::FLASH_DURATION),
+        }
+    }
+
+    /// Returns true if the terminal bell should be rung, and marks it as done.
+    pub fn take_bell(&mut self) -> bool {
+        std::mem::take(&mut self.ring_bell)
+    }
+
+    /// Returns true if the footer should currently be flashing.
+    pub fn is_flashing(&mut self) -> bool {
+        match self.flash_until {
+            Some(until) if Instant::now() < until => true,
+            Some(_) => {
+                self.flash_until = None;
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+/// Widget that inverts the colors of an area that has already been drawn.
+pub struct Flash;
+
+impl Widget for Flash {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        fill_area(area, buf, |cell| {
+            cell.modifier.insert(Modifier::REVERSED);
+        });
+    }
+}
+
+/// Short messages about things that happened, which are shown one at a time for a few seconds each.
+///
+/// Background jobs can push messages while something else is being shown, and they'll wait their turn.
+#[derive(Default)]
+pub struct StatusQueue {
+    messages: VecDeque<StatusMessage>,
+    /// When the first message in the queue started being shown.
+    shown_since: Option<Instant>,
+}
+
+impl StatusQueue {
+    const MESSAGE_DURATION: Duration = Duration::from_secs(3);
+
+    /// The most messages that can wait to be shown, after which the oldest ones are dropped.
+    const MAX_QUEUED: usize = 8;
+
+    pub fn info<S: Into<String>>(&mut self, text: S) {
+        self.push(StatusMessage {
+            text: text.into(),
+            is_warning: false,
+        });
+    }
+
+    pub fn warn<S: Into<String>>(&mut self, text: S) {
+        self.push(StatusMessage {
+            text: text.into(),
+            is_warning: true,
+        });
+    }
+
+    fn push(&mut self, message: StatusMessage) {
+        if self.messages.len() >= Self::MAX_QUEUED {
+            self.messages.pop_front();
+            self.shown_since = None;
+        }
+
+        self.messages.push_back(message);
+    }
+
+    /// Returns the message that should be shown right now, moving on from any that have been shown long enough.
+    pub fn current(&mut self) -> Option<&StatusMessage> {
+        let now = Instant::now();
+
+        if let Some(since) = self.shown_since {
+            if now.duration_since(since) >= Self::MESSAGE_DURATION {
+                self.messages.pop_front();
+                self.shown_since = None;
+            }
+        }
+
+        let message = self.messages.front()?;
+        self.shown_since.get_or_insert(now);
+        Some(message)
+    }
+}
+
+pub struct StatusMessage {
+    pub text: String,
+    /// Whether the message is about something that didn't work, rather than something that did.
+    pub is_warning: bool,
+}
+
+/// Widget to draw a [`StatusMessage`] on a single line.
+pub struct StatusLine<'a>(pub &'a StatusMessage);
+
+impl<'a> Widget for StatusLine<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+
+        let color = if self.0.is_warning {
+            Color::Yellow
+        } else {
+            colors::WHITE
+        };
+
+        let style = Style::default().fg(color).add_modifier(Modifier::ITALIC);
+        buf.set_stringn(area.x, area.y, &self.0.text, area.width as usize, style);
+    }
+}
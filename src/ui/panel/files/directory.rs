@@ -1,10 +1,12 @@
-use super::{Backend, Draw, Frame, KeyCode, Panel};
+use super::{icon::IconStyle, Backend, Command, Draw, Frame, KeyCode, Panel};
 use crate::{
-    archive::{Archive, ArchiveEntry, EntryProperties, NodeID},
-    ui::util::fill_area,
+    archive::{filter, query::EntryQuery, ArchiveEntries, ArchiveEntry, EntryProperties, NodeID},
+    ui::util::{fill_area, SimpleText},
 };
 use crate::{ui::colors, util::size};
-use smallvec::{smallvec, SmallVec};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::ops::Range;
 use std::{ops::Deref, sync::Arc};
 use tui::buffer::Buffer;
@@ -15,65 +17,208 @@ use unicode_width::UnicodeWidthStr;
 
 /// Widget to browse a given directory.
 pub struct DirectoryViewer {
-    archive: Arc<Archive>,
+    files: Arc<ArchiveEntries>,
     entries: WrappedSelection<DirectoryEntry>,
     directory: NodeID,
+    /// The highlighted entry, or the directory itself when it has no entries to show.
     highlighted: NodeID,
+    /// The number of entries that fit on screen as of the last draw, which is how far a page moves.
+    page_height: usize,
+    /// Whether hidden entries were left out, which is used to tell why there's nothing to show.
+    hide_hidden: bool,
+    /// Only entries that match this are shown.
+    name_filter: Option<EntryQuery>,
 }
 
 impl DirectoryViewer {
-    /// Create a new [`DirectoryViewer`] to view the given `directory` in the given `archive`.
-    ///
-    /// Returns None if the given `directory` has no entries (children) to show.
-    pub fn new(archive: Arc<Archive>, directory: NodeID) -> Option<Self> {
-        let dir_entry = &archive[directory];
+    const NEXT_SELECTED_KEY: char = ']';
+    const PREV_SELECTED_KEY: char = '[';
+    const SELECT_ALL_KEY: char = 'A';
+    const INVERT_SELECTION_KEY: char = '*';
+    const LAST_ENTRY_KEY: char = 'G';
+
+    pub const COMMANDS: &'static [Command] = &[
+        Command::char("select highlighted entry", ' '),
+        Command::char("select every entry in directory", Self::SELECT_ALL_KEY),
+        Command::char("invert selection in directory", Self::INVERT_SELECTION_KEY),
+        Command::char("go to next selected entry", Self::NEXT_SELECTED_KEY),
+        Command::char("go to previous selected entry", Self::PREV_SELECTED_KEY),
+        Command::char("go to last entry", Self::LAST_ENTRY_KEY),
+    ];
 
-        if dir_entry.children.is_empty() {
+    /// Create a new [`DirectoryViewer`] to view the given `directory` in the given `files`.
+    ///
+    /// Only entries that pass the size filter in the given `options` will be shown, and entries in `marked` start out selected.
+    ///
+    /// Returns None if the given `directory` isn't a directory.
+    pub fn new(
+        files: Arc<ArchiveEntries>,
+        directory: NodeID,
+        options: ViewOptions,
+        marked: &HashSet<NodeID>,
+    ) -> Option<Self> {
+        if !files[directory].props.is_dir() {
             return None;
         }
 
+        let entries = Self::visible_entries(&files, directory, options, marked);
+        Some(Self::with_entries(files, directory, entries, options))
+    }
+
+    fn with_entries(
+        files: Arc<ArchiveEntries>,
+        directory: NodeID,
+        entries: Vec<DirectoryEntry>,
+        options: ViewOptions,
+    ) -> Self {
+        let highlighted = entries.first().map_or(directory, |entry| entry.id);
+
+        Self {
+            files,
+            entries: WrappedSelection::new(entries, !options.stop_at_edges),
+            directory,
+            highlighted,
+            page_height: 1,
+            hide_hidden: options.hide_hidden,
+            name_filter: None,
+        }
+    }
+
+    /// Returns the entries of the given `directory` that pass the size filter in `options`, in the order they're shown.
+    fn visible_entries(
+        files: &ArchiveEntries,
+        directory: NodeID,
+        options: ViewOptions,
+        marked: &HashSet<NodeID>,
+    ) -> Vec<DirectoryEntry> {
+        let dir_entry = &files[directory];
+
         let mut children = dir_entry
             .children
             .iter()
+            .filter(|&&id| options.shows(&files[id]))
             .map(|&id| {
-                let entry = &archive[id];
+                let entry = &files[id];
 
                 let size = match &entry.props {
                     EntryProperties::File(props) => size::formatted(props.raw_size_bytes),
-                    EntryProperties::Directory => entry.children.len().to_string(),
+                    EntryProperties::Directory => size::formatted(entry.total_size_bytes),
                 };
 
+                let date = entry
+                    .last_modified
+                    .as_ref()
+                    .map(|date| format!("{}-{:02}-{:02}", date.year, date.month, date.day));
+
                 DirectoryEntry {
                     id,
-                    selected: false,
+                    selected: marked.contains(&id),
                     size,
+                    date,
+                    icon: options.icons.icon(entry),
                 }
             })
             .collect::<Vec<_>>();
 
         children.sort_unstable_by(|x, y| {
-            let x = &archive[x.id];
-            let y = &archive[y.id];
+            let x = &files[x.id];
+            let y = &files[y.id];
 
             let by_kind_desc = y.props.is_dir().cmp(&x.props.is_dir());
-            let by_name_desc = x.name.cmp(&y.name);
-            by_kind_desc.then(by_name_desc)
+            by_kind_desc.then_with(|| options.sort.compare(x, y))
         });
 
-        // We're guaranteed to have at least one child, so this is safe
-        let highlighted = children[0].id;
+        children
+    }
+
+    /// Create a copy of this viewer that filters and sorts its entries with the given `options`.
+    ///
+    /// The highlighted entry is carried over if it's still visible.
+    pub fn with_options(&self, options: ViewOptions, marked: &HashSet<NodeID>) -> Self {
+        self.rebuilt(options, marked, self.name_filter.clone())
+    }
 
-        Some(Self {
-            archive,
-            entries: WrappedSelection::new(children),
-            directory,
-            highlighted,
-        })
+    /// Create a copy of this viewer that only shows entries that match the query in `filter`, or every entry if
+    /// there's no `filter`.
+    ///
+    /// See [`EntryQuery`] for what a query can ask for.
+    ///
+    /// The highlighted entry is carried over if it's still visible.
+    pub fn with_name_filter(
+        &self,
+        filter: Option<&str>,
+        options: ViewOptions,
+        marked: &HashSet<NodeID>,
+    ) -> Self {
+        self.rebuilt(options, marked, filter.map(EntryQuery::parse))
+    }
+
+    fn rebuilt(
+        &self,
+        options: ViewOptions,
+        marked: &HashSet<NodeID>,
+        name_filter: Option<EntryQuery>,
+    ) -> Self {
+        let mut entries = Self::visible_entries(&self.files, self.directory, options, marked);
+
+        if let Some(filter) = &name_filter {
+            entries.retain(|entry| name_matches(&self.files[entry.id], filter));
+        }
+
+        let mut viewer =
+            Self::with_entries(Arc::clone(&self.files), self.directory, entries, options);
+
+        viewer.name_filter = name_filter;
+        viewer.highlight(self.highlighted);
+        viewer
+    }
+
+    /// Returns the query entries are being filtered by, if there is one.
+    #[inline(always)]
+    pub fn name_filter(&self) -> Option<&str> {
+        self.name_filter.as_ref().map(EntryQuery::as_str)
+    }
+
+    /// Highlight the entry with the given `id` if it's visible.
+    ///
+    /// Returns true if the entry was found.
+    pub fn highlight(&mut self, id: NodeID) -> bool {
+        match self.entries.iter().position(|entry| entry.id == id) {
+            Some(index) => {
+                self.entries.index = index;
+                self.highlighted = id;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the number of visible entries.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.entries.len()
     }
 
+    /// Returns true if the directory has no entries to show.
     #[inline(always)]
-    pub fn highlighted(&self) -> &DirectoryEntry {
-        self.entries.selected()
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the highlighted entry, or None if the directory has no entries to show.
+    #[inline(always)]
+    pub fn highlighted(&self) -> Option<&DirectoryEntry> {
+        if self.entries.is_empty() {
+            return None;
+        }
+
+        Some(self.entries.selected())
+    }
+
+    /// Returns every visible entry in the order they're shown.
+    #[inline(always)]
+    pub fn entries(&self) -> &[DirectoryEntry] {
+        &self.entries
     }
 
     #[inline(always)]
@@ -86,17 +231,126 @@ impl DirectoryViewer {
         self.directory
     }
 
-    pub fn selected_ids(&self) -> SmallVec<[NodeID; 4]> {
-        let selected = self
-            .entries
+    /// Returns the ID of the entry drawn on the given `row`, counting from the top of the viewer as of the last draw.
+    pub fn entry_at_row(&self, row: u16) -> Option<NodeID> {
+        let window = scroll_window(self.entries.index(), self.entries.len(), self.page_height);
+        let index = window.start + row as usize;
+
+        if index < window.end {
+            Some(self.entries[index].id)
+        } else {
+            None
+        }
+    }
+
+    /// Highlight the next entry, or the previous one if `down` is false, without wrapping around.
+    pub fn scroll(&mut self, down: bool) -> DirectoryResult {
+        if self.entries.is_empty() {
+            return DirectoryResult::Ok;
+        }
+
+        let index = self.entries.index();
+
+        let index = if down {
+            (index + 1).min(self.entries.len().saturating_sub(1))
+        } else {
+            index.saturating_sub(1)
+        };
+
+        let &DirectoryEntry { id, .. } = self.entries.select(index);
+        self.highlighted = id;
+        DirectoryResult::EntryHighlight(id)
+    }
+
+    /// Returns the widest size description of the visible entries.
+    fn size_width(&self) -> u16 {
+        self.entries
             .iter()
-            .filter_map(|entry| if entry.selected { Some(entry.id) } else { None })
-            .collect::<SmallVec<_>>();
+            .map(|entry| entry.size.len() as u16)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Highlight the first entry that matches `query`, starting from the entry with the given `origin` id.
+    ///
+    /// The highlight goes back to `origin` if nothing matches.
+    pub fn find(&mut self, query: &str, origin: NodeID) -> DirectoryResult {
+        self.highlight(origin);
+
+        let query = EntryQuery::parse(query);
+
+        if !name_matches(&self.files[self.highlighted], &query) {
+            let files = &self.files;
+            let found = self
+                .entries
+                .next_matching(|entry| name_matches(&files[entry.id], &query));
+
+            if let Some(entry) = found {
+                self.highlighted = entry.id;
+            }
+        }
+
+        DirectoryResult::EntryHighlight(self.highlighted)
+    }
 
-        if selected.is_empty() {
-            smallvec![self.highlighted().id]
+    /// Highlight the next entry that matches `query`, or the previous one if `backwards` is true.
+    pub fn find_next(&mut self, query: &str, backwards: bool) -> DirectoryResult {
+        let query = EntryQuery::parse(query);
+        let files = &self.files;
+        let predicate = |entry: &DirectoryEntry| name_matches(&files[entry.id], &query);
+
+        let found = if backwards {
+            self.entries.prev_matching(predicate)
         } else {
-            selected
+            self.entries.next_matching(predicate)
+        };
+
+        let found = found.map(|entry| entry.id);
+        self.highlight_found(found)
+    }
+
+    /// Set whether each visible entry is selected to what `select` returns when given whether it currently is.
+    fn set_selections<F>(&mut self, select: F) -> DirectoryResult
+    where
+        F: Fn(bool) -> bool,
+    {
+        for entry in self.entries.iter_mut() {
+            entry.selected = select(entry.selected);
+        }
+
+        DirectoryResult::SelectionChanged
+    }
+
+    /// Highlight the next entry whose name starts with `prefix`, ignoring case.
+    ///
+    /// If `include_highlighted` is true, the highlighted entry is kept if it already starts with `prefix`.
+    pub fn jump_to_prefix(&mut self, prefix: &str, include_highlighted: bool) -> DirectoryResult {
+        let prefix = prefix.to_lowercase();
+        let files = &self.files;
+        let starts_with_prefix =
+            |entry: &DirectoryEntry| files[entry.id].name.to_lowercase().starts_with(&prefix);
+
+        if include_highlighted
+            && matches!(self.highlighted(), Some(entry) if starts_with_prefix(entry))
+        {
+            return DirectoryResult::Ok;
+        }
+
+        let found = self
+            .entries
+            .next_matching(starts_with_prefix)
+            .map(|entry| entry.id);
+
+        self.highlight_found(found)
+    }
+
+    fn highlight_found(&mut self, found: Option<NodeID>) -> DirectoryResult {
+        match found {
+            Some(id) => {
+                self.highlighted = id;
+                DirectoryResult::EntryHighlight(id)
+            }
+            None => DirectoryResult::Ok,
         }
     }
 }
@@ -105,14 +359,48 @@ impl Panel for DirectoryViewer {
     type KeyResult = DirectoryResult;
 
     fn process_key(&mut self, key: KeyCode) -> Self::KeyResult {
+        // Leaving is the only thing that can be done in a directory without entries
+        if self.entries.is_empty() {
+            return match key {
+                KeyCode::Left => DirectoryResult::ViewParent(self.directory),
+                _ => DirectoryResult::Ok,
+            };
+        }
+
         match key {
             KeyCode::Up | KeyCode::Down => {
+                let index = self.entries.index();
+
                 let &DirectoryEntry { id, .. } = match key {
                     KeyCode::Up => self.entries.prev(),
                     KeyCode::Down => self.entries.next(),
                     _ => unreachable!(),
                 };
 
+                // The selection doesn't move past the ends when wrapping is turned off
+                if self.entries.index() == index {
+                    return DirectoryResult::Ok;
+                }
+
+                self.highlighted = id;
+                DirectoryResult::EntryHighlight(id)
+            }
+            KeyCode::PageUp
+            | KeyCode::PageDown
+            | KeyCode::Home
+            | KeyCode::End
+            | KeyCode::Char(Self::LAST_ENTRY_KEY) => {
+                let last = self.entries.len().saturating_sub(1);
+                let index = self.entries.index();
+
+                let &DirectoryEntry { id, .. } = self.entries.select(match key {
+                    KeyCode::PageUp => index.saturating_sub(self.page_height),
+                    KeyCode::PageDown => (index + self.page_height).min(last),
+                    KeyCode::Home => 0,
+                    KeyCode::End | KeyCode::Char(Self::LAST_ENTRY_KEY) => last,
+                    _ => unreachable!(),
+                });
+
                 self.highlighted = id;
                 DirectoryResult::EntryHighlight(id)
             }
@@ -120,26 +408,71 @@ impl Panel for DirectoryViewer {
                 let entry = self.entries.selected_mut();
                 entry.selected = !entry.selected;
 
-                let next = self.entries.next();
-                self.highlighted = next.id;
+                let (id, selected) = (entry.id, entry.selected);
+
+                let highlighted = self.entries.next().id;
+                self.highlighted = highlighted;
 
-                DirectoryResult::Ok
+                DirectoryResult::SelectionToggled {
+                    id,
+                    selected,
+                    highlighted,
+                }
+            }
+            KeyCode::Char(Self::NEXT_SELECTED_KEY) => {
+                let found = self.entries.next_matching(|entry| entry.selected);
+                let found = found.map(|entry| entry.id);
+                self.highlight_found(found)
             }
+            KeyCode::Char(Self::PREV_SELECTED_KEY) => {
+                let found = self.entries.prev_matching(|entry| entry.selected);
+                let found = found.map(|entry| entry.id);
+                self.highlight_found(found)
+            }
+            KeyCode::Char(Self::SELECT_ALL_KEY) => self.set_selections(|_| true),
+            KeyCode::Char(Self::INVERT_SELECTION_KEY) => self.set_selections(|selected| !selected),
             KeyCode::Right | KeyCode::Enter => {
                 DirectoryResult::ViewChild(self.entries.selected().id)
             }
-            KeyCode::Left => DirectoryResult::ViewParent(self.entries.selected().id),
+            KeyCode::Left => DirectoryResult::ViewParent(self.directory),
             _ => DirectoryResult::Ok,
         }
     }
 }
 
-impl<B: Backend> Draw<B> for DirectoryViewer {
-    fn draw(&mut self, rect: Rect, frame: &mut Frame<B>) {
+impl DirectoryViewer {
+    /// Draw the entries of the directory with the given `columns`.
+    ///
+    /// A date column is only drawn when `columns` has room for one.
+    pub fn draw_columns<B: Backend>(
+        &mut self,
+        columns: &Columns,
+        rect: Rect,
+        frame: &mut Frame<B>,
+    ) {
         if rect.width <= 1 || rect.height <= 1 {
             return;
         }
 
+        self.page_height = rect.height as usize;
+
+        if self.entries.is_empty() {
+            let style = Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::ITALIC);
+
+            let placeholder = SimpleText::new(self.placeholder()).style(style);
+            let area = Rect {
+                x: rect.x + Columns::BASE_OFFSET,
+                width: rect.width - Columns::BASE_OFFSET,
+                height: 1,
+                ..rect
+            };
+
+            frame.render_widget(placeholder, area);
+            return;
+        }
+
         let window = scroll_window(
             self.entries.index(),
             self.entries.len(),
@@ -149,7 +482,12 @@ impl<B: Backend> Draw<B> for DirectoryViewer {
         let items = &self.entries[window.start..window.end];
 
         for (i, item) in items.iter().enumerate() {
-            let rendered = RenderedItem::new(&self.archive, item, item.id == self.highlighted);
+            let rendered = RenderedItem {
+                files: &self.files,
+                entry: item,
+                highlighted: item.id == self.highlighted,
+                columns,
+            };
 
             let pos = Rect {
                 y: rect.y + (i as u16),
@@ -159,46 +497,446 @@ impl<B: Backend> Draw<B> for DirectoryViewer {
 
             frame.render_widget(rendered, pos);
         }
+
+        // The size column always leaves the last column free, so the scrollbar never covers anything
+        if self.entries.len() > rect.height as usize {
+            let scrollbar = Scrollbar {
+                thumb: scrollbar_thumb(&window, self.entries.len(), rect.height as usize),
+            };
+
+            let area = Rect {
+                x: rect.right() - 1,
+                width: 1,
+                ..rect
+            };
+
+            frame.render_widget(scrollbar, area);
+        }
+    }
+
+    /// Returns what to draw in place of the entries when there are none to show.
+    fn placeholder(&self) -> &'static str {
+        let children = &self.files[self.directory].children;
+
+        if self.name_filter.is_some() {
+            "(nothing matches the filter)"
+        } else if children.is_empty() {
+            if self.directory == NodeID::first() {
+                "(empty archive)"
+            } else {
+                "(empty)"
+            }
+        } else if self.hide_hidden
+            && children
+                .iter()
+                .all(|&id| filter::is_hidden(&self.files[id].name))
+        {
+            "(only hidden entries)"
+        } else {
+            "(hidden by size filter)"
+        }
+    }
+
+    /// Returns the columns to draw this viewer's entries with in the given `rect`.
+    pub fn columns(&self, rect: Rect, with_date: bool) -> Columns {
+        Columns::new(rect, self.size_width(), with_date)
+    }
+}
+
+impl<B: Backend> Draw<B> for DirectoryViewer {
+    fn draw(&mut self, rect: Rect, frame: &mut Frame<B>) {
+        let columns = self.columns(rect, false);
+        self.draw_columns(&columns, rect, frame);
+    }
+}
+
+/// The filtering, sorting, icons, and movement applied to the entries of a [`DirectoryViewer`].
+#[derive(Copy, Clone, Default)]
+pub struct ViewOptions {
+    pub size_filter: SizeFilter,
+    pub sort: SortOrder,
+    pub icons: IconStyle,
+    /// Whether moving up from the first entry or down from the last one stays put instead of wrapping around.
+    pub stop_at_edges: bool,
+    /// Whether dotfiles and junk left behind by operating systems are left out.
+    pub hide_hidden: bool,
+}
+
+impl ViewOptions {
+    /// Returns true if the given `entry` passes the size filter and isn't hidden.
+    pub fn shows(self, entry: &ArchiveEntry) -> bool {
+        self.size_filter.passes(entry) && !(self.hide_hidden && filter::is_hidden(&entry.name))
+    }
+}
+
+/// Hides files below a certain size to quickly find out what is taking up space.
+#[derive(Copy, Clone, PartialEq)]
+pub enum SizeFilter {
+    None,
+    Min1M,
+    Min10M,
+    Min100M,
+}
+
+impl SizeFilter {
+    const MB: u64 = 1024 * 1024;
+
+    /// Returns the next largest threshold, or no filter if this is the largest one.
+    pub fn next(self) -> Self {
+        match self {
+            Self::None => Self::Min1M,
+            Self::Min1M => Self::Min10M,
+            Self::Min10M => Self::Min100M,
+            Self::Min100M => Self::None,
+        }
+    }
+
+    fn min_bytes(self) -> u64 {
+        match self {
+            Self::None => 0,
+            Self::Min1M => Self::MB,
+            Self::Min10M => 10 * Self::MB,
+            Self::Min100M => 100 * Self::MB,
+        }
+    }
+
+    /// Returns a short description of the threshold, or None if nothing is filtered.
+    pub fn desc(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Min1M => Some(">=1M"),
+            Self::Min10M => Some(">=10M"),
+            Self::Min100M => Some(">=100M"),
+        }
+    }
+
+    /// Returns true if the given `entry` should be visible.
+    ///
+    /// Directories always pass so they can still be navigated into.
+    pub fn passes(self, entry: &ArchiveEntry) -> bool {
+        match &entry.props {
+            EntryProperties::File(props) => props.raw_size_bytes >= self.min_bytes(),
+            EntryProperties::Directory => true,
+        }
+    }
+}
+
+impl Default for SizeFilter {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+/// A column that entries can be sorted by.
+#[derive(Copy, Clone, PartialEq)]
+pub enum SortColumn {
+    Name,
+    Size,
+    Date,
+    /// How small files were compressed relative to their original size, which doesn't have a column of its own.
+    Ratio,
+}
+
+impl SortColumn {
+    /// Returns the next column to sort by, wrapping back around to the name.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Name => Self::Size,
+            Self::Size => Self::Date,
+            Self::Date => Self::Ratio,
+            Self::Ratio => Self::Name,
+        }
+    }
+
+    pub fn desc(self) -> &'static str {
+        match self {
+            Self::Name => "Name",
+            Self::Size => "Size",
+            Self::Date => "Date",
+            Self::Ratio => "Ratio",
+        }
+    }
+}
+
+/// The column entries are sorted by and in which direction.
+///
+/// Directories are always listed before files regardless of the order.
+#[derive(Copy, Clone, PartialEq)]
+pub struct SortOrder {
+    pub column: SortColumn,
+    pub descending: bool,
+}
+
+impl SortOrder {
+    /// Sort by the given `column`, or flip the direction if we're already sorting by it.
+    pub fn toggle(self, column: SortColumn) -> Self {
+        if self.column == column {
+            Self {
+                descending: !self.descending,
+                ..self
+            }
+        } else {
+            Self {
+                column,
+                descending: false,
+            }
+        }
+    }
+
+    pub fn compare(self, x: &ArchiveEntry, y: &ArchiveEntry) -> Ordering {
+        let ordering = match self.column {
+            SortColumn::Name => Ordering::Equal,
+            SortColumn::Size => x.total_size_bytes.cmp(&y.total_size_bytes),
+            SortColumn::Date => x.last_modified.cmp(&y.last_modified),
+            SortColumn::Ratio => Self::ratio_of(x)
+                .partial_cmp(&Self::ratio_of(y))
+                .unwrap_or(Ordering::Equal),
+        };
+
+        // Entries that are equal in the sorted column should still have a predictable order
+        let ordering = ordering.then_with(|| x.name.cmp(&y.name));
+
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    /// Returns the compressed size of the given `entry` as a fraction of its uncompressed size.
+    ///
+    /// Empty files and directories count as not being compressed at all.
+    fn ratio_of(entry: &ArchiveEntry) -> f64 {
+        match &entry.props {
+            EntryProperties::File(props) if props.raw_size_bytes > 0 => {
+                props.compressed_size_bytes as f64 / props.raw_size_bytes as f64
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// Returns the column name with an arrow that points in the direction it's sorted.
+    pub fn desc(self) -> String {
+        let arrow = if self.descending { "↓" } else { "↑" };
+        format!("{}{}", self.column.desc(), arrow)
+    }
+}
+
+impl Default for SortOrder {
+    fn default() -> Self {
+        Self {
+            column: SortColumn::Name,
+            descending: false,
+        }
+    }
+}
+
+/// The horizontal position of each column that an entry is drawn with.
+pub struct Columns {
+    pub name_x: u16,
+    pub date_x: Option<u16>,
+    pub size_x: u16,
+    size_width: u16,
+}
+
+impl Columns {
+    const BASE_OFFSET: u16 = 1;
+    const DATE_WIDTH: u16 = 10;
+    const MIN_NAME_WIDTH: u16 = 8;
+
+    fn new(rect: Rect, size_width: u16, with_date: bool) -> Self {
+        // The header needs to fit the column name and its sort arrow even when every entry's size is shorter
+        let size_width = size_width.max(1 + SortColumn::Size.desc().len() as u16);
+        let size_x = rect
+            .width
+            .saturating_sub(size_width)
+            .saturating_sub(Self::BASE_OFFSET);
+
+        let date_x = size_x
+            .checked_sub(Self::BASE_OFFSET + Self::DATE_WIDTH)
+            .filter(|&date_x| with_date && date_x >= Self::MIN_NAME_WIDTH);
+
+        Self {
+            name_x: Self::BASE_OFFSET,
+            date_x,
+            size_x,
+            size_width,
+        }
+    }
+
+    /// Returns the column at the given horizontal offset from the start of the row.
+    pub fn column_at(&self, x: u16) -> SortColumn {
+        if x >= self.size_x {
+            return SortColumn::Size;
+        }
+
+        match self.date_x {
+            Some(date_x) if x >= date_x => SortColumn::Date,
+            _ => SortColumn::Name,
+        }
+    }
+}
+
+/// Widget to draw the name of each column above the entries of a [`DirectoryViewer`].
+pub struct ColumnHeader<'a> {
+    pub columns: &'a Columns,
+    pub sort: SortOrder,
+}
+
+impl<'a> ColumnHeader<'a> {
+    fn title(&self, column: SortColumn) -> String {
+        if self.sort.column == column {
+            self.sort.desc()
+        } else {
+            column.desc().to_string()
+        }
+    }
+}
+
+impl<'a> Widget for ColumnHeader<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.width <= self.columns.size_x || area.height == 0 {
+            return;
+        }
+
+        let style = Style::default()
+            .fg(colors::WHITE)
+            .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+
+        let name = self.title(SortColumn::Name);
+        let max_name_len = self
+            .columns
+            .date_x
+            .unwrap_or(self.columns.size_x)
+            .saturating_sub(self.columns.name_x + Columns::BASE_OFFSET);
+
+        buf.set_stringn(
+            area.x + self.columns.name_x,
+            area.y,
+            name,
+            max_name_len as usize,
+            style,
+        );
+
+        if let Some(date_x) = self.columns.date_x {
+            buf.set_string(area.x + date_x, area.y, self.title(SortColumn::Date), style);
+        }
+
+        // The size column is right aligned to match the entries below it
+        let size = self.title(SortColumn::Size);
+        let size_x = (self.columns.size_x + self.columns.size_width)
+            .saturating_sub(UnicodeWidthStr::width(size.as_str()) as u16);
+
+        buf.set_stringn(
+            area.x + size_x,
+            area.y,
+            size,
+            area.width.saturating_sub(size_x) as usize,
+            style,
+        );
     }
 }
 
+#[derive(Copy, Clone)]
 pub enum DirectoryResult {
     Ok,
     ViewChild(NodeID),
+    /// View the parent of the given directory.
     ViewParent(NodeID),
     EntryHighlight(NodeID),
+    SelectionToggled {
+        id: NodeID,
+        selected: bool,
+        highlighted: NodeID,
+    },
+    /// The selection of any number of entries changed at once.
+    SelectionChanged,
 }
 
 pub struct WrappedSelection<T> {
     items: Vec<T>,
     index: usize,
+    /// Whether moving past either end goes to the other one, rather than staying on the last or first item.
+    wrap: bool,
 }
 
 impl<T> WrappedSelection<T>
 where
     T: Clone,
 {
-    pub fn new(items: Vec<T>) -> Self {
-        Self { items, index: 0 }
+    pub fn new(items: Vec<T>, wrap: bool) -> Self {
+        Self {
+            items,
+            index: 0,
+            wrap,
+        }
     }
 
     #[inline(always)]
     pub fn next(&mut self) -> &T {
-        self.index = (self.index + 1) % self.items.len().max(1);
+        let last = self.items.len().saturating_sub(1);
+
+        self.index = match self.index {
+            index if index < last => index + 1,
+            _ if self.wrap => 0,
+            index => index,
+        };
+
         self.selected()
     }
 
     #[inline(always)]
     pub fn prev(&mut self) -> &T {
-        self.index = if self.index == 0 {
-            self.items.len().saturating_sub(1)
-        } else {
-            self.index - 1
+        self.index = match self.index {
+            0 if self.wrap => self.items.len().saturating_sub(1),
+            0 => 0,
+            index => index - 1,
         };
 
         self.selected()
     }
 
+    /// Move the selection to the next item that matches the given `predicate`, wrapping around if needed.
+    ///
+    /// Returns None and leaves the selection untouched if no other item matches.
+    pub fn next_matching<F>(&mut self, predicate: F) -> Option<&T>
+    where
+        F: Fn(&T) -> bool,
+    {
+        let len = self.items.len();
+
+        let index = (1..len)
+            .map(|offset| (self.index + offset) % len)
+            .find(|&i| predicate(&self.items[i]))?;
+
+        self.index = index;
+        Some(self.selected())
+    }
+
+    /// Move the selection to the previous item that matches the given `predicate`, wrapping around if needed.
+    ///
+    /// Returns None and leaves the selection untouched if no other item matches.
+    pub fn prev_matching<F>(&mut self, predicate: F) -> Option<&T>
+    where
+        F: Fn(&T) -> bool,
+    {
+        let len = self.items.len();
+
+        let index = (1..len)
+            .map(|offset| (self.index + len - offset) % len)
+            .find(|&i| predicate(&self.items[i]))?;
+
+        self.index = index;
+        Some(self.selected())
+    }
+
+    /// Move the selection to the item at the given `index`, which must be in bounds.
+    #[inline(always)]
+    pub fn select(&mut self, index: usize) -> &T {
+        self.index = index;
+        self.selected()
+    }
+
     #[inline(always)]
     pub fn selected(&self) -> &T {
         &self.items[self.index]
@@ -209,6 +947,11 @@ where
         &mut self.items[self.index]
     }
 
+    #[inline(always)]
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<T> {
+        self.items.iter_mut()
+    }
+
     #[inline(always)]
     pub fn index(&self) -> usize {
         self.index
@@ -228,23 +971,42 @@ pub struct DirectoryEntry {
     pub id: NodeID,
     pub selected: bool,
     pub size: String,
+    pub date: Option<String>,
+    /// The icon drawn before the name of the entry, if icons are turned on.
+    pub icon: Option<&'static str>,
+}
+
+/// Widget to draw a slim bar along the edge of a [`DirectoryViewer`] that shows which part of its entries are visible.
+struct Scrollbar {
+    /// The rows the part that's visible covers.
+    thumb: Range<usize>,
+}
+
+impl Widget for Scrollbar {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for (row, y) in (area.top()..area.bottom()).enumerate() {
+            let (symbol, color) = if self.thumb.contains(&row) {
+                ("┃", colors::WHITE)
+            } else {
+                ("│", Color::DarkGray)
+            };
+
+            let cell = buf.get_mut(area.x, y);
+            cell.reset();
+            cell.set_symbol(symbol);
+            cell.fg = color;
+        }
+    }
 }
 
 struct RenderedItem<'a> {
-    archive: &'a Archive,
+    files: &'a ArchiveEntries,
     entry: &'a DirectoryEntry,
     highlighted: bool,
+    columns: &'a Columns,
 }
 
 impl<'a> RenderedItem<'a> {
-    fn new(archive: &'a Archive, entry: &'a DirectoryEntry, highlighted: bool) -> Self {
-        Self {
-            archive,
-            entry,
-            highlighted,
-        }
-    }
-
     fn apply_line_color(&self, node: &ArchiveEntry, area: Rect, buf: &mut Buffer) {
         let primary_color = match &node.props {
             EntryProperties::File(_) => colors::WHITE,
@@ -273,20 +1035,20 @@ impl<'a> RenderedItem<'a> {
 impl<'a> Widget for RenderedItem<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         const BASE_NAME_OFFSET: u16 = 1;
-        const BASE_SIZE_OFFSET: u16 = 1;
         const MIN_SPACING: u16 = 1;
+        const ICON_SPACING: u16 = 1;
 
         let name_offset = if self.entry.selected {
-            BASE_NAME_OFFSET * 2
+            self.columns.name_x + BASE_NAME_OFFSET
         } else {
-            BASE_NAME_OFFSET
+            self.columns.name_x
         };
 
         if area.width <= name_offset || area.height == 0 {
             return;
         }
 
-        let node = &self.archive[self.entry.id];
+        let node = &self.files[self.entry.id];
 
         self.apply_line_color(node, area, buf);
 
@@ -296,29 +1058,113 @@ impl<'a> Widget for RenderedItem<'a> {
             Style::default()
         };
 
+        let name_offset = match self.entry.icon {
+            Some(icon) => {
+                buf.set_stringn(
+                    area.x + name_offset,
+                    area.y,
+                    icon,
+                    area.width.saturating_sub(name_offset) as usize,
+                    style,
+                );
+
+                name_offset + UnicodeWidthStr::width(icon) as u16 + ICON_SPACING
+            }
+            None => name_offset,
+        };
+
+        if area.width <= name_offset {
+            return;
+        }
+
+        let label = label(node);
+
         buf.set_stringn(
             area.x + name_offset,
             area.y,
-            &node.name,
+            &label,
             // This caps the maximum length to always show at least one free character at the end
             area.width.saturating_sub(name_offset + BASE_NAME_OFFSET) as usize,
             style,
         );
 
-        let name_len = name_offset + UnicodeWidthStr::width(node.name.as_str()) as u16;
-        let size_start = area
-            .width
-            .saturating_sub(self.entry.size.len() as u16)
-            .saturating_sub(BASE_SIZE_OFFSET);
-        let remaining_space = size_start.saturating_sub(MIN_SPACING);
+        let name_len = name_offset + UnicodeWidthStr::width(label.as_ref()) as u16;
+
+        // Sizes are right aligned so their units line up
+        let size_start = (self.columns.size_x + self.columns.size_width)
+            .saturating_sub(self.entry.size.len() as u16);
+
+        let date_start = match (self.columns.date_x, &self.entry.date) {
+            (Some(date_x), Some(date)) => Some((date_x, date)),
+            _ => None,
+        };
+
+        let desc_start = date_start.map_or(size_start, |(date_x, _)| date_x);
+        let remaining_space = desc_start.saturating_sub(MIN_SPACING);
 
         // Draw the description of the entry only if we have enough room for it
         if remaining_space >= name_len {
             buf.set_string(area.x + size_start, area.y, &self.entry.size, style);
+
+            if let Some((date_x, date)) = date_start {
+                buf.set_string(area.x + date_x, area.y, date, style);
+            }
         }
     }
 }
 
+/// Returns the name of the given `entry` as it's listed, which also shows where it points to if it's a symlink, or
+/// what kind of file it is if it's a special file like a device node, like `ls -l` does.
+///
+/// Files that need a password to be read are marked as locked.
+fn label(entry: &ArchiveEntry) -> Cow<str> {
+    let props = match &entry.props {
+        EntryProperties::File(props) => props,
+        EntryProperties::Directory => return Cow::Borrowed(&entry.name),
+    };
+
+    let label = match (&props.link_target, props.special) {
+        (Some(target), _) => Cow::Owned(format!("{} -> {}", entry.name, target)),
+        (None, Some(special)) => Cow::Owned(format!("{} [{}]", entry.name, special.desc())),
+        (None, None) => Cow::Borrowed(entry.name.as_str()),
+    };
+
+    if props.encrypted {
+        Cow::Owned(format!("{} [locked]", label))
+    } else {
+        label
+    }
+}
+
+/// Returns true if the given `entry` matches `query`, with the text of the query being looked for in its name.
+fn name_matches(entry: &ArchiveEntry, query: &EntryQuery) -> bool {
+    query.matches(entry, &entry.name)
+}
+
+/// Calculate which rows of a scrollbar with the given `height` cover the `visible` range of items.
+///
+/// The thumb is always at least one row tall, and only touches the top of the bar when the first item is visible.
+/// It touches the bottom whenever the last item is visible.
+fn scrollbar_thumb(visible: &Range<usize>, num_items: usize, height: usize) -> Range<usize> {
+    if num_items <= height {
+        return 0..height;
+    }
+
+    let len = (height * visible.len() / num_items).max(1);
+
+    let start = if visible.end == num_items {
+        height - len
+    } else if visible.start == 0 {
+        0
+    } else {
+        (height * visible.start / num_items)
+            .max(1)
+            .min(height - len)
+    };
+
+    start..start + len
+}
+
 /// Calculate how many items are visible based off a given cursor position.
 ///
 /// Returns a range that represents the visible bounds.
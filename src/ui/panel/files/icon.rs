@@ -0,0 +1,138 @@
+use crate::archive::{ArchiveEntry, EntryProperties};
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+
+/// How the kind of each entry is marked before its name.
+#[derive(Copy, Clone, PartialEq)]
+pub enum IconStyle {
+    None,
+    /// Glyphs from a [Nerd Font](https://www.nerdfonts.com), which the terminal has to be using to show them.
+    Nerd,
+    /// A letter for each kind of entry, which works with any font.
+    Ascii,
+}
+
+impl IconStyle {
+    /// Returns the icon to draw before the name of the given `entry`, or None if icons are turned off.
+    pub fn icon(self, entry: &ArchiveEntry) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Nerd => Some(EntryKind::of(entry).glyph()),
+            Self::Ascii => Some(EntryKind::of(entry).marker()),
+        }
+    }
+}
+
+impl Default for IconStyle {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl FromStr for IconStyle {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "nerd" => Ok(Self::Nerd),
+            "ascii" => Ok(Self::Ascii),
+            _ => Err(anyhow!(
+                "invalid icon style: {} (expected nerd, ascii, or none)",
+                value.trim()
+            )),
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+enum EntryKind {
+    Directory,
+    Symlink,
+    Executable,
+    Image,
+    Video,
+    Audio,
+    Archive,
+    Code,
+    Document,
+    Text,
+    Other,
+}
+
+impl EntryKind {
+    /// Figure out what kind of entry the given `entry` is from its extension, falling back to its permissions for
+    /// files without a known one.
+    fn of(entry: &ArchiveEntry) -> Self {
+        let props = match &entry.props {
+            EntryProperties::Directory => return Self::Directory,
+            EntryProperties::File(props) if props.is_symlink => return Self::Symlink,
+            EntryProperties::File(props) => props,
+        };
+
+        let extension = entry.extension().unwrap_or_default().to_ascii_lowercase();
+
+        match Self::from_extension(&extension) {
+            Self::Other if matches!(props.unix_mode, Some(mode) if mode & 0o111 != 0) => {
+                Self::Executable
+            }
+            kind => kind,
+        }
+    }
+
+    fn from_extension(extension: &str) -> Self {
+        match extension {
+            "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp" | "svg" | "ico" | "tif" | "tiff"
+            | "psd" | "xcf" | "heic" | "avif" => Self::Image,
+            "mp4" | "mkv" | "webm" | "avi" | "mov" | "wmv" | "flv" | "m4v" | "mpg" | "mpeg" => {
+                Self::Video
+            }
+            "mp3" | "flac" | "ogg" | "opus" | "wav" | "m4a" | "aac" | "wma" | "mid" | "midi" => {
+                Self::Audio
+            }
+            "zip" | "tar" | "gz" | "tgz" | "xz" | "bz2" | "zst" | "7z" | "rar" | "lz4" | "jar"
+            | "deb" | "rpm" | "iso" | "dmg" | "apk" => Self::Archive,
+            "rs" | "c" | "h" | "cpp" | "hpp" | "cc" | "cs" | "go" | "java" | "kt" | "py" | "rb"
+            | "js" | "ts" | "jsx" | "tsx" | "php" | "lua" | "sh" | "bash" | "zsh" | "fish"
+            | "pl" | "swift" | "zig" | "hs" | "ml" | "ex" | "exs" | "clj" | "scala" | "html"
+            | "css" | "scss" | "sql" | "vim" => Self::Code,
+            "pdf" | "doc" | "docx" | "odt" | "rtf" | "xls" | "xlsx" | "ods" | "ppt" | "pptx"
+            | "odp" | "epub" => Self::Document,
+            "txt" | "md" | "rst" | "log" | "json" | "toml" | "yaml" | "yml" | "xml" | "ini"
+            | "cfg" | "conf" | "csv" => Self::Text,
+            _ => Self::Other,
+        }
+    }
+
+    fn glyph(self) -> &'static str {
+        match self {
+            Self::Directory => "\u{f07b}",
+            Self::Symlink => "\u{f0c1}",
+            Self::Executable => "\u{f489}",
+            Self::Image => "\u{f1c5}",
+            Self::Video => "\u{f1c8}",
+            Self::Audio => "\u{f1c7}",
+            Self::Archive => "\u{f1c6}",
+            Self::Code => "\u{f1c9}",
+            Self::Document => "\u{f15c}",
+            Self::Text => "\u{f0f6}",
+            Self::Other => "\u{f15b}",
+        }
+    }
+
+    fn marker(self) -> &'static str {
+        match self {
+            Self::Directory => "d",
+            Self::Symlink => "l",
+            Self::Executable => "x",
+            Self::Image => "i",
+            Self::Video => "v",
+            Self::Audio => "a",
+            Self::Archive => "z",
+            Self::Code => "c",
+            Self::Document => "p",
+            Self::Text => "t",
+            Self::Other => "-",
+        }
+    }
+}
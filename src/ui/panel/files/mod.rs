@@ -1,52 +1,585 @@
 mod directory;
+mod icon;
 
-use self::directory::DirectoryEntry;
-use super::{Backend, Draw, Frame, KeyCode, Panel, Rect};
-use crate::archive::{Archive, NodeID};
+pub use self::directory::{SizeFilter, SortColumn, SortOrder};
+pub use self::icon::IconStyle;
+
+use self::directory::{ColumnHeader, Columns, DirectoryEntry, ViewOptions};
+use super::{Backend, Command, Draw, Frame, KeyCode, Panel, Rect};
+use crate::archive::{
+    filter::{self, PathPattern},
+    ArchiveEntries, NodeID,
+};
+use crate::ui::event::MouseAction;
+use anyhow::{anyhow, Result};
 use directory::{DirectoryResult, DirectoryViewer};
-use smallvec::SmallVec;
-use std::{mem, sync::Arc};
+use smallvec::{smallvec, SmallVec};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use std::{collections::HashSet, mem, sync::Arc};
 use tui::layout::{Constraint, Direction, Layout};
 
 /// Widget to navigate and browse a given directory with its parent and child to ease navigation.
+// The bools are display toggles that can each be flipped on their own
+#[allow(clippy::struct_excessive_bools)]
 pub struct PathViewer {
-    archive: Arc<Archive>,
+    files: Arc<ArchiveEntries>,
     parent_dir: Option<DirectoryViewer>,
     cur_dir: DirectoryViewer,
     child_dir: Option<DirectoryViewer>,
+    options: ViewOptions,
+    show_header: bool,
+    /// Whether the date each entry was last modified is drawn next to its size in the current directory.
+    show_date: bool,
+    ratios: PaneRatios,
+    /// Whether the parent directory is shown on the left.
+    show_parent: bool,
+    /// Whether the highlighted directory's entries are shown on the right.
+    show_child: bool,
+    /// The area and columns of the header from the last draw, which is used to tell which column was clicked.
+    header: Option<(Rect, Columns)>,
+    /// The area of each directory from the last draw, which is used to tell which entry was clicked.
+    panes: Option<Panes>,
+    /// Entries that have been selected in any directory, which stay selected while navigating.
+    marked: HashSet<NodeID>,
+    /// The name being searched for in the current directory, if there is one.
+    search: Option<Search>,
+    /// The start of a name being typed to jump to, if one is being typed.
+    type_ahead: Option<TypeAhead>,
 }
 
 impl PathViewer {
-    /// Create a new `PathViewer` to view the given `directory` in the given `archive`.
+    const SIZE_FILTER_KEY: char = 'z';
+    const SORT_COLUMN_KEY: char = 'o';
+    const SORT_DIRECTION_KEY: char = 'O';
+    const UNMARK_ALL_KEY: char = 'u';
+    const TYPE_AHEAD_KEY: char = '\'';
+    const TOGGLE_PARENT_KEY: char = '<';
+    const TOGGLE_CHILD_KEY: char = '>';
+    const TOGGLE_DATE_KEY: char = 'd';
+    const TOGGLE_HIDDEN_KEY: char = '.';
+
+    const COMMANDS: &'static [Command] = &[
+        Command::char("sort by next column", Self::SORT_COLUMN_KEY),
+        Command::char("reverse sort order", Self::SORT_DIRECTION_KEY),
+        Command::char("cycle size filter", Self::SIZE_FILTER_KEY),
+        Command::char("deselect everything", Self::UNMARK_ALL_KEY),
+        Command::char("jump to entry by name", Self::TYPE_AHEAD_KEY),
+        Command::char("toggle parent directory", Self::TOGGLE_PARENT_KEY),
+        Command::char("toggle child directory", Self::TOGGLE_CHILD_KEY),
+        Command::char("toggle date column", Self::TOGGLE_DATE_KEY),
+        Command::char("toggle hidden entries", Self::TOGGLE_HIDDEN_KEY),
+    ];
+
+    /// How long to wait for the next letter of a name being typed before going back to treating keys normally.
+    const TYPE_AHEAD_TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Create a new `PathViewer` to view the given `directory` in the given `files`.
     ///
-    /// Returns None if the given `directory` has no entries (children) to show.
-    pub fn new(archive: Arc<Archive>, directory: NodeID) -> Option<Self> {
-        let cur_dir = DirectoryViewer::new(Arc::clone(&archive), directory)?;
-        let child_dir = DirectoryViewer::new(Arc::clone(&archive), cur_dir.highlighted().id);
+    /// If `show_header` is true, a row with the name of each column is drawn above the current directory.
+    /// If `show_date` is true, the date each entry was last modified is drawn in the current directory.
+    /// The parent, current, and child directories split the width between them by the given `ratios`, and each
+    /// entry is marked with its kind in the given `icons` style.
+    /// If `wrap` is true, moving past the first or last entry of a directory goes to the other end of it.
+    ///
+    /// Returns None if the given `directory` isn't a directory.
+    pub fn new(
+        files: Arc<ArchiveEntries>,
+        directory: NodeID,
+        show_header: bool,
+        show_date: bool,
+        ratios: PaneRatios,
+        icons: IconStyle,
+        wrap: bool,
+    ) -> Option<Self> {
+        let options = ViewOptions {
+            icons,
+            stop_at_edges: !wrap,
+            ..ViewOptions::default()
+        };
+        let marked = HashSet::new();
+
+        let cur_dir = DirectoryViewer::new(Arc::clone(&files), directory, options, &marked)?;
+        let child_dir = cur_dir
+            .highlighted()
+            .and_then(|entry| DirectoryViewer::new(Arc::clone(&files), entry.id, options, &marked));
 
         Some(Self {
-            archive,
+            files,
             parent_dir: None,
             cur_dir,
             child_dir,
+            options,
+            show_header,
+            show_date,
+            ratios,
+            show_parent: true,
+            show_child: true,
+            header: None,
+            panes: None,
+            marked,
+            search: None,
+            type_ahead: None,
         })
     }
 
+    /// Returns a viewer for the given `directory`, or None if it isn't a directory.
     fn dir_viewer(&self, directory: NodeID) -> Option<DirectoryViewer> {
-        DirectoryViewer::new(Arc::clone(&self.archive), directory)
+        DirectoryViewer::new(
+            Arc::clone(&self.files),
+            directory,
+            self.options,
+            &self.marked,
+        )
+    }
+
+    /// Rebuild every visible directory with the given `options`.
+    ///
+    /// Returns None and leaves everything untouched if the size filter would hide every entry that's shown in the
+    /// current directory.
+    fn apply_options(&mut self, options: ViewOptions) -> Option<PathViewerResult> {
+        let cur_dir = self.cur_dir.with_options(options, &self.marked);
+
+        if cur_dir.is_empty() && !self.cur_dir.is_empty() {
+            return None;
+        }
+
+        Some(self.rebuild(cur_dir, options))
+    }
+
+    /// Replace the current directory with `cur_dir` and rebuild the others with the given `options` to match.
+    fn rebuild(&mut self, cur_dir: DirectoryViewer, options: ViewOptions) -> PathViewerResult {
+        self.cur_dir = cur_dir;
+        self.options = options;
+
+        self.parent_dir = self
+            .parent_dir
+            .as_ref()
+            .map(|parent| parent.with_options(options, &self.marked));
+
+        self.child_dir = self.highlighted_dir_viewer();
+
+        PathViewerResult::PathSelected
+    }
+
+    /// Returns the given `viewer` with every entry shown again, since filters only apply while a directory is the
+    /// current one.
+    fn unfiltered(&self, viewer: DirectoryViewer) -> DirectoryViewer {
+        match viewer.name_filter() {
+            Some(_) => viewer.with_name_filter(None, self.options, &self.marked),
+            None => viewer,
+        }
+    }
+
+    /// Returns a viewer for the highlighted entry if it's a directory.
+    fn highlighted_dir_viewer(&self) -> Option<DirectoryViewer> {
+        self.highlighted()
+            .and_then(|entry| self.dir_viewer(entry.id))
+    }
+
+    /// Switch to the next size filter threshold that still leaves entries to show in the current directory.
+    fn cycle_size_filter(&mut self) -> PathViewerResult {
+        let mut options = self.options;
+
+        loop {
+            options.size_filter = options.size_filter.next();
+
+            // Not filtering anything shows every entry again, so this will terminate
+            if let Some(result) = self.apply_options(options) {
+                break result;
+            }
+        }
+    }
+
+    /// Hide or show dotfiles and junk left behind by operating systems in every directory.
+    ///
+    /// Unlike the size filter, this is applied even if it leaves nothing to show in the current directory, since
+    /// hidden entries are usually kept out of the way for the whole session.
+    fn toggle_hidden(&mut self) -> PathViewerResult {
+        let options = ViewOptions {
+            hide_hidden: !self.options.hide_hidden,
+            ..self.options
+        };
+
+        let cur_dir = self.cur_dir.with_options(options, &self.marked);
+        self.rebuild(cur_dir, options)
+    }
+
+    /// Sort every directory by the given `sort` order.
+    pub fn sort_by(&mut self, sort: SortOrder) -> PathViewerResult {
+        let options = ViewOptions {
+            sort,
+            ..self.options
+        };
+
+        // Sorting never hides entries, so this can't fail
+        self.apply_options(options).unwrap_or(PathViewerResult::Ok)
+    }
+
+    /// Deselect every entry in every directory.
+    fn unmark_all(&mut self) -> PathViewerResult {
+        if self.marked.is_empty() {
+            return PathViewerResult::Ok;
+        }
+
+        self.marked.clear();
+
+        // Rebuilding with the same options never hides entries, so this can't fail
+        self.apply_options(self.options)
+            .unwrap_or(PathViewerResult::Ok)
+    }
+
+    /// Select every visible entry in or below the current directory whose path relative to it matches `pattern`.
+    pub fn select_matching(&mut self, pattern: &PathPattern) {
+        let directory = self.directory();
+        let options = self.options;
+
+        let matched = self
+            .files
+            .children_iter(&self.files[directory].children)
+            .filter(|(_, entry, path)| {
+                let hidden = options.hide_hidden && filter::is_hidden_path(path);
+                options.size_filter.passes(entry) && !hidden && pattern.matches(path)
+            })
+            .map(|(id, _, _)| id)
+            .collect::<Vec<_>>();
+
+        self.marked.extend(matched);
+
+        // Rebuilding with the same options never hides entries, so this can't fail
+        self.apply_options(self.options);
+    }
+
+    /// Respond to the given mouse `action` at the given screen position.
+    ///
+    /// Clicking a header column sorts by it, and clicking an entry in any directory highlights it. Double clicking
+    /// an entry in the current directory views it, and scrolling moves the highlight in the current directory.
+    pub fn process_mouse(&mut self, action: MouseAction, x: u16, y: u16) -> PathViewerResult {
+        match action {
+            MouseAction::ScrollUp | MouseAction::ScrollDown => {
+                let result = self.cur_dir.scroll(action == MouseAction::ScrollDown);
+                return self.apply_dir_result(result);
+            }
+            MouseAction::Click | MouseAction::DoubleClick => (),
+        }
+
+        if let Some((area, columns)) = &self.header {
+            if contains(*area, x, y) {
+                let column = columns.column_at(x - area.x);
+                return self.sort_by(self.options.sort.toggle(column));
+            }
+        }
+
+        let panes = match self.panes {
+            Some(panes) => panes,
+            None => return PathViewerResult::Ok,
+        };
+
+        if contains(panes.current, x, y) {
+            let id = match self.cur_dir.entry_at_row(y - panes.current.y) {
+                Some(id) => id,
+                None => return PathViewerResult::Ok,
+            };
+
+            let result = if action == MouseAction::DoubleClick {
+                DirectoryResult::ViewChild(id)
+            } else {
+                self.cur_dir.highlight(id);
+                DirectoryResult::EntryHighlight(id)
+            };
+
+            return self.apply_dir_result(result);
+        }
+
+        let (dir, area) = if contains(panes.parent, x, y) {
+            (&self.parent_dir, panes.parent)
+        } else if contains(panes.child, x, y) {
+            (&self.child_dir, panes.child)
+        } else {
+            return PathViewerResult::Ok;
+        };
+
+        match dir.as_ref().and_then(|dir| dir.entry_at_row(y - area.y)) {
+            Some(id) => self.reveal(id),
+            None => PathViewerResult::Ok,
+        }
+    }
+
+    /// View the directory that contains the entry with the given `id` and highlight it.
+    ///
+    /// The size filter is turned off if it would hide the entry, and hidden entries are shown if it's one of them or
+    /// inside of one.
+    pub fn reveal(&mut self, id: NodeID) -> PathViewerResult {
+        let directory = match self.files[id].parent {
+            Some(directory) => directory,
+            None => return PathViewerResult::Ok,
+        };
+
+        if !self.options.size_filter.passes(&self.files[id]) {
+            self.options.size_filter = SizeFilter::None;
+        }
+
+        if self.options.hide_hidden && filter::is_hidden_path(&self.files.path_of(id)) {
+            self.options.hide_hidden = false;
+        }
+
+        let mut cur_dir = match self.dir_viewer(directory) {
+            Some(cur_dir) => cur_dir,
+            None => return PathViewerResult::Ok,
+        };
+
+        cur_dir.highlight(id);
+
+        self.parent_dir = self.files[directory].parent.and_then(|parent| {
+            let mut parent_dir = self.dir_viewer(parent)?;
+            parent_dir.highlight(directory);
+            Some(parent_dir)
+        });
+
+        self.cur_dir = cur_dir;
+        self.search = None;
+
+        self.child_dir = if self.files[id].props.is_dir() {
+            self.dir_viewer(id)
+        } else {
+            None
+        };
+
+        PathViewerResult::PathSelected
+    }
+
+    /// View the directory with the given `id`, or view the directory that contains it and highlight it if it's a
+    /// file.
+    ///
+    /// Hidden entries are shown if the directory is hidden or inside of a hidden directory.
+    pub fn view(&mut self, id: NodeID) -> PathViewerResult {
+        if !self.files[id].props.is_dir() {
+            return self.reveal(id);
+        }
+
+        if self.options.hide_hidden && filter::is_hidden_path(&self.files.path_of(id)) {
+            self.options.hide_hidden = false;
+        }
+
+        let cur_dir = match self.dir_viewer(id) {
+            Some(cur_dir) => cur_dir,
+            None => return PathViewerResult::Ok,
+        };
+
+        self.parent_dir = self.files[id].parent.and_then(|parent| {
+            let mut parent_dir = self.dir_viewer(parent)?;
+            parent_dir.highlight(id);
+            Some(parent_dir)
+        });
+
+        self.cur_dir = cur_dir;
+        self.child_dir = self.highlighted_dir_viewer();
+        self.search = None;
+
+        PathViewerResult::PathSelected
+    }
+
+    /// Start searching the current directory for a name, which [`PathViewer::search`] updates as it's typed.
+    pub fn start_search(&mut self) {
+        self.search = Some(Search {
+            query: String::new(),
+            origin: self.highlighted_id(),
+        });
+    }
+
+    /// Highlight the first entry whose name contains `query`, starting from the entry that was highlighted
+    /// when the search started.
+    pub fn search(&mut self, query: &str) -> PathViewerResult {
+        let search = match &mut self.search {
+            Some(search) => search,
+            None => return PathViewerResult::Ok,
+        };
+
+        search.query.clear();
+        search.query.push_str(query);
+
+        let result = self.cur_dir.find(query, search.origin);
+        self.apply_dir_result(result)
+    }
+
+    /// Keep searching for `query` so [`PathViewer::search_next`] can cycle through its matches.
+    ///
+    /// An empty `query` ends the search instead.
+    pub fn finish_search(&mut self, query: &str) {
+        if query.is_empty() {
+            self.search = None;
+        }
+    }
+
+    /// Stop searching and highlight the entry that was highlighted when the search started.
+    pub fn cancel_search(&mut self) -> PathViewerResult {
+        match self.search.take() {
+            Some(search) => {
+                self.cur_dir.highlight(search.origin);
+                self.apply_dir_result(DirectoryResult::EntryHighlight(search.origin))
+            }
+            None => PathViewerResult::Ok,
+        }
+    }
+
+    /// Stop searching, but keep the highlighted entry.
+    #[inline(always)]
+    pub fn clear_search(&mut self) {
+        self.search = None;
+    }
+
+    /// Highlight the next entry that matches the search, or the previous one if `backwards` is true.
+    pub fn search_next(&mut self, backwards: bool) -> PathViewerResult {
+        let search = match &self.search {
+            Some(search) => search,
+            None => return PathViewerResult::Ok,
+        };
+
+        let result = self.cur_dir.find_next(&search.query, backwards);
+        self.apply_dir_result(result)
+    }
+
+    /// Returns the name being searched for, if there is a search.
+    #[inline(always)]
+    pub fn search_query(&self) -> Option<&str> {
+        self.search.as_ref().map(|search| search.query.as_str())
+    }
+
+    /// Only show the entries of the current directory whose name contains `query`, ignoring case.
+    ///
+    /// An empty `query` shows every entry again. The filter is kept until it's cleared or the current directory
+    /// changes.
+    pub fn filter(&mut self, query: &str) -> PathViewerResult {
+        let filter = Some(query).filter(|query| !query.is_empty());
+
+        if filter == self.name_filter() {
+            return PathViewerResult::Ok;
+        }
+
+        self.cur_dir = self
+            .cur_dir
+            .with_name_filter(filter, self.options, &self.marked);
+
+        self.child_dir = self.highlighted_dir_viewer();
+        PathViewerResult::PathSelected
+    }
+
+    /// Returns the text the current directory is being filtered by, if there is a filter.
+    #[inline(always)]
+    pub fn name_filter(&self) -> Option<&str> {
+        self.cur_dir.name_filter()
+    }
+
+    /// Jump to the next entry whose name starts with what has been typed since the type-ahead key was pressed.
+    ///
+    /// Returns None if the key isn't part of a name being typed, which happens once a key other than a letter is
+    /// pressed or too long has passed since the last one.
+    pub fn type_ahead(&mut self, key: KeyCode) -> Option<PathViewerResult> {
+        let type_ahead = self.type_ahead.as_mut()?;
+
+        let ch = match key {
+            KeyCode::Char(ch) if type_ahead.last_key.elapsed() < Self::TYPE_AHEAD_TIMEOUT => ch,
+            _ => {
+                self.type_ahead = None;
+                return None;
+            }
+        };
+
+        // The first letter moves on to the next entry so pressing it again cycles through every match
+        let include_highlighted = !type_ahead.prefix.is_empty();
+
+        type_ahead.prefix.push(ch);
+        type_ahead.last_key = Instant::now();
+
+        let result = self
+            .cur_dir
+            .jump_to_prefix(&type_ahead.prefix, include_highlighted);
+
+        Some(self.apply_dir_result(result))
     }
 
     pub fn process_key(&mut self, key: KeyCode) -> PathViewerResult {
-        match self.cur_dir.process_key(key) {
+        match key {
+            KeyCode::Char(Self::TYPE_AHEAD_KEY) => {
+                self.type_ahead = Some(TypeAhead {
+                    prefix: String::new(),
+                    last_key: Instant::now(),
+                });
+
+                return PathViewerResult::Ok;
+            }
+            KeyCode::Char(Self::SIZE_FILTER_KEY) => return self.cycle_size_filter(),
+            KeyCode::Char(Self::TOGGLE_HIDDEN_KEY) => return self.toggle_hidden(),
+            KeyCode::Char(Self::SORT_COLUMN_KEY) => {
+                let sort = self.options.sort;
+                return self.sort_by(sort.toggle(sort.column.next()));
+            }
+            KeyCode::Char(Self::SORT_DIRECTION_KEY) => {
+                let sort = self.options.sort;
+                return self.sort_by(sort.toggle(sort.column));
+            }
+            KeyCode::Char(Self::UNMARK_ALL_KEY) => return self.unmark_all(),
+            KeyCode::Char(Self::TOGGLE_PARENT_KEY) => {
+                self.show_parent = !self.show_parent;
+                return PathViewerResult::Ok;
+            }
+            KeyCode::Char(Self::TOGGLE_CHILD_KEY) => {
+                self.show_child = !self.show_child;
+                return PathViewerResult::Ok;
+            }
+            KeyCode::Char(Self::TOGGLE_DATE_KEY) => {
+                self.show_date = !self.show_date;
+                return PathViewerResult::Ok;
+            }
+            _ => (),
+        }
+
+        let result = self.cur_dir.process_key(key);
+        self.apply_dir_result(result)
+    }
+
+    /// Update the surrounding directories to match what happened in the current one.
+    fn apply_dir_result(&mut self, result: DirectoryResult) -> PathViewerResult {
+        match result {
             DirectoryResult::Ok => PathViewerResult::Ok,
             DirectoryResult::EntryHighlight(id) => {
-                self.child_dir = if self.archive[id].props.is_dir() {
+                self.child_dir = if self.files[id].props.is_dir() {
                     self.dir_viewer(id)
                 } else {
                     None
                 };
 
-                PathViewerResult::PathSelected(id)
+                PathViewerResult::PathSelected
+            }
+            DirectoryResult::SelectionToggled {
+                id,
+                selected,
+                highlighted,
+            } => {
+                if selected {
+                    self.marked.insert(id);
+                } else {
+                    self.marked.remove(&id);
+                }
+
+                self.child_dir = if self.files[highlighted].props.is_dir() {
+                    self.dir_viewer(highlighted)
+                } else {
+                    None
+                };
+
+                PathViewerResult::PathSelected
+            }
+            DirectoryResult::SelectionChanged => {
+                for entry in self.cur_dir.entries() {
+                    if entry.selected {
+                        self.marked.insert(entry.id);
+                    } else {
+                        self.marked.remove(&entry.id);
+                    }
+                }
+
+                PathViewerResult::PathSelected
             }
             DirectoryResult::ViewChild(id) => {
                 let new_cur = match self.dir_viewer(id) {
@@ -55,12 +588,11 @@ impl PathViewer {
                 };
 
                 let old_cur = mem::replace(&mut self.cur_dir, new_cur);
-                let highlighted_node = self.highlighted().id;
-
-                self.parent_dir = Some(old_cur);
-                self.child_dir = self.dir_viewer(highlighted_node);
+                self.parent_dir = Some(self.unfiltered(old_cur));
+                self.child_dir = self.highlighted_dir_viewer();
+                self.search = None;
 
-                PathViewerResult::PathSelected(highlighted_node)
+                PathViewerResult::PathSelected
             }
             DirectoryResult::ViewParent(id) => {
                 let new_cur = match mem::take(&mut self.parent_dir) {
@@ -68,35 +600,162 @@ impl PathViewer {
                     None => return PathViewerResult::Ok,
                 };
 
-                self.child_dir = Some(mem::replace(&mut self.cur_dir, new_cur));
+                let old_cur = mem::replace(&mut self.cur_dir, new_cur);
+                self.child_dir = Some(self.unfiltered(old_cur));
+                self.search = None;
 
-                let parent = self.archive[id]
+                let parent = self.files[id]
                     .parent
-                    .and_then(|parent| self.archive[parent].parent)
-                    .and_then(|parent| self.archive[parent].parent);
+                    .and_then(|parent| self.files[parent].parent);
 
                 if let Some(parent) = parent {
                     self.parent_dir = self.dir_viewer(parent);
                 }
 
-                PathViewerResult::PathSelected(self.highlighted().id)
+                PathViewerResult::PathSelected
             }
         }
     }
 
+    /// Returns the snapshot of the archive's entries that is being viewed.
+    #[inline(always)]
+    pub fn files(&self) -> &ArchiveEntries {
+        &self.files
+    }
+
+    /// Returns a new reference to the snapshot of the archive's entries that is being viewed.
+    #[inline(always)]
+    pub fn shared_files(&self) -> Arc<ArchiveEntries> {
+        Arc::clone(&self.files)
+    }
+
+    /// Returns every action that is bound to a key while browsing the archive.
+    pub fn commands() -> impl Iterator<Item = &'static Command> {
+        Self::COMMANDS.iter().chain(DirectoryViewer::COMMANDS)
+    }
+
     #[inline(always)]
     pub fn directory(&self) -> NodeID {
         self.cur_dir.directory()
     }
 
-    /// Returns a reference to the currently highlighted [`DirectoryEntry`].
+    /// Returns a reference to the currently highlighted [`DirectoryEntry`], or None if the current directory has no
+    /// entries to show.
     #[inline(always)]
-    pub fn highlighted(&self) -> &DirectoryEntry {
+    pub fn highlighted(&self) -> Option<&DirectoryEntry> {
         self.cur_dir.highlighted()
     }
 
+    /// Returns the ID of the highlighted entry, or of the current directory itself if it has no entries to show.
+    #[inline(always)]
+    pub fn highlighted_id(&self) -> NodeID {
+        self.highlighted()
+            .map_or(self.directory(), |entry| entry.id)
+    }
+
+    /// Returns the area the highlighted file's content can be previewed in, which is where a highlighted directory's
+    /// entries would be shown.
+    ///
+    /// Returns None if that area is hidden, or if a directory or nothing is highlighted. The area is the one from the
+    /// last draw.
+    pub fn preview_area(&self) -> Option<Rect> {
+        if !self.show_child {
+            return None;
+        }
+
+        let entry = self.highlighted()?;
+
+        if self.files[entry.id].props.is_dir() {
+            return None;
+        }
+
+        self.panes.map(|panes| panes.child)
+    }
+
+    /// Returns every selected entry from any directory, or the highlighted entry if nothing is selected.
     pub fn selected_ids(&self) -> SmallVec<[NodeID; 4]> {
-        self.cur_dir.selected_ids()
+        if self.marked.is_empty() {
+            return smallvec![self.highlighted_id()];
+        }
+
+        // Entries inside of a selected directory are already covered by it
+        let mut ids = self
+            .marked
+            .iter()
+            .copied()
+            .filter(|&id| !self.has_marked_ancestor(id))
+            .collect::<SmallVec<_>>();
+
+        ids.sort_unstable();
+        ids
+    }
+
+    fn has_marked_ancestor(&self, id: NodeID) -> bool {
+        let mut cur_node = self.files[id].parent;
+
+        while let Some(node) = cur_node {
+            if self.marked.contains(&node) {
+                return true;
+            }
+
+            cur_node = self.files[node].parent;
+        }
+
+        false
+    }
+
+    /// Returns the path of every selected entry relative to the root of the archive.
+    pub fn marked_paths(&self) -> Vec<String> {
+        self.marked
+            .iter()
+            .map(|&id| self.files.path_of(id).to_string_lossy().into_owned())
+            .collect()
+    }
+
+    /// Select the entry at each of the given `paths` that still exists, which should be relative to the root of the
+    /// archive.
+    pub fn mark_paths(&mut self, paths: &[String]) {
+        let found = paths
+            .iter()
+            .filter_map(|path| self.files.find_path(path))
+            .collect::<Vec<_>>();
+
+        if found.is_empty() {
+            return;
+        }
+
+        self.marked.extend(found);
+
+        // Rebuilding with the same options never hides entries, so this can't fail
+        self.apply_options(self.options);
+    }
+
+    /// Returns the number of entries that are selected across every directory.
+    #[inline(always)]
+    pub fn num_marked(&self) -> usize {
+        self.marked.len()
+    }
+
+    /// Returns the number of visible entries in the currently viewed directory.
+    #[inline(always)]
+    pub fn num_entries(&self) -> usize {
+        self.cur_dir.len()
+    }
+
+    #[inline(always)]
+    pub fn size_filter(&self) -> SizeFilter {
+        self.options.size_filter
+    }
+
+    /// Returns true if dotfiles and junk left behind by operating systems are hidden.
+    #[inline(always)]
+    pub fn hides_hidden(&self) -> bool {
+        self.options.hide_hidden
+    }
+
+    #[inline(always)]
+    pub fn sort(&self) -> SortOrder {
+        self.options.sort
     }
 
     /// Returns the index of the selected entry in the currently viewed directory.
@@ -104,34 +763,182 @@ impl PathViewer {
     pub fn highlighted_index(&self) -> usize {
         self.cur_dir.highlighted_index()
     }
-}
 
-impl<B: Backend> Draw<B> for PathViewer {
-    fn draw(&mut self, rect: Rect, frame: &mut Frame<B>) {
+    /// Split the given `rect` between the directories that are shown, with a column of space between each of them.
+    ///
+    /// Directories that are hidden get an empty area.
+    fn split_panes(&self, rect: Rect) -> Panes {
+        let parent = if self.show_parent {
+            self.ratios.parent
+        } else {
+            0
+        };
+        let child = if self.show_child {
+            self.ratios.child
+        } else {
+            0
+        };
+        let total = parent + self.ratios.current + child;
+
+        let mut constraints = Vec::with_capacity(5);
+
+        if parent > 0 {
+            constraints.push(Constraint::Ratio(parent, total));
+            constraints.push(Constraint::Length(1));
+        }
+
+        constraints.push(Constraint::Ratio(self.ratios.current, total));
+
+        if child > 0 {
+            constraints.push(Constraint::Length(1));
+            constraints.push(Constraint::Ratio(child, total));
+        }
+
         let layout = Layout::default()
-            .constraints([
-                Constraint::Percentage(25),
-                Constraint::Length(1),
-                Constraint::Percentage(50),
-                Constraint::Length(1),
-                Constraint::Percentage(25),
-            ])
+            .constraints(constraints)
             .direction(Direction::Horizontal)
             .split(rect);
 
-        if let Some(parent_dir) = &mut self.parent_dir {
-            parent_dir.draw(layout[0], frame);
+        let current = if parent > 0 { 2 } else { 0 };
+
+        Panes {
+            parent: if parent > 0 {
+                layout[0]
+            } else {
+                Rect::default()
+            },
+            current: layout[current],
+            child: if child > 0 {
+                layout[current + 2]
+            } else {
+                Rect::default()
+            },
+        }
+    }
+}
+
+impl<B: Backend> Draw<B> for PathViewer {
+    fn draw(&mut self, rect: Rect, frame: &mut Frame<B>) {
+        let rect = if self.show_header {
+            Rect {
+                y: rect.y + 1,
+                height: rect.height.saturating_sub(1),
+                ..rect
+            }
+        } else {
+            rect
+        };
+
+        let panes = self.split_panes(rect);
+
+        if let (Some(parent_dir), true) = (&mut self.parent_dir, self.show_parent) {
+            parent_dir.draw(panes.parent, frame);
         }
 
-        self.cur_dir.draw(layout[2], frame);
+        self.panes = Some(panes);
+
+        let columns = self.cur_dir.columns(panes.current, self.show_date);
+
+        if self.show_header {
+            let header_area = Rect {
+                y: panes.current.y - 1,
+                height: 1,
+                ..panes.current
+            };
+
+            let header = ColumnHeader {
+                columns: &columns,
+                sort: self.options.sort,
+            };
 
-        if let Some(child_dir) = &mut self.child_dir {
-            child_dir.draw(layout[4], frame);
+            frame.render_widget(header, header_area);
+            self.cur_dir.draw_columns(&columns, panes.current, frame);
+            self.header = Some((header_area, columns));
+        } else {
+            self.cur_dir.draw_columns(&columns, panes.current, frame);
+        }
+
+        if let (Some(child_dir), true) = (&mut self.child_dir, self.show_child) {
+            child_dir.draw(panes.child, frame);
         }
     }
 }
 
+/// A search for an entry by name in the current directory.
+struct Search {
+    query: String,
+    /// The entry that was highlighted when the search started.
+    origin: NodeID,
+}
+
+/// How much of the width each directory of a [`PathViewer`] takes up compared to the others.
+#[derive(Copy, Clone)]
+pub struct PaneRatios {
+    pub parent: u32,
+    pub current: u32,
+    pub child: u32,
+}
+
+impl Default for PaneRatios {
+    fn default() -> Self {
+        Self {
+            parent: 1,
+            current: 2,
+            child: 1,
+        }
+    }
+}
+
+impl FromStr for PaneRatios {
+    type Err = anyhow::Error;
+
+    /// Parse ratios written as PARENT:CURRENT:CHILD, like `1:2:1`.
+    fn from_str(value: &str) -> Result<Self> {
+        let invalid = || {
+            anyhow!(
+                "invalid pane ratios: {} (expected three whole numbers above zero like 1:2:1)",
+                value.trim()
+            )
+        };
+
+        let parts = value
+            .trim()
+            .split(':')
+            .map(|part| part.trim().parse::<u32>().ok().filter(|&part| part > 0))
+            .collect::<Option<SmallVec<[_; 3]>>>()
+            .ok_or_else(invalid)?;
+
+        match parts.as_slice() {
+            &[parent, current, child] => Ok(Self {
+                parent,
+                current,
+                child,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Where each directory of a [`PathViewer`] was drawn.
+#[derive(Copy, Clone)]
+struct Panes {
+    parent: Rect,
+    current: Rect,
+    child: Rect,
+}
+
+/// The start of a name being typed to jump to the entry that has it.
+struct TypeAhead {
+    prefix: String,
+    last_key: Instant,
+}
+
 pub enum PathViewerResult {
     Ok,
-    PathSelected(NodeID),
+    PathSelected,
+}
+
+/// Returns true if the given screen position is inside of `area`.
+fn contains(area: Rect, x: u16, y: u16) -> bool {
+    x >= area.x && x < area.right() && y >= area.y && y < area.bottom()
 }
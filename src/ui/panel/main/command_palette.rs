@@ -0,0 +1,234 @@
+use crate::ui::{
+    colors,
+    panel::Command,
+    util::{
+        fill_area,
+        input::{Input, InputResult, InputState},
+    },
+};
+use crossterm::event::KeyCode;
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::Widget,
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Popup to find an action by typing part of its name, so features can be found without knowing what key they're on.
+pub struct CommandPalette {
+    commands: Vec<Command>,
+    input: InputState,
+    /// The index of each command that matches what was typed, from the best match to the worst.
+    matches: Vec<usize>,
+    /// The index in `matches` of the highlighted command.
+    selected: usize,
+}
+
+impl CommandPalette {
+    pub const DESC: &'static str = "run command";
+
+    /// The most commands that are shown at once.
+    const MAX_VISIBLE: usize = 10;
+
+    /// The space between the name of a command and its key.
+    const SPACING: usize = 2;
+
+    pub fn new(commands: Vec<Command>) -> Self {
+        let mut palette = Self {
+            commands,
+            input: InputState::new(),
+            matches: Vec::new(),
+            selected: 0,
+        };
+
+        palette.update_matches();
+        palette
+    }
+
+    #[inline(always)]
+    pub fn input_mut(&mut self) -> &mut InputState {
+        &mut self.input
+    }
+
+    pub fn process_key(&mut self, key: KeyCode) -> PaletteResult {
+        let len = self.matches.len();
+
+        match key {
+            KeyCode::Up | KeyCode::BackTab if len > 0 => {
+                self.selected = (self.selected + len - 1) % len;
+                PaletteResult::Ok
+            }
+            KeyCode::Down | KeyCode::Tab if len > 0 => {
+                self.selected = (self.selected + 1) % len;
+                PaletteResult::Ok
+            }
+            KeyCode::Up | KeyCode::BackTab | KeyCode::Down | KeyCode::Tab => PaletteResult::Ok,
+            key => match self.input.process_key(key) {
+                InputResult::Ok => {
+                    self.update_matches();
+                    PaletteResult::Ok
+                }
+                InputResult::Return => PaletteResult::Close,
+                InputResult::ProcessInput(_) => match self.matches.get(self.selected) {
+                    Some(&index) => PaletteResult::Run(self.commands[index].key),
+                    None => PaletteResult::Close,
+                },
+            },
+        }
+    }
+
+    fn update_matches(&mut self) {
+        let query = self.input.text().trim().to_lowercase();
+
+        let mut scored = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter_map(|(index, command)| Some((index, fuzzy_score(command.name, &query)?)))
+            .collect::<Vec<_>>();
+
+        // Commands that match equally well stay in the order they're listed in
+        scored.sort_unstable_by_key(|&(index, score)| (Reverse(score), index));
+
+        self.matches = scored.into_iter().map(|(index, _)| index).collect();
+        self.selected = 0;
+    }
+
+    /// Returns the area the popup should be drawn in so it sits directly above the given `input_area`.
+    pub fn area(&self, input_area: Rect) -> Rect {
+        let widest = self
+            .commands
+            .iter()
+            .map(|command| command.name.width() + Self::SPACING + key_desc(command.key).width())
+            .max()
+            .unwrap_or(0);
+
+        // Room for a space on each side
+        let width = (widest as u16).saturating_add(2).min(input_area.width);
+        let height = (self.matches.len().min(Self::MAX_VISIBLE) as u16).min(input_area.y);
+
+        Rect {
+            x: input_area.x,
+            y: input_area.y - height,
+            width,
+            height,
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub enum PaletteResult {
+    Ok,
+    Close,
+    /// Run the picked command by pressing the key it's bound to.
+    Run(KeyCode),
+}
+
+impl Widget for &CommandPalette {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let style = Style::default()
+            .bg(Input::DEFAULT_BG_COLOR)
+            .fg(colors::WHITE);
+
+        fill_area(area, buf, |cell| {
+            cell.reset();
+            cell.set_style(style);
+        });
+
+        // Keep the highlighted command in view
+        let scroll = (self.selected + 1).saturating_sub(area.height as usize);
+
+        let visible = self
+            .matches
+            .iter()
+            .enumerate()
+            .skip(scroll)
+            .take(area.height as usize);
+
+        let inner_width = area.width.saturating_sub(2);
+
+        for (i, (index, &command_index)) in visible.enumerate() {
+            let command = &self.commands[command_index];
+            let y = area.y + i as u16;
+
+            let style = if index == self.selected {
+                Style::default()
+                    .fg(colors::BLACK)
+                    .bg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                style
+            };
+
+            let row = Rect {
+                y,
+                height: 1,
+                ..area
+            };
+
+            buf.set_style(row, style);
+            buf.set_stringn(area.x + 1, y, command.name, inner_width as usize, style);
+
+            let key = key_desc(command.key);
+            let key_x = (area.x + 1 + inner_width).saturating_sub(key.width() as u16);
+
+            buf.set_string(key_x, y, key, style.fg(Color::DarkGray));
+        }
+    }
+}
+
+/// Returns the name of the given `key` as it's shown next to a command.
+fn key_desc(key: KeyCode) -> Cow<'static, str> {
+    match key {
+        KeyCode::Char(' ') => "Space".into(),
+        KeyCode::Char(ch) => ch.to_string().into(),
+        KeyCode::Esc => "Esc".into(),
+        _ => "".into(),
+    }
+}
+
+/// Returns how well the given `name` matches `query`, which must be lowercase, or None if `name` doesn't have every
+/// character of `query` in the same order.
+///
+/// Characters that follow each other or start a word count for more, so `tdc` ranks "toggle date column" above
+/// names that only happen to have those letters somewhere.
+fn fuzzy_score(name: &str, query: &str) -> Option<u32> {
+    let mut remaining = query.chars().filter(|ch| !ch.is_whitespace()).peekable();
+    let mut score = 0;
+    let mut prev: Option<char> = None;
+    let mut prev_matched = false;
+
+    for ch in name.chars() {
+        let next = match remaining.peek() {
+            Some(&next) => next,
+            None => break,
+        };
+
+        let matched = ch.to_ascii_lowercase() == next;
+
+        if matched {
+            remaining.next();
+            score += 1;
+
+            if prev_matched {
+                score += 2;
+            }
+
+            if !matches!(prev, Some(prev) if prev.is_alphanumeric()) {
+                score += 3;
+            }
+        }
+
+        prev = Some(ch);
+        prev_matched = matched;
+    }
+
+    if remaining.peek().is_none() {
+        Some(score)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,84 @@
+use super::report::{Report, ReportList};
+use crate::{archive::compare::Difference, ui::colors, util::number};
+use crossterm::event::KeyCode;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+
+/// Scrollable list of differences found between the archive and a directory on disk.
+pub struct CompareReport {
+    differences: Vec<Difference>,
+    list: ReportList,
+}
+
+impl CompareReport {
+    pub fn new(differences: Vec<Difference>) -> Self {
+        Self {
+            differences,
+            list: ReportList::scrolled(),
+        }
+    }
+
+    pub fn process_key(&mut self, key: KeyCode) {
+        self.list.process_key(key, self.differences.len());
+    }
+
+    fn header(&self) -> String {
+        match self.differences.len() {
+            0 => "Directory Matches Archive".into(),
+            1 => "1 Difference Found".into(),
+            num => format!("{} Differences Found", number::grouped(num as u64)),
+        }
+    }
+
+    fn difference_desc(difference: &Difference) -> (&'static str, Color) {
+        match difference {
+            Difference::Missing(_) => ("missing ", Color::Red),
+            Difference::Extra(_) => ("extra   ", Color::Yellow),
+            Difference::Changed(_) => ("changed ", Color::Magenta),
+        }
+    }
+}
+
+impl Widget for &CompareReport {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let header_color = if self.differences.is_empty() {
+            Color::Green
+        } else {
+            Color::Red
+        };
+
+        Report::new(
+            &self.list,
+            self.header(),
+            header_color,
+            &self.differences,
+            |difference, area, buf, _| {
+                let (desc, color) = CompareReport::difference_desc(difference);
+
+                let (x, _) = buf.set_stringn(
+                    area.x,
+                    area.y,
+                    desc,
+                    area.width as usize,
+                    Style::default().fg(color),
+                );
+
+                let path = difference.path().to_string_lossy();
+                let remaining = area.right().saturating_sub(x) as usize;
+
+                buf.set_stringn(
+                    x,
+                    area.y,
+                    path,
+                    remaining,
+                    Style::default().fg(colors::WHITE),
+                );
+            },
+        )
+        .render(area, buf);
+    }
+}
@@ -0,0 +1,161 @@
+use super::report::{Report, ReportList};
+use crate::{
+    archive::{
+        duplicates::{DuplicateFinder, DuplicateSet},
+        NodeID,
+    },
+    ui::colors,
+    util::{number, size},
+};
+use crossterm::event::KeyCode;
+use std::sync::Arc;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+
+/// Scrollable list of sets of files with identical content, which fills in while the files are being compared.
+///
+/// Each set is listed under a line that says how much space it wastes, and only the files themselves can be
+/// highlighted.
+pub struct DuplicatesReport {
+    pub finder: Arc<DuplicateFinder>,
+    /// The highlighted file among every listed file, which leaves out the line above each set.
+    list: ReportList,
+}
+
+impl DuplicatesReport {
+    /// How far the files of a set are indented under it.
+    const INDENT: &'static str = "  ";
+
+    pub fn new(finder: Arc<DuplicateFinder>) -> Self {
+        Self {
+            finder,
+            list: ReportList::highlighted(),
+        }
+    }
+
+    /// Returns the ID of the highlighted file, or None if no duplicates have been found.
+    pub fn highlighted(&self) -> Option<NodeID> {
+        self.finder
+            .sets()
+            .iter()
+            .flat_map(|set| &set.files)
+            .nth(self.list.index())
+            .map(|&(id, _)| id)
+    }
+
+    pub fn process_key(&mut self, key: KeyCode) {
+        let len = Self::num_files(&self.finder.sets());
+        self.list.process_key(key, len);
+    }
+
+    fn num_files(sets: &[DuplicateSet]) -> usize {
+        sets.iter().map(|set| set.files.len()).sum()
+    }
+
+    fn header(&self, num_sets: usize) -> String {
+        let sets = match num_sets {
+            1 => "1 Set".into(),
+            num => format!("{} Sets", number::grouped(num as u64)),
+        };
+
+        if self.finder.is_cancelled() {
+            format!("Comparing Cancelled After {} Of Duplicates", sets)
+        } else if !self.finder.is_finished() {
+            format!("Comparing Files, {} Of Duplicates So Far", sets)
+        } else if num_sets == 0 {
+            "No Duplicate Files Found".into()
+        } else {
+            format!("{} Of Duplicate Files Found", sets)
+        }
+    }
+
+    fn detail(&self, sets: &[DuplicateSet]) -> String {
+        let wasted = sets.iter().map(DuplicateSet::wasted_bytes).sum::<u64>();
+
+        let mut detail = if wasted > 0 {
+            format!(
+                "{} would be saved by keeping one copy of each. ",
+                size::formatted(wasted)
+            )
+        } else {
+            String::new()
+        };
+
+        if self.finder.is_finished() {
+            detail.push_str("Press Enter to go to the highlighted file or Esc to go back");
+        } else {
+            detail.push_str("Press Esc to stop comparing");
+        }
+
+        detail
+    }
+
+    fn set_heading(set: &DuplicateSet) -> String {
+        format!(
+            "{} copies of {}, {} wasted",
+            number::grouped(set.files.len() as u64),
+            size::formatted(set.size),
+            size::formatted(set.wasted_bytes())
+        )
+    }
+}
+
+/// A line of the list, which is either the heading of a set or one of its files.
+enum Row<'a> {
+    Set(&'a DuplicateSet),
+    File(&'a str),
+}
+
+impl Widget for &DuplicatesReport {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let sets = self.finder.sets();
+        let mut rows = Vec::new();
+        let mut num_files = 0;
+        let mut highlighted_row = 0;
+
+        for set in sets.iter() {
+            rows.push(Row::Set(set));
+
+            for (_, path) in &set.files {
+                if num_files == self.list.index() {
+                    highlighted_row = rows.len();
+                }
+
+                rows.push(Row::File(path));
+                num_files += 1;
+            }
+        }
+
+        Report::new(
+            &self.list,
+            self.header(sets.len()),
+            Color::Yellow,
+            &rows,
+            |row, area, buf, style| {
+                let width = area.width as usize;
+
+                match row {
+                    Row::Set(set) => {
+                        let style = Style::default().fg(Color::Yellow);
+                        let heading = DuplicatesReport::set_heading(set);
+
+                        buf.set_stringn(area.x, area.y, heading, width, style);
+                    }
+                    Row::File(path) => {
+                        let style = style.unwrap_or_else(|| Style::default().fg(colors::WHITE));
+                        let text = format!("{}{}", DuplicatesReport::INDENT, path);
+
+                        buf.set_stringn(area.x, area.y, text, width, style);
+                    }
+                }
+            },
+        )
+        .detail(self.detail(&sets))
+        .highlighted_row(highlighted_row)
+        .render(area, buf);
+    }
+}
@@ -0,0 +1,213 @@
+use crate::{
+    archive::{Archive, ArchiveEntries, EntryProperties, NodeID},
+    ui::{colors, util::fill_area},
+    util::{number, size},
+};
+use anyhow::Result;
+use tui::{
+    buffer::{Buffer, Cell},
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Widget},
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Popup that lists everything known about an entry, which is more than fits in the stats under the viewed
+/// directory.
+pub struct EntryInfo {
+    rows: Vec<(&'static str, String)>,
+}
+
+impl EntryInfo {
+    const TITLE: &'static str = " Entry Info ";
+
+    /// The space between the label and value of each row.
+    const SPACING: u16 = 2;
+
+    /// Gather everything known about the entry with the given `id`, which includes details read from the `archive`
+    /// for files.
+    pub fn new(archive: &Archive, files: &ArchiveEntries, id: NodeID) -> Result<Self> {
+        let entry = &files[id];
+
+        let mut rows = vec![("Path", files.path_of(id).to_string_lossy().into_owned())];
+
+        match &entry.props {
+            EntryProperties::File(props) => {
+                let details = archive.file_details(entry)?;
+
+                let kind = match props.special {
+                    Some(special) => Self::capitalized(special.desc()),
+                    None if props.is_symlink => "Symbolic Link".into(),
+                    None => "File".into(),
+                };
+
+                rows.push(("Type", kind));
+
+                if let Some(target) = &props.link_target {
+                    rows.push(("Target", target.clone()));
+                }
+                rows.push(("Size", Self::size_text(props.raw_size_bytes)));
+                rows.push(("Compressed", Self::size_text(props.compressed_size_bytes)));
+                rows.push((
+                    "Ratio",
+                    Self::ratio_text(props.compressed_size_bytes, props.raw_size_bytes),
+                ));
+                rows.push(("Method", details.compression.to_string()));
+                rows.push(("CRC32", format!("{:08x}", props.crc32)));
+
+                if let Some(mode) = props.unix_mode {
+                    rows.push(("Permissions", format!("{:04o}", mode)));
+                }
+
+                let encrypted = if props.encrypted { "Yes" } else { "No" };
+                rows.push(("Encrypted", encrypted.into()));
+
+                if !details.comment.is_empty() {
+                    // Newlines would otherwise be drawn as nothing and run the lines of the comment together
+                    rows.push(("Comment", details.comment.replace('\n', " ")));
+                }
+            }
+            EntryProperties::Directory => {
+                let (num_entries, num_encrypted, raw, compressed) = files
+                    .children_iter(&[id])
+                    .filter(|&(child, _, _)| child != id)
+                    .fold(
+                        (0, 0, 0, 0),
+                        |(num, encrypted, raw, compressed), (_, entry, _)| match &entry.props {
+                            EntryProperties::File(props) => (
+                                num + 1,
+                                encrypted + u64::from(props.encrypted),
+                                raw + props.raw_size_bytes,
+                                compressed + props.compressed_size_bytes,
+                            ),
+                            EntryProperties::Directory => (num + 1, encrypted, raw, compressed),
+                        },
+                    );
+
+                rows.push(("Type", "Directory".into()));
+                rows.push(("Entries", number::grouped(num_entries)));
+
+                if num_encrypted > 0 {
+                    rows.push(("Encrypted", number::grouped(num_encrypted)));
+                }
+
+                rows.push(("Size", Self::size_text(raw)));
+                rows.push(("Compressed", Self::size_text(compressed)));
+                rows.push(("Ratio", Self::ratio_text(compressed, raw)));
+            }
+        }
+
+        if let Some(date) = &entry.last_modified {
+            rows.push((
+                "Modified",
+                format!(
+                    "{}-{:02}-{:02} {:02}:{:02}",
+                    date.year, date.month, date.day, date.hour, date.minute,
+                ),
+            ));
+        }
+
+        rows.push(("Name Encoding", entry.encoding.name().into()));
+
+        Ok(Self { rows })
+    }
+
+    fn size_text(bytes: u64) -> String {
+        format!(
+            "{} ({} bytes)",
+            size::formatted(bytes),
+            number::grouped(bytes)
+        )
+    }
+
+    /// Returns the given `text` with the first letter of each word in uppercase.
+    fn capitalized(text: &str) -> String {
+        text.split(' ')
+            .map(|word| {
+                let mut chars = word.chars();
+
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect(),
+                    None => String::new(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn ratio_text(compressed: u64, raw: u64) -> String {
+        if raw == 0 {
+            return "-".into();
+        }
+
+        let pcnt = ((compressed as f64 / raw as f64) * 100.0).round();
+        format!("{}%", pcnt)
+    }
+
+    fn label_width(&self) -> u16 {
+        self.rows
+            .iter()
+            .map(|(label, _)| label.len() as u16)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the area the popup should be drawn in so it sits in the middle of the given `area`.
+    pub fn area(&self, area: Rect) -> Rect {
+        let widest_value = self
+            .rows
+            .iter()
+            .map(|(_, value)| value.width() as u16)
+            .max()
+            .unwrap_or(0);
+
+        // Room for the border and a space on each side
+        let width = (self.label_width() + Self::SPACING + widest_value + 4).min(area.width);
+        let height = (self.rows.len() as u16 + 2).min(area.height);
+
+        Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        }
+    }
+}
+
+impl Widget for &EntryInfo {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        fill_area(area, buf, Cell::reset);
+
+        let block = Block::default()
+            .title(EntryInfo::TITLE)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let label_style = Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD);
+
+        let value_style = Style::default().fg(colors::WHITE);
+        let value_x = inner.x + 1 + self.label_width() + EntryInfo::SPACING;
+
+        for (i, (label, value)) in self.rows.iter().take(inner.height as usize).enumerate() {
+            let y = inner.y + i as u16;
+
+            buf.set_stringn(
+                inner.x + 1,
+                y,
+                label,
+                inner.width.saturating_sub(1) as usize,
+                label_style,
+            );
+
+            if value_x < inner.right() {
+                let remaining = inner.right().saturating_sub(value_x + 1) as usize;
+                buf.set_stringn(value_x, y, value, remaining, value_style);
+            }
+        }
+    }
+}
@@ -1,11 +1,11 @@
 use std::borrow::Cow;
 
+use crate::util::{number, size};
 use crate::{
     archive::ArchiveEntry,
-    archive::{Archive, EntryProperties},
-    ui::{colors, util::SimpleText},
+    archive::{ArchiveEntries, EntryProperties},
+    ui::{colors, panel::files::PathViewer, util::SimpleText},
 };
-use crate::{archive::NodeID, util::size};
 use tui::{
     buffer::Buffer,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
@@ -23,32 +23,22 @@ pub struct EntryStats<'a> {
 }
 
 impl<'a> EntryStats<'a> {
-    pub fn new(
-        archive: &Archive,
-        viewed_dir: NodeID,
-        selected: NodeID,
-        selected_idx: usize,
-    ) -> Self {
-        let dir_entry = &archive[viewed_dir];
-        let selected = &archive[selected];
+    pub fn new(path_viewer: &PathViewer, num_pinned: usize) -> Self {
+        let files = path_viewer.files();
+        let dir_entry = &files[path_viewer.directory()];
+        let selected = &files[path_viewer.highlighted_id()];
 
         Self {
             date: Self::date_text(selected),
             encoding: Self::encoding_text(selected),
             compressed_size: Self::compressed_size_text(selected),
-            total_size: Self::total_size_text(archive, dir_entry),
-            selection: Self::selection_text(dir_entry, selected_idx),
+            total_size: Self::total_size_text(files, dir_entry),
+            selection: Self::selection_text(path_viewer, num_pinned),
         }
     }
 
-    pub fn update(
-        &mut self,
-        archive: &Archive,
-        viewed_dir: NodeID,
-        selected: NodeID,
-        selected_idx: usize,
-    ) {
-        *self = Self::new(archive, viewed_dir, selected, selected_idx);
+    pub fn update(&mut self, path_viewer: &PathViewer, num_pinned: usize) {
+        *self = Self::new(path_viewer, num_pinned);
     }
 
     fn date_text(entry: &ArchiveEntry) -> Option<String> {
@@ -83,17 +73,18 @@ impl<'a> EntryStats<'a> {
         format!("{} [{}%]", size::formatted_compact(compressed), pcnt).into()
     }
 
-    fn total_size_text(archive: &Archive, dir: &ArchiveEntry) -> Cow<'a, str> {
-        let (raw_size, compressed_size) = dir.children.iter().map(|&id| &archive[id]).fold(
-            (0, 0),
-            |(acc_raw, acc_com), entry| match &entry.props {
-                EntryProperties::File(props) => (
-                    acc_raw + props.raw_size_bytes,
-                    acc_com + props.compressed_size_bytes,
-                ),
-                EntryProperties::Directory => (acc_raw, acc_com),
-            },
-        );
+    fn total_size_text(files: &ArchiveEntries, dir: &ArchiveEntry) -> Cow<'a, str> {
+        let (raw_size, compressed_size) =
+            dir.children
+                .iter()
+                .map(|&id| &files[id])
+                .fold((0, 0), |(acc_raw, acc_com), entry| match &entry.props {
+                    EntryProperties::File(props) => (
+                        acc_raw + props.raw_size_bytes,
+                        acc_com + props.compressed_size_bytes,
+                    ),
+                    EntryProperties::Directory => (acc_raw, acc_com),
+                });
 
         if raw_size == 0 {
             Cow::Borrowed("empty")
@@ -110,15 +101,72 @@ impl<'a> EntryStats<'a> {
         }
     }
 
-    fn selection_text(dir_entry: &ArchiveEntry, selected: usize) -> String {
-        format!("{}/{}", 1 + selected, dir_entry.children.len())
+    fn selection_text(path_viewer: &PathViewer, num_pinned: usize) -> String {
+        let position = match path_viewer.highlighted() {
+            Some(_) => number::grouped(1 + path_viewer.highlighted_index() as u64),
+            None => number::grouped(0),
+        };
+        let num_entries = number::grouped(path_viewer.num_entries() as u64);
+
+        let sort = path_viewer.sort().desc();
+
+        let selection = match path_viewer.size_filter().desc() {
+            Some(filter) => format!("{} {} {}/{}", sort, filter, position, num_entries),
+            None => format!("{} {}/{}", sort, position, num_entries),
+        };
+
+        let selection = if path_viewer.hides_hidden() {
+            format!("-hidden {}", selection)
+        } else {
+            selection
+        };
+
+        let selection = match path_viewer.name_filter() {
+            Some(filter) => format!("|{} {}", filter, selection),
+            None => selection,
+        };
+
+        let selection = match path_viewer.search_query() {
+            Some(query) if !query.is_empty() => format!("/{} {}", query, selection),
+            _ => selection,
+        };
+
+        let selection = match path_viewer.num_marked() {
+            0 => selection,
+            num => format!("{} [{}]", selection, Self::marked_text(path_viewer, num)),
+        };
+
+        let selection = match num_pinned {
+            0 => selection,
+            num => format!("{} [{} pinned]", selection, number::grouped(num as u64)),
+        };
+
+        // Extracting or previewing an encrypted file fails, so it should be clear up front that there are some
+        match path_viewer.files().num_encrypted {
+            0 => selection,
+            num => format!("{} [{} locked]", selection, number::grouped(num as u64)),
+        }
+    }
+
+    /// Returns how many entries are selected and how big everything they cover is, so it's clear how much would be
+    /// extracted.
+    fn marked_text(path_viewer: &PathViewer, num_marked: usize) -> String {
+        let (raw, compressed) = path_viewer.files().file_sizes(&path_viewer.selected_ids());
+
+        format!(
+            "{} selected, {} ({} compressed)",
+            number::grouped(num_marked as u64),
+            size::formatted_extra_compact(raw),
+            size::formatted_extra_compact(compressed)
+        )
     }
 }
 
 impl<'a> Widget for EntryStats<'a> {
     fn render(self, rect: Rect, buf: &mut Buffer) {
         const MARGIN: u16 = 1;
-        const PADDING: Constraint = Constraint::Length(2);
+        const PADDING_LEN: u16 = 2;
+        const PADDING: Constraint = Constraint::Length(PADDING_LEN);
 
         if rect.width <= MARGIN || rect.height == 0 {
             return;
@@ -161,33 +209,54 @@ impl<'a> Widget for EntryStats<'a> {
 
         encoding.render(left_layout[2], buf);
 
+        let selection_len = self.selection.len() as u16;
+        // The selection can get long enough not to fit when entries are marked, so it takes over the total size of
+        // the directory and then the middle of the bar when it needs the room
+        let fits_with_total =
+            self.total_size.len() as u16 + PADDING_LEN + selection_len <= layout[4].width;
+        let fits_right = selection_len <= layout[4].width;
+
         if let Some(compressed_size) = &self.compressed_size {
-            let text = SimpleText::new(compressed_size)
-                .alignment(Alignment::Center)
-                .style(style);
+            if fits_right {
+                let text = SimpleText::new(compressed_size)
+                    .alignment(Alignment::Center)
+                    .style(style);
 
-            text.render(layout[2], buf);
+                text.render(layout[2], buf);
+            }
         }
 
-        let right_layout = Layout::default()
-            .constraints([
-                Constraint::Min(self.total_size.len() as u16),
-                PADDING,
-                Constraint::Length(self.selection.len() as u16),
-            ])
-            .direction(Direction::Horizontal)
-            .split(layout[4]);
+        if fits_with_total {
+            let right_layout = Layout::default()
+                .constraints([
+                    Constraint::Min(self.total_size.len() as u16),
+                    PADDING,
+                    Constraint::Length(selection_len),
+                ])
+                .direction(Direction::Horizontal)
+                .split(layout[4]);
+
+            let total_size = SimpleText::new(self.total_size)
+                .alignment(Alignment::Right)
+                .style(style);
 
-        let total_size = SimpleText::new(self.total_size)
-            .alignment(Alignment::Right)
-            .style(style);
+            total_size.render(right_layout[0], buf);
+        }
 
-        total_size.render(right_layout[0], buf);
+        let selection_area = if fits_right {
+            layout[4]
+        } else {
+            Rect {
+                x: layout[2].x,
+                width: layout[4].right().saturating_sub(layout[2].x),
+                ..layout[4]
+            }
+        };
 
         let selection = SimpleText::new(&self.selection)
             .alignment(Alignment::Right)
             .style(style);
 
-        selection.render(right_layout[2], buf);
+        selection.render(selection_area, buf);
     }
 }
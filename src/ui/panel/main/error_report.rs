@@ -0,0 +1,89 @@
+use crate::ui::util::SimpleText;
+use anyhow::Error;
+use crossterm::event::KeyCode;
+use tui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans, Text},
+    widgets::{Paragraph, Widget, Wrap},
+};
+
+/// Scrollable view of an error along with everything that caused it, since the top-level message alone rarely says
+/// what actually went wrong.
+pub struct ErrorReport {
+    header: &'static str,
+    error: Error,
+    scroll: u16,
+}
+
+impl ErrorReport {
+    const CAUSE_PREFIX: &'static str = "caused by: ";
+
+    pub fn new(header: &'static str, error: Error) -> Self {
+        Self {
+            header,
+            error,
+            scroll: 0,
+        }
+    }
+
+    pub fn process_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down => {
+                let last_line = self.error.chain().count().saturating_sub(1) as u16;
+                self.scroll = (self.scroll + 1).min(last_line);
+            }
+            _ => (),
+        }
+    }
+
+    /// Returns a line for the error and each of its causes, from the error itself to the underlying cause.
+    fn lines(&self) -> Text {
+        let style = Style::default().fg(Color::Red);
+        let cause_style = Style::default().fg(Color::LightRed);
+
+        let mut lines = self.error.chain();
+
+        let first = lines
+            .next()
+            .map(|err| Spans::from(Span::styled(err.to_string(), style)));
+
+        let causes = lines.map(|cause| {
+            Spans::from(vec![
+                Span::styled(Self::CAUSE_PREFIX, cause_style.add_modifier(Modifier::BOLD)),
+                Span::styled(cause.to_string(), cause_style),
+            ])
+        });
+
+        first.into_iter().chain(causes).collect::<Vec<_>>().into()
+    }
+}
+
+impl Widget for &ErrorReport {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let layout = Layout::default()
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Percentage(100),
+            ])
+            .direction(Direction::Vertical)
+            .margin(1)
+            .split(area);
+
+        let header = SimpleText::new(self.header)
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD));
+
+        header.render(layout[0], buf);
+
+        let msg = Paragraph::new(self.lines())
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0));
+
+        msg.render(layout[2], buf);
+    }
+}
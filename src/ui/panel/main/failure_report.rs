@@ -0,0 +1,72 @@
+use super::report::{Report, ReportList};
+use crate::{archive::extract::Failure, ui::colors, util::number};
+use crossterm::event::KeyCode;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+
+/// Scrollable list of entries that couldn't be extracted along with why.
+pub struct FailureReport {
+    failures: Vec<Failure>,
+    list: ReportList,
+}
+
+impl FailureReport {
+    const SEPARATOR: &'static str = ": ";
+
+    pub fn new(failures: Vec<Failure>) -> Self {
+        Self {
+            failures,
+            list: ReportList::scrolled(),
+        }
+    }
+
+    pub fn process_key(&mut self, key: KeyCode) {
+        self.list.process_key(key, self.failures.len());
+    }
+
+    fn header(&self) -> String {
+        match self.failures.len() {
+            1 => "1 Entry Failed To Extract".into(),
+            num => format!("{} Entries Failed To Extract", number::grouped(num as u64)),
+        }
+    }
+}
+
+impl Widget for &FailureReport {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Report::new(
+            &self.list,
+            self.header(),
+            Color::Red,
+            &self.failures,
+            |failure, area, buf, _| {
+                let (x, _) = buf.set_stringn(
+                    area.x,
+                    area.y,
+                    failure.path.to_string_lossy(),
+                    area.width as usize,
+                    Style::default().fg(colors::WHITE),
+                );
+
+                let (x, _) = buf.set_stringn(
+                    x,
+                    area.y,
+                    FailureReport::SEPARATOR,
+                    area.right().saturating_sub(x) as usize,
+                    Style::default(),
+                );
+
+                // The alternate format includes every cause on one line
+                let error = format!("{:#}", failure.error);
+                let remaining = area.right().saturating_sub(x) as usize;
+
+                buf.set_stringn(x, area.y, error, remaining, Style::default().fg(Color::Red));
+            },
+        )
+        .render(area, buf);
+    }
+}
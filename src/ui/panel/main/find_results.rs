@@ -0,0 +1,96 @@
+use super::report::{Report, ReportList};
+use crate::{
+    archive::{query::EntryQuery, ArchiveEntries, NodeID},
+    ui::colors,
+    util::number,
+};
+use crossterm::event::KeyCode;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+
+/// Scrollable list of every entry in the archive whose path matches what was searched for.
+pub struct FindResults {
+    query: String,
+    results: Vec<FoundEntry>,
+    list: ReportList,
+}
+
+impl FindResults {
+    /// Find every entry in the given `files` that matches `query`, with the text of the query being looked for in
+    /// the path of each entry.
+    pub fn search(files: &ArchiveEntries, query: &str) -> Self {
+        let matcher = EntryQuery::parse(query);
+
+        let mut results = files
+            .children_iter(&[NodeID::first()])
+            .filter(|&(id, _, _)| id != NodeID::first())
+            .filter_map(|(id, entry, path)| {
+                let path = path.to_string_lossy();
+
+                if matcher.matches(entry, &path) {
+                    Some(FoundEntry {
+                        id,
+                        path: path.into_owned(),
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        results.sort_unstable_by(|x, y| x.path.cmp(&y.path));
+
+        Self {
+            query: query.to_string(),
+            results,
+            list: ReportList::highlighted(),
+        }
+    }
+
+    /// Returns the ID of the highlighted result, or None if nothing matched.
+    pub fn highlighted(&self) -> Option<NodeID> {
+        self.results.get(self.list.index()).map(|result| result.id)
+    }
+
+    pub fn process_key(&mut self, key: KeyCode) {
+        self.list.process_key(key, self.results.len());
+    }
+
+    fn header(&self) -> String {
+        match self.results.len() {
+            0 => format!("No Entries Match \"{}\"", self.query),
+            1 => format!("1 Entry Matches \"{}\"", self.query),
+            num => format!(
+                "{} Entries Match \"{}\"",
+                number::grouped(num as u64),
+                self.query
+            ),
+        }
+    }
+}
+
+struct FoundEntry {
+    id: NodeID,
+    path: String,
+}
+
+impl Widget for &FindResults {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Report::new(
+            &self.list,
+            self.header(),
+            Color::Cyan,
+            &self.results,
+            |result, area, buf, style| {
+                let style = style.unwrap_or_else(|| Style::default().fg(colors::WHITE));
+                buf.set_stringn(area.x, area.y, &result.path, area.width as usize, style);
+            },
+        )
+        .detail("Press Enter to go to the highlighted entry or Esc to go back")
+        .render(area, buf);
+    }
+}
@@ -0,0 +1,161 @@
+use super::report::{Report, ReportList};
+use crate::{
+    archive::{filter, ArchiveEntries, EntryProperties, NodeID},
+    ui::{
+        colors,
+        panel::files::{SortColumn, SortOrder},
+    },
+    util::{number, size},
+};
+use crossterm::event::KeyCode;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Scrollable list of every file in the archive by its full path, like `unzip -l`.
+pub struct FlatView {
+    entries: Vec<FlatEntry>,
+    sort: SortOrder,
+    list: ReportList,
+}
+
+impl FlatView {
+    const SORT_COLUMN_KEY: char = 'o';
+    const SORT_DIRECTION_KEY: char = 'O';
+
+    /// The space between the path of an entry and its date and size.
+    const SPACING: u16 = 2;
+
+    /// Create a new `FlatView` of every file in the given `files` sorted by `sort`.
+    ///
+    /// If `hide_hidden` is true, dotfiles, junk left behind by operating systems, and everything inside of them are
+    /// left out.
+    pub fn new(files: &ArchiveEntries, sort: SortOrder, hide_hidden: bool) -> Self {
+        let entries = files
+            .children_iter(&[NodeID::first()])
+            .filter(|(_, _, path)| !(hide_hidden && filter::is_hidden_path(path)))
+            .filter_map(|(id, entry, path)| {
+                let props = match &entry.props {
+                    EntryProperties::File(props) => props,
+                    EntryProperties::Directory => return None,
+                };
+
+                let date = entry
+                    .last_modified
+                    .as_ref()
+                    .map(|date| format!("{}-{:02}-{:02}", date.year, date.month, date.day));
+
+                Some(FlatEntry {
+                    id,
+                    path: path.to_string_lossy().into_owned(),
+                    size: size::formatted(props.raw_size_bytes),
+                    date,
+                })
+            })
+            .collect();
+
+        let mut view = Self {
+            entries,
+            sort,
+            list: ReportList::highlighted(),
+        };
+
+        view.sort_by(files, sort);
+        view
+    }
+
+    fn sort_by(&mut self, files: &ArchiveEntries, sort: SortOrder) {
+        self.entries.sort_unstable_by(|x, y| match sort.column {
+            // Every file would otherwise be sorted by its name alone
+            SortColumn::Name if sort.descending => y.path.cmp(&x.path),
+            SortColumn::Name => x.path.cmp(&y.path),
+            _ => sort
+                .compare(&files[x.id], &files[y.id])
+                .then_with(|| x.path.cmp(&y.path)),
+        });
+
+        self.sort = sort;
+        self.list.reset();
+    }
+
+    /// Returns the ID of the highlighted file, or None if the archive has no files.
+    pub fn highlighted(&self) -> Option<NodeID> {
+        self.entries.get(self.list.index()).map(|entry| entry.id)
+    }
+
+    pub fn process_key(&mut self, files: &ArchiveEntries, key: KeyCode) {
+        match key {
+            KeyCode::Char(Self::SORT_COLUMN_KEY) => {
+                self.sort_by(files, self.sort.toggle(self.sort.column.next()));
+            }
+            KeyCode::Char(Self::SORT_DIRECTION_KEY) => {
+                self.sort_by(files, self.sort.toggle(self.sort.column));
+            }
+            key => self.list.process_key(key, self.entries.len()),
+        }
+    }
+
+    fn header(&self) -> String {
+        let files = match self.entries.len() {
+            1 => "1 File".into(),
+            num => format!("{} Files", number::grouped(num as u64)),
+        };
+
+        format!("{} By {}", files, self.sort.desc())
+    }
+}
+
+struct FlatEntry {
+    id: NodeID,
+    path: String,
+    size: String,
+    date: Option<String>,
+}
+
+impl Widget for &FlatView {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let size_width = self
+            .entries
+            .iter()
+            .map(|entry| entry.size.len() as u16)
+            .max()
+            .unwrap_or(0);
+
+        Report::new(
+            &self.list,
+            self.header(),
+            Color::Yellow,
+            &self.entries,
+            |entry, area, buf, style| {
+                let style = style.unwrap_or_else(|| Style::default().fg(colors::WHITE));
+
+                let desc = match &entry.date {
+                    Some(date) => format!(
+                        "{}{:>width$}",
+                        date,
+                        entry.size,
+                        width = (FlatView::SPACING + size_width) as usize
+                    ),
+                    None => format!("{:>width$}", entry.size, width = size_width as usize),
+                };
+
+                let desc_width = UnicodeWidthStr::width(desc.as_str()) as u16;
+                let desc_x = area.right().saturating_sub(desc_width);
+                let max_path_width = desc_x
+                    .saturating_sub(area.x)
+                    .saturating_sub(FlatView::SPACING);
+
+                buf.set_stringn(area.x, area.y, &entry.path, max_path_width as usize, style);
+
+                if desc_x >= area.x + FlatView::SPACING {
+                    buf.set_string(desc_x, area.y, desc, style);
+                }
+            },
+        )
+        .render(area, buf);
+    }
+}
@@ -0,0 +1,126 @@
+use super::report::{Report, ReportList};
+use crate::{
+    archive::{
+        grep::{Grepper, LineMatch},
+        NodeID,
+    },
+    ui::colors,
+    util::number,
+};
+use crossterm::event::KeyCode;
+use std::sync::{atomic::Ordering, Arc};
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+
+/// Scrollable list of lines that matched a content search, which fills in while the search is running.
+pub struct GrepReport {
+    pub grepper: Arc<Grepper>,
+    list: ReportList,
+}
+
+impl GrepReport {
+    const SEPARATOR: &'static str = ":";
+
+    pub fn new(grepper: Arc<Grepper>) -> Self {
+        Self {
+            grepper,
+            list: ReportList::highlighted(),
+        }
+    }
+
+    /// Returns the ID of the entry the highlighted line is in, or None if nothing has matched.
+    pub fn highlighted(&self) -> Option<NodeID> {
+        self.grepper
+            .matches()
+            .get(self.list.index())
+            .map(|line_match| line_match.id)
+    }
+
+    pub fn process_key(&mut self, key: KeyCode) {
+        let len = self.grepper.matches().len();
+        self.list.process_key(key, len);
+    }
+
+    fn header(&self, num_matches: usize) -> String {
+        let matches = match num_matches {
+            1 => "1 Match".into(),
+            num => format!("{} Matches", number::grouped(num as u64)),
+        };
+
+        if self.grepper.is_cancelled() {
+            format!("Search Cancelled After {}", matches)
+        } else if !self.grepper.is_finished() {
+            format!("Searching, {} So Far", matches)
+        } else if num_matches == 0 {
+            "No Matches Found".into()
+        } else {
+            format!("{} Found", matches)
+        }
+    }
+
+    fn detail(&self, num_matches: usize) -> String {
+        let mut detail = match self.grepper.skipped.load(Ordering::Relaxed) {
+            0 => String::new(),
+            1 => "1 file was skipped for being binary, too large, or unreadable. ".into(),
+            num => format!(
+                "{} files were skipped for being binary, too large, or unreadable. ",
+                number::grouped(u64::from(num))
+            ),
+        };
+
+        if num_matches >= Grepper::MAX_MATCHES {
+            detail.push_str("Searching stopped at the match limit. ");
+        }
+
+        if self.grepper.is_finished() {
+            detail.push_str("Press Enter to go to the highlighted match or Esc to go back");
+        } else {
+            detail.push_str("Press Esc to stop searching");
+        }
+
+        detail
+    }
+
+    fn render_match(line_match: &LineMatch, area: Rect, buf: &mut Buffer, style: Option<Style>) {
+        let (path_style, line_style) = match style {
+            Some(style) => (style, style),
+            None => (
+                Style::default().fg(Color::Cyan),
+                Style::default().fg(colors::WHITE),
+            ),
+        };
+
+        let location = format!(
+            "{}{}{}{}",
+            line_match.path.display(),
+            Self::SEPARATOR,
+            line_match.line_num,
+            Self::SEPARATOR
+        );
+
+        let (x, _) = buf.set_stringn(area.x, area.y, location, area.width as usize, path_style);
+        let remaining = area.right().saturating_sub(x) as usize;
+
+        buf.set_stringn(x, area.y, &line_match.line, remaining, line_style);
+    }
+}
+
+impl Widget for &GrepReport {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let matches = self.grepper.matches();
+
+        Report::new(
+            &self.list,
+            self.header(matches.len()),
+            Color::Cyan,
+            &matches,
+            GrepReport::render_match,
+        )
+        .detail(self.detail(matches.len()))
+        .render(area, buf);
+    }
+}
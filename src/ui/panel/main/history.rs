@@ -0,0 +1,192 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Paths that were previously extracted to, which are kept across runs.
+///
+/// The most recently used path is last.
+pub struct PathHistory {
+    paths: Vec<String>,
+    file: Option<PathBuf>,
+}
+
+impl PathHistory {
+    const MAX_ENTRIES: usize = 100;
+
+    /// Load the history from the user's data directory.
+    ///
+    /// A history that can't be read is treated as empty, as it's only a convenience.
+    pub fn load(name: &str) -> Self {
+        let file = data_dir().map(|dir| dir.join(name));
+
+        let paths = file
+            .as_ref()
+            .and_then(|file| fs::read_to_string(file).ok())
+            .map(|contents| {
+                contents
+                    .lines()
+                    .filter(|line| !line.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { paths, file }
+    }
+
+    /// Returns every path in the history from oldest to newest.
+    #[inline(always)]
+    pub fn paths(&self) -> &[String] {
+        &self.paths
+    }
+
+    /// Move the given `path` to the end of the history and save it.
+    ///
+    /// Relative paths are stored from the current directory so they still point to the same place in later runs.
+    pub fn push<P>(&mut self, path: P) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let path = match absolute(path.as_ref()) {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        self.paths.retain(|existing| *existing != path);
+        self.paths.push(path);
+
+        let excess = self.paths.len().saturating_sub(Self::MAX_ENTRIES);
+        self.paths.drain(..excess);
+
+        match &self.file {
+            Some(file) => write_lines(file, self.paths.iter().cloned()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Archives that were previously opened and when, which are kept across runs so they can be picked from the start
+/// screen.
+///
+/// The most recently opened archive is last.
+pub struct ArchiveHistory {
+    archives: Vec<RecentArchive>,
+    file: Option<PathBuf>,
+}
+
+impl ArchiveHistory {
+    const FILE_NAME: &'static str = "archive_history";
+    const MAX_ENTRIES: usize = 50;
+
+    /// Load the history from the user's data directory.
+    ///
+    /// A history that can't be read is treated as empty, as it's only a convenience.
+    pub fn load() -> Self {
+        let file = data_dir().map(|dir| dir.join(Self::FILE_NAME));
+
+        let archives = file
+            .as_ref()
+            .and_then(|file| fs::read_to_string(file).ok())
+            .map(|contents| contents.lines().filter_map(RecentArchive::parse).collect())
+            .unwrap_or_default();
+
+        Self { archives, file }
+    }
+
+    /// Returns every archive in the history from the least to the most recently opened.
+    #[inline(always)]
+    pub fn archives(&self) -> &[RecentArchive] {
+        &self.archives
+    }
+
+    /// Record that the archive at the given `path` was just opened and save the history.
+    ///
+    /// Relative paths are stored from the current directory so they still point to the same place in later runs.
+    pub fn push<P>(&mut self, path: P) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let path = match absolute(path.as_ref()) {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        self.archives.retain(|existing| existing.path != path);
+        self.archives.push(RecentArchive {
+            path,
+            opened_at: Utc::now().timestamp(),
+        });
+
+        let excess = self.archives.len().saturating_sub(Self::MAX_ENTRIES);
+        self.archives.drain(..excess);
+
+        match &self.file {
+            Some(file) => write_lines(file, self.archives.iter().map(RecentArchive::line)),
+            None => Ok(()),
+        }
+    }
+}
+
+pub struct RecentArchive {
+    pub path: String,
+    /// When the archive was last opened, in seconds since the Unix epoch.
+    pub opened_at: i64,
+}
+
+impl RecentArchive {
+    /// Parse a line of the history file, which is the time the archive was opened followed by its path.
+    fn parse(line: &str) -> Option<Self> {
+        let (opened_at, path) = line.split_once(' ')?;
+
+        Some(Self {
+            path: path.to_string(),
+            opened_at: opened_at.parse().ok()?,
+        })
+    }
+
+    fn line(&self) -> String {
+        format!("{} {}", self.opened_at, self.path)
+    }
+}
+
+/// Returns the directory that data kept across runs is stored in.
+pub fn data_dir() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => Path::new(&env::var_os("HOME")?).join(".local/share"),
+    };
+
+    Some(dir.join(env!("CARGO_PKG_NAME")))
+}
+
+/// Returns the given `path` joined to the current directory if it's relative, or None if it can't be stored in a
+/// history file.
+pub fn absolute(path: &Path) -> Option<String> {
+    let path = match env::current_dir() {
+        Ok(cur_dir) if path.is_relative() => cur_dir.join(path),
+        _ => path.to_path_buf(),
+    };
+
+    // Each path is stored on its own line
+    match path.to_str() {
+        Some(path) if !path.contains('\n') => Some(path.to_string()),
+        _ => None,
+    }
+}
+
+/// Write each of the given `lines` to `file`, creating the directory it's in if it doesn't exist yet.
+pub fn write_lines<I>(file: &Path, lines: I) -> Result<()>
+where
+    I: Iterator<Item = String>,
+{
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir).with_context(|| anyhow!("failed to create {}", dir.display()))?;
+    }
+
+    let mut contents = lines.collect::<Vec<_>>().join("\n");
+    contents.push('\n');
+
+    fs::write(file, contents).with_context(|| anyhow!("failed to write {}", file.display()))
+}
@@ -0,0 +1,263 @@
+use super::key_hints::CancelHint;
+use super::progress_bar::ProgressBar;
+use crate::{
+    archive::extract::{Extractor, Failure},
+    ui::{
+        colors,
+        util::{pad_rect_horiz, SimpleText},
+    },
+    util::{duration, size},
+};
+use anyhow::Error;
+use crossterm::event::KeyCode;
+use std::mem;
+use std::path::PathBuf;
+use std::sync::{atomic::Ordering, Arc};
+use tui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::Widget,
+};
+
+/// Extractions that run one after another in the background, in the order they were queued.
+pub struct JobQueue {
+    jobs: Vec<Job>,
+    index: usize,
+    /// Whether a task is currently working through the queue.
+    running: bool,
+    outcomes: Vec<JobOutcome>,
+    /// Whether the arrow keys pick which job to cancel instead of moving through the archive.
+    pub selecting: bool,
+}
+
+impl JobQueue {
+    /// The most jobs that are shown at once.
+    pub const MAX_VISIBLE: usize = 4;
+
+    pub fn new() -> Self {
+        Self {
+            jobs: Vec::new(),
+            index: 0,
+            running: false,
+            outcomes: Vec::new(),
+            selecting: false,
+        }
+    }
+
+    /// Add an extraction to the end of the queue.
+    ///
+    /// Returns true if nothing is working through the queue yet, in which case the caller should
+    /// start doing so by taking jobs with [`JobQueue::next`].
+    pub fn push(&mut self, extractor: Arc<Extractor>, path: PathBuf) -> bool {
+        self.jobs.push(Job {
+            extractor,
+            path,
+            started: false,
+        });
+
+        !mem::replace(&mut self.running, true)
+    }
+
+    /// Start the next job that hasn't been started yet.
+    ///
+    /// Returns None if there are no jobs left, after which the queue has to be worked through again
+    /// once a new job is pushed.
+    pub fn next(&mut self) -> Option<(Arc<Extractor>, PathBuf)> {
+        if let Some(job) = self.jobs.iter_mut().find(|job| !job.started) {
+            job.started = true;
+            return Some((Arc::clone(&job.extractor), job.path.clone()));
+        }
+
+        self.running = false;
+        None
+    }
+
+    /// Remove the job that belongs to the given `extractor` and hold on to the `outcome` until it can be shown.
+    pub fn finish(&mut self, extractor: &Arc<Extractor>, outcome: Option<JobOutcome>) {
+        self.jobs
+            .retain(|job| !Arc::ptr_eq(&job.extractor, extractor));
+        self.index = self.index.min(self.jobs.len().saturating_sub(1));
+
+        if self.jobs.is_empty() {
+            self.selecting = false;
+        }
+
+        self.outcomes.extend(outcome);
+    }
+
+    /// Take the oldest outcome of a finished job that hasn't been shown yet.
+    pub fn take_outcome(&mut self) -> Option<JobOutcome> {
+        if self.outcomes.is_empty() {
+            return None;
+        }
+
+        Some(self.outcomes.remove(0))
+    }
+
+    /// Cancel the highlighted job, or remove it from the queue if it hasn't started yet.
+    pub fn cancel_highlighted(&mut self) {
+        match self.jobs.get(self.index) {
+            Some(job) if job.started => job.extractor.cancel(),
+            Some(_) => {
+                self.jobs.remove(self.index);
+                self.index = self.index.min(self.jobs.len().saturating_sub(1));
+
+                if self.jobs.is_empty() {
+                    self.selecting = false;
+                }
+            }
+            None => (),
+        }
+    }
+
+    /// Cancel every job that has started and remove the rest from the queue.
+    pub fn cancel_all(&mut self) {
+        self.jobs.retain(|job| {
+            if job.started {
+                job.extractor.cancel();
+            }
+
+            job.started
+        });
+
+        self.index = self.index.min(self.jobs.len().saturating_sub(1));
+
+        if self.jobs.is_empty() {
+            self.selecting = false;
+        }
+    }
+
+    /// Returns every job in the order they were queued.
+    pub fn iter(&self) -> impl Iterator<Item = &Job> {
+        self.jobs.iter()
+    }
+
+    pub fn process_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Up => self.index = self.index.saturating_sub(1),
+            KeyCode::Down => {
+                self.index = (self.index + 1).min(self.jobs.len().saturating_sub(1));
+            }
+            _ => (),
+        }
+    }
+
+    /// Returns the jobs that should be shown, along with whether each one is highlighted.
+    pub fn visible(&self) -> impl Iterator<Item = (&Job, bool)> {
+        // Keep the highlighted job in view
+        let scroll = (self.index + 1).saturating_sub(Self::MAX_VISIBLE);
+        let index = self.index;
+
+        self.jobs
+            .iter()
+            .enumerate()
+            .skip(scroll)
+            .take(Self::MAX_VISIBLE)
+            .map(move |(i, job)| (job, i == index))
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+}
+
+pub struct Job {
+    pub extractor: Arc<Extractor>,
+    pub path: PathBuf,
+    pub started: bool,
+}
+
+/// The result of a finished job that needs the user's attention.
+pub enum JobOutcome {
+    Failed(Error),
+    PartlyFailed(Vec<Failure>),
+}
+
+/// A row for each visible job in a [`JobQueue`] that shows how far along it is.
+pub struct JobList<'a> {
+    pub queue: &'a JobQueue,
+    pub cancel_key: &'static str,
+}
+
+impl<'a> JobList<'a> {
+    /// Returns the number of rows needed to show the visible jobs.
+    pub fn height(&self) -> u16 {
+        self.queue.len().min(JobQueue::MAX_VISIBLE) as u16
+    }
+}
+
+impl<'a> Widget for JobList<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let cancel_hint_width = CancelHint {
+            key: self.cancel_key,
+        }
+        .width();
+
+        for (i, (job, highlighted)) in self.queue.visible().enumerate() {
+            let row = Rect {
+                y: area.y + i as u16,
+                height: 1,
+                ..area
+            };
+
+            if row.y >= area.bottom() {
+                break;
+            }
+
+            let layout = Layout::default()
+                .constraints([
+                    Constraint::Percentage(30),
+                    Constraint::Min(1),
+                    Constraint::Length(cancel_hint_width),
+                ])
+                .direction(Direction::Horizontal)
+                .split(row);
+
+            let label_style = if highlighted && self.queue.selecting {
+                Style::default()
+                    .fg(colors::BLACK)
+                    .bg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(colors::WHITE)
+            };
+
+            let label = SimpleText::new(job.path.to_string_lossy()).style(label_style);
+            label.render(pad_rect_horiz(layout[0], 1), buf);
+
+            let extractor = &job.extractor;
+
+            let mut progress = ProgressBar::with_counts(
+                extractor.extracted.load(Ordering::Relaxed),
+                extractor.total_to_extract,
+            );
+
+            if !job.started {
+                progress = progress.detail("queued".into());
+            } else if let Some(throughput) = extractor.throughput() {
+                progress = progress.detail(format!(
+                    "{}/s, ETA {}",
+                    size::formatted_compact(throughput.bytes_per_sec),
+                    duration::formatted_compact(throughput.remaining)
+                ));
+            }
+
+            progress.render(layout[1], buf);
+
+            if highlighted && self.queue.selecting {
+                let cancel_hint = CancelHint {
+                    key: self.cancel_key,
+                };
+
+                cancel_hint.render(layout[2], buf);
+            }
+        }
+    }
+}
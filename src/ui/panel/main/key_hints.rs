@@ -12,6 +12,7 @@ use tui::{
 pub struct KeyHints {
     pub extract_to_dir_key: char,
     pub extract_to_cwd_key: char,
+    pub extract_dir_here_key: char,
     pub mount_state: MountState,
 }
 
@@ -24,9 +25,18 @@ impl KeyHints {
 
         let extract_all = KeyHint::with_char(self.extract_to_dir_key, "to dir", style);
         let extract_to_cwd = KeyHint::with_char(self.extract_to_cwd_key, "to cwd", style);
-
-        let extract_items =
-            text_fragments![style, "Extract [", extract_all, ", ", extract_to_cwd, ']'];
+        let extract_dir_here = KeyHint::with_char(self.extract_dir_here_key, "this dir", style);
+
+        let extract_items = text_fragments![
+            style,
+            "Extract [",
+            extract_all,
+            ", ",
+            extract_to_cwd,
+            ", ",
+            extract_dir_here,
+            ']'
+        ];
 
         let extract_keys = TextFragments::new(&extract_items);
         extract_keys.render(area, buf);
@@ -38,7 +48,6 @@ impl KeyHints {
                 let style = Style::default().fg(Self::MOUNTED_COLOR);
 
                 let unmount_hint = KeyHint::with_str(unmount, "unmount", style);
-
                 let mount_items = text_fragments![style, "Mount [", unmount_hint, ']'];
 
                 let mount_keys = TextFragments::new(&mount_items).alignment(Alignment::Right);
@@ -59,6 +68,7 @@ impl KeyHints {
                 let mount_keys = TextFragments::new(&mount_items).alignment(Alignment::Right);
                 mount_keys.render(area, buf);
             }
+            MountState::Unavailable => (),
         }
     }
 }
@@ -75,6 +85,35 @@ impl Widget for KeyHints {
     }
 }
 
+/// Hint for the key that cancels the running operation.
+pub struct CancelHint {
+    pub key: &'static str,
+}
+
+impl CancelHint {
+    const COLOR: Color = Color::DarkGray;
+
+    /// Returns the number of columns needed to render the hint.
+    pub fn width(&self) -> u16 {
+        let style = Style::default();
+        let hint = KeyHint::with_str(self.key, "cancel", style);
+
+        2 + hint.total_fragments_len()
+    }
+}
+
+impl Widget for CancelHint {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let style = Style::default().fg(Self::COLOR);
+
+        let cancel_hint = KeyHint::with_str(self.key, "cancel", style);
+        let items = text_fragments![style, '[', cancel_hint, ']'];
+
+        let cancel_keys = TextFragments::new(&items).alignment(Alignment::Right);
+        cancel_keys.render(area, buf);
+    }
+}
+
 struct KeyHint<'a> {
     items: [Fragment<'a>; 3],
 }
@@ -117,4 +156,6 @@ pub enum MountState {
         mount_at_tmp: char,
         mount_at_dir: char,
     },
+    /// Archives can't be mounted, so no hint is shown.
+    Unavailable,
 }
@@ -0,0 +1,226 @@
+use super::report::{Report, ReportList};
+use crate::{
+    archive::{ArchiveEntries, EntryProperties, NodeID},
+    ui::colors,
+    util::{number, size},
+};
+use crossterm::event::KeyCode;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Scrollable list of the largest files anywhere in the archive, to find out what's taking up most of its space.
+pub struct LargestFiles {
+    /// Every file in the archive, from the largest to the smallest by what they're ranked by.
+    files: Vec<LargeFile>,
+    /// Whether files are ranked by their size in the archive instead of their uncompressed size.
+    by_compressed: bool,
+    total_raw: u64,
+    total_compressed: u64,
+    list: ReportList,
+}
+
+impl LargestFiles {
+    /// The most files that are listed.
+    const LIMIT: usize = 100;
+
+    const RANK_KEY: char = 'o';
+
+    /// The space between the path of a file and its sizes.
+    const SPACING: u16 = 2;
+
+    pub fn new(files: &ArchiveEntries) -> Self {
+        let files = files
+            .children_iter(&[NodeID::first()])
+            .filter_map(|(id, entry, path)| match &entry.props {
+                EntryProperties::File(props) => Some(LargeFile {
+                    id,
+                    rank: 0,
+                    path: path.to_string_lossy().into_owned(),
+                    raw_size: props.raw_size_bytes,
+                    compressed_size: props.compressed_size_bytes,
+                }),
+                EntryProperties::Directory => None,
+            })
+            .collect::<Vec<_>>();
+
+        let total_raw = files.iter().map(|file| file.raw_size).sum();
+        let total_compressed = files.iter().map(|file| file.compressed_size).sum();
+
+        let mut report = Self {
+            files,
+            by_compressed: false,
+            total_raw,
+            total_compressed,
+            list: ReportList::highlighted(),
+        };
+
+        report.rank(false);
+        report
+    }
+
+    /// Order the files from the largest to the smallest by their size in the archive if `by_compressed` is true, or
+    /// by their uncompressed size otherwise.
+    fn rank(&mut self, by_compressed: bool) {
+        self.files.sort_unstable_by(|x, y| {
+            y.size(by_compressed)
+                .cmp(&x.size(by_compressed))
+                .then_with(|| x.path.cmp(&y.path))
+        });
+
+        for (i, file) in self.files.iter_mut().enumerate() {
+            file.rank = i + 1;
+        }
+
+        self.by_compressed = by_compressed;
+        self.list.reset();
+    }
+
+    /// Returns the files that are listed.
+    fn listed(&self) -> &[LargeFile] {
+        &self.files[..self.files.len().min(Self::LIMIT)]
+    }
+
+    /// Returns the ID of the highlighted file, or None if the archive has no files.
+    pub fn highlighted(&self) -> Option<NodeID> {
+        self.listed().get(self.list.index()).map(|file| file.id)
+    }
+
+    pub fn process_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Char(Self::RANK_KEY) => self.rank(!self.by_compressed),
+            key => self.list.process_key(key, self.listed().len()),
+        }
+    }
+
+    fn header(&self) -> String {
+        let measure = if self.by_compressed {
+            "Compressed Size"
+        } else {
+            "Size"
+        };
+
+        match self.listed().len() {
+            0 => "No Files".into(),
+            1 => format!("Largest File By {}", measure),
+            num => format!(
+                "{} Largest Files By {}",
+                number::grouped(num as u64),
+                measure
+            ),
+        }
+    }
+
+    fn hint(&self) -> &'static str {
+        if self.by_compressed {
+            "Press o to rank by size, Enter to go to the highlighted file, or Esc to go back"
+        } else {
+            "Press o to rank by compressed size, Enter to go to the highlighted file, or Esc to go back"
+        }
+    }
+
+    /// Returns the uncompressed size of the given `file`, its size in the archive, and how much of the archive it
+    /// makes up by what files are ranked by.
+    fn sizes(&self, file: &LargeFile) -> [String; 3] {
+        [
+            size::formatted(file.raw_size),
+            size::formatted(file.compressed_size),
+            self.share(file),
+        ]
+    }
+
+    /// Returns how much of the archive the given `file` makes up by what files are ranked by.
+    fn share(&self, file: &LargeFile) -> String {
+        let total = if self.by_compressed {
+            self.total_compressed
+        } else {
+            self.total_raw
+        };
+
+        if total == 0 {
+            return "0%".into();
+        }
+
+        let percent = file.size(self.by_compressed) as f64 / total as f64 * 100.0;
+        format!("{:.1}%", percent)
+    }
+}
+
+struct LargeFile {
+    id: NodeID,
+    /// The file's position in the list, starting from 1.
+    rank: usize,
+    path: String,
+    raw_size: u64,
+    compressed_size: u64,
+}
+
+impl LargeFile {
+    fn size(&self, compressed: bool) -> u64 {
+        if compressed {
+            self.compressed_size
+        } else {
+            self.raw_size
+        }
+    }
+}
+
+impl Widget for &LargestFiles {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let listed = self.listed();
+        let rank_width = listed.len().to_string().len();
+
+        // Each size gets its own column so they line up
+        let mut widths = [0; 3];
+
+        for file in listed {
+            for (width, size) in widths.iter_mut().zip(&self.sizes(file)) {
+                *width = size.width().max(*width);
+            }
+        }
+
+        let desc_width = widths.iter().sum::<usize>() as u16 + 2 * LargestFiles::SPACING;
+
+        Report::new(
+            &self.list,
+            self.header(),
+            Color::Magenta,
+            listed,
+            |file, area, buf, style| {
+                let style = style.unwrap_or_else(|| Style::default().fg(colors::WHITE));
+
+                let path = format!("{:>width$}. {}", file.rank, file.path, width = rank_width);
+
+                let desc_x = area.right().saturating_sub(desc_width);
+                let max_path_width = desc_x
+                    .saturating_sub(area.x)
+                    .saturating_sub(LargestFiles::SPACING);
+
+                buf.set_stringn(area.x, area.y, path, max_path_width as usize, style);
+
+                if desc_x >= area.x + LargestFiles::SPACING {
+                    let spacing = LargestFiles::SPACING as usize;
+                    let sizes = self.sizes(file);
+
+                    let desc = format!(
+                        "{:>raw$}{:>compressed$}{:>share$}",
+                        sizes[0],
+                        sizes[1],
+                        sizes[2],
+                        raw = widths[0],
+                        compressed = widths[1] + spacing,
+                        share = widths[2] + spacing,
+                    );
+
+                    buf.set_string(desc_x, area.y, desc, style);
+                }
+            },
+        )
+        .detail(self.hint())
+        .render(area, buf);
+    }
+}
@@ -1,28 +1,93 @@
+mod command_palette;
+mod compare_report;
+mod duplicates_report;
+mod entry_info;
 mod entry_stats;
+mod error_report;
+mod failure_report;
+mod find_results;
+mod flat_view;
+mod grep_report;
+mod history;
+mod jobs;
 mod key_hints;
+mod largest_files;
+mod mount_changes;
+mod mount_status;
+mod opener;
+mod path_bar;
+mod preview;
 mod progress_bar;
+mod rename_preview;
+mod report;
+mod session;
+mod staging;
+mod type_stats;
 
-use self::{entry_stats::EntryStats, key_hints::KeyHints};
+use self::{
+    command_palette::{CommandPalette, PaletteResult},
+    compare_report::CompareReport,
+    duplicates_report::DuplicatesReport,
+    entry_info::EntryInfo,
+    entry_stats::EntryStats,
+    error_report::ErrorReport,
+    failure_report::FailureReport,
+    find_results::FindResults,
+    flat_view::FlatView,
+    grep_report::GrepReport,
+    history::PathHistory,
+    jobs::{JobList, JobOutcome, JobQueue},
+    key_hints::KeyHints,
+    largest_files::LargestFiles,
+    mount_changes::MountChangesReport,
+    mount_status::MountStatus,
+    opener::Opener,
+    path_bar::PathBar,
+    preview::{Preview, PreviewCache},
+    rename_preview::RenamePreview,
+    session::Session,
+    staging::Staging,
+    type_stats::TypeStats,
+};
 use super::files::{PathViewer, PathViewerResult};
-use super::{Backend, Draw, Frame, KeyCode, Panel, Rect};
+use super::{Backend, Command, Draw, Frame, KeyCode, Panel, Rect};
 use crate::{
     archive::{
-        extract::Extractor, mount::ArchiveMountSession, mount::MountedArchive, Archive, NodeID,
+        compare::Comparer,
+        duplicates::DuplicateFinder,
+        extract::Extractor,
+        filter::{PathFilter, PathPattern},
+        grep::Grepper,
+        mount::{
+            self, ArchiveMountSession, MountChanges, MountUser, MountedArchive, SpaceEstimate,
+        },
+        rename::RenameRule,
+        Archive, NodeID,
     },
     ui::{
+        event::MouseAction,
+        notify::{Flash, JobKind, PendingNotify, StatusLine, StatusQueue},
         util::{
+            completion::CompletionPopup,
             input::{Input, InputResult, InputState},
             pad_rect_horiz, SimpleText,
         },
-        InputLock,
+        Config, Graphics, InputLock,
     },
+    util::{number, size},
 };
-use anyhow::{Context, Error, Result};
+use anyhow::{anyhow, Context, Error, Result};
 use async_std::task;
+pub use history::{ArchiveHistory, RecentArchive};
 use key_hints::MountState;
-use parking_lot::Mutex;
+use parking_lot::{Mutex, MutexGuard};
 use progress_bar::ProgressBar;
-use smallvec::SmallVec;
+use regex::Regex;
+use smallvec::{smallvec, SmallVec};
+use std::borrow::Cow;
+use std::env;
+use std::mem;
+use std::path::{Path, PathBuf};
 use std::sync::{atomic::Ordering, Arc};
 use tui::{
     layout::{Alignment, Constraint, Direction, Layout},
@@ -30,62 +95,1453 @@ use tui::{
     widgets::{Paragraph, Wrap},
 };
 
-pub struct MainPanel<'a> {
-    archive: Arc<Archive>,
-    path_viewer: PathViewer,
-    entry_stats: EntryStats<'a>,
-    state: Arc<Mutex<PanelState>>,
-    mount_session: Option<ArchiveMountSession>,
-}
+pub struct MainPanel<'a> {
+    archive: Arc<Archive>,
+    path_viewer: PathViewer,
+    entry_stats: EntryStats<'a>,
+    state: Arc<Mutex<PanelState>>,
+    mount_session: Option<ArchiveMountSession>,
+    /// Whether FUSE is available to mount the archive with.
+    can_mount: bool,
+    config: Config,
+    pending_notify: Arc<Mutex<PendingNotify>>,
+    /// Messages about things that happened, which background jobs can add to as well.
+    status: Arc<Mutex<StatusQueue>>,
+    staging: Staging,
+    opener: Opener,
+    jobs: Arc<Mutex<JobQueue>>,
+    extract_history: PathHistory,
+    /// A panel the user asked to open in a new tab, which is waiting to be taken by the UI.
+    new_tab: Option<Box<MainPanel<'a>>>,
+    /// Text the user asked to copy to the clipboard, which is waiting to be taken by the UI.
+    clipboard: Option<String>,
+    /// The content of the highlighted file and the ones highlighted before it.
+    previews: PreviewCache,
+    /// Where the terminal should draw the previewed image, if one was shown in the last draw.
+    graphic_area: Option<Rect>,
+}
+
+impl<'a> MainPanel<'a> {
+    const EXTRACT_TO_DIR_KEY: char = 's';
+    const EXTRACT_TO_CWD_KEY: char = 'e';
+    const EXTRACT_DIR_HERE_KEY: char = 'x';
+    const MOUNT_AT_DIR_KEY: char = 'l';
+    const MOUNT_AT_TMP_KEY: char = 'm';
+    const COMPARE_KEY: char = 'c';
+    const RENAME_KEY: char = 'r';
+    const FILTER_KEY: char = 'g';
+    const RELOAD_KEY: char = 'R';
+    const PIN_KEY: char = 'p';
+    const VIEW_PINNED_KEY: char = 'P';
+    const OPEN_KEY: char = 'v';
+    const JOBS_KEY: char = 'j';
+    const SEARCH_KEY: char = '/';
+    const NEXT_MATCH_KEY: char = 'n';
+    const PREV_MATCH_KEY: char = 'N';
+    const NARROW_KEY: char = '|';
+    const FIND_KEY: char = 'f';
+    const GO_TO_KEY: char = 'J';
+    const GREP_KEY: char = 'F';
+    const FLAT_VIEW_KEY: char = 'a';
+    const LARGEST_FILES_KEY: char = 'Z';
+    const TYPE_STATS_KEY: char = 'K';
+    const DUPLICATES_KEY: char = 'D';
+    const NEW_TAB_KEY: char = 't';
+    const OPEN_TAB_KEY: char = 'T';
+    const SELECT_MATCHING_KEY: char = '+';
+    const INFO_KEY: char = 'i';
+    const COMMAND_PALETTE_KEY: char = ':';
+    const COPY_PATH_KEY: char = 'y';
+    const COPY_MOUNT_PATH_KEY: char = 'Y';
+    const COPY_NAME_KEY: char = 'b';
+    const HEX_KEY: char = 'H';
+    const ENCODING_KEY: char = 'C';
+    const UNMOUNT_KEY: KeyCodeDesc = KeyCodeDesc::new(KeyCode::Esc, "Esc");
+    const CANCEL_KEY: KeyCodeDesc = KeyCodeDesc::new(KeyCode::Esc, "Esc");
+
+    const COMMANDS: &'static [Command] = &[
+        Command::char("extract to directory", Self::EXTRACT_TO_DIR_KEY),
+        Command::char("extract to current directory", Self::EXTRACT_TO_CWD_KEY),
+        Command::char("extract viewed directory", Self::EXTRACT_DIR_HERE_KEY),
+        Command::char("mount at directory", Self::MOUNT_AT_DIR_KEY),
+        Command::char("mount in temporary directory", Self::MOUNT_AT_TMP_KEY),
+        Command::new("unmount", Self::UNMOUNT_KEY.key),
+        Command::char("compare with directory", Self::COMPARE_KEY),
+        Command::char("set rename rule", Self::RENAME_KEY),
+        Command::char("set extract filter", Self::FILTER_KEY),
+        Command::char("reload archive", Self::RELOAD_KEY),
+        Command::char("pin selected entries", Self::PIN_KEY),
+        Command::char("view pinned entries", Self::VIEW_PINNED_KEY),
+        Command::char("open with default program", Self::OPEN_KEY),
+        Command::char("pick job", Self::JOBS_KEY),
+        Command::char("search viewed directory", Self::SEARCH_KEY),
+        Command::char("filter viewed directory", Self::NARROW_KEY),
+        Command::char("find in archive", Self::FIND_KEY),
+        Command::char("go to path", Self::GO_TO_KEY),
+        Command::char("search contents", Self::GREP_KEY),
+        Command::char("list every file", Self::FLAT_VIEW_KEY),
+        Command::char("list largest files", Self::LARGEST_FILES_KEY),
+        Command::char("show file type statistics", Self::TYPE_STATS_KEY),
+        Command::char("find duplicate files", Self::DUPLICATES_KEY),
+        Command::char("new tab here", Self::NEW_TAB_KEY),
+        Command::char("open archive in new tab", Self::OPEN_TAB_KEY),
+        Command::char("select matching", Self::SELECT_MATCHING_KEY),
+        Command::char("show entry info", Self::INFO_KEY),
+        Command::char("copy path in archive", Self::COPY_PATH_KEY),
+        Command::char("copy path in mount", Self::COPY_MOUNT_PATH_KEY),
+        Command::char("copy name", Self::COPY_NAME_KEY),
+        Command::char("toggle hex view", Self::HEX_KEY),
+        Command::char("cycle text encoding", Self::ENCODING_KEY),
+    ];
+
+    /// Create a panel that views the given `archive`, starting where it was left off the last time it was viewed.
+    pub fn new(archive: Archive, config: Config) -> Result<Self> {
+        let mut panel = Self::with_archive(Arc::new(archive), config)?;
+
+        if let Some(session) = Session::load(panel.archive.path()) {
+            session.restore(&mut panel.path_viewer);
+            panel
+                .entry_stats
+                .update(&panel.path_viewer, panel.staging.len());
+        }
+
+        Ok(panel)
+    }
+
+    /// Remember where the archive was left off, so it's viewed from there the next time it's opened.
+    pub fn save_session(&self) -> Result<()> {
+        Session::of(&self.path_viewer).save(self.archive.path())
+    }
+
+    /// Create a panel that views the root directory of an `archive` that other panels may be viewing too.
+    fn with_archive(archive: Arc<Archive>, config: Config) -> Result<Self> {
+        let path_viewer = PathViewer::new(
+            archive.entries(),
+            NodeID::first(),
+            config.column_header,
+            config.date_column,
+            config.pane_ratios,
+            config.icons,
+            config.wrap,
+        )
+        .context("failed to view the root of the archive")?;
+
+        // The history is only a convenience, so failing to save it shouldn't stop the archive from being viewed
+        ArchiveHistory::load().push(archive.path()).ok();
+
+        let entry_stats = EntryStats::new(&path_viewer, 0);
+
+        Ok(Self {
+            archive,
+            path_viewer,
+            entry_stats,
+            state: Arc::new(Mutex::new(PanelState::default())),
+            mount_session: None,
+            can_mount: mount::is_available(),
+            config,
+            pending_notify: Arc::new(Mutex::new(PendingNotify::default())),
+            status: Arc::new(Mutex::new(StatusQueue::default())),
+            staging: Staging::new(),
+            opener: Opener::new(),
+            jobs: Arc::new(Mutex::new(JobQueue::new())),
+            extract_history: PathHistory::load("extract_history"),
+            new_tab: None,
+            clipboard: None,
+            previews: PreviewCache::new(),
+            graphic_area: None,
+        })
+    }
+
+    /// Respond to the given mouse `action` at the given screen position in the viewed directories.
+    pub fn process_mouse(&mut self, action: MouseAction, x: u16, y: u16) {
+        if self.staging.viewing || !matches!(*self.state.lock(), PanelState::Free) {
+            return;
+        }
+
+        match self.path_viewer.process_mouse(action, x, y) {
+            PathViewerResult::Ok => (),
+            PathViewerResult::PathSelected => self
+                .entry_stats
+                .update(&self.path_viewer, self.staging.len()),
+        }
+    }
+
+    /// Returns true if the terminal bell should be rung because a background job finished.
+    pub fn take_bell(&mut self) -> bool {
+        self.pending_notify.lock().take_bell()
+    }
+
+    /// Returns the panel the user asked to open in a new tab, if there is one.
+    pub fn take_new_tab(&mut self) -> Option<Self> {
+        self.new_tab.take().map(|panel| *panel)
+    }
+
+    /// Returns the text the user asked to copy to the clipboard, if there is any.
+    pub fn take_clipboard(&mut self) -> Option<String> {
+        self.clipboard.take()
+    }
+
+    /// Returns the previewed image and where the terminal should draw it, if one was shown in the last draw.
+    pub fn graphic_placement(&self) -> Option<(NodeID, Rect)> {
+        Some((self.previews.current()?.id(), self.graphic_area?))
+    }
+
+    /// Returns true if the previewed file is still being read.
+    pub fn is_loading(&self) -> bool {
+        self.previews.current().is_some_and(Preview::is_loading)
+    }
+
+    /// Scroll the previewed file up or down by a line, depending on whether `down` is true.
+    pub fn scroll_preview(&mut self, down: bool) {
+        if !matches!(*self.state.lock(), PanelState::Free) {
+            return;
+        }
+
+        if let Some(preview) = self.previews.current_mut() {
+            preview.scroll(down);
+        }
+    }
+
+    /// Apply `update` to the previewed file, if there is one.
+    fn update_preview(&mut self, update: fn(&mut Preview)) {
+        if let Some(preview) = self.previews.current_mut() {
+            update(preview);
+        }
+    }
+
+    /// Returns the escape sequences that make the terminal draw the previewed image where
+    /// [`MainPanel::graphic_placement`] says it goes.
+    pub fn graphic(&mut self) -> Option<&[u8]> {
+        let area = self.graphic_area?;
+        self.previews
+            .current_mut()?
+            .graphic(self.config.graphics, area)
+    }
+
+    /// Returns the text that names this panel in the tab bar, which is the archive and the viewed directory.
+    pub fn title(&self) -> String {
+        let archive_name = archive_name(&self.archive);
+        let directory = self.path_viewer.directory();
+
+        if directory == NodeID::first() {
+            return archive_name.into_owned();
+        }
+
+        format!(
+            "{}/{}",
+            archive_name,
+            self.path_viewer.files()[directory].name
+        )
+    }
+
+    /// Returns a description of each operation that quitting would cut short.
+    ///
+    /// The mount is only included if `include_mount` is true, since it may be kept mounted after quitting.
+    pub fn active_operations(&self, include_mount: bool) -> Vec<String> {
+        let mut operations = self
+            .jobs
+            .lock()
+            .iter()
+            .map(|job| {
+                let verb = if job.started {
+                    "extracting"
+                } else {
+                    "waiting to extract"
+                };
+
+                format!("{} to {}", verb, job.path.display())
+            })
+            .collect::<Vec<_>>();
+
+        match &self.mount_session {
+            Some(session) if include_mount => {
+                operations.push(format!("mounted at {}", session.path().display()));
+            }
+            _ => (),
+        }
+
+        operations
+    }
+
+    /// Returns true if any extraction is queued or running.
+    pub fn has_jobs(&self) -> bool {
+        !self.jobs.lock().is_empty()
+    }
+
+    /// Cancel every running extraction and remove the queued ones.
+    pub fn cancel_jobs(&self) {
+        self.jobs.lock().cancel_all();
+    }
+
+    /// Open another tab that views the same archive at the same location as this one.
+    fn open_tab_here(&mut self) -> Result<()> {
+        let mut panel = Self::with_archive(Arc::clone(&self.archive), self.config.clone())?;
+
+        // Another tab may have reloaded the archive, which leaves this one viewing entries that no longer exist
+        if Arc::ptr_eq(
+            &panel.path_viewer.shared_files(),
+            &self.path_viewer.shared_files(),
+        ) {
+            panel.go_to_entry(Some(self.path_viewer.highlighted_id()));
+        }
+
+        self.new_tab = Some(Box::new(panel));
+        Ok(())
+    }
+
+    /// Open the archive at the given `path` in another tab.
+    fn open_tab(&mut self, path: &str) -> Result<()> {
+        let archive =
+            Archive::read(path).with_context(|| anyhow!("failed to read files from {}", path))?;
+
+        let panel = Self::new(archive, self.config.clone())?;
+        self.new_tab = Some(Box::new(panel));
+        Ok(())
+    }
+
+    /// Returns a command palette with every action that can be taken right now.
+    fn command_palette(&self) -> CommandPalette {
+        let mount_keys = [
+            KeyCode::Char(Self::MOUNT_AT_DIR_KEY),
+            KeyCode::Char(Self::MOUNT_AT_TMP_KEY),
+        ];
+
+        let commands = Self::COMMANDS
+            .iter()
+            .chain(PathViewer::commands())
+            .filter(|command| match command.key {
+                key if key == Self::UNMOUNT_KEY.key
+                    || key == KeyCode::Char(Self::COPY_MOUNT_PATH_KEY) =>
+                {
+                    self.mount_session.is_some()
+                }
+                key if mount_keys.contains(&key) => self.can_mount && self.mount_session.is_none(),
+                _ => true,
+            })
+            .copied()
+            .collect();
+
+        CommandPalette::new(commands)
+    }
+
+    /// Queue extracting the given `nodes` to `path`, or ask the user to confirm it first if it won't fit.
+    ///
+    /// If `path` is a file, the extraction would fail, so the user is asked where to extract to instead with the
+    /// given `retry` action and `path` already entered. The action should extract the same `nodes`.
+    ///
+    /// Returns the state the panel should be in afterwards.
+    fn start_extract(
+        &self,
+        nodes: SmallVec<[NodeID; 4]>,
+        path: PathBuf,
+        retry: InputAction,
+    ) -> PanelState {
+        if path.exists() && !path.is_dir() {
+            self.status
+                .lock()
+                .warn(format!("{} already exists as a file", path.display()));
+
+            let input = self.input_state(retry).prefilled(&path.to_string_lossy());
+
+            return PanelState::Input(input, retry);
+        }
+
+        let archive = Arc::clone(&self.archive);
+        let mut options = self.config.extract_options.clone();
+        options.skip_hidden = self.path_viewer.hides_hidden();
+
+        let extractor = match Extractor::prepare(archive, nodes, options) {
+            Ok(extractor) => extractor,
+            Err(err) => return PanelState::error(ErrorKind::Extract, err),
+        };
+
+        match extractor.space_check(&path) {
+            Ok(Some(estimate)) => PanelState::ConfirmExtract(Box::new(PendingExtract {
+                extractor,
+                path,
+                estimate,
+            })),
+            Ok(None) => {
+                self.queue_extract(extractor, path);
+                PanelState::Free
+            }
+            Err(err) => PanelState::error(ErrorKind::Extract, err),
+        }
+    }
+
+    /// Add the given `extractor` to the job queue and start working through it if nothing else is.
+    fn queue_extract(&self, extractor: Extractor, path: PathBuf) {
+        let should_run = self.jobs.lock().push(Arc::new(extractor), path);
+
+        if !should_run {
+            return;
+        }
+
+        let jobs = Arc::clone(&self.jobs);
+        let status = Arc::clone(&self.status);
+
+        let kind = if self.config.extract_options.verify {
+            JobKind::Verify
+        } else {
+            JobKind::Extract
+        };
+
+        self.spawn_job(kind, move || {
+            let mut any_finished = false;
+
+            loop {
+                // The queue can't stay locked while extracting, otherwise it couldn't be drawn
+                let next = jobs.lock().next();
+
+                let (extractor, path) = match next {
+                    Some(next) => next,
+                    None => return any_finished,
+                };
+
+                let dest = path.display().to_string();
+                let result = extractor.extract(path);
+                let failures = extractor.take_failures();
+
+                let outcome = match result {
+                    Ok(()) if failures.is_empty() => None,
+                    Ok(()) => Some(JobOutcome::PartlyFailed(failures)),
+                    Err(err) => Some(JobOutcome::Failed(err)),
+                };
+
+                let succeeded = outcome.is_none();
+                jobs.lock().finish(&extractor, outcome);
+
+                if extractor.is_cancelled() {
+                    continue;
+                }
+
+                // Failures are shown in full once nothing else is being shown
+                if succeeded {
+                    status
+                        .lock()
+                        .info(format!("finished extracting to {}", dest));
+                }
+
+                any_finished = true;
+            }
+        });
+    }
+
+    /// Run `job` in the background and notify the user once it returns the way they asked to be for its `kind`.
+    ///
+    /// `job` should return false if it didn't finish, like when it was cancelled, so the user isn't notified.
+    fn spawn_job<F>(&self, kind: JobKind, job: F)
+    where
+        F: FnOnce() -> bool + Send + 'static,
+    {
+        let pending_notify = Arc::clone(&self.pending_notify);
+        let notify = self.config.notifications.get(kind);
+
+        task::spawn(async move {
+            if job() {
+                pending_notify.lock().push(notify);
+            }
+        });
+    }
+
+    /// Notify the user that a job of the given `kind` finished without having to run in the background.
+    fn notify_finished(&self, kind: JobKind) {
+        self.pending_notify
+            .lock()
+            .push(self.config.notifications.get(kind));
+    }
+
+    fn compare_async(&self, nodes: SmallVec<[NodeID; 4]>, path: String) -> Arc<Comparer> {
+        let archive = Arc::clone(&self.archive);
+        let comparer = Arc::new(Comparer::prepare(archive, nodes));
+        let state = Arc::clone(&self.state);
+        let task_comparer = Arc::clone(&comparer);
+
+        self.spawn_job(JobKind::Compare, move || {
+            let result = task_comparer.compare(path);
+            let mut panel_state = state.lock();
+
+            *panel_state = match result {
+                Ok(differences) => PanelState::CompareReport(CompareReport::new(differences)),
+                Err(err) => PanelState::error(ErrorKind::Compare, err),
+            };
+
+            true
+        });
+
+        comparer
+    }
+
+    /// Search the contents of the given `nodes` for lines that match `pattern` in the background.
+    fn grep_async(&self, nodes: SmallVec<[NodeID; 4]>, pattern: Regex) -> Arc<Grepper> {
+        let archive = Arc::clone(&self.archive);
+        let grepper = Arc::new(Grepper::prepare(archive, nodes, pattern));
+        let state = Arc::clone(&self.state);
+        let task_grepper = Arc::clone(&grepper);
+
+        self.spawn_job(JobKind::Grep, move || {
+            if let Err(err) = task_grepper.grep() {
+                let mut state = state.lock();
+
+                // The search may have been closed and replaced with another one since then
+                if let PanelState::Grep(report) = &*state {
+                    if Arc::ptr_eq(&report.grepper, &task_grepper) {
+                        *state = PanelState::error(ErrorKind::Grep, err);
+                    }
+                }
+            }
+
+            !task_grepper.is_cancelled()
+        });
+
+        grepper
+    }
+
+    /// Find files with identical content anywhere in the archive in the background.
+    fn find_duplicates_async(&self) -> Arc<DuplicateFinder> {
+        let archive = Arc::clone(&self.archive);
+        let finder = Arc::new(DuplicateFinder::prepare(archive));
+        let state = Arc::clone(&self.state);
+        let task_finder = Arc::clone(&finder);
+
+        self.spawn_job(JobKind::Duplicates, move || {
+            if let Err(err) = task_finder.find() {
+                let mut state = state.lock();
+
+                // The report may have been closed and replaced with another one since then
+                if let PanelState::Duplicates(report) = &*state {
+                    if Arc::ptr_eq(&report.finder, &task_finder) {
+                        *state = PanelState::error(ErrorKind::Duplicates, err);
+                    }
+                }
+            }
+
+            !task_finder.is_cancelled()
+        });
+
+        finder
+    }
+
+    /// Returns the entries a content search should look through, which is the whole archive unless entries
+    /// were selected or pinned.
+    fn grep_nodes(&self) -> SmallVec<[NodeID; 4]> {
+        if self.staging.viewing || self.path_viewer.num_marked() > 0 {
+            self.target_nodes()
+        } else {
+            smallvec![NodeID::first()]
+        }
+    }
+
+    /// Mount the archive at the given `path`, or ask the user to confirm it first if it's on a tmpfs
+    /// that may run out of space.
+    ///
+    /// If `temp_dir` is true, the archive is mounted at a new directory inside of `path` instead.
+    ///
+    /// Returns the state the panel should be in afterwards.
+    fn start_mount(&mut self, path: PathBuf, temp_dir: bool) -> PanelState {
+        let prepared =
+            MountedArchive::new(&self.archive, self.config.mount_options).and_then(|mounted| {
+                let estimate = mounted.tmpfs_space_check(&path)?;
+                Ok((mounted, estimate))
+            });
+
+        match prepared {
+            Ok((mounted, Some(estimate))) => PanelState::ConfirmMount(Box::new(PendingMount {
+                mounted,
+                path,
+                temp_dir,
+                estimate,
+            })),
+            Ok((mounted, None)) => self.mount(mounted, &path, temp_dir),
+            Err(err) => PanelState::error(ErrorKind::Mount, err),
+        }
+    }
+
+    /// Extract the selected entries to the current directory without prompting for a path.
+    fn extract_to_cwd(&self) -> PanelState {
+        match env::current_dir() {
+            Ok(path) => self.start_extract(self.target_nodes(), path, InputAction::Extract),
+            Err(err) => PanelState::error(
+                ErrorKind::Extract,
+                Error::new(err).context("failed to get current directory"),
+            ),
+        }
+    }
+
+    /// Extract the viewed directory to the configured destination without prompting for a path.
+    ///
+    /// The root directory is extracted to a directory named after the archive.
+    fn extract_dir_here(&self) -> PanelState {
+        let directory = self.path_viewer.directory();
+        let mut path = self.config.extract_dest.clone();
+
+        if directory == NodeID::first() {
+            path.push(
+                self.archive
+                    .file_stem()
+                    .unwrap_or_else(|| "archive".as_ref()),
+            );
+        }
+
+        self.start_extract(smallvec![directory], path, InputAction::ExtractDir)
+    }
+
+    /// Open the highlighted file with the system's default program in the background.
+    fn open_highlighted(&self) {
+        let id = self.path_viewer.highlighted_id();
+        let files = self.path_viewer.shared_files();
+
+        if files[id].props.is_dir() {
+            return;
+        }
+
+        let archive = Arc::clone(&self.archive);
+        let path = self.opener.path_for(&files, id);
+        let state = Arc::clone(&self.state);
+
+        task::spawn(async move {
+            let result = Opener::open(&archive, &files, id, &path);
+            let mut state = state.lock();
+
+            // The program can stay open for a while, so don't interrupt anything that was started since then
+            if let (Err(err), PanelState::Free) = (result, &*state) {
+                *state = PanelState::error(ErrorKind::Open, err);
+            }
+        });
+    }
+
+    /// Returns the state that shows the details of the highlighted entry, or why they couldn't be read.
+    fn highlighted_info(&self) -> PanelState {
+        let files = self.path_viewer.files();
+
+        match EntryInfo::new(&self.archive, files, self.path_viewer.highlighted_id()) {
+            Ok(info) => PanelState::Info(info),
+            Err(err) => PanelState::error(ErrorKind::Info, err),
+        }
+    }
+
+    /// Copy the path or name of the highlighted entry to the clipboard, depending on which `key` was pressed.
+    fn copy_highlighted(&mut self, key: char) {
+        let files = self.path_viewer.files();
+        let id = self.path_viewer.highlighted_id();
+
+        let text = match (key, &self.mount_session) {
+            (Self::COPY_NAME_KEY, _) => files[id].name.clone(),
+            (Self::COPY_MOUNT_PATH_KEY, Some(session)) => {
+                session.path().join(files.path_of(id)).display().to_string()
+            }
+            (Self::COPY_MOUNT_PATH_KEY, None) => {
+                self.status.lock().warn("the archive isn't mounted");
+                return;
+            }
+            _ => files.path_of(id).display().to_string(),
+        };
+
+        self.status
+            .lock()
+            .info(format!("copied {} to the clipboard", text));
+
+        self.clipboard = Some(text);
+    }
+
+    /// Returns the entries that actions should apply to, which are the pinned entries while they're being viewed.
+    fn target_nodes(&self) -> SmallVec<[NodeID; 4]> {
+        if self.staging.viewing {
+            self.staging.ids()
+        } else {
+            self.path_viewer.selected_ids()
+        }
+    }
+
+    /// Read the archive from disk again and go back to viewing its root directory.
+    ///
+    /// Selected entries that are still in the archive stay selected.
+    fn reload(&mut self) -> Result<()> {
+        // The IDs of entries can change when the archive is read again, so selections are carried over by path instead
+        let marked = self.path_viewer.marked_paths();
+
+        self.archive.reload()?;
+
+        self.path_viewer = PathViewer::new(
+            self.archive.entries(),
+            NodeID::first(),
+            self.config.column_header,
+            self.config.date_column,
+            self.config.pane_ratios,
+            self.config.icons,
+            self.config.wrap,
+        )
+        .context("failed to view the root of the archive")?;
+
+        self.path_viewer.mark_paths(&marked);
+        self.previews.clear();
+        self.staging.clear();
+        self.entry_stats
+            .update(&self.path_viewer, self.staging.len());
+        self.status.lock().info("reloaded archive");
+        Ok(())
+    }
+
+    /// Reload the archive unless jobs are queued, since they still refer to entries from the archive as it is now.
+    ///
+    /// Returns the state the panel should be in afterwards.
+    fn start_reload(&mut self) -> PanelState {
+        if !self.jobs.lock().is_empty() {
+            self.status
+                .lock()
+                .warn("the archive can't be reloaded while jobs are queued");
+
+            return PanelState::Free;
+        }
+
+        match self.reload() {
+            Ok(()) => PanelState::Free,
+            Err(err) => PanelState::error(ErrorKind::Reload, err),
+        }
+    }
+
+    /// View the entry at the given `path` in the archive.
+    ///
+    /// Returns the state the panel should be in afterwards.
+    fn go_to(&mut self, path: &str) -> PanelState {
+        match self.path_viewer.files().find_path(path) {
+            Some(id) => {
+                self.staging.viewing = false;
+                self.path_viewer.view(id);
+                self.entry_stats
+                    .update(&self.path_viewer, self.staging.len());
+                PanelState::Free
+            }
+            None => PanelState::error(
+                ErrorKind::GoTo,
+                anyhow!("nothing in the archive is at {}", path),
+            ),
+        }
+    }
+
+    /// Select every entry that matches `pattern` and say how many more entries that selected.
+    fn select_matching(&mut self, pattern: &PathPattern) {
+        let num_marked = self.path_viewer.num_marked();
+        self.path_viewer.select_matching(pattern);
+
+        let message = match self.path_viewer.num_marked() - num_marked {
+            0 => "no more entries matched".into(),
+            1 => "selected 1 more entry".into(),
+            num => format!("selected {} more entries", number::grouped(num as u64)),
+        };
+
+        self.status.lock().info(message);
+        self.entry_stats
+            .update(&self.path_viewer, self.staging.len());
+    }
+
+    /// Start the given `action` with the text the user entered.
+    ///
+    /// Returns the state the panel should be in afterwards.
+    fn process_input(&mut self, action: InputAction, input: &str) -> PanelState {
+        match action {
+            InputAction::Extract => {
+                // The history is only a convenience, so failing to save it shouldn't stop the extraction
+                self.extract_history.push(input).ok();
+
+                let nodes = self.target_nodes();
+                self.start_extract(nodes, input.into(), InputAction::Extract)
+            }
+            InputAction::ExtractDir => {
+                self.extract_history.push(input).ok();
+
+                let directory = self.path_viewer.directory();
+                self.start_extract(smallvec![directory], input.into(), InputAction::ExtractDir)
+            }
+            InputAction::Mount => self.start_mount(input.into(), false),
+            InputAction::Compare => {
+                let nodes = self.target_nodes();
+                PanelState::Comparing(self.compare_async(nodes, input.to_string()))
+            }
+            // Entering nothing goes back to extracting entries with their original paths
+            InputAction::Rename if input.is_empty() => {
+                self.config.extract_options.rename = None;
+                self.status
+                    .lock()
+                    .info("extracting entries with their original paths");
+                PanelState::Free
+            }
+            InputAction::Rename => match input.parse::<RenameRule>() {
+                Ok(rule) => {
+                    let nodes = self.target_nodes();
+                    let renamed = rule.preview(self.path_viewer.files(), &nodes);
+                    PanelState::RenamePreview(RenamePreview::new(Arc::new(rule), renamed))
+                }
+                Err(err) => PanelState::error(ErrorKind::Rename, err),
+            },
+            // Entering nothing goes back to extracting every entry
+            InputAction::Filter if input.trim().is_empty() => {
+                self.config.extract_options.filter = None;
+                self.status.lock().info("extracting every file");
+                PanelState::Free
+            }
+            InputAction::Filter => match PathFilter::parse(input) {
+                Ok(filter) => {
+                    self.config.extract_options.filter = Some(Arc::new(filter));
+                    self.status.lock().info("only extracting matching files");
+                    PanelState::Free
+                }
+                Err(err) => PanelState::error(ErrorKind::Filter, err),
+            },
+            InputAction::Search => {
+                self.path_viewer.finish_search(input);
+                self.entry_stats
+                    .update(&self.path_viewer, self.staging.len());
+                PanelState::Free
+            }
+            // The filter was already applied while it was typed
+            InputAction::Narrow => PanelState::Free,
+            InputAction::Find | InputAction::Grep | InputAction::OpenTab | InputAction::Select
+                if input.trim().is_empty() =>
+            {
+                PanelState::Free
+            }
+            InputAction::Find => {
+                let results = FindResults::search(self.path_viewer.files(), input);
+                self.notify_finished(JobKind::Find);
+                PanelState::FindResults(results)
+            }
+            InputAction::GoTo => self.go_to(input.trim()),
+            InputAction::Grep => match Regex::new(input) {
+                Ok(pattern) => {
+                    let nodes = self.grep_nodes();
+                    PanelState::Grep(GrepReport::new(self.grep_async(nodes, pattern)))
+                }
+                Err(err) => PanelState::error(ErrorKind::Grep, err.into()),
+            },
+            InputAction::Select => match PathPattern::parse(input) {
+                Ok(pattern) => {
+                    self.select_matching(&pattern);
+                    PanelState::Free
+                }
+                Err(err) => PanelState::error(ErrorKind::Select, err),
+            },
+            InputAction::OpenTab => match self.open_tab(input) {
+                Ok(()) => PanelState::Free,
+                Err(err) => PanelState::error(ErrorKind::OpenTab, err),
+            },
+        }
+    }
+
+    /// Returns a new input for the given `action`, where actions that take a path can complete it.
+    fn input_state(&self, action: InputAction) -> InputState {
+        match action {
+            InputAction::Extract | InputAction::ExtractDir => {
+                InputState::with_history(self.extract_history.paths().to_vec()).complete_paths()
+            }
+            InputAction::Mount | InputAction::Compare | InputAction::OpenTab => {
+                InputState::new().complete_paths()
+            }
+            InputAction::Rename
+            | InputAction::Filter
+            | InputAction::Search
+            | InputAction::Narrow
+            | InputAction::Find
+            | InputAction::Grep
+            | InputAction::Select => InputState::new(),
+            InputAction::GoTo => {
+                InputState::new().complete_archive_paths(self.path_viewer.shared_files())
+            }
+        }
+    }
+
+    /// Show what the `input` entered so far does for actions that take effect while it's being typed.
+    fn preview_input(&mut self, action: InputAction, input: &str) {
+        match action {
+            InputAction::Search => self.path_viewer.search(input),
+            InputAction::Narrow => self.path_viewer.filter(input),
+            _ => return,
+        };
+
+        self.entry_stats
+            .update(&self.path_viewer, self.staging.len());
+    }
+
+    /// Undo anything the given `action` showed while its input was being typed.
+    fn cancel_input(&mut self, action: InputAction) {
+        match action {
+            InputAction::Search => self.path_viewer.cancel_search(),
+            InputAction::Narrow => self.path_viewer.filter(""),
+            _ => return,
+        };
+
+        self.entry_stats
+            .update(&self.path_viewer, self.staging.len());
+    }
+
+    /// Process a key while a report is shown in the given `state`, where Esc closes it and every other key is passed
+    /// on to it.
+    fn process_report_key(state: &mut PanelState, key: KeyCode) {
+        if let KeyCode::Esc = key {
+            state.reset();
+            return;
+        }
+
+        match state {
+            PanelState::CompareReport(report) => report.process_key(key),
+            PanelState::FailureReport(report) => report.process_key(key),
+            PanelState::MountChanges(report) => report.process_key(key),
+            PanelState::TypeStats(report) => report.process_key(key),
+            PanelState::Error(report) => report.process_key(key),
+            _ => (),
+        }
+    }
+
+    /// Process a key while the given flat `view` of every file is shown, where Enter goes to the highlighted file.
+    ///
+    /// Returns true if the view should be closed.
+    fn process_flat_view_key(&mut self, view: &mut FlatView, key: KeyCode) -> bool {
+        match key {
+            KeyCode::Enter => {
+                self.go_to_entry(view.highlighted());
+                true
+            }
+            KeyCode::Esc => true,
+            key => {
+                view.process_key(self.path_viewer.files(), key);
+                false
+            }
+        }
+    }
+
+    /// Process a key while the given `report` of the largest files is shown, where Enter goes to the highlighted file.
+    ///
+    /// Returns true if the report should be closed.
+    fn process_largest_files_key(&mut self, report: &mut LargestFiles, key: KeyCode) -> bool {
+        match key {
+            KeyCode::Enter => {
+                self.go_to_entry(report.highlighted());
+                true
+            }
+            KeyCode::Esc => true,
+            key => {
+                report.process_key(key);
+                false
+            }
+        }
+    }
+
+    /// Process a key while the given find `results` are shown, where Enter goes to the highlighted entry.
+    ///
+    /// Returns true if the results should be closed.
+    fn process_find_key(&mut self, results: &mut FindResults, key: KeyCode) -> bool {
+        match key {
+            KeyCode::Enter => {
+                self.go_to_entry(results.highlighted());
+                true
+            }
+            KeyCode::Esc => true,
+            key => {
+                results.process_key(key);
+                false
+            }
+        }
+    }
+
+    /// Process a key while the given content search `report` is shown, where Enter goes to the entry of the
+    /// highlighted line and Esc stops the search if it's still running.
+    ///
+    /// Returns true if the report should be closed.
+    fn process_grep_key(&mut self, report: &mut GrepReport, key: KeyCode) -> bool {
+        match key {
+            KeyCode::Enter => {
+                report.grepper.cancel();
+                self.go_to_entry(report.highlighted());
+                true
+            }
+            KeyCode::Esc if !report.grepper.is_finished() => {
+                report.grepper.cancel();
+                false
+            }
+            KeyCode::Esc => true,
+            key => {
+                report.process_key(key);
+                false
+            }
+        }
+    }
+
+    /// Process a key while the given `report` of duplicate files is shown, where Enter goes to the highlighted file
+    /// and Esc stops comparing files if it's still running.
+    ///
+    /// Returns true if the report should be closed.
+    fn process_duplicates_key(&mut self, report: &mut DuplicatesReport, key: KeyCode) -> bool {
+        match key {
+            KeyCode::Enter => {
+                report.finder.cancel();
+                self.go_to_entry(report.highlighted());
+                true
+            }
+            KeyCode::Esc if !report.finder.is_finished() => {
+                report.finder.cancel();
+                false
+            }
+            KeyCode::Esc => true,
+            key => {
+                report.process_key(key);
+                false
+            }
+        }
+    }
+
+    /// View the directory of the entry with the given `id` with it highlighted, if there is one.
+    fn go_to_entry(&mut self, id: Option<NodeID>) {
+        if let Some(id) = id {
+            self.staging.viewing = false;
+            self.path_viewer.reveal(id);
+            self.entry_stats
+                .update(&self.path_viewer, self.staging.len());
+        }
+    }
+
+    /// Process a key that starts a search, moves between its matches, copies the highlighted entry, or opens another
+    /// view of the archive or the command palette while it's being browsed.
+    ///
+    /// Returns None if the key does none of those.
+    fn process_view_key(&mut self, state: &mut PanelState, key: KeyCode) -> Option<InputLock> {
+        if self.staging.viewing || !matches!(state, PanelState::Free) {
+            return None;
+        }
+
+        // Letters typed to jump to an entry would otherwise start whatever action they're bound to, or quit
+        if self.path_viewer.type_ahead(key).is_some() {
+            self.entry_stats
+                .update(&self.path_viewer, self.staging.len());
+            return Some(InputLock::Locked);
+        }
+
+        let searching = self.path_viewer.search_query().is_some();
+
+        match key {
+            KeyCode::Char(Self::SEARCH_KEY) => {
+                self.path_viewer.start_search();
+                *state = PanelState::Input(InputState::new(), InputAction::Search);
+                return Some(InputLock::Locked);
+            }
+            KeyCode::Char(
+                key @ (Self::NARROW_KEY
+                | Self::FIND_KEY
+                | Self::GO_TO_KEY
+                | Self::GREP_KEY
+                | Self::SELECT_MATCHING_KEY
+                | Self::OPEN_TAB_KEY),
+            ) => {
+                let action = match key {
+                    Self::NARROW_KEY => InputAction::Narrow,
+                    Self::FIND_KEY => InputAction::Find,
+                    Self::GO_TO_KEY => InputAction::GoTo,
+                    Self::GREP_KEY => InputAction::Grep,
+                    Self::SELECT_MATCHING_KEY => InputAction::Select,
+                    Self::OPEN_TAB_KEY => InputAction::OpenTab,
+                    _ => unreachable!(),
+                };
+
+                *state = PanelState::Input(self.input_state(action), action);
+                return Some(InputLock::Locked);
+            }
+            KeyCode::Char(Self::FLAT_VIEW_KEY) => {
+                let view = FlatView::new(
+                    self.path_viewer.files(),
+                    self.path_viewer.sort(),
+                    self.path_viewer.hides_hidden(),
+                );
+                *state = PanelState::FlatView(view);
+                return Some(InputLock::Unlocked);
+            }
+            KeyCode::Char(Self::LARGEST_FILES_KEY) => {
+                *state = PanelState::LargestFiles(LargestFiles::new(self.path_viewer.files()));
+                return Some(InputLock::Unlocked);
+            }
+            KeyCode::Char(Self::TYPE_STATS_KEY) => {
+                *state = PanelState::TypeStats(TypeStats::new(self.path_viewer.files()));
+                return Some(InputLock::Unlocked);
+            }
+            KeyCode::Char(Self::DUPLICATES_KEY) => {
+                *state =
+                    PanelState::Duplicates(DuplicatesReport::new(self.find_duplicates_async()));
+                return Some(InputLock::Unlocked);
+            }
+            KeyCode::Char(Self::HEX_KEY) => self.update_preview(Preview::toggle_hex),
+            KeyCode::Char(Self::ENCODING_KEY) => self.update_preview(Preview::cycle_encoding),
+            KeyCode::Char(Self::NEW_TAB_KEY) => {
+                if let Err(err) = self.open_tab_here() {
+                    *state = PanelState::error(ErrorKind::OpenTab, err);
+                }
+
+                return Some(InputLock::Unlocked);
+            }
+            KeyCode::Char(Self::INFO_KEY) => {
+                *state = self.highlighted_info();
+                return Some(InputLock::Unlocked);
+            }
+            KeyCode::Char(
+                key @ (Self::COPY_PATH_KEY | Self::COPY_MOUNT_PATH_KEY | Self::COPY_NAME_KEY),
+            ) => {
+                self.copy_highlighted(key);
+                return Some(InputLock::Unlocked);
+            }
+            KeyCode::Char(Self::COMMAND_PALETTE_KEY) => {
+                *state = PanelState::CommandPalette(self.command_palette());
+                return Some(InputLock::Locked);
+            }
+            KeyCode::Char(Self::NEXT_MATCH_KEY | Self::PREV_MATCH_KEY) if searching => {
+                self.path_viewer
+                    .search_next(key == KeyCode::Char(Self::PREV_MATCH_KEY));
+            }
+            key if key == Self::CANCEL_KEY.key && searching => self.path_viewer.clear_search(),
+            key if key == Self::CANCEL_KEY.key && self.path_viewer.name_filter().is_some() => {
+                self.path_viewer.filter("");
+            }
+            _ => return None,
+        }
+
+        self.entry_stats
+            .update(&self.path_viewer, self.staging.len());
+
+        Some(InputLock::Unlocked)
+    }
+
+    /// Process a key while the archive is being browsed, which can happen while a background job is running.
+    fn process_browse_key(&mut self, state: &mut PanelState, key: KeyCode) -> InputLock {
+        if let Some(lock) = self.process_view_key(state, key) {
+            return lock;
+        }
+
+        match (&*state, key) {
+            (PanelState::Free, key) if self.staging.viewing && key == Self::UNMOUNT_KEY.key => {
+                self.staging.viewing = false;
+                InputLock::Unlocked
+            }
+            // Esc also unmounts, so jobs can only be cancelled while one is being picked
+            (_, key) if key == Self::CANCEL_KEY.key && self.jobs.lock().selecting => {
+                self.jobs.lock().cancel_highlighted();
+                InputLock::Unlocked
+            }
+            (_, KeyCode::Char(Self::JOBS_KEY)) => {
+                let mut jobs = self.jobs.lock();
+                jobs.selecting = !jobs.selecting && !jobs.is_empty();
+                InputLock::Unlocked
+            }
+            (_, KeyCode::Up | KeyCode::Down) if self.jobs.lock().selecting => {
+                self.jobs.lock().process_key(key);
+                InputLock::Unlocked
+            }
+            (_, KeyCode::Char(Self::PIN_KEY)) => {
+                self.toggle_pinned();
+                InputLock::Unlocked
+            }
+            (_, KeyCode::Char(Self::VIEW_PINNED_KEY)) => {
+                self.staging.viewing = !self.staging.viewing && !self.staging.is_empty();
+                InputLock::Unlocked
+            }
+            (PanelState::Free, KeyCode::Char(Self::EXTRACT_TO_DIR_KEY))
+            | (PanelState::Free, KeyCode::Char(Self::MOUNT_AT_DIR_KEY))
+            | (PanelState::Free, KeyCode::Char(Self::COMPARE_KEY))
+            | (PanelState::Free, KeyCode::Char(Self::RENAME_KEY))
+            | (PanelState::Free, KeyCode::Char(Self::FILTER_KEY))
+                if self.can_mount || key != KeyCode::Char(Self::MOUNT_AT_DIR_KEY) =>
+            {
+                let action = match key {
+                    KeyCode::Char(Self::EXTRACT_TO_DIR_KEY) => InputAction::Extract,
+                    KeyCode::Char(Self::MOUNT_AT_DIR_KEY) => InputAction::Mount,
+                    KeyCode::Char(Self::COMPARE_KEY) => InputAction::Compare,
+                    KeyCode::Char(Self::RENAME_KEY) => InputAction::Rename,
+                    KeyCode::Char(Self::FILTER_KEY) => InputAction::Filter,
+                    _ => unreachable!(),
+                };
+
+                *state = PanelState::Input(self.input_state(action), action);
+                InputLock::Locked
+            }
+            (PanelState::Free, KeyCode::Char(Self::OPEN_KEY)) if !self.staging.viewing => {
+                self.open_highlighted();
+                InputLock::Unlocked
+            }
+            (PanelState::Free, KeyCode::Char(Self::EXTRACT_TO_CWD_KEY)) => {
+                *state = self.extract_to_cwd();
+                InputLock::Unlocked
+            }
+            (PanelState::Free, KeyCode::Char(Self::EXTRACT_DIR_HERE_KEY)) => {
+                *state = self.extract_dir_here();
+                InputLock::Unlocked
+            }
+            (PanelState::Free, KeyCode::Char(Self::MOUNT_AT_TMP_KEY))
+                if self.can_mount && self.mount_session.is_none() =>
+            {
+                *state = self.start_mount(env::temp_dir(), true);
+                InputLock::Unlocked
+            }
+            (PanelState::Free, key) if key == Self::UNMOUNT_KEY.key => {
+                *state = self.start_unmount();
+                InputLock::Unlocked
+            }
+            (PanelState::Free, KeyCode::Char(Self::RELOAD_KEY)) => {
+                *state = self.start_reload();
+                InputLock::Unlocked
+            }
+            (_, key) if self.staging.viewing => {
+                self.staging.process_key(key);
+                InputLock::Unlocked
+            }
+            (_, key) => {
+                match self.path_viewer.process_key(key) {
+                    PathViewerResult::Ok => (),
+                    PathViewerResult::PathSelected => {
+                        self.entry_stats
+                            .update(&self.path_viewer, self.staging.len());
+                    }
+                }
+
+                InputLock::Unlocked
+            }
+        }
+    }
 
-impl<'a> MainPanel<'a> {
-    const EXTRACT_TO_DIR_KEY: char = 's';
-    const EXTRACT_TO_CWD_KEY: char = 'e';
-    const MOUNT_AT_DIR_KEY: char = 'l';
-    const MOUNT_AT_TMP_KEY: char = 'm';
-    const UNMOUNT_KEY: KeyCodeDesc = KeyCodeDesc::new(KeyCode::Esc, "Esc");
+    /// Pin the selected entries, or unpin them if they're all pinned already.
+    ///
+    /// While the pinned entries are being viewed, the highlighted one is unpinned instead.
+    fn toggle_pinned(&mut self) {
+        if self.staging.viewing {
+            self.staging.remove_highlighted();
+        } else {
+            let ids = self.path_viewer.selected_ids();
+            self.staging.toggle(self.path_viewer.files(), &ids);
+        }
 
-    pub fn new(archive: Archive) -> Result<Self> {
-        let archive = Arc::new(archive);
-        let path_viewer =
-            PathViewer::new(Arc::clone(&archive), NodeID::first()).context("archive is empty")?;
+        self.entry_stats
+            .update(&self.path_viewer, self.staging.len());
+    }
 
-        let entry_stats = EntryStats::new(
-            &archive,
-            path_viewer.directory(),
-            path_viewer.highlighted().id,
-            path_viewer.highlighted_index(),
-        );
+    /// Unmount the archive if it's mounted, or ask the user to confirm forcing it if it's still being used.
+    ///
+    /// Returns the state the panel should be in afterwards.
+    fn start_unmount(&mut self) -> PanelState {
+        let session = match self.mount_session.take() {
+            Some(session) => session,
+            None => return PanelState::Free,
+        };
 
-        Ok(Self {
-            archive,
-            path_viewer,
-            entry_stats,
-            state: Arc::new(Mutex::new(PanelState::default())),
-            mount_session: None,
-        })
+        match session.unmount() {
+            Ok(changes) => Self::unmounted_state(changes),
+            Err(session) => {
+                let busy = BusyMount {
+                    path: session.path().to_path_buf(),
+                    users: mount::processes_using(session.path()),
+                };
+
+                self.mount_session = Some(session);
+                PanelState::ConfirmUnmount(Box::new(busy))
+            }
+        }
     }
 
-    fn extract_async(&self, nodes: SmallVec<[NodeID; 4]>, path: String) -> Arc<Extractor> {
-        let archive = Arc::clone(&self.archive);
-        let extractor = Arc::new(Extractor::prepare(archive, nodes));
-        let state = Arc::clone(&self.state);
-        let task_extractor = Arc::clone(&extractor);
+    /// Returns the state the panel should be in after unmounting, which lists the `changes` made through the
+    /// mount if there were any.
+    fn unmounted_state(changes: Option<MountChanges>) -> PanelState {
+        match changes {
+            Some(changes) => PanelState::MountChanges(MountChangesReport::new(changes)),
+            None => PanelState::Free,
+        }
+    }
 
-        task::spawn(async move {
-            let result = task_extractor.extract(path);
-            let mut panel_state = state.lock();
+    /// Unmount the archive if it's mounted, even if it's still being used, and return what was changed through it.
+    pub fn unmount(&mut self) -> Option<MountChanges> {
+        self.mount_session
+            .take()
+            .and_then(ArchiveMountSession::force_unmount)
+    }
 
-            match result {
-                Ok(_) => panel_state.reset(),
-                Err(err) => *panel_state = PanelState::Error(ErrorKind::Extract, err),
+    /// Take the session of the archive's mount, if it's mounted, so it can be kept mounted after the panel is gone.
+    pub fn take_mount_session(&mut self) -> Option<ArchiveMountSession> {
+        self.mount_session.take()
+    }
+
+    /// Mount the archive at the given `path` and keep its session.
+    ///
+    /// If `temp_dir` is true, the archive is mounted at a new directory inside of `path` instead.
+    ///
+    /// Returns the state the panel should be in afterwards.
+    fn mount(&mut self, mounted: MountedArchive, path: &Path, temp_dir: bool) -> PanelState {
+        let result = if temp_dir {
+            mounted.mount_in_temp_dir(path)
+        } else {
+            mounted.mount(path)
+        };
+
+        match result {
+            Ok(handle) => {
+                self.mount_session = Some(handle);
+                self.notify_finished(JobKind::Mount);
+                PanelState::Free
             }
-        });
+            Err(err) => PanelState::error(ErrorKind::Mount, err),
+        }
+    }
 
-        extractor
+    /// Show how the last finished job went if it failed and nothing else is being shown.
+    ///
+    /// Finished jobs wait to be shown so they don't interrupt anything the user is doing.
+    fn show_job_outcome(state: &mut PanelState, jobs: &mut JobQueue) {
+        if let PanelState::Free = *state {
+            match jobs.take_outcome() {
+                Some(JobOutcome::Failed(err)) => {
+                    *state = PanelState::error(ErrorKind::Extract, err);
+                }
+                Some(JobOutcome::PartlyFailed(failures)) => {
+                    *state = PanelState::FailureReport(FailureReport::new(failures));
+                }
+                None => (),
+            }
+        }
+    }
+
+    /// Process a key while the command palette is open in the given `state`, which closes it once a command is
+    /// picked and runs that command.
+    fn process_palette_key(
+        &mut self,
+        state: &mut MutexGuard<PanelState>,
+        key: KeyCode,
+    ) -> InputLock {
+        let result = match &mut **state {
+            PanelState::CommandPalette(palette) => palette.process_key(key),
+            _ => return InputLock::Unlocked,
+        };
+
+        match result {
+            PaletteResult::Ok => InputLock::Locked,
+            PaletteResult::Close => {
+                state.reset();
+                InputLock::Locked
+            }
+            PaletteResult::Run(key) => {
+                state.reset();
+
+                // The command's key locks the state again
+                MutexGuard::unlocked(state, || self.process_key(key))
+            }
+        }
+    }
+
+    /// Start whatever the user was asked to confirm in the given `state`.
+    ///
+    /// Returns the state the panel should be in afterwards.
+    fn confirm(&mut self, state: PanelState) -> PanelState {
+        match state {
+            PanelState::ConfirmExtract(pending) => {
+                self.queue_extract(pending.extractor, pending.path);
+                PanelState::Free
+            }
+            PanelState::ConfirmMount(pending) => {
+                self.mount(pending.mounted, &pending.path, pending.temp_dir)
+            }
+            PanelState::ConfirmUnmount(_) => Self::unmounted_state(self.unmount()),
+            state => state,
+        }
+    }
+
+    fn key_hints(&self) -> KeyHints {
+        let mount_state = match &self.mount_session {
+            Some(_) => MountState::Mounted {
+                unmount: Self::UNMOUNT_KEY.desc,
+            },
+            None if self.can_mount => MountState::Unmounted {
+                mount_at_dir: alpha_upper(Self::MOUNT_AT_DIR_KEY),
+                mount_at_tmp: alpha_upper(Self::MOUNT_AT_TMP_KEY),
+            },
+            None => MountState::Unavailable,
+        };
+
+        KeyHints {
+            extract_to_dir_key: alpha_upper(Self::EXTRACT_TO_DIR_KEY),
+            extract_to_cwd_key: alpha_upper(Self::EXTRACT_TO_CWD_KEY),
+            extract_dir_here_key: alpha_upper(Self::EXTRACT_DIR_HERE_KEY),
+            mount_state,
+        }
+    }
+
+    /// Draw the given `path_viewer` with a line above it that shows where the viewed directory is.
+    ///
+    /// The highlighted file is previewed next to it, and the `preview` is read again if a different file has been
+    /// highlighted since it was last drawn. Returns where the terminal should draw the file if it's an image that
+    /// can be drawn with the given `graphics`.
+    fn draw_path_viewer<B: Backend>(
+        archive: &Arc<Archive>,
+        path_viewer: &mut PathViewer,
+        previews: &mut PreviewCache,
+        graphics: Graphics,
+        area: Rect,
+        frame: &mut Frame<B>,
+    ) -> Option<Rect> {
+        let layout = Layout::default()
+            .constraints([Constraint::Length(1), Constraint::Min(1)])
+            .direction(Direction::Vertical)
+            .split(area);
+
+        let archive_name = archive_name(archive);
+        let path = path_viewer.files().path_of(path_viewer.directory());
+
+        let path_bar = PathBar {
+            archive_name: &archive_name,
+            path: &path,
+        };
+
+        frame.render_widget(path_bar, pad_rect_horiz(layout[0], 1));
+        path_viewer.draw(layout[1], frame);
+
+        let preview_area = path_viewer.preview_area()?;
+        let id = path_viewer.highlighted_id();
+
+        let files = path_viewer.shared_files();
+        let preview = previews.get_or_load(archive, &files, id, graphics);
+
+        preview.fit_scroll(preview_area);
+        frame.render_widget(&*preview, preview_area);
+        preview.graphic_area(graphics, preview_area)
+    }
+
+    fn draw_mount_warning<B: Backend>(pending: &PendingMount, area: Rect, frame: &mut Frame<B>) {
+        let layout = Layout::default()
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Percentage(100),
+            ])
+            .direction(Direction::Vertical)
+            .margin(1)
+            .split(area);
+
+        let style = Style::default().fg(Color::Yellow);
+
+        let header = SimpleText::new("Mount Location May Run Out Of Space")
+            .alignment(Alignment::Center)
+            .style(style.add_modifier(Modifier::BOLD));
+
+        frame.render_widget(header, layout[0]);
+
+        let text = format!(
+            "{} is on a tmpfs with {} free, but the archive is {} uncompressed. \
+             Files read through the mount are cached in memory, which a tmpfs shares, \
+             so consider mounting in a disk-backed directory such as /var/tmp instead.\n\n\
+             Press Enter to mount anyway or Esc to cancel.",
+            pending.path.display(),
+            size::formatted(pending.estimate.available_bytes),
+            size::formatted(pending.estimate.needed_bytes),
+        );
+
+        let msg = Paragraph::new(text)
+            .alignment(Alignment::Center)
+            .style(style)
+            .wrap(Wrap { trim: false });
+
+        frame.render_widget(msg, layout[2]);
     }
 
-    fn draw_error<B: Backend>(kind: ErrorKind, error: &Error, area: Rect, frame: &mut Frame<B>) {
+    fn draw_unmount_warning<B: Backend>(busy: &BusyMount, area: Rect, frame: &mut Frame<B>) {
         let layout = Layout::default()
             .constraints([
                 Constraint::Length(1),
@@ -96,109 +1552,290 @@ impl<'a> MainPanel<'a> {
             .margin(1)
             .split(area);
 
-        let style = Style::default().fg(Color::Red);
+        let style = Style::default().fg(Color::Yellow);
+
+        let header = SimpleText::new("Mount Location Is Busy")
+            .alignment(Alignment::Center)
+            .style(style.add_modifier(Modifier::BOLD));
+
+        frame.render_widget(header, layout[0]);
+
+        let users = if busy.users.is_empty() {
+            String::new()
+        } else {
+            let names = busy
+                .users
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
 
-        let header_text = match kind {
-            ErrorKind::Extract => "Error Extracting Archive",
-            ErrorKind::Mount => "Error Mounting Archive",
+            format!("It's being used by {}.\n\n", names.join(", "))
         };
 
-        let header = SimpleText::new(header_text)
+        let text = format!(
+            "{} can't be unmounted while files inside of it are open or a program is running inside of it.\n\n\
+             {}Press Enter to unmount it anyway, which cuts off anything still using it, or Esc to keep it mounted.",
+            busy.path.display(),
+            users,
+        );
+
+        let msg = Paragraph::new(text)
+            .alignment(Alignment::Center)
+            .style(style)
+            .wrap(Wrap { trim: false });
+
+        frame.render_widget(msg, layout[2]);
+    }
+
+    fn draw_extract_warning<B: Backend>(
+        pending: &PendingExtract,
+        area: Rect,
+        frame: &mut Frame<B>,
+    ) {
+        let layout = Layout::default()
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Percentage(100),
+            ])
+            .direction(Direction::Vertical)
+            .margin(1)
+            .split(area);
+
+        let style = Style::default().fg(Color::Yellow);
+
+        let header = SimpleText::new("Extract Location Does Not Have Enough Space")
             .alignment(Alignment::Center)
             .style(style.add_modifier(Modifier::BOLD));
 
         frame.render_widget(header, layout[0]);
 
-        // TODO: display causes
-        let msg = Paragraph::new(format!("{}", error))
+        let text = format!(
+            "{} only has {} free, but the entries being extracted are {} uncompressed.\n\n\
+             Press Enter to extract anyway or Esc to cancel.",
+            pending.path.display(),
+            size::formatted(pending.estimate.available_bytes),
+            size::formatted(pending.estimate.needed_bytes),
+        );
+
+        let msg = Paragraph::new(text)
             .alignment(Alignment::Center)
             .style(style)
             .wrap(Wrap { trim: false });
 
         frame.render_widget(msg, layout[2]);
     }
+
+    /// Draw the input for the given `action` in `input_area`, along with the directories it could be completed to.
+    fn draw_input<B: Backend>(
+        state: &mut InputState,
+        action: InputAction,
+        input_area: Rect,
+        frame: &mut Frame<B>,
+    ) {
+        let input = Input::new(action.desc());
+        frame.render_stateful_widget(input, input_area, state);
+
+        if let Some(completion) = state.completion() {
+            let popup = CompletionPopup::new(completion);
+            let area = popup.area(input_area);
+            frame.render_widget(popup, area);
+        }
+
+        if let Some((x, y)) = state.cursor_pos {
+            frame.set_cursor(x, y);
+        }
+    }
+
+    /// Draw the line at the bottom of the panel, which shows the progress of a running task, the input being typed,
+    /// or the keys that can be pressed.
+    fn draw_bottom_line<B: Backend>(
+        &self,
+        state: &mut PanelState,
+        area: Rect,
+        frame: &mut Frame<B>,
+    ) {
+        match state {
+            PanelState::Grep(report) if !report.grepper.is_finished() => {
+                let progress = ProgressBar::with_counts(
+                    report.grepper.searched.load(Ordering::Relaxed),
+                    report.grepper.total_to_search,
+                );
+
+                frame.render_widget(progress, area);
+            }
+            PanelState::Duplicates(report) if !report.finder.is_finished() => {
+                let progress = ProgressBar::with_counts(
+                    report.finder.checked.load(Ordering::Relaxed),
+                    report.finder.total_to_check,
+                );
+
+                frame.render_widget(progress, area);
+            }
+            PanelState::Free
+            | PanelState::Error(_)
+            | PanelState::CompareReport(_)
+            | PanelState::FailureReport(_)
+            | PanelState::MountChanges(_)
+            | PanelState::RenamePreview(_)
+            | PanelState::FindResults(_)
+            | PanelState::Grep(_)
+            | PanelState::Duplicates(_)
+            | PanelState::FlatView(_)
+            | PanelState::LargestFiles(_)
+            | PanelState::TypeStats(_)
+            | PanelState::Info(_)
+            | PanelState::ConfirmMount(_)
+            | PanelState::ConfirmUnmount(_)
+            | PanelState::ConfirmExtract(_) => {
+                frame.render_widget(self.key_hints(), pad_rect_horiz(area, 1));
+            }
+            PanelState::Comparing(comparer) => {
+                let progress = ProgressBar::with_counts(
+                    comparer.compared.load(Ordering::Relaxed),
+                    comparer.total_to_compare,
+                );
+
+                frame.render_widget(progress, area);
+            }
+            PanelState::CommandPalette(palette) => {
+                Self::draw_command_palette(palette, area, frame);
+            }
+            PanelState::Input(state, action) => Self::draw_input(state, *action, area, frame),
+        }
+    }
+
+    /// Draw the given command `palette` with its input in `input_area` and the matching commands above it.
+    fn draw_command_palette<B: Backend>(
+        palette: &mut CommandPalette,
+        input_area: Rect,
+        frame: &mut Frame<B>,
+    ) {
+        let area = palette.area(input_area);
+        frame.render_widget(&*palette, area);
+
+        let input = palette.input_mut();
+        frame.render_stateful_widget(Input::new(CommandPalette::DESC), input_area, input);
+
+        if let Some((x, y)) = input.cursor_pos {
+            frame.set_cursor(x, y);
+        }
+    }
 }
 
 impl<'a> Panel for MainPanel<'a> {
     type KeyResult = InputLock;
 
     fn process_key(&mut self, key: KeyCode) -> Self::KeyResult {
-        let mut state = self.state.lock();
+        // The state is locked through its own reference so we're free to call mutable methods on self
+        let state = Arc::clone(&self.state);
+        let mut state = state.lock();
 
         match &mut *state {
-            PanelState::Free | PanelState::Extracting(_) => match (&*state, key) {
-                (PanelState::Free, KeyCode::Char(Self::EXTRACT_TO_DIR_KEY))
-                | (PanelState::Free, KeyCode::Char(Self::MOUNT_AT_DIR_KEY)) => {
-                    let action = match key {
-                        KeyCode::Char(Self::EXTRACT_TO_DIR_KEY) => InputAction::Extract,
-                        KeyCode::Char(Self::MOUNT_AT_DIR_KEY) => InputAction::Mount,
-                        _ => unreachable!(),
-                    };
-
-                    *state = PanelState::Input(InputState::new(), action);
-                    InputLock::Locked
+            PanelState::Free | PanelState::Comparing(_) => self.process_browse_key(&mut state, key),
+            PanelState::Input(input, action) => {
+                match input.process_key(key) {
+                    InputResult::Ok => self.preview_input(*action, input.text()),
+                    InputResult::Return => {
+                        self.cancel_input(*action);
+                        state.reset();
+                    }
+                    InputResult::ProcessInput(path) => *state = self.process_input(*action, path),
                 }
-                (PanelState::Free, key) if key == Self::UNMOUNT_KEY.key => {
-                    self.mount_session = None;
-                    InputLock::Unlocked
+
+                InputLock::Locked
+            }
+            PanelState::CommandPalette(_) => self.process_palette_key(&mut state, key),
+            PanelState::ConfirmExtract(_)
+            | PanelState::ConfirmMount(_)
+            | PanelState::ConfirmUnmount(_) => {
+                match key {
+                    KeyCode::Enter => *state = self.confirm(mem::take(&mut *state)),
+                    KeyCode::Esc => state.reset(),
+                    _ => (),
                 }
-                (_, key) => {
-                    match self.path_viewer.process_key(key) {
-                        PathViewerResult::Ok => (),
-                        PathViewerResult::PathSelected(id) => {
-                            self.entry_stats.update(
-                                &self.archive,
-                                self.path_viewer.directory(),
-                                id,
-                                self.path_viewer.highlighted_index(),
-                            );
-                        }
+
+                InputLock::Locked
+            }
+            PanelState::RenamePreview(preview) => {
+                match key {
+                    KeyCode::Enter => {
+                        self.config.extract_options.rename = Some(Arc::clone(&preview.rule));
+                        state.reset();
                     }
+                    KeyCode::Esc => state.reset(),
+                    key => preview.process_key(key),
+                }
+
+                InputLock::Locked
+            }
+            PanelState::FindResults(results) => {
+                if self.process_find_key(results, key) {
+                    state.reset();
+                }
 
-                    InputLock::Unlocked
+                InputLock::Unlocked
+            }
+            PanelState::Grep(report) => {
+                if self.process_grep_key(report, key) {
+                    state.reset();
                 }
-            },
-            PanelState::Input(input, action) => {
-                match input.process_key(key) {
-                    InputResult::Ok => (),
-                    InputResult::Return => state.reset(),
-                    InputResult::ProcessInput(path) => match action {
-                        InputAction::Extract => {
-                            let nodes = self.path_viewer.selected_ids();
-
-                            let path = path.to_string();
-                            let extractor = self.extract_async(nodes, path);
-                            *state = PanelState::Extracting(extractor);
-                        }
-                        InputAction::Mount => {
-                            let mounted = MountedArchive::new(Arc::clone(&self.archive));
-
-                            match mounted.mount(path) {
-                                Ok(handle) => {
-                                    self.mount_session = Some(handle);
-                                    state.reset();
-                                }
-                                Err(err) => *state = PanelState::Error(ErrorKind::Mount, err),
-                            }
-                        }
-                    },
+
+                InputLock::Unlocked
+            }
+            PanelState::Duplicates(report) => {
+                if self.process_duplicates_key(report, key) {
+                    state.reset();
                 }
 
-                InputLock::Locked
+                InputLock::Unlocked
+            }
+            PanelState::FlatView(view) => {
+                if self.process_flat_view_key(view, key) {
+                    state.reset();
+                }
+
+                InputLock::Unlocked
+            }
+            PanelState::LargestFiles(report) => {
+                if self.process_largest_files_key(report, key) {
+                    state.reset();
+                }
+
+                InputLock::Unlocked
             }
-            PanelState::Error(_, _) => {
-                if let KeyCode::Esc = key {
+            PanelState::Info(_) => {
+                if let KeyCode::Esc | KeyCode::Char(Self::INFO_KEY) = key {
                     state.reset();
                 }
 
                 InputLock::Unlocked
             }
+            PanelState::CompareReport(_)
+            | PanelState::FailureReport(_)
+            | PanelState::MountChanges(_)
+            | PanelState::TypeStats(_)
+            | PanelState::Error(_) => {
+                Self::process_report_key(&mut state, key);
+                InputLock::Unlocked
+            }
         }
     }
 }
 
 impl<'a, B: Backend> Draw<B> for MainPanel<'a> {
     fn draw(&mut self, rect: Rect, frame: &mut Frame<B>) {
+        let mut state = self.state.lock();
+        let mut jobs = self.jobs.lock();
+
+        Self::show_job_outcome(&mut state, &mut jobs);
+        self.graphic_area = None;
+
+        let job_list = JobList {
+            queue: &jobs,
+            cancel_key: Self::CANCEL_KEY.desc,
+        };
+
         let layout = Layout::default()
             .constraints([
                 // Path viewer / error
@@ -207,59 +1844,83 @@ impl<'a, B: Backend> Draw<B> for MainPanel<'a> {
                 Constraint::Length(1),
                 // Entry stats
                 Constraint::Length(1),
+                // Extraction jobs
+                Constraint::Length(job_list.height()),
+                // Mount status
+                Constraint::Length(u16::from(self.mount_session.is_some())),
+                // Status message
+                Constraint::Length(u16::from(self.status.lock().current().is_some())),
                 // Key hints / input / progress bar
                 Constraint::Length(1),
             ])
             .direction(Direction::Vertical)
             .split(rect);
 
-        let mut state = self.state.lock();
+        match &*state {
+            PanelState::Error(report) => frame.render_widget(report, rect),
+            PanelState::CompareReport(report) => frame.render_widget(report, layout[0]),
+            PanelState::FailureReport(report) => frame.render_widget(report, layout[0]),
+            PanelState::MountChanges(report) => frame.render_widget(report, layout[0]),
+            PanelState::RenamePreview(preview) => frame.render_widget(preview, layout[0]),
+            PanelState::FindResults(results) => frame.render_widget(results, layout[0]),
+            PanelState::Grep(report) => frame.render_widget(report, layout[0]),
+            PanelState::Duplicates(report) => frame.render_widget(report, layout[0]),
+            PanelState::FlatView(view) => frame.render_widget(view, layout[0]),
+            PanelState::LargestFiles(report) => frame.render_widget(report, layout[0]),
+            PanelState::TypeStats(report) => frame.render_widget(report, layout[0]),
+            PanelState::ConfirmMount(pending) => Self::draw_mount_warning(pending, rect, frame),
+            PanelState::ConfirmUnmount(busy) => Self::draw_unmount_warning(busy, rect, frame),
+            PanelState::ConfirmExtract(pending) => Self::draw_extract_warning(pending, rect, frame),
+            PanelState::Info(info) => {
+                Self::draw_path_viewer(
+                    &self.archive,
+                    &mut self.path_viewer,
+                    &mut self.previews,
+                    self.config.graphics,
+                    layout[0],
+                    frame,
+                );
+                frame.render_widget(info, info.area(layout[0]));
+            }
+            _ if self.staging.viewing => frame.render_widget(&self.staging, layout[0]),
+            _ => {
+                let graphic_area = Self::draw_path_viewer(
+                    &self.archive,
+                    &mut self.path_viewer,
+                    &mut self.previews,
+                    self.config.graphics,
+                    layout[0],
+                    frame,
+                );
 
-        if let PanelState::Error(kind, err) = &*state {
-            Self::draw_error(*kind, err, rect, frame);
-        } else {
-            self.path_viewer.draw(layout[0], frame);
+                // The image would cover up anything that's drawn over the viewed directory, like the command palette
+                if matches!(*state, PanelState::Free) {
+                    self.graphic_area = graphic_area;
+                }
+            }
         }
 
         frame.render_widget(self.entry_stats.clone(), layout[2]);
+        frame.render_widget(job_list, layout[3]);
+        drop(jobs);
 
-        match &mut *state {
-            PanelState::Free | PanelState::Error(_, _) => {
-                let mount_state = if self.mount_session.is_some() {
-                    MountState::Mounted {
-                        unmount: Self::UNMOUNT_KEY.desc,
-                    }
-                } else {
-                    MountState::Unmounted {
-                        mount_at_dir: alpha_upper(Self::MOUNT_AT_DIR_KEY),
-                        mount_at_tmp: alpha_upper(Self::MOUNT_AT_TMP_KEY),
-                    }
-                };
+        if let Some(session) = &self.mount_session {
+            let status = MountStatus {
+                path: session.path(),
+                stats: session.stats(),
+            };
 
-                let key_hints = KeyHints {
-                    extract_to_dir_key: alpha_upper(Self::EXTRACT_TO_DIR_KEY),
-                    extract_to_cwd_key: alpha_upper(Self::EXTRACT_TO_CWD_KEY),
-                    mount_state,
-                };
+            frame.render_widget(status, layout[4]);
+        }
 
-                frame.render_widget(key_hints, pad_rect_horiz(layout[3], 1));
-            }
-            PanelState::Extracting(extractor) => {
-                let extracted = extractor.extracted.load(Ordering::Relaxed) as f32;
-                let total_ext = extractor.total_to_extract as f32;
-                let pcnt = ((extracted / total_ext) * 100.0).round() as u8;
+        if let Some(message) = self.status.lock().current() {
+            frame.render_widget(StatusLine(message), pad_rect_horiz(layout[5], 1));
+        }
 
-                let progress = ProgressBar::new(pcnt);
-                frame.render_widget(progress, layout[3]);
-            }
-            PanelState::Input(state, action) => {
-                let input = Input::new(action.desc());
-                frame.render_stateful_widget(input, layout[3], state);
+        self.draw_bottom_line(&mut state, layout[6], frame);
 
-                if let Some((x, y)) = state.cursor_pos {
-                    frame.set_cursor(x, y);
-                }
-            }
+        if self.pending_notify.lock().is_flashing() {
+            frame.render_widget(Flash, layout[6]);
         }
     }
 }
@@ -267,11 +1928,30 @@ impl<'a, B: Backend> Draw<B> for MainPanel<'a> {
 enum PanelState {
     Free,
     Input(InputState, InputAction),
-    Extracting(Arc<Extractor>),
-    Error(ErrorKind, Error),
+    CommandPalette(CommandPalette),
+    Comparing(Arc<Comparer>),
+    CompareReport(CompareReport),
+    FailureReport(FailureReport),
+    MountChanges(MountChangesReport),
+    RenamePreview(RenamePreview),
+    FindResults(FindResults),
+    Grep(GrepReport),
+    Duplicates(DuplicatesReport),
+    FlatView(FlatView),
+    LargestFiles(LargestFiles),
+    TypeStats(TypeStats),
+    Info(EntryInfo),
+    ConfirmMount(Box<PendingMount>),
+    ConfirmUnmount(Box<BusyMount>),
+    ConfirmExtract(Box<PendingExtract>),
+    Error(ErrorReport),
 }
 
 impl PanelState {
+    fn error(kind: ErrorKind, err: Error) -> Self {
+        Self::Error(ErrorReport::new(kind.desc(), err))
+    }
+
     #[inline(always)]
     fn reset(&mut self) {
         *self = Self::default();
@@ -284,17 +1964,65 @@ impl Default for PanelState {
     }
 }
 
+/// A mount that is waiting for the user to confirm it.
+struct PendingMount {
+    mounted: MountedArchive,
+    path: PathBuf,
+    /// Whether to mount at a new directory inside of `path`.
+    temp_dir: bool,
+    estimate: SpaceEstimate,
+}
+
+/// A mount that couldn't be unmounted since it's still being used.
+struct BusyMount {
+    path: PathBuf,
+    /// The processes that are using the mount, if they could be found.
+    users: Vec<MountUser>,
+}
+
+/// An extraction that is waiting for the user to confirm it.
+struct PendingExtract {
+    extractor: Extractor,
+    path: PathBuf,
+    estimate: SpaceEstimate,
+}
+
 #[derive(Copy, Clone)]
 enum InputAction {
     Extract,
+    /// Extract the viewed directory, which is only asked for when where it would be extracted to is taken by a file.
+    ExtractDir,
     Mount,
+    Compare,
+    Rename,
+    Filter,
+    Search,
+    /// Narrow the viewed directory down to entries whose name contains the input as it's typed.
+    Narrow,
+    Find,
+    /// Go to the directory at the path in the archive that was entered, or to the file there.
+    GoTo,
+    Grep,
+    OpenTab,
+    Select,
 }
 
 impl InputAction {
     fn desc(self) -> &'static str {
         match self {
             Self::Extract => "extract to",
+            Self::ExtractDir => "extract viewed directory to",
             Self::Mount => "mount at",
+            Self::Compare => "compare with",
+            Self::Rename => "rename rule (PATTERN => REPLACEMENT)",
+            Self::Filter => "only extract (GLOB !EXCLUDED_GLOB ...)",
+            Self::Search => "search",
+            Self::Narrow => "filter",
+            Self::Find => "find in archive",
+            Self::GoTo => "go to path",
+            Self::Grep => "search contents for (REGEX)",
+            Self::OpenTab => "open in new tab",
+            Self::Select => "select matching (GLOB !EXCLUDED_GLOB ... or re:REGEX)",
         }
     }
 }
@@ -303,6 +2031,45 @@ impl InputAction {
 enum ErrorKind {
     Extract,
     Mount,
+    Compare,
+    Reload,
+    Rename,
+    Filter,
+    Open,
+    Grep,
+    OpenTab,
+    Select,
+    Info,
+    GoTo,
+    Duplicates,
+}
+
+impl ErrorKind {
+    fn desc(self) -> &'static str {
+        match self {
+            Self::Extract => "Error Extracting Archive",
+            Self::Mount => "Error Mounting Archive",
+            Self::Compare => "Error Comparing Archive",
+            Self::Reload => "Error Reloading Archive",
+            Self::Rename => "Error Parsing Rename Rule",
+            Self::Filter => "Error Parsing Glob Patterns",
+            Self::Open => "Error Opening File",
+            Self::Grep => "Error Searching Contents",
+            Self::OpenTab => "Error Opening Archive",
+            Self::Select => "Error Parsing Selection Pattern",
+            Self::Info => "Error Reading Entry",
+            Self::GoTo => "Error Going To Path",
+            Self::Duplicates => "Error Finding Duplicates",
+        }
+    }
+}
+
+/// Returns the name of the given `archive`'s file, which is how it's referred to in the interface.
+fn archive_name(archive: &Archive) -> Cow<str> {
+    archive
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default()
 }
 
 // TODO: use char::to_ascii_uppercase if/when it's made a const fn
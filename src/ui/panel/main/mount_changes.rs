@@ -0,0 +1,82 @@
+use super::report::{Report, ReportList};
+use crate::{
+    archive::mount::{MountChange, MountChanges},
+    ui::colors,
+    util::number,
+};
+use crossterm::event::KeyCode;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::Widget,
+};
+
+/// Scrollable list of files that were changed through a writable mount.
+pub struct MountChangesReport {
+    changes: MountChanges,
+    list: ReportList,
+}
+
+impl MountChangesReport {
+    pub fn new(changes: MountChanges) -> Self {
+        Self {
+            changes,
+            list: ReportList::scrolled(),
+        }
+    }
+
+    pub fn process_key(&mut self, key: KeyCode) {
+        self.list.process_key(key, self.changes.changes.len());
+    }
+
+    fn header(&self) -> String {
+        match self.changes.changes.len() {
+            1 => "1 Entry Changed While Mounted".into(),
+            num => format!(
+                "{} Entries Changed While Mounted",
+                number::grouped(num as u64)
+            ),
+        }
+    }
+}
+
+impl Widget for &MountChangesReport {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Report::new(
+            &self.list,
+            self.header(),
+            Color::Yellow,
+            &self.changes.changes,
+            |change, area, buf, _| {
+                let (symbol, color) = match change {
+                    MountChange::Added(_) => ("+ ", Color::Green),
+                    MountChange::Modified(_) => ("~ ", Color::Yellow),
+                    MountChange::Removed(_) => ("- ", Color::Red),
+                };
+
+                let (x, _) = buf.set_stringn(
+                    area.x,
+                    area.y,
+                    symbol,
+                    area.width as usize,
+                    Style::default().fg(color).add_modifier(Modifier::BOLD),
+                );
+
+                buf.set_stringn(
+                    x,
+                    area.y,
+                    change.path().to_string_lossy(),
+                    area.right().saturating_sub(x) as usize,
+                    Style::default().fg(colors::WHITE),
+                );
+            },
+        )
+        // Changes can't be written back into the archive yet
+        .detail(format!(
+            "Changed files were kept in {}",
+            self.changes.dir.display()
+        ))
+        .render(area, buf);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{
+    archive::mount::MountStats,
+    ui::util::SimpleText,
+    util::{number, size},
+};
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use tui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    widgets::Widget,
+};
+
+/// Row that shows where the archive is mounted and how well reading from it is being cached.
+pub struct MountStatus<'a> {
+    pub path: &'a Path,
+    pub stats: &'a MountStats,
+}
+
+impl<'a> MountStatus<'a> {
+    const COLOR: Color = Color::Cyan;
+
+    fn cache_text(&self) -> String {
+        let stats = self.stats;
+
+        let entries = match stats.cached_entries.load(Ordering::Relaxed) {
+            1 => "1 entry".into(),
+            num => format!("{} entries", number::grouped(num as u64)),
+        };
+
+        let cached = format!(
+            "{} cached [{}/{}]",
+            entries,
+            size::formatted_extra_compact(stats.cached_bytes.load(Ordering::Relaxed)),
+            size::formatted_extra_compact(stats.max_cached_bytes),
+        );
+
+        let cached = match stats.spilled_bytes.load(Ordering::Relaxed) {
+            0 => cached,
+            spilled => format!(
+                "{} + {} on disk",
+                cached,
+                size::formatted_extra_compact(spilled)
+            ),
+        };
+
+        let reads = number::grouped(stats.reads.load(Ordering::Relaxed));
+
+        match stats.hit_rate() {
+            Some(hit_rate) => format!("{}  {} reads [{}% hits]", cached, reads, hit_rate.round()),
+            None => format!("{}  {} reads", cached, reads),
+        }
+    }
+}
+
+impl<'a> Widget for MountStatus<'a> {
+    fn render(self, rect: Rect, buf: &mut Buffer) {
+        const MARGIN: u16 = 1;
+
+        if rect.width <= MARGIN || rect.height == 0 {
+            return;
+        }
+
+        let cache_text = self.cache_text();
+
+        let layout = Layout::default()
+            .constraints([
+                Constraint::Min(1),
+                Constraint::Length(2),
+                Constraint::Length(cache_text.len() as u16),
+            ])
+            .direction(Direction::Horizontal)
+            .horizontal_margin(MARGIN)
+            .split(rect);
+
+        let style = Style::default().fg(Self::COLOR);
+
+        let path = SimpleText::new(format!("Mounted at {}", self.path.display()))
+            .alignment(Alignment::Left)
+            .style(style);
+
+        path.render(layout[0], buf);
+
+        let cache = SimpleText::new(cache_text)
+            .alignment(Alignment::Right)
+            .style(style);
+
+        cache.render(layout[2], buf);
+    }
+}
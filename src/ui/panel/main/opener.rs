@@ -0,0 +1,84 @@
+use crate::archive::{Archive, ArchiveEntries, NodeID};
+use anyhow::{anyhow, Context, Result};
+use std::env;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Opens entries with the system's default program by extracting them to a temporary directory first.
+///
+/// The temporary directory is removed when this is dropped.
+pub struct Opener {
+    dir: PathBuf,
+}
+
+impl Opener {
+    const DEFAULT_OPENER: &'static str = "xdg-open";
+
+    pub fn new() -> Self {
+        let dir = env::temp_dir().join(format!("vear-{}", std::process::id()));
+        Self { dir }
+    }
+
+    /// Returns the path the given `id` should be extracted to.
+    ///
+    /// Each entry gets its own directory so entries with the same name don't overwrite each other
+    /// while they're still open.
+    pub fn path_for(&self, files: &ArchiveEntries, id: NodeID) -> PathBuf {
+        // Names like ".." could otherwise point outside of the directory
+        let name = Path::new(&files[id].name)
+            .file_name()
+            .unwrap_or_else(|| "entry".as_ref());
+
+        self.dir.join(format!("{}", *id)).join(name)
+    }
+
+    /// Extract the entry with the given `id` to the given `path` and open it with the program in the
+    /// `OPENER` environment variable, or `xdg-open` if it isn't set.
+    ///
+    /// This blocks until the program exits.
+    pub fn open(archive: &Archive, files: &ArchiveEntries, id: NodeID, path: &Path) -> Result<()> {
+        if !path.exists() {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| anyhow!("failed to create {}", parent.display()))?;
+            }
+
+            let mut file = File::create(path)
+                .with_context(|| anyhow!("failed to create {}", path.display()))?;
+
+            // A partially written file would otherwise be opened as-is next time
+            if let Err(err) = archive.write_entry(&files[id], &mut file) {
+                fs::remove_file(path).ok();
+                return Err(err);
+            }
+        }
+
+        let opener = env::var_os("OPENER").unwrap_or_else(|| OsString::from(Self::DEFAULT_OPENER));
+
+        let status = Command::new(&opener)
+            .arg(path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .with_context(|| anyhow!("failed to run {}", opener.to_string_lossy()))?;
+
+        if !status.success() {
+            return Err(anyhow!(
+                "{} exited with {}",
+                opener.to_string_lossy(),
+                status
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Opener {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.dir).ok();
+    }
+}
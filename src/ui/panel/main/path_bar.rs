@@ -0,0 +1,148 @@
+use crate::ui::colors;
+use std::path::Path;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::Widget,
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Line that shows where the viewed directory is, starting with the name of the archive.
+///
+/// Directories in the middle of the path are left out when it doesn't fit, since the archive and the
+/// directories closest to the viewed one say the most about where it is.
+pub struct PathBar<'a> {
+    pub archive_name: &'a str,
+    /// The path of the viewed directory from the root of the archive.
+    pub path: &'a Path,
+}
+
+impl<'a> PathBar<'a> {
+    const SEPARATOR: &'static str = "/";
+    const ELLIPSIS: &'static str = "…";
+
+    /// Returns every part of the path from the archive name to the viewed directory.
+    fn visible_parts(&self) -> Vec<Part> {
+        let mut parts = vec![Part::Archive(self.archive_name.to_string())];
+
+        parts.extend(
+            self.path
+                .iter()
+                .map(|name| Part::Directory(name.to_string_lossy().into_owned())),
+        );
+
+        parts
+    }
+
+    /// Leave out directories after the archive name until the `parts` fit in `width` columns.
+    fn shorten(mut parts: Vec<Part>, width: usize) -> Vec<Part> {
+        while Part::total_width(&parts) > width && parts.len() > 2 {
+            // The first directory after the archive name becomes the marker for every left out directory
+            if let Part::Omitted = parts[1] {
+                // The viewed directory itself is never left out
+                if parts.len() == 3 {
+                    break;
+                }
+
+                parts.remove(2);
+            } else {
+                parts[1] = Part::Omitted;
+            }
+        }
+
+        if Part::total_width(&parts) <= width {
+            return parts;
+        }
+
+        // Only the innermost part is left, which has to be cut short to fit
+        let last = match parts.pop() {
+            Some(Part::Archive(name)) => Part::Archive(truncate_start(&name, width)),
+            Some(Part::Directory(name)) => Part::Directory(truncate_start(&name, width)),
+            _ => Part::Omitted,
+        };
+
+        vec![last]
+    }
+}
+
+enum Part {
+    Archive(String),
+    Directory(String),
+    /// Stands in for directories that were left out to make the path fit.
+    Omitted,
+}
+
+impl Part {
+    fn text(&self) -> &str {
+        match self {
+            Self::Archive(name) | Self::Directory(name) => name,
+            Self::Omitted => PathBar::ELLIPSIS,
+        }
+    }
+
+    /// Returns the number of columns the given `parts` take up with a separator between each of them.
+    fn total_width(parts: &[Self]) -> usize {
+        let names_width = parts.iter().map(|part| part.text().width()).sum::<usize>();
+
+        names_width + parts.len().saturating_sub(1) * PathBar::SEPARATOR.len()
+    }
+}
+
+impl<'a> Widget for PathBar<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+
+        let parts = Self::shorten(self.visible_parts(), area.width as usize);
+
+        let archive_style = Style::default()
+            .fg(colors::WHITE)
+            .add_modifier(Modifier::BOLD);
+
+        let dir_style = Style::default().fg(Color::LightBlue);
+        let separator_style = Style::default().fg(Color::DarkGray);
+
+        let mut x = area.x;
+
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                let remaining = area.right().saturating_sub(x) as usize;
+                x = buf
+                    .set_stringn(x, area.y, Self::SEPARATOR, remaining, separator_style)
+                    .0;
+            }
+
+            let style = match part {
+                Part::Archive(_) => archive_style,
+                Part::Directory(_) => dir_style,
+                Part::Omitted => separator_style,
+            };
+
+            let remaining = area.right().saturating_sub(x) as usize;
+            x = buf.set_stringn(x, area.y, part.text(), remaining, style).0;
+        }
+    }
+}
+
+/// Cut the start off of the given `text` so it fits in `width` columns, marking that it was cut with an ellipsis.
+fn truncate_start(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+
+    let mut kept_width = 0;
+
+    let start = text
+        .char_indices()
+        .rev()
+        .take_while(|(_, ch)| {
+            kept_width += ch.width().unwrap_or(0);
+            kept_width < width
+        })
+        .last()
+        .map_or(text.len(), |(i, _)| i);
+
+    format!("{}{}", PathBar::ELLIPSIS, &text[start..])
+}
@@ -0,0 +1,823 @@
+use crate::{
+    archive::{
+        document::DocumentInfo,
+        grep::{self, Grepper},
+        media::MediaInfo,
+        nested::NestedListing,
+        Archive, ArchiveEntries, ArchiveEntry, EntryProperties, NodeID,
+    },
+    ui::{colors, Graphics},
+    util::{number, size},
+};
+use async_std::task;
+use chardetng::EncodingDetector;
+use encoding_rs::Encoding;
+use image::{io::Reader as ImageReader, DynamicImage, GenericImageView, ImageFormat};
+use parking_lot::{Mutex, MutexGuard};
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+
+/// The start of the highlighted file's content, which is shown where the entries of a directory would be.
+///
+/// Binary files are shown as a hex dump with the printable bytes next to each line, and text files are shown as
+/// they are in whichever encoding they seem to be in. Either one can be switched to the other in case the guess was
+/// wrong, and so can the encoding. Images are drawn by the terminal when it's able to, and described otherwise.
+/// Archives have their top-level entries listed, and audio, video, and document files have the details in their
+/// metadata shown.
+///
+/// The file is read in the background, since large images and archives can take a while to decompress.
+pub struct Preview {
+    id: NodeID,
+    /// The content of the file once it's been read.
+    content: Arc<Mutex<Option<PreviewContent>>>,
+    /// Set when the preview is no longer needed, so reading it can stop early.
+    cancelled: Arc<AtomicBool>,
+    started_at: Instant,
+    /// Whether the file is shown as a hex dump, if it's been switched from how it's shown by default.
+    hex: Option<bool>,
+    /// The encoding text is shown in, if it's been switched from the one that was detected.
+    encoding: Option<&'static Encoding>,
+    /// How many lines the preview is scrolled down by.
+    scroll: usize,
+    /// The area the image was last encoded for, along with the escape sequences that draw it there.
+    encoded: Option<(Rect, Vec<u8>)>,
+}
+
+impl Preview {
+    /// How many spaces a tab is drawn as.
+    const TAB_WIDTH: usize = 4;
+
+    /// The fewest and most bytes that are drawn on each line of a hex dump.
+    const MIN_HEX_LINE_BYTES: usize = 4;
+    const MAX_HEX_LINE_BYTES: usize = 16;
+
+    const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+    const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
+
+    /// Start reading the file with the given `id` in `files` from the `archive` in the background.
+    ///
+    /// Images are read in full so they can be drawn with the given `graphics`, unless the terminal can't draw them.
+    pub fn load(
+        archive: Arc<Archive>,
+        files: Arc<ArchiveEntries>,
+        id: NodeID,
+        graphics: Graphics,
+    ) -> Self {
+        let content = Arc::new(Mutex::new(None));
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        {
+            let content = Arc::clone(&content);
+            let cancelled = Arc::clone(&cancelled);
+
+            task::spawn(async move {
+                let read = PreviewContent::read(&archive, &files[id], graphics, &cancelled);
+                *content.lock() = Some(read);
+            });
+        }
+
+        Self {
+            id,
+            content,
+            cancelled,
+            started_at: Instant::now(),
+            hex: None,
+            encoding: None,
+            scroll: 0,
+            encoded: None,
+        }
+    }
+
+    /// Returns the ID of the file that is previewed.
+    #[inline(always)]
+    pub fn id(&self) -> NodeID {
+        self.id
+    }
+
+    /// Returns true if the file is still being read.
+    #[inline(always)]
+    pub fn is_loading(&self) -> bool {
+        self.content.lock().is_none()
+    }
+
+    /// Switch between showing the file as a hex dump and as text, or as an image or list of entries if it's one of
+    /// those.
+    pub fn toggle_hex(&mut self) {
+        let content = self.content.lock();
+
+        if let Some(content) = &*content {
+            self.hex = Some(!self.hex.unwrap_or(content.hex));
+            self.scroll = 0;
+        }
+    }
+
+    /// Show the file as text in the next encoding after the one it's shown in now.
+    pub fn cycle_encoding(&mut self) {
+        let content = self.content.lock();
+
+        if let Some(content) = &*content {
+            let encodings = Self::encodings();
+            let current = self.encoding.unwrap_or(content.encoding);
+
+            // Detected encodings that aren't in the list go back to the start of it
+            let next = encodings
+                .iter()
+                .position(|&encoding| encoding == current)
+                .map_or(0, |pos| (pos + 1) % encodings.len());
+
+            self.encoding = Some(encodings[next]);
+            self.hex = Some(false);
+        }
+    }
+
+    /// Returns the encodings that text can be switched between, with the most common ones first.
+    fn encodings() -> [&'static Encoding; 17] {
+        use encoding_rs::{
+            BIG5, EUC_JP, EUC_KR, GB18030, ISO_8859_15, KOI8_R, SHIFT_JIS, UTF_16BE, UTF_16LE,
+            UTF_8, WINDOWS_1250, WINDOWS_1251, WINDOWS_1252, WINDOWS_1253, WINDOWS_1254,
+            WINDOWS_1255, WINDOWS_1256,
+        };
+
+        [
+            UTF_8,
+            UTF_16LE,
+            UTF_16BE,
+            WINDOWS_1252,
+            ISO_8859_15,
+            WINDOWS_1250,
+            WINDOWS_1251,
+            KOI8_R,
+            WINDOWS_1253,
+            WINDOWS_1254,
+            WINDOWS_1255,
+            WINDOWS_1256,
+            SHIFT_JIS,
+            EUC_JP,
+            GB18030,
+            BIG5,
+            EUC_KR,
+        ]
+    }
+
+    /// Scroll the preview up or down by a line, depending on whether `down` is true.
+    ///
+    /// How far it can go is worked out when it's drawn, since that depends on how big it's drawn.
+    pub fn scroll(&mut self, down: bool) {
+        if down {
+            self.scroll += 1;
+        } else {
+            self.scroll = self.scroll.saturating_sub(1);
+        }
+    }
+
+    /// Keep the preview from being scrolled past its last line when it's drawn in the given `area`.
+    pub fn fit_scroll(&mut self, area: Rect) {
+        let content = self.content.lock();
+
+        let num_lines = match &*content {
+            Some(content) => self.view(content).num_lines(area),
+            None => 0,
+        };
+
+        self.scroll = self
+            .scroll
+            .min(num_lines.saturating_sub(area.height as usize));
+    }
+
+    /// Returns the part of the given `area` the terminal should draw the image in when the preview is drawn there,
+    /// or None if an image isn't being shown.
+    pub fn graphic_area(&self, graphics: Graphics, area: Rect) -> Option<Rect> {
+        if graphics == Graphics::None || area.height < 2 {
+            return None;
+        }
+
+        let content = self.content.lock();
+
+        let details = match self.view(content.as_ref()?) {
+            View::Image(image, details) if image.decoded.is_some() => details.len() as u16,
+            _ => return None,
+        };
+
+        // The lines above the image describe it
+        let used = (1 + details).min(area.height - 1);
+
+        Some(Rect {
+            y: area.y + used,
+            height: area.height - used,
+            ..area
+        })
+    }
+
+    /// Returns the escape sequences that make the terminal draw the image in the given `area`, which should come
+    /// from [`Preview::graphic_area`].
+    pub fn graphic(&mut self, graphics: Graphics, area: Rect) -> Option<&[u8]> {
+        // Scaling and encoding the image is slow, so it's only done again when it's drawn somewhere else
+        if self.encoded.as_ref().map(|(encoded_area, _)| *encoded_area) != Some(area) {
+            let content = self.content.lock();
+            let image = content.as_ref()?.image.as_ref()?.decoded.as_ref()?;
+            let encoded = graphics.encode(image, area)?;
+
+            drop(content);
+            self.encoded = Some((area, encoded));
+        }
+
+        self.encoded.as_ref().map(|(_, encoded)| encoded.as_slice())
+    }
+
+    /// Returns how the given `content` should be shown.
+    fn view<'a>(&self, content: &'a PreviewContent) -> View<'a> {
+        let bytes = match &content.bytes {
+            Ok(bytes) => bytes,
+            Err(err) => return View::Note(err),
+        };
+
+        if bytes.is_empty() {
+            return View::Note("Empty");
+        }
+
+        if self.hex.unwrap_or(content.hex) {
+            return View::Hex(bytes);
+        }
+
+        let details = content
+            .media
+            .as_ref()
+            .map_or(&[][..], |media| media.details.as_slice());
+
+        match (
+            &content.image,
+            &content.document,
+            &content.listing,
+            &content.media,
+        ) {
+            (Some(image), ..) => View::Image(image, details),
+            (None, Some(Ok(document)), ..) => View::Details(document.kind, &document.details),
+            (None, None, Some(Ok(listing)), _) => View::Listing(listing),
+            (None, Some(Err(err)), ..) | (None, None, Some(Err(err)), _) => View::Note(err),
+            (None, None, None, Some(media)) => View::Details(media.kind, &media.details),
+            (None, None, None, None) => View::Text {
+                bytes,
+                encoding: self.encoding.unwrap_or(content.encoding),
+                detected: content.encoding,
+            },
+        }
+    }
+
+    fn render_loading(&self, area: Rect, buf: &mut Buffer) {
+        let frame = self.started_at.elapsed().as_millis() / Self::SPINNER_INTERVAL.as_millis();
+        let spinner = Self::SPINNER[frame as usize % Self::SPINNER.len()];
+
+        let style = Style::default().fg(Color::DarkGray);
+        let text = format!("{} Loading", spinner);
+        buf.set_stringn(area.x, area.y, text, area.width as usize, style);
+    }
+
+    /// Draw the given `title` with each of the `details` of a file below it, skipping the first `skip` details.
+    fn render_details(
+        title: &str,
+        details: &[(&str, String)],
+        skip: usize,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let width = area.width as usize;
+        let name_style = Style::default().fg(Color::DarkGray);
+        let value_style = Style::default().fg(colors::WHITE);
+
+        buf.set_stringn(area.x, area.y, title, width, name_style);
+
+        // The values line up after the longest name
+        let name_width = details
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or(0)
+            + 2;
+
+        let rows = details
+            .iter()
+            .skip(skip)
+            .take(area.height.saturating_sub(1) as usize);
+
+        for (y, (name, value)) in (area.y + 1..).zip(rows) {
+            let (x, _) = buf.set_stringn(area.x, y, name, width, name_style);
+            let x = x.max(area.x + name_width as u16);
+            let remaining = area.right().saturating_sub(x) as usize;
+            buf.set_stringn(x, y, value, remaining, value_style);
+        }
+    }
+
+    /// Draw the top-level entries of an archive under a line that says how many entries it has in total, with the
+    /// size of each one on the right.
+    fn render_listing(&self, listing: &NestedListing, area: Rect, buf: &mut Buffer) {
+        let width = area.width as usize;
+
+        let total = match listing.total_entries {
+            1 => "Archive with 1 entry".into(),
+            num => format!("Archive with {} entries", number::grouped(num as u64)),
+        };
+
+        let note_style = Style::default().fg(Color::DarkGray);
+        buf.set_stringn(area.x, area.y, total, width, note_style);
+
+        let rows = listing
+            .entries
+            .iter()
+            .skip(self.scroll)
+            .take(area.height.saturating_sub(1) as usize);
+
+        for (i, entry) in rows.enumerate() {
+            let y = area.y + 1 + i as u16;
+            let size = size::formatted(entry.size_bytes);
+
+            let (name, color) = if entry.is_dir {
+                (format!("{}/", entry.name), Color::LightBlue)
+            } else {
+                (entry.name.clone(), colors::WHITE)
+            };
+
+            // The name is cut short before it reaches the size
+            let name_width = width.saturating_sub(size.len() + 1);
+            buf.set_stringn(area.x, y, name, name_width, Style::default().fg(color));
+
+            if size.len() < width {
+                let x = area.right() - size.len() as u16;
+                buf.set_stringn(x, y, size, width, note_style);
+            }
+        }
+    }
+
+    /// Draw the given `bytes` as text in the given `encoding`, under a line that says which encoding it is and the
+    /// one that was `detected` if it's different.
+    fn render_text(
+        &self,
+        bytes: &[u8],
+        encoding: &'static Encoding,
+        detected: &'static Encoding,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let (text, _) = encoding.decode_with_bom_removal(bytes);
+        let style = Style::default().fg(colors::WHITE);
+
+        let header = if encoding == detected {
+            format!("{} text", encoding.name())
+        } else {
+            format!("{} text, detected as {}", encoding.name(), detected.name())
+        };
+
+        let note_style = Style::default().fg(Color::DarkGray);
+        buf.set_stringn(area.x, area.y, header, area.width as usize, note_style);
+
+        let lines = text
+            .lines()
+            .skip(self.scroll)
+            .take(area.height.saturating_sub(1) as usize);
+
+        for (y, line) in (area.y + 1..).zip(lines) {
+            let line = line
+                .chars()
+                .flat_map(|ch| {
+                    let (ch, count) = match ch {
+                        '\t' => (' ', Self::TAB_WIDTH),
+                        // Control characters would be interpreted by the terminal instead of drawn
+                        ch if ch.is_control() => ('.', 1),
+                        ch => (ch, 1),
+                    };
+
+                    std::iter::repeat_n(ch, count)
+                })
+                .collect::<String>();
+
+            buf.set_stringn(area.x, y, line, area.width as usize, style);
+        }
+    }
+
+    /// Draw the given `bytes` in lines of an offset, the value of each byte in hex, and the bytes that are
+    /// printable, with as many bytes on each line as fit in the `area`.
+    fn render_hex(&self, bytes: &[u8], area: Rect, buf: &mut Buffer) {
+        let offset_width = Self::hex_offset_width(bytes);
+        let line_bytes = Self::hex_line_bytes(bytes, area);
+
+        let offset_style = Style::default().fg(Color::DarkGray);
+        let hex_style = Style::default().fg(colors::WHITE);
+        let char_style = Style::default().fg(Color::Cyan);
+
+        let lines = bytes
+            .chunks(line_bytes)
+            .enumerate()
+            .skip(self.scroll)
+            .take(area.height as usize);
+
+        for (y, (line, bytes)) in (area.y..).zip(lines) {
+            let offset = format!("{:0width$x}  ", line * line_bytes, width = offset_width);
+
+            let mut hex = String::with_capacity(line_bytes * 3 + 1);
+
+            for byte in bytes {
+                write!(hex, "{:02x} ", byte).ok();
+            }
+
+            // Short lines are padded so their characters line up with the rest
+            hex.push_str(&"   ".repeat(line_bytes - bytes.len()));
+            hex.push(' ');
+
+            let chars = bytes
+                .iter()
+                .map(|&byte| {
+                    if byte.is_ascii_graphic() || byte == b' ' {
+                        byte as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>();
+
+            let width = area.width as usize;
+            let (x, _) = buf.set_stringn(area.x, y, offset, width, offset_style);
+            let remaining = area.right().saturating_sub(x) as usize;
+            let (x, _) = buf.set_stringn(x, y, hex, remaining, hex_style);
+            let remaining = area.right().saturating_sub(x) as usize;
+            buf.set_stringn(x, y, chars, remaining, char_style);
+        }
+    }
+
+    /// Returns how many hex digits the offset of each line of a hex dump of the given `bytes` takes up.
+    fn hex_offset_width(bytes: &[u8]) -> usize {
+        format!("{:x}", bytes.len()).len().max(4)
+    }
+
+    /// Returns how many bytes fit on each line of a hex dump of the given `bytes` in the given `area`.
+    fn hex_line_bytes(bytes: &[u8], area: Rect) -> usize {
+        // Each byte takes up three columns in hex and one as a character, after the offset and two spaces, and a
+        // space between the hex and characters
+        let used = Self::hex_offset_width(bytes) + 3;
+        let fits = (area.width as usize).saturating_sub(used) / 4;
+
+        (fits / 4 * 4).clamp(Self::MIN_HEX_LINE_BYTES, Self::MAX_HEX_LINE_BYTES)
+    }
+}
+
+impl Drop for Preview {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// The previews of the files that were highlighted most recently, so going back to one of them doesn't read it again.
+///
+/// Previews keep how they were switched and scrolled while they're cached. One that's still being read when another
+/// file is highlighted is dropped instead, so moving quickly through a directory doesn't leave every file it passed
+/// being read in the background.
+pub struct PreviewCache {
+    /// The snapshot of the archive's entries the previews were read from, since the IDs of entries can change when
+    /// the archive is reloaded.
+    files: Option<Arc<ArchiveEntries>>,
+    /// The previews, starting with the one that was shown most recently.
+    previews: VecDeque<Preview>,
+}
+
+impl PreviewCache {
+    /// The most previews that are kept at once.
+    const CAPACITY: usize = 16;
+
+    pub fn new() -> Self {
+        Self {
+            files: None,
+            previews: VecDeque::with_capacity(Self::CAPACITY),
+        }
+    }
+
+    /// Returns the preview that was shown most recently.
+    #[inline(always)]
+    pub fn current(&self) -> Option<&Preview> {
+        self.previews.front()
+    }
+
+    /// Returns the preview that was shown most recently.
+    #[inline(always)]
+    pub fn current_mut(&mut self) -> Option<&mut Preview> {
+        self.previews.front_mut()
+    }
+
+    /// Returns the preview of the file with the given `id` in `files`, which starts being read from the `archive`
+    /// if it isn't cached.
+    ///
+    /// The cache is cleared first if `files` is a different snapshot than the one the cached previews were read from.
+    pub fn get_or_load(
+        &mut self,
+        archive: &Arc<Archive>,
+        files: &Arc<ArchiveEntries>,
+        id: NodeID,
+        graphics: Graphics,
+    ) -> &mut Preview {
+        let same_files = self
+            .files
+            .as_ref()
+            .is_some_and(|cached| Arc::ptr_eq(cached, files));
+
+        if !same_files {
+            self.clear();
+            self.files = Some(Arc::clone(files));
+        }
+
+        match self.previews.iter().position(|preview| preview.id() == id) {
+            Some(0) => (),
+            Some(pos) => {
+                let preview = self.previews.remove(pos).unwrap();
+                self.drop_unfinished();
+                self.previews.push_front(preview);
+            }
+            None => {
+                self.drop_unfinished();
+
+                let preview = Preview::load(Arc::clone(archive), Arc::clone(files), id, graphics);
+                self.previews.push_front(preview);
+                self.previews.truncate(Self::CAPACITY);
+            }
+        }
+
+        &mut self.previews[0]
+    }
+
+    /// Drop every preview, which stops reading the ones that are still being read.
+    pub fn clear(&mut self) {
+        self.previews.clear();
+        self.files = None;
+    }
+
+    /// Drop the most recently shown preview if it's still being read.
+    fn drop_unfinished(&mut self) {
+        if self.previews.front().is_some_and(Preview::is_loading) {
+            self.previews.pop_front();
+        }
+    }
+}
+
+impl Widget for &Preview {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let content: MutexGuard<_> = self.content.lock();
+
+        let content = match &*content {
+            Some(content) => content,
+            None => return self.render_loading(area, buf),
+        };
+
+        let note_style = Style::default().fg(Color::DarkGray);
+
+        match self.view(content) {
+            View::Text {
+                bytes,
+                encoding,
+                detected,
+            } => self.render_text(bytes, encoding, detected, area, buf),
+            View::Hex(bytes) => self.render_hex(bytes, area, buf),
+            // The terminal draws the image itself below this after the rest of the interface is drawn
+            View::Image(image, details) => {
+                Preview::render_details(&image.desc(), details, 0, area, buf);
+            }
+            View::Details(kind, details) => {
+                Preview::render_details(kind, details, self.scroll, area, buf);
+            }
+            View::Listing(listing) => self.render_listing(listing, area, buf),
+            View::Note(note) => {
+                buf.set_stringn(area.x, area.y, note, area.width as usize, note_style);
+            }
+        }
+    }
+}
+
+/// How the content of a file is shown.
+enum View<'a> {
+    /// Text in the given `encoding`, along with the one that was `detected`.
+    Text {
+        bytes: &'a [u8],
+        encoding: &'static Encoding,
+        detected: &'static Encoding,
+    },
+    Hex(&'a [u8]),
+    /// An image, along with the details of it from its metadata.
+    Image(&'a ImagePreview, &'a [(&'static str, String)]),
+    /// What kind of file it is, along with the details of it from its metadata.
+    Details(&'a str, &'a [(&'static str, String)]),
+    Listing(&'a NestedListing),
+    /// A line that's shown instead of the content, like why it couldn't be read.
+    Note(&'a str),
+}
+
+impl<'a> View<'a> {
+    /// Returns how many lines the content takes up when it's drawn in the given `area`.
+    fn num_lines(&self, area: Rect) -> usize {
+        match self {
+            // The first line says which encoding the text is in
+            Self::Text {
+                bytes, encoding, ..
+            } => {
+                let (text, _) = encoding.decode_with_bom_removal(bytes);
+                text.lines().count() + 1
+            }
+            Self::Hex(bytes) => {
+                let line_bytes = Preview::hex_line_bytes(bytes, area);
+                bytes.len().div_ceil(line_bytes)
+            }
+            // The first line says how many entries there are
+            Self::Listing(listing) => listing.entries.len() + 1,
+            // The first line says what kind of file it is
+            Self::Details(_, details) => details.len() + 1,
+            // Images aren't scrolled, since the terminal draws them in a fixed spot
+            Self::Image(..) | Self::Note(_) => 1,
+        }
+    }
+}
+
+/// What was read of a file to preview it.
+struct PreviewContent {
+    /// The start of the file, or why it couldn't be read.
+    bytes: Result<Vec<u8>, String>,
+    /// Details of the file if it's an image.
+    image: Option<ImagePreview>,
+    /// The entries of the file if it's an archive, or why they couldn't be read.
+    listing: Option<Result<NestedListing, String>>,
+    /// The details in the headers of the file if it's audio, video, or an image.
+    media: Option<MediaInfo>,
+    /// The details in the metadata of the file if it's a PDF or office document, or why they couldn't be read.
+    document: Option<Result<DocumentInfo, String>>,
+    /// Whether the file is shown as a hex dump by default, which it is if it looks binary.
+    hex: bool,
+    /// The encoding the file seems to be in if it's text.
+    encoding: &'static Encoding,
+}
+
+impl PreviewContent {
+    /// The most that is read from the start of a file.
+    const MAX_BYTES: u64 = 64 * 1024;
+
+    /// Read the start of the given file `entry` from the `archive`, along with anything else about it that suits
+    /// the kind of file it is.
+    ///
+    /// Only the start of the file is read once the preview has been `cancelled`.
+    fn read(
+        archive: &Archive,
+        entry: &ArchiveEntry,
+        graphics: Graphics,
+        cancelled: &AtomicBool,
+    ) -> Self {
+        let bytes = match &entry.props {
+            EntryProperties::File(props) if props.encrypted => {
+                Err("Encrypted files can't be previewed".into())
+            }
+            _ => archive
+                .read_start(entry, Self::MAX_BYTES)
+                .map_err(|err| format!("Can't be previewed: {}", err.root_cause())),
+        };
+
+        let cancelled = || cancelled.load(Ordering::Relaxed);
+
+        let image = match &bytes {
+            Ok(bytes) if !cancelled() => ImagePreview::load(archive, entry, bytes, graphics),
+            _ => None,
+        };
+
+        let document = match &bytes {
+            Ok(bytes) if !cancelled() && DocumentInfo::is_document(bytes) => Some(
+                DocumentInfo::read(archive, entry, bytes)
+                    .map_err(|err| format!("Can't read document: {}", err.root_cause())),
+            ),
+            _ => None,
+        };
+
+        // Office documents are archives too, but their metadata is more useful than the files that make them up
+        let listing = match &bytes {
+            Ok(bytes) if !cancelled() && document.is_none() && NestedListing::is_archive(bytes) => {
+                Some(
+                    NestedListing::read(archive, entry)
+                        .map_err(|err| format!("Can't list entries: {}", err.root_cause())),
+                )
+            }
+            _ => None,
+        };
+
+        let media = match &bytes {
+            Ok(bytes) if listing.is_none() && document.is_none() => {
+                MediaInfo::read(bytes, entry.total_size_bytes)
+            }
+            _ => None,
+        };
+
+        let hex = match &bytes {
+            Ok(_)
+                if image.is_some()
+                    || document.is_some()
+                    || listing.is_some()
+                    || media.is_some() =>
+            {
+                false
+            }
+            // UTF-16 text would look binary from all of the zeros in it
+            Ok(bytes) if Encoding::for_bom(bytes).is_some() => false,
+            Ok(bytes) => grep::is_binary(&bytes[..bytes.len().min(Grepper::BINARY_CHECK_BYTES)]),
+            Err(_) => false,
+        };
+
+        let encoding = match &bytes {
+            Ok(bytes) => Self::detect_encoding(bytes, bytes.len() as u64 >= entry.total_size_bytes),
+            Err(_) => encoding_rs::UTF_8,
+        };
+
+        Self {
+            bytes,
+            image,
+            listing,
+            media,
+            document,
+            hex,
+            encoding,
+        }
+    }
+
+    /// Guess which encoding the given `bytes` of text are in, which are `complete` if they're the whole file.
+    fn detect_encoding(bytes: &[u8], complete: bool) -> &'static Encoding {
+        if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+            return encoding;
+        }
+
+        let mut detector = EncodingDetector::new();
+        detector.feed(bytes, complete);
+        detector.guess(None, true)
+    }
+}
+
+/// What's known about a file that's an image.
+struct ImagePreview {
+    format: ImageFormat,
+    /// The width and height of the image in pixels, if they could be read.
+    dimensions: Option<(u32, u32)>,
+    /// The image itself, if the terminal can draw it and it could be decoded.
+    decoded: Option<DynamicImage>,
+}
+
+impl ImagePreview {
+    /// The largest image that is read to be drawn.
+    const MAX_BYTES: u64 = 32 * 1024 * 1024;
+
+    /// Returns a preview of the image whose file starts with the given `bytes`, or None if it's not an image.
+    ///
+    /// The rest of the file is only read when the terminal can draw it with the given `graphics`.
+    fn load(
+        archive: &Archive,
+        entry: &ArchiveEntry,
+        bytes: &[u8],
+        graphics: Graphics,
+    ) -> Option<Self> {
+        let format = image::guess_format(bytes).ok()?;
+
+        // Every RIFF file is guessed to be a WebP image, including audio and video ones
+        if format == ImageFormat::WebP && bytes.get(8..12) != Some(b"WEBP") {
+            return None;
+        }
+
+        let dimensions = ImageReader::with_format(Cursor::new(bytes), format)
+            .into_dimensions()
+            .ok();
+
+        let decoded = if graphics == Graphics::None || entry.total_size_bytes > Self::MAX_BYTES {
+            None
+        } else {
+            archive
+                .read_start(entry, Self::MAX_BYTES)
+                .ok()
+                .and_then(|bytes| image::load_from_memory_with_format(&bytes, format).ok())
+        };
+
+        let dimensions = decoded
+            .as_ref()
+            .map(|image| (image.width(), image.height()))
+            .or(dimensions);
+
+        Some(Self {
+            format,
+            dimensions,
+            decoded,
+        })
+    }
+
+    /// Returns a line that says what kind of image this is and how big it is.
+    fn desc(&self) -> String {
+        let format = format!("{:?}", self.format).to_ascii_uppercase();
+
+        match self.dimensions {
+            Some((width, height)) => format!("{} image, {} x {}", format, width, height),
+            None => format!("{} image", format),
+        }
+    }
+}
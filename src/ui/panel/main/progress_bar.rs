@@ -1,6 +1,6 @@
 use crate::ui::{
     colors,
-    util::{fill_area, text_fragments::TextFragments},
+    util::{fill_area, pad_rect_horiz, text_fragments::TextFragments, SimpleText},
 };
 use smallvec::SmallVec;
 use std::char;
@@ -13,6 +13,7 @@ use tui::{
 
 pub struct ProgressBar {
     progress: u8,
+    detail: Option<String>,
 }
 
 impl ProgressBar {
@@ -23,8 +24,27 @@ impl ProgressBar {
     pub fn new(progress: u8) -> Self {
         Self {
             progress: progress.min(100),
+            detail: None,
         }
     }
+
+    /// Show the given `detail` at the end of the bar if there's room for it.
+    #[inline(always)]
+    pub fn detail(mut self, detail: String) -> Self {
+        self.detail = Some(detail);
+        self
+    }
+
+    /// Create a new [`ProgressBar`] that shows how much of `total` the `done` count covers.
+    pub fn with_counts(done: u32, total: u32) -> Self {
+        let pcnt = if total > 0 {
+            ((done as f32 / total as f32) * 100.0).round() as u8
+        } else {
+            100
+        };
+
+        Self::new(pcnt)
+    }
 }
 
 impl Widget for ProgressBar {
@@ -62,5 +82,19 @@ impl Widget for ProgressBar {
 
         let text = TextFragments::new(&fragments).alignment(Alignment::Center);
         text.render(area, buf);
+
+        // The detail is padded from the edge and shouldn't run into the percentage in the middle,
+        // which is at most 4 characters wide
+        let room = (area.width / 2).saturating_sub(1 + 2 + 1) as usize;
+
+        let detail = match self.detail {
+            Some(detail) if detail.len() < room => detail,
+            Some(_) | None => return,
+        };
+
+        SimpleText::new(detail)
+            .alignment(Alignment::Right)
+            .style(style)
+            .render(pad_rect_horiz(area, 1), buf);
     }
 }
@@ -0,0 +1,119 @@
+use crate::{
+    archive::rename::RenameRule,
+    ui::{colors, util::SimpleText},
+};
+use crossterm::event::KeyCode;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::Widget,
+};
+
+/// Scrollable table of what each entry would be renamed to by a [`RenameRule`] before it's used.
+pub struct RenamePreview {
+    pub rule: Arc<RenameRule>,
+    renamed: Vec<(PathBuf, PathBuf)>,
+    scroll: usize,
+}
+
+impl RenamePreview {
+    const SEPARATOR: &'static str = " -> ";
+
+    pub fn new(rule: Arc<RenameRule>, renamed: Vec<(PathBuf, PathBuf)>) -> Self {
+        Self {
+            rule,
+            renamed,
+            scroll: 0,
+        }
+    }
+
+    pub fn process_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down => {
+                self.scroll = (self.scroll + 1).min(self.renamed.len().saturating_sub(1));
+            }
+            _ => (),
+        }
+    }
+}
+
+impl Widget for &RenamePreview {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let layout = Layout::default()
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Percentage(100),
+            ])
+            .direction(Direction::Vertical)
+            .margin(1)
+            .split(area);
+
+        let header = SimpleText::new("Rename Preview")
+            .alignment(Alignment::Center)
+            .style(
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            );
+
+        header.render(layout[0], buf);
+
+        let hint =
+            SimpleText::new("Press Enter to use this rule when extracting or Esc to discard it")
+                .alignment(Alignment::Center)
+                .style(Style::default().fg(colors::WHITE));
+
+        hint.render(layout[1], buf);
+
+        let list_area = layout[3];
+        let visible = self
+            .renamed
+            .iter()
+            .skip(self.scroll)
+            .take(list_area.height as usize);
+
+        // Each side gets half of the row so the new paths line up with each other
+        let column_width =
+            (list_area.width as usize).saturating_sub(RenamePreview::SEPARATOR.len()) / 2;
+
+        for (i, (old_path, new_path)) in visible.enumerate() {
+            let y = list_area.y + i as u16;
+
+            let new_color = if old_path == new_path {
+                colors::WHITE
+            } else {
+                Color::Green
+            };
+
+            let (x, _) = buf.set_stringn(
+                list_area.x,
+                y,
+                old_path.to_string_lossy(),
+                column_width,
+                Style::default().fg(colors::WHITE),
+            );
+
+            let x = x.max(list_area.x + column_width as u16);
+            buf.set_string(x, y, RenamePreview::SEPARATOR, Style::default());
+            let x = x + RenamePreview::SEPARATOR.len() as u16;
+
+            let remaining = list_area.right().saturating_sub(x) as usize;
+            let new_path = new_path.to_string_lossy();
+
+            // An empty path means the entry was stripped away, so its contents end up in the output directory itself
+            let new_path = if new_path.is_empty() {
+                "./".into()
+            } else {
+                new_path
+            };
+
+            buf.set_stringn(x, y, new_path, remaining, Style::default().fg(new_color));
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use crate::ui::{colors, util::SimpleText};
+use crossterm::event::KeyCode;
+use std::cell::Cell;
+use tui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::Widget,
+};
+
+/// Where a list of rows shown by a [`Report`] is scrolled to, and which row is highlighted if rows can be.
+pub struct ReportList {
+    index: usize,
+    /// The first visible row, which only moves when the highlighted row would leave the view.
+    scroll: Cell<usize>,
+    highlight: bool,
+}
+
+impl ReportList {
+    /// Create a list where the arrow keys move which row is highlighted.
+    pub fn highlighted() -> Self {
+        Self {
+            index: 0,
+            scroll: Cell::new(0),
+            highlight: true,
+        }
+    }
+
+    /// Create a list where the arrow keys only scroll, for reports that have nothing to go to.
+    pub fn scrolled() -> Self {
+        Self {
+            highlight: false,
+            ..Self::highlighted()
+        }
+    }
+
+    /// Returns the index of the highlighted row, or the first visible one if rows can't be highlighted.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Go back to the first row, such as after the rows were sorted differently.
+    pub fn reset(&mut self) {
+        self.index = 0;
+        self.scroll.set(0);
+    }
+
+    /// Move through a list of `len` rows with the arrow keys.
+    pub fn process_key(&mut self, key: KeyCode, len: usize) {
+        match key {
+            KeyCode::Up => self.index = self.index.saturating_sub(1),
+            KeyCode::Down => self.index = (self.index + 1).min(len.saturating_sub(1)),
+            _ => (),
+        }
+    }
+
+    /// Returns the first row to show in a view that's `height` rows tall, scrolled just enough to keep the
+    /// `highlighted` row in view.
+    fn scroll_to(&self, highlighted: usize, height: usize) -> usize {
+        let scroll = self.scroll.get();
+
+        let scroll = if highlighted < scroll {
+            highlighted
+        } else if highlighted >= scroll + height {
+            (highlighted + 1).saturating_sub(height)
+        } else {
+            scroll
+        };
+
+        self.scroll.set(scroll);
+        scroll
+    }
+}
+
+/// Scrollable list of rows under a title, which every report shown in place of the archive is drawn with.
+///
+/// Each row is drawn by the given function, which is also passed the style the entire row should be drawn with when
+/// it stands out from the rest, like when it's highlighted.
+pub struct Report<'a, T, F>
+where
+    F: Fn(&T, Rect, &mut Buffer, Option<Style>),
+{
+    list: &'a ReportList,
+    title: String,
+    color: Color,
+    detail: Option<String>,
+    heading: Option<T>,
+    rows: &'a [T],
+    highlighted_row: Option<usize>,
+    draw_row: F,
+}
+
+impl<'a, T, F> Report<'a, T, F>
+where
+    F: Fn(&T, Rect, &mut Buffer, Option<Style>),
+{
+    /// Create a new `Report` with the given `title` drawn in `color`, which is also what the highlighted row is drawn
+    /// in.
+    pub fn new<S>(list: &'a ReportList, title: S, color: Color, rows: &'a [T], draw_row: F) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            list,
+            title: title.into(),
+            color,
+            detail: None,
+            heading: None,
+            rows,
+            highlighted_row: Some(list.index).filter(|_| list.highlight),
+            draw_row,
+        }
+    }
+
+    /// Show a line under the title, which usually says what can be done.
+    pub fn detail<S>(mut self, detail: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Show the given `heading` row above the list that stays in place while it's scrolled, like the names of each
+    /// column.
+    pub fn heading(mut self, heading: T) -> Self {
+        self.heading = Some(heading);
+        self
+    }
+
+    /// Highlight the given `row` instead of the list's index, for reports with rows that can't be highlighted
+    /// themselves.
+    pub fn highlighted_row(mut self, row: usize) -> Self {
+        self.highlighted_row = Some(row).filter(|_| self.list.highlight);
+        self
+    }
+}
+
+impl<'a, T, F> Widget for Report<'a, T, F>
+where
+    F: Fn(&T, Rect, &mut Buffer, Option<Style>),
+{
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut constraints = vec![Constraint::Length(1)];
+
+        if self.detail.is_some() {
+            constraints.push(Constraint::Length(1));
+        }
+
+        // Leave a blank line between the title and the list
+        constraints.push(Constraint::Length(1));
+
+        if self.heading.is_some() {
+            constraints.push(Constraint::Length(1));
+        }
+
+        constraints.push(Constraint::Percentage(100));
+
+        let layout = Layout::default()
+            .constraints(constraints)
+            .direction(Direction::Vertical)
+            .margin(1)
+            .split(area);
+
+        let bold = Style::default().fg(self.color).add_modifier(Modifier::BOLD);
+
+        SimpleText::new(self.title)
+            .alignment(Alignment::Center)
+            .style(bold)
+            .render(layout[0], buf);
+
+        if let Some(detail) = self.detail {
+            SimpleText::new(detail)
+                .alignment(Alignment::Center)
+                .style(Style::default().fg(colors::WHITE))
+                .render(layout[1], buf);
+        }
+
+        let list_area = layout[layout.len() - 1];
+
+        if let Some(heading) = &self.heading {
+            (self.draw_row)(heading, layout[layout.len() - 2], buf, Some(bold));
+        }
+
+        let height = list_area.height as usize;
+
+        let scroll = match self.highlighted_row {
+            Some(row) => self.list.scroll_to(row, height),
+            None => self.list.index,
+        };
+
+        let highlight_style = Style::default()
+            .fg(colors::BLACK)
+            .bg(self.color)
+            .add_modifier(Modifier::BOLD);
+
+        let visible = self.rows.iter().enumerate().skip(scroll).take(height);
+
+        for (i, (index, row)) in visible.enumerate() {
+            let area = Rect {
+                y: list_area.y + i as u16,
+                height: 1,
+                ..list_area
+            };
+
+            let style = if self.highlighted_row == Some(index) {
+                buf.set_style(area, highlight_style);
+                Some(highlight_style)
+            } else {
+                None
+            };
+
+            (self.draw_row)(row, area, buf, style);
+        }
+    }
+}
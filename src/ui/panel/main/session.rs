@@ -0,0 +1,154 @@
+use super::history;
+use crate::ui::panel::files::{PathViewer, SortColumn, SortOrder};
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where an archive was left off when it was last viewed, which is kept across runs so viewing it again picks up
+/// from there.
+///
+/// Entries are referred to by their path in the archive, since the archive may have changed since it was viewed.
+pub struct Session {
+    /// The path of the directory that was being viewed.
+    directory: String,
+    /// The path of the highlighted entry, if the directory wasn't empty.
+    highlighted: Option<String>,
+    sort: SortOrder,
+    /// The path of every selected entry.
+    marked: Vec<String>,
+}
+
+impl Session {
+    /// Remember where the given `path_viewer` is.
+    pub fn of(path_viewer: &PathViewer) -> Self {
+        let files = path_viewer.files();
+        let path_of = |id| files.path_of(id).to_string_lossy().into_owned();
+
+        Self {
+            directory: path_of(path_viewer.directory()),
+            highlighted: path_viewer.highlighted().map(|entry| path_of(entry.id)),
+            sort: path_viewer.sort(),
+            marked: path_viewer.marked_paths(),
+        }
+    }
+
+    /// Go back to where this session left off in the given `path_viewer`.
+    ///
+    /// Anything that no longer exists in the archive is skipped.
+    pub fn restore(&self, path_viewer: &mut PathViewer) {
+        path_viewer.sort_by(self.sort);
+        path_viewer.mark_paths(&self.marked);
+
+        let files = path_viewer.files();
+
+        let highlighted = self
+            .highlighted
+            .as_ref()
+            .and_then(|path| files.find_path(path));
+
+        if let Some(id) = highlighted {
+            path_viewer.reveal(id);
+        } else if let Some(id) = files.find_path(&self.directory) {
+            path_viewer.view(id);
+        }
+    }
+
+    /// Load the session of the archive at the given `archive_path` from the user's data directory, if there is one.
+    ///
+    /// A session that can't be read is treated as if there wasn't one, as it's only a convenience.
+    pub fn load(archive_path: &Path) -> Option<Self> {
+        let (archive_path, file) = Self::file_of(archive_path)?;
+        let contents = fs::read_to_string(file).ok()?;
+        let mut lines = contents.lines();
+
+        // Files are named after a hash of the archive's path, so a different archive could have the same one
+        if lines.next()?.strip_prefix("archive ")? != archive_path {
+            return None;
+        }
+
+        let mut session = Self {
+            directory: String::new(),
+            highlighted: None,
+            sort: SortOrder::default(),
+            marked: Vec::new(),
+        };
+
+        for line in lines {
+            let (key, value) = match line.split_once(' ') {
+                Some(pair) => pair,
+                None => continue,
+            };
+
+            match key {
+                "directory" => session.directory = value.into(),
+                "highlighted" => session.highlighted = Some(value.into()),
+                "sort" => session.sort = Self::parse_sort(value).unwrap_or_default(),
+                "marked" => session.marked.push(value.into()),
+                _ => (),
+            }
+        }
+
+        Some(session)
+    }
+
+    /// Save the session as the one of the archive at the given `archive_path`.
+    pub fn save(&self, archive_path: &Path) -> Result<()> {
+        let (archive_path, file) = match Self::file_of(archive_path) {
+            Some(paths) => paths,
+            None => return Ok(()),
+        };
+
+        let mut lines = vec![
+            format!("archive {}", archive_path),
+            format!("directory {}", self.directory),
+        ];
+
+        if let Some(highlighted) = &self.highlighted {
+            lines.push(format!("highlighted {}", highlighted));
+        }
+
+        let direction = if self.sort.descending { "desc" } else { "asc" };
+        lines.push(format!("sort {} {}", self.sort.column.desc(), direction));
+
+        // Paths are stored one per line, so any with a line break in them can't be kept
+        let marked = self.marked.iter().filter(|path| !path.contains('\n'));
+        lines.extend(marked.map(|path| format!("marked {}", path)));
+
+        history::write_lines(&file, lines.into_iter())
+    }
+
+    /// Returns the absolute path of the archive at the given `archive_path` and the file its session is kept in.
+    fn file_of(archive_path: &Path) -> Option<(String, PathBuf)> {
+        let archive_path = history::absolute(archive_path)?;
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(archive_path.as_bytes());
+
+        let name = format!("{:08x}", hasher.finalize());
+        let file = history::data_dir()?.join("sessions").join(name);
+
+        Some((archive_path, file))
+    }
+
+    /// Parse a sort order written as a column name followed by `asc` or `desc`, like `Size desc`.
+    fn parse_sort(value: &str) -> Option<SortOrder> {
+        let (column, direction) = value.split_once(' ')?;
+
+        let column = [
+            SortColumn::Name,
+            SortColumn::Size,
+            SortColumn::Date,
+            SortColumn::Ratio,
+        ]
+        .iter()
+        .copied()
+        .find(|candidate| candidate.desc() == column)?;
+
+        let descending = match direction {
+            "asc" => false,
+            "desc" => true,
+            _ => return None,
+        };
+
+        Some(SortOrder { column, descending })
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{
+    archive::{ArchiveEntries, NodeID},
+    ui::{colors, util::SimpleText},
+    util::number,
+};
+use crossterm::event::KeyCode;
+use smallvec::SmallVec;
+use tui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::Widget,
+};
+
+/// Entries that have been pinned from anywhere in the archive so they can be extracted together.
+pub struct Staging {
+    entries: Vec<StagedEntry>,
+    index: usize,
+    /// Whether the staged entries are being shown in place of the current directory.
+    pub viewing: bool,
+}
+
+impl Staging {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            index: 0,
+            viewing: false,
+        }
+    }
+
+    /// Pin the given `ids`, or unpin them if every one of them is already pinned.
+    pub fn toggle(&mut self, files: &ArchiveEntries, ids: &[NodeID]) {
+        if ids.iter().all(|&id| self.contains(id)) {
+            self.entries.retain(|entry| !ids.contains(&entry.id));
+            self.index = self.index.min(self.entries.len().saturating_sub(1));
+            return;
+        }
+
+        for &id in ids {
+            if self.contains(id) {
+                continue;
+            }
+
+            self.entries.push(StagedEntry {
+                id,
+                path: files.path_of(id).to_string_lossy().into_owned(),
+            });
+        }
+
+        self.entries.sort_unstable_by(|x, y| x.path.cmp(&y.path));
+    }
+
+    fn contains(&self, id: NodeID) -> bool {
+        self.entries.iter().any(|entry| entry.id == id)
+    }
+
+    /// Unpin every entry.
+    ///
+    /// This must be done whenever the archive is reloaded, as the pinned node ID's would no longer be valid.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index = 0;
+        self.viewing = false;
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn ids(&self) -> SmallVec<[NodeID; 4]> {
+        self.entries.iter().map(|entry| entry.id).collect()
+    }
+
+    pub fn process_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Up => self.index = self.index.saturating_sub(1),
+            KeyCode::Down => {
+                self.index = (self.index + 1).min(self.entries.len().saturating_sub(1));
+            }
+            _ => (),
+        }
+    }
+
+    /// Unpin the highlighted entry and stop viewing the staged entries if none are left.
+    pub fn remove_highlighted(&mut self) {
+        if self.index < self.entries.len() {
+            self.entries.remove(self.index);
+        }
+
+        self.index = self.index.min(self.entries.len().saturating_sub(1));
+
+        if self.entries.is_empty() {
+            self.viewing = false;
+        }
+    }
+}
+
+struct StagedEntry {
+    id: NodeID,
+    path: String,
+}
+
+impl Widget for &Staging {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let layout = Layout::default()
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Percentage(100),
+            ])
+            .direction(Direction::Vertical)
+            .margin(1)
+            .split(area);
+
+        let header = match self.entries.len() {
+            1 => "1 Entry Pinned".into(),
+            num => format!("{} Entries Pinned", number::grouped(num as u64)),
+        };
+
+        let header = SimpleText::new(header).alignment(Alignment::Center).style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+
+        header.render(layout[0], buf);
+
+        let list_area = layout[2];
+
+        // Keep the highlighted entry in view
+        let scroll = (self.index + 1).saturating_sub(list_area.height as usize);
+
+        let visible = self
+            .entries
+            .iter()
+            .enumerate()
+            .skip(scroll)
+            .take(list_area.height as usize);
+
+        for (i, (index, entry)) in visible.enumerate() {
+            let style = if index == self.index {
+                Style::default()
+                    .fg(colors::BLACK)
+                    .bg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(colors::WHITE)
+            };
+
+            buf.set_stringn(
+                list_area.x,
+                list_area.y + i as u16,
+                &entry.path,
+                list_area.width as usize,
+                style,
+            );
+        }
+    }
+}
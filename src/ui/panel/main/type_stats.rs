@@ -0,0 +1,275 @@
+use super::report::{Report, ReportList};
+use crate::{
+    archive::{ArchiveEntries, EntryProperties, NodeID},
+    ui::colors,
+    util::{number, size},
+};
+use crossterm::event::KeyCode;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Table that sums up the files in the archive by their extension, to see which kinds of files make up most of it
+/// and how well they compress.
+pub struct TypeStats {
+    types: Vec<FileType>,
+    num_files: usize,
+    column: StatsColumn,
+    descending: bool,
+    list: ReportList,
+}
+
+impl TypeStats {
+    const SORT_COLUMN_KEY: char = 'o';
+    const SORT_DIRECTION_KEY: char = 'O';
+
+    /// The space between each column.
+    const SPACING: usize = 2;
+
+    /// What files without an extension are listed as.
+    const NO_EXTENSION: &'static str = "(none)";
+
+    pub fn new(files: &ArchiveEntries) -> Self {
+        let mut types = HashMap::<String, FileType>::new();
+        let mut num_files = 0;
+
+        let entries = files
+            .children_iter(&[NodeID::first()])
+            .filter_map(|(_, entry, _)| match &entry.props {
+                EntryProperties::File(props) => Some((entry, props)),
+                EntryProperties::Directory => None,
+            });
+
+        for (entry, props) in entries {
+            let extension = match entry.extension() {
+                Some(extension) if !extension.is_empty() => extension.to_lowercase(),
+                _ => Self::NO_EXTENSION.into(),
+            };
+
+            let file_type = types
+                .entry(extension)
+                .or_insert_with_key(|extension| FileType::new(extension.clone()));
+
+            file_type.count += 1;
+            file_type.raw_size += props.raw_size_bytes;
+            file_type.compressed_size += props.compressed_size_bytes;
+
+            num_files += 1;
+        }
+
+        let mut stats = Self {
+            types: types.into_values().collect(),
+            num_files,
+            column: StatsColumn::Size,
+            descending: true,
+            list: ReportList::highlighted(),
+        };
+
+        stats.sort();
+        stats
+    }
+
+    fn sort(&mut self) {
+        let (column, descending) = (self.column, self.descending);
+
+        self.types.sort_unstable_by(|x, y| {
+            let ordering = column
+                .compare(x, y)
+                // Types that are equal in the sorted column should still have a predictable order
+                .then_with(|| x.extension.cmp(&y.extension));
+
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+
+        self.list.reset();
+    }
+
+    pub fn process_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Char(Self::SORT_COLUMN_KEY) => {
+                self.column = self.column.next();
+                // Columns of numbers are most useful from the largest down
+                self.descending = self.column != StatsColumn::Type;
+                self.sort();
+            }
+            KeyCode::Char(Self::SORT_DIRECTION_KEY) => {
+                self.descending = !self.descending;
+                self.sort();
+            }
+            key => self.list.process_key(key, self.types.len()),
+        }
+    }
+
+    fn header(&self) -> String {
+        let files = match self.num_files {
+            1 => "1 File".into(),
+            num => format!("{} Files", number::grouped(num as u64)),
+        };
+
+        match self.types.len() {
+            1 => format!("{} Of 1 Type", files),
+            num => format!("{} Of {} Types", files, number::grouped(num as u64)),
+        }
+    }
+
+    /// Returns the title of the given `column`, with an arrow pointing in the direction it's sorted if it's the one
+    /// that's sorted by.
+    fn title(&self, column: StatsColumn) -> String {
+        if column != self.column {
+            return column.desc().into();
+        }
+
+        let arrow = if self.descending { "↓" } else { "↑" };
+        format!("{}{}", column.desc(), arrow)
+    }
+}
+
+/// The files with one extension, all added together.
+struct FileType {
+    extension: String,
+    count: usize,
+    raw_size: u64,
+    compressed_size: u64,
+}
+
+impl FileType {
+    fn new(extension: String) -> Self {
+        Self {
+            extension,
+            count: 0,
+            raw_size: 0,
+            compressed_size: 0,
+        }
+    }
+
+    /// Returns how big the files are in the archive compared to their original size.
+    ///
+    /// Empty files count as not being compressed at all.
+    fn ratio(&self) -> f64 {
+        if self.raw_size == 0 {
+            return 1.0;
+        }
+
+        self.compressed_size as f64 / self.raw_size as f64
+    }
+
+    /// Returns the text of each column of the type's row, in the same order as [`StatsColumn::ALL`].
+    fn cells(&self) -> [String; 5] {
+        [
+            self.extension.clone(),
+            number::grouped(self.count as u64),
+            size::formatted(self.raw_size),
+            size::formatted(self.compressed_size),
+            format!("{:.0}%", self.ratio() * 100.0),
+        ]
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum StatsColumn {
+    Type,
+    Count,
+    Size,
+    Compressed,
+    Ratio,
+}
+
+impl StatsColumn {
+    const ALL: [Self; 5] = [
+        Self::Type,
+        Self::Count,
+        Self::Size,
+        Self::Compressed,
+        Self::Ratio,
+    ];
+
+    fn next(self) -> Self {
+        match self {
+            Self::Type => Self::Count,
+            Self::Count => Self::Size,
+            Self::Size => Self::Compressed,
+            Self::Compressed => Self::Ratio,
+            Self::Ratio => Self::Type,
+        }
+    }
+
+    fn desc(self) -> &'static str {
+        match self {
+            Self::Type => "Type",
+            Self::Count => "Files",
+            Self::Size => "Size",
+            Self::Compressed => "Compressed",
+            Self::Ratio => "Ratio",
+        }
+    }
+
+    fn compare(self, x: &FileType, y: &FileType) -> Ordering {
+        match self {
+            Self::Type => x.extension.cmp(&y.extension),
+            Self::Count => x.count.cmp(&y.count),
+            Self::Size => x.raw_size.cmp(&y.raw_size),
+            Self::Compressed => x.compressed_size.cmp(&y.compressed_size),
+            Self::Ratio => x.ratio().partial_cmp(&y.ratio()).unwrap_or(Ordering::Equal),
+        }
+    }
+}
+
+impl Widget for &TypeStats {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let titles = StatsColumn::ALL.map(|column| self.title(column));
+        let rows = self.types.iter().map(FileType::cells).collect::<Vec<_>>();
+
+        // Every column is as wide as its widest cell, including the title
+        let mut widths = titles.clone().map(|title| title.width());
+
+        for cells in &rows {
+            for (width, cell) in widths.iter_mut().zip(cells) {
+                *width = cell.width().max(*width);
+            }
+        }
+
+        Report::new(
+            &self.list,
+            self.header(),
+            Color::Green,
+            &rows,
+            |cells, area, buf, style| {
+                let style = style.unwrap_or_else(|| Style::default().fg(colors::WHITE));
+                draw_row(cells, &widths, area, buf, style);
+            },
+        )
+        .detail("Press o to sort by the next column, O to reverse the order, or Esc to go back")
+        .heading(titles)
+        .render(area, buf);
+    }
+}
+
+/// Draw the given `cells` of a row of the table in the given `area`, where the first cell is aligned to the left
+/// and the rest are aligned to the right.
+fn draw_row(cells: &[String; 5], widths: &[usize; 5], area: Rect, buf: &mut Buffer, style: Style) {
+    let text = cells
+        .iter()
+        .zip(widths)
+        .enumerate()
+        .map(|(i, (cell, &width))| {
+            if i == 0 {
+                format!("{:<width$}", cell, width = width)
+            } else {
+                format!("{:>width$}", cell, width = width + TypeStats::SPACING)
+            }
+        })
+        .collect::<String>();
+
+    buf.set_style(area, style);
+    buf.set_stringn(area.x, area.y, text, area.width as usize, style);
+}
@@ -1,7 +1,8 @@
 mod files;
 pub mod main;
 
-pub use main::MainPanel;
+pub use files::{IconStyle, PaneRatios};
+pub use main::{ArchiveHistory, MainPanel, RecentArchive};
 
 use anyhow::Result;
 use crossterm::event::KeyCode;
@@ -9,6 +10,23 @@ use tui::backend::Backend;
 use tui::layout::Rect;
 use tui::Frame;
 
+/// An action that can be picked from the command palette, which runs it by pressing the key it's bound to.
+#[derive(Copy, Clone)]
+pub struct Command {
+    pub name: &'static str,
+    pub key: KeyCode,
+}
+
+impl Command {
+    pub const fn new(name: &'static str, key: KeyCode) -> Self {
+        Self { name, key }
+    }
+
+    pub const fn char(name: &'static str, key: char) -> Self {
+        Self::new(name, KeyCode::Char(key))
+    }
+}
+
 pub trait Panel {
     type KeyResult;
 
@@ -0,0 +1,101 @@
+use super::{colors, util::fill_area};
+use tui::{
+    buffer::{Buffer, Cell},
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Widget},
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Popup that asks whether to quit while extractions are running or archives are mounted, since quitting would cut
+/// them short.
+pub struct QuitPrompt<'a> {
+    /// A description of each operation that quitting would cut short.
+    pub operations: &'a [String],
+    /// Whether any of the `operations` are extractions that can be waited on or cancelled.
+    pub has_jobs: bool,
+    /// Whether vear is waiting for the extractions to finish before quitting.
+    pub waiting: bool,
+}
+
+impl<'a> QuitPrompt<'a> {
+    const TITLE: &'static str = " Quit? ";
+    const HEADER: &'static str = "These are still in progress:";
+    const BULLET: &'static str = "• ";
+
+    fn hint(&self) -> &'static str {
+        match (self.waiting, self.has_jobs) {
+            (true, _) => "Waiting for jobs to finish [Q -> quit now, Esc -> stay]",
+            (false, true) => "[W -> wait for jobs, C -> cancel jobs, Q -> quit now, Esc -> stay]",
+            (false, false) => "[Q -> quit anyway, Esc -> stay]",
+        }
+    }
+
+    /// Returns the area the popup should be drawn in so it sits in the middle of the given `area`.
+    pub fn area(&self, area: Rect) -> Rect {
+        let widest_operation = self
+            .operations
+            .iter()
+            .map(|operation| Self::BULLET.width() + operation.width())
+            .max()
+            .unwrap_or(0);
+
+        let widest = widest_operation
+            .max(Self::HEADER.width())
+            .max(self.hint().width());
+
+        // Room for the border and a space on each side
+        let width = (widest as u16 + 4).min(area.width);
+        // Room for the border, the header, and the hint with a blank line before it
+        let height = (self.operations.len() as u16 + 5).min(area.height);
+
+        Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        }
+    }
+}
+
+impl<'a> Widget for QuitPrompt<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        fill_area(area, buf, Cell::reset);
+
+        let block = Block::default()
+            .title(Self::TITLE)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow));
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        if inner.height == 0 || inner.width <= 1 {
+            return;
+        }
+
+        let x = inner.x + 1;
+        let max_width = inner.width.saturating_sub(2) as usize;
+
+        let header_style = Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD);
+
+        buf.set_stringn(x, inner.y, Self::HEADER, max_width, header_style);
+
+        let operation_style = Style::default().fg(colors::WHITE);
+        let max_operations = inner.height.saturating_sub(3) as usize;
+
+        for (i, operation) in self.operations.iter().take(max_operations).enumerate() {
+            let text = format!("{}{}", Self::BULLET, operation);
+            buf.set_stringn(x, inner.y + 1 + i as u16, text, max_width, operation_style);
+        }
+
+        let hint_style = Style::default().fg(Color::Yellow);
+        let hint_y = inner.bottom() - 1;
+
+        if hint_y > inner.y {
+            buf.set_stringn(x, hint_y, self.hint(), max_width, hint_style);
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use super::{
+    colors,
+    event::{self, EventKind, Events},
+    panel::{ArchiveHistory, RecentArchive},
+    util::SimpleText,
+    UI,
+};
+use crate::util::size;
+use anyhow::{anyhow, Context, Result};
+use chrono::{Local, TimeZone};
+use crossterm::event::KeyCode;
+use crossterm::terminal;
+use std::fs;
+use std::io;
+use tui::backend::CrosstermBackend;
+use tui::buffer::Buffer;
+use tui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::widgets::Widget;
+use tui::Terminal;
+use unicode_width::UnicodeWidthStr;
+
+/// Let the user pick one of the archives they opened before, for when they didn't give one to open.
+///
+/// Returns None if the user quit without picking one, or an error if no archive has been opened before.
+pub async fn pick_recent_archive() -> Result<Option<String>> {
+    let history = ArchiveHistory::load();
+
+    if history.archives().is_empty() {
+        return Err(anyhow!("the path of an archive to open is required"));
+    }
+
+    let mut screen = StartScreen::init(&history)?;
+    let result = screen.run().await;
+
+    screen.exit()?;
+    result
+}
+
+/// Screen that lists the archives that were opened before, from the most recently opened one down.
+struct StartScreen {
+    terminal: Terminal<CrosstermBackend<io::Stdout>>,
+    events: Events,
+    list: RecentList,
+}
+
+impl StartScreen {
+    fn init(history: &ArchiveHistory) -> Result<Self> {
+        terminal::enable_raw_mode().context("failed to enable raw mode")?;
+
+        let backend = CrosstermBackend::new(io::stdout());
+        let mut terminal = Terminal::new(backend).context("terminal creation failed")?;
+
+        terminal.clear().context("failed to clear terminal")?;
+
+        terminal
+            .hide_cursor()
+            .context("failed to hide mouse cursor")?;
+
+        Ok(Self {
+            terminal,
+            events: Events::new(),
+            list: RecentList::new(history.archives()),
+        })
+    }
+
+    async fn run(&mut self) -> Result<Option<String>> {
+        loop {
+            let list = &self.list;
+
+            self.terminal.draw(|frame| {
+                frame.render_widget(list, frame.size());
+            })?;
+
+            let key = match self.events.next().await {
+                Ok(Some(EventKind::Key(key))) => key,
+                Ok(_) => continue,
+                Err(event::ErrorKind::ExitRequest) => return Ok(None),
+                Err(event::ErrorKind::Other(err)) => return Err(err),
+            };
+
+            match key {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
+                KeyCode::Enter => return Ok(Some(self.list.highlighted().path.clone())),
+                key => self.list.process_key(key),
+            }
+        }
+    }
+
+    fn exit(mut self) -> Result<()> {
+        self.terminal.clear().ok();
+        UI::restore_terminal(false)
+    }
+}
+
+struct RecentList {
+    archives: Vec<ListedArchive>,
+    index: usize,
+}
+
+impl RecentList {
+    /// The space between the path of an archive and its details.
+    const SPACING: u16 = 2;
+
+    /// Create a list of the given `archives`, which go from the least to the most recently opened.
+    fn new(archives: &[RecentArchive]) -> Self {
+        let archives = archives.iter().rev().map(ListedArchive::new).collect();
+
+        Self { archives, index: 0 }
+    }
+
+    fn highlighted(&self) -> &ListedArchive {
+        &self.archives[self.index]
+    }
+
+    fn process_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Up => self.index = self.index.saturating_sub(1),
+            KeyCode::Down => self.index = (self.index + 1).min(self.archives.len() - 1),
+            KeyCode::Home => self.index = 0,
+            KeyCode::End => self.index = self.archives.len() - 1,
+            _ => (),
+        }
+    }
+}
+
+struct ListedArchive {
+    path: String,
+    /// The size of the archive's file, or None if it no longer exists.
+    size: Option<u64>,
+    opened_at: String,
+}
+
+impl ListedArchive {
+    fn new(archive: &RecentArchive) -> Self {
+        let size = fs::metadata(&archive.path)
+            .ok()
+            .map(|metadata| metadata.len());
+
+        let opened_at = Local
+            .timestamp_opt(archive.opened_at, 0)
+            .single()
+            .map(|date| date.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+
+        Self {
+            path: archive.path.clone(),
+            size,
+            opened_at,
+        }
+    }
+
+    fn size_text(&self) -> String {
+        match self.size {
+            Some(size) => size::formatted(size),
+            None => "missing".into(),
+        }
+    }
+}
+
+impl Widget for &RecentList {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let layout = Layout::default()
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Percentage(100),
+            ])
+            .direction(Direction::Vertical)
+            .margin(1)
+            .split(area);
+
+        let header = SimpleText::new("Recently Opened Archives")
+            .alignment(Alignment::Center)
+            .style(
+                Style::default()
+                    .fg(Color::Blue)
+                    .add_modifier(Modifier::BOLD),
+            );
+
+        header.render(layout[0], buf);
+
+        let hint = SimpleText::new("Press Enter to open the highlighted archive or q to quit")
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(colors::WHITE));
+
+        hint.render(layout[1], buf);
+
+        let list_area = layout[3];
+
+        // Keep the highlighted archive in view
+        let scroll = (self.index + 1).saturating_sub(list_area.height as usize);
+
+        let rows = self
+            .archives
+            .iter()
+            .enumerate()
+            .skip(scroll)
+            .take(list_area.height as usize)
+            .map(|(index, archive)| (index, archive, archive.size_text()))
+            .collect::<Vec<_>>();
+
+        // The sizes get their own column so they line up
+        let size_width = rows
+            .iter()
+            .map(|(_, _, size)| size.width())
+            .max()
+            .unwrap_or(0);
+
+        let date_width = rows
+            .iter()
+            .map(|(_, archive, _)| archive.opened_at.width())
+            .max()
+            .unwrap_or(0);
+
+        let desc_width = (size_width + date_width) as u16 + RecentList::SPACING;
+
+        for (i, (index, archive, size)) in rows.into_iter().enumerate() {
+            let style = if index == self.index {
+                Style::default()
+                    .fg(colors::BLACK)
+                    .bg(Color::Blue)
+                    .add_modifier(Modifier::BOLD)
+            } else if archive.size.is_none() {
+                Style::default().fg(Color::DarkGray)
+            } else {
+                Style::default().fg(colors::WHITE)
+            };
+
+            let y = list_area.y + i as u16;
+
+            let row = Rect {
+                y,
+                height: 1,
+                ..list_area
+            };
+
+            buf.set_style(row, style);
+
+            let desc_x = list_area.right().saturating_sub(desc_width);
+            let max_path_width = desc_x
+                .saturating_sub(list_area.x)
+                .saturating_sub(RecentList::SPACING);
+
+            buf.set_stringn(
+                list_area.x,
+                y,
+                &archive.path,
+                max_path_width as usize,
+                style,
+            );
+
+            if desc_x >= list_area.x + RecentList::SPACING {
+                let desc = format!(
+                    "{:>size$}{:>date$}",
+                    size,
+                    archive.opened_at,
+                    size = size_width,
+                    date = date_width + RecentList::SPACING as usize,
+                );
+
+                buf.set_string(desc_x, y, desc, style);
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use super::colors;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::Widget,
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Line that names each open tab, with the active one highlighted.
+///
+/// Tabs before the active one are left out when they don't all fit, so the active tab is always shown.
+pub struct TabBar<'a> {
+    pub titles: &'a [String],
+    pub active: usize,
+}
+
+impl<'a> TabBar<'a> {
+    /// The space on either side of each title.
+    const PADDING: usize = 1;
+
+    /// The space between each tab.
+    const SPACING: u16 = 1;
+
+    /// Titles longer than this are cut short so a single tab can't take up the whole line.
+    const MAX_TITLE_WIDTH: usize = 32;
+
+    fn tab_width(title: &str) -> usize {
+        title.width().min(Self::MAX_TITLE_WIDTH) + Self::PADDING * 2 + Self::SPACING as usize
+    }
+
+    /// Returns the index of the first tab to draw so the active tab fits in `width` columns.
+    fn first_visible(&self, width: usize) -> usize {
+        let mut used = 0;
+
+        for index in (0..=self.active).rev() {
+            used += Self::tab_width(&self.titles[index]);
+
+            if used > width {
+                return (index + 1).min(self.active);
+            }
+        }
+
+        0
+    }
+}
+
+impl<'a> Widget for TabBar<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+
+        let active_style = Style::default()
+            .fg(colors::BLACK)
+            .bg(colors::WHITE)
+            .add_modifier(Modifier::BOLD);
+
+        let inactive_style = Style::default().fg(Color::Gray).bg(Color::DarkGray);
+
+        let first = self.first_visible(area.width as usize);
+        let mut x = area.x;
+
+        for (index, title) in self.titles.iter().enumerate().skip(first) {
+            let remaining = area.right().saturating_sub(x) as usize;
+
+            if remaining == 0 {
+                break;
+            }
+
+            let style = if index == self.active {
+                active_style
+            } else {
+                inactive_style
+            };
+
+            let padding = " ".repeat(Self::PADDING);
+            let text = format!("{}{}{}", padding, title, padding);
+            let max_width = remaining.min(Self::tab_width(title) - Self::SPACING as usize);
+
+            x = buf.set_stringn(x, area.y, text, max_width, style).0 + Self::SPACING;
+        }
+    }
+}
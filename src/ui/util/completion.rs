@@ -0,0 +1,257 @@
+use super::{fill_area, input::Input};
+use crate::archive::ArchiveEntries;
+use crate::ui::colors;
+use std::fs;
+use std::path::Path;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::Widget,
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Directories that a partially typed path can be completed to.
+pub struct PathCompletion {
+    /// The typed path up to and including its last `/`.
+    dir: String,
+    /// The names of each matching directory in sorted order.
+    candidates: Vec<String>,
+    selected: Option<usize>,
+}
+
+impl PathCompletion {
+    /// Find every directory on the filesystem the given `path` can be completed to.
+    ///
+    /// Relative paths are completed from the current directory.
+    pub fn new(path: &str) -> Self {
+        let (dir, prefix) = split_path(path);
+
+        let search_dir = if dir.is_empty() {
+            Path::new(".")
+        } else {
+            Path::new(dir)
+        };
+
+        let candidates = fs::read_dir(search_dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| {
+                        let entry = entry.ok()?;
+                        let name = entry.file_name().into_string().ok()?;
+
+                        if !is_candidate(&name, prefix) {
+                            return None;
+                        }
+
+                        // This follows symlinks so links to directories can be completed as well
+                        if !entry.path().is_dir() {
+                            return None;
+                        }
+
+                        Some(name)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        Self::with_candidates(dir, candidates)
+    }
+
+    /// Find every directory in the given archive `files` the given `path` can be completed to.
+    ///
+    /// Paths are always completed from the root of the archive, whether they start with a `/` or not.
+    pub fn in_archive(files: &ArchiveEntries, path: &str) -> Self {
+        let (dir, prefix) = split_path(path);
+
+        let candidates = files
+            .find_path(dir)
+            .map(|dir| {
+                files[dir]
+                    .children
+                    .iter()
+                    .map(|&id| &files[id])
+                    .filter(|entry| entry.props.is_dir() && is_candidate(&entry.name, prefix))
+                    .map(|entry| entry.name.clone())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        Self::with_candidates(dir, candidates)
+    }
+
+    fn with_candidates(dir: &str, mut candidates: Vec<String>) -> Self {
+        candidates.sort_unstable();
+
+        Self {
+            dir: dir.to_string(),
+            candidates,
+            selected: None,
+        }
+    }
+
+    /// Returns what the path should be completed to, which is either the only candidate or
+    /// the part that every candidate starts with.
+    ///
+    /// Returns None if there are no candidates.
+    pub fn completed(&self) -> Option<String> {
+        match self.candidates.as_slice() {
+            [] => None,
+            [only] => Some(self.path_to(only)),
+            [first, rest @ ..] => Some(format!("{}{}", self.dir, common_prefix(first, rest))),
+        }
+    }
+
+    /// Select the next candidate, or the previous one if `backwards` is true.
+    ///
+    /// Returns the path the selected candidate completes to.
+    pub fn cycle(&mut self, backwards: bool) -> Option<String> {
+        let len = self.candidates.len();
+
+        if len == 0 {
+            return None;
+        }
+
+        let selected = match (self.selected, backwards) {
+            (Some(selected), false) => (selected + 1) % len,
+            (Some(selected), true) => (selected + len - 1) % len,
+            (None, false) => 0,
+            (None, true) => len - 1,
+        };
+
+        self.selected = Some(selected);
+        Some(self.path_to(&self.candidates[selected]))
+    }
+
+    fn path_to(&self, name: &str) -> String {
+        format!("{}{}/", self.dir, name)
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+}
+
+/// Split the given `path` into the part up to and including its last `/` and the partially typed name after it.
+fn split_path(path: &str) -> (&str, &str) {
+    match path.rfind('/') {
+        Some(pos) => path.split_at(pos + 1),
+        None => ("", path),
+    }
+}
+
+/// Returns true if a directory called `name` should be offered to complete the partially typed `prefix`.
+fn is_candidate(name: &str, prefix: &str) -> bool {
+    // Hidden directories are only completed when they're being typed out
+    name.starts_with(prefix) && (!name.starts_with('.') || prefix.starts_with('.'))
+}
+
+/// Returns the longest part of `first` that every name in `rest` starts with as well.
+fn common_prefix<'a>(first: &'a str, rest: &[String]) -> &'a str {
+    let mut len = first.len();
+
+    for name in rest {
+        len = first[..len]
+            .char_indices()
+            .zip(name.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or_else(|| len.min(name.len()), |((pos, _), _)| pos);
+    }
+
+    &first[..len]
+}
+
+/// Popup that lists the candidates of a [`PathCompletion`] and highlights the selected one.
+pub struct CompletionPopup<'a> {
+    completion: &'a PathCompletion,
+}
+
+impl<'a> CompletionPopup<'a> {
+    /// The most candidates that are shown at once.
+    const MAX_VISIBLE: usize = 8;
+
+    pub fn new(completion: &'a PathCompletion) -> Self {
+        Self { completion }
+    }
+
+    /// Returns the area the popup should be drawn in so it sits directly above the given `input_area`.
+    pub fn area(&self, input_area: Rect) -> Rect {
+        let widest = self
+            .completion
+            .candidates
+            .iter()
+            .map(|name| UnicodeWidthStr::width(name.as_str()))
+            .max()
+            .unwrap_or(0);
+
+        // Room for the trailing slash and a space on each side
+        let width = (widest as u16).saturating_add(3).min(input_area.width);
+        let height = (self.completion.len().min(Self::MAX_VISIBLE) as u16).min(input_area.y);
+
+        Rect {
+            x: input_area.x,
+            y: input_area.y - height,
+            width,
+            height,
+        }
+    }
+}
+
+impl<'a> Widget for CompletionPopup<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let style = Style::default()
+            .bg(Input::DEFAULT_BG_COLOR)
+            .fg(colors::WHITE);
+
+        fill_area(area, buf, |cell| {
+            cell.reset();
+            cell.set_style(style);
+        });
+
+        let selected = self.completion.selected.unwrap_or(0);
+
+        // Keep the selected candidate in view
+        let scroll = (selected + 1).saturating_sub(area.height as usize);
+
+        let visible = self
+            .completion
+            .candidates
+            .iter()
+            .enumerate()
+            .skip(scroll)
+            .take(area.height as usize);
+
+        for (i, (index, name)) in visible.enumerate() {
+            let y = area.y + i as u16;
+
+            let style = if self.completion.selected == Some(index) {
+                Style::default()
+                    .fg(colors::BLACK)
+                    .bg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                style
+            };
+
+            let row = Rect {
+                y,
+                height: 1,
+                ..area
+            };
+
+            fill_area(row, buf, |cell| {
+                cell.set_style(style);
+            });
+
+            let text = format!("{}/", name);
+            buf.set_stringn(
+                area.x + 1,
+                y,
+                text,
+                area.width.saturating_sub(2) as usize,
+                style,
+            );
+        }
+    }
+}
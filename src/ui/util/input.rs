@@ -1,9 +1,12 @@
+use crate::archive::ArchiveEntries;
 use crate::ui::colors;
 
 use super::{
-    fill_area, pad_rect_left, text_fragments::Fragment, text_fragments::TextFragments, SimpleText,
+    completion::PathCompletion, fill_area, pad_rect_left, text_fragments::Fragment,
+    text_fragments::TextFragments, SimpleText,
 };
 use crossterm::event::KeyCode;
+use std::sync::Arc;
 use tui::{
     buffer::Buffer,
     layout::Rect,
@@ -62,24 +65,145 @@ impl StatefulWidget for Input {
 pub struct InputState {
     caret: Caret,
     pub cursor_pos: Option<(u16, u16)>,
+    /// Previous inputs that can be recalled, from oldest to newest.
+    history: Vec<String>,
+    /// The index of the recalled input in `history`, if one is being shown.
+    history_pos: Option<usize>,
+    /// What was typed before recalling an input, which also limits what can be recalled to inputs that start with it.
+    draft: String,
+    /// Where Tab completes the input as a path from, if it does at all.
+    path_source: Option<PathSource>,
+    /// The candidates Tab cycles through when a path could be completed to more than one directory.
+    completion: Option<Box<PathCompletion>>,
 }
 
 impl InputState {
     pub fn new() -> Self {
+        Self::with_history(Vec::new())
+    }
+
+    /// Create a new `InputState` where the up and down arrows recall the given `history`, which goes from oldest to newest.
+    pub fn with_history(history: Vec<String>) -> Self {
         Self {
             caret: Caret::new(),
             cursor_pos: None,
+            history,
+            history_pos: None,
+            draft: String::new(),
+            path_source: None,
+            completion: None,
+        }
+    }
+
+    /// Let Tab complete the input to directories on the filesystem.
+    #[inline(always)]
+    pub fn complete_paths(mut self) -> Self {
+        self.path_source = Some(PathSource::Filesystem);
+        self
+    }
+
+    /// Let Tab complete the input to directories inside of an archive with the given `files`.
+    #[inline(always)]
+    pub fn complete_archive_paths(mut self, files: Arc<ArchiveEntries>) -> Self {
+        self.path_source = Some(PathSource::Archive(files));
+        self
+    }
+
+    /// Start out with the given `text` already entered, so it only has to be adjusted.
+    #[inline(always)]
+    pub fn prefilled(mut self, text: &str) -> Self {
+        self.caret.set(text);
+        self
+    }
+
+    /// Returns the text that has been entered so far.
+    #[inline(always)]
+    pub fn text(&self) -> &str {
+        &self.caret.buffer
+    }
+
+    /// Returns the candidates that Tab cycles through, if there is more than one.
+    #[inline(always)]
+    pub fn completion(&self) -> Option<&PathCompletion> {
+        self.completion.as_deref()
+    }
+
+    /// Complete the input as far as it can go, or select the next candidate if there is more than one.
+    fn complete(&mut self, backwards: bool) {
+        self.history_pos = None;
+
+        if let Some(completion) = &mut self.completion {
+            if let Some(path) = completion.cycle(backwards) {
+                self.caret.set(&path);
+            }
+
+            return;
+        }
+
+        let completion = match &self.path_source {
+            Some(PathSource::Filesystem) => PathCompletion::new(&self.caret.buffer),
+            Some(PathSource::Archive(files)) => {
+                PathCompletion::in_archive(files, &self.caret.buffer)
+            }
+            None => return,
+        };
+
+        if let Some(path) = completion.completed() {
+            self.caret.set(&path);
+        }
+
+        // There's nothing to pick from otherwise
+        if completion.len() > 1 {
+            self.completion = Some(Box::new(completion));
+        }
+    }
+
+    /// Show the next older input from the history that starts with the draft.
+    fn recall_older(&mut self) {
+        if self.history_pos.is_none() {
+            self.draft = self.caret.buffer.clone();
+        }
+
+        let end = self.history_pos.unwrap_or(self.history.len());
+        let draft = &self.draft;
+
+        if let Some(pos) = (0..end).rev().find(|&i| self.history[i].starts_with(draft)) {
+            self.history_pos = Some(pos);
+            self.caret.set(&self.history[pos]);
+        }
+    }
+
+    /// Show the next newer input from the history that starts with the draft, or the draft itself if there are none.
+    fn recall_newer(&mut self) {
+        let start = match self.history_pos {
+            Some(pos) => pos + 1,
+            None => return,
+        };
+
+        let draft = &self.draft;
+        self.history_pos =
+            (start..self.history.len()).find(|&i| self.history[i].starts_with(draft));
+
+        match self.history_pos {
+            Some(pos) => self.caret.set(&self.history[pos]),
+            None => self.caret.set(&self.draft),
         }
     }
 
     pub fn process_key(&mut self, key: KeyCode) -> InputResult {
+        if !matches!(key, KeyCode::Tab | KeyCode::BackTab) {
+            self.completion = None;
+        }
+
         match key {
             KeyCode::Char(ch) => {
                 self.caret.push(ch);
+                self.history_pos = None;
                 InputResult::Ok
             }
             KeyCode::Backspace => {
                 self.caret.pop();
+                self.history_pos = None;
                 InputResult::Ok
             }
             KeyCode::Enter => InputResult::ProcessInput(&self.caret.buffer),
@@ -91,6 +215,14 @@ impl InputState {
                 self.caret.move_right();
                 InputResult::Ok
             }
+            KeyCode::Up if !self.history.is_empty() => {
+                self.recall_older();
+                InputResult::Ok
+            }
+            KeyCode::Down if !self.history.is_empty() => {
+                self.recall_newer();
+                InputResult::Ok
+            }
             KeyCode::Home | KeyCode::Up => {
                 self.caret.move_front();
                 InputResult::Ok
@@ -99,6 +231,14 @@ impl InputState {
                 self.caret.move_end();
                 InputResult::Ok
             }
+            KeyCode::Tab if self.path_source.is_some() => {
+                self.complete(false);
+                InputResult::Ok
+            }
+            KeyCode::BackTab if self.path_source.is_some() => {
+                self.complete(true);
+                InputResult::Ok
+            }
             KeyCode::Esc => InputResult::Return,
             _ => InputResult::Ok,
         }
@@ -144,6 +284,12 @@ impl InputState {
     }
 }
 
+/// Where the directories that a path is completed to come from.
+enum PathSource {
+    Filesystem,
+    Archive(Arc<ArchiveEntries>),
+}
+
 struct Caret {
     buffer: String,
     cursor: GraphemeCursor,
@@ -215,6 +361,13 @@ impl Caret {
         }
     }
 
+    /// Replace the text with the given `text` and move to the end of it.
+    fn set(&mut self, text: &str) {
+        self.buffer = text.to_string();
+        self.cursor = GraphemeCursor::new(self.buffer.len(), self.buffer.len(), true);
+        self.display_offset = UnicodeWidthStr::width(self.buffer.as_str());
+    }
+
     fn move_front(&mut self) {
         self.cursor.set_cursor(0);
         self.display_offset = 0;
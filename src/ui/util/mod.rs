@@ -1,3 +1,4 @@
+pub mod completion;
 pub mod input;
 pub mod text_fragments;
 
@@ -89,3 +90,29 @@ where
         }
     }
 }
+
+/// Encode the given `bytes` as standard base64 with padding, which is how escape sequences pass binary data to the
+/// terminal.
+pub fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0, |group, (i, &byte)| {
+            group | u32::from(byte) << (16 - i * 8)
+        });
+
+        // Each byte in the chunk fills at least part of one more character, and the rest is padding
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (group >> (18 - i * 6)) & 0x3F;
+                encoded.push(ALPHABET[index as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
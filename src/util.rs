@@ -59,4 +59,223 @@ pub mod size {
     pub fn formatted_compact(bytes: u64) -> String {
         gen_format!(bytes, "{}" => "{:.02}", "{}")
     }
+
+    /// Parse a number of bytes with an optional `K`, `M`, or `G` suffix, like `500K` or `20M`.
+    ///
+    /// Returns None if `value` isn't a valid size or is too big.
+    pub fn parse(value: &str) -> Option<u64> {
+        let value = value.trim();
+
+        let (number, multiplier) = match value.chars().last().map(|c| c.to_ascii_uppercase()) {
+            Some('K') => (&value[..value.len() - 1], 1024),
+            Some('M') => (&value[..value.len() - 1], 1024 * 1024),
+            Some('G') => (&value[..value.len() - 1], 1024 * 1024 * 1024),
+            _ => (value, 1),
+        };
+
+        number.parse::<u64>().ok()?.checked_mul(multiplier)
+    }
+}
+
+pub mod duration {
+    use std::time::Duration;
+
+    /// Format the given `duration` with its two largest units, like `1h05m` or `3m20s`.
+    pub fn formatted_compact(duration: Duration) -> String {
+        let secs = duration.as_secs();
+        let (hours, mins, secs) = (secs / 3600, (secs / 60) % 60, secs % 60);
+
+        if hours > 0 {
+            format!("{}h{:02}m", hours, mins)
+        } else if mins > 0 {
+            format!("{}m{:02}s", mins, secs)
+        } else {
+            format!("{}s", secs)
+        }
+    }
+
+    /// Format the given `duration` the way a media player would, like `1:02:05` or `3:20`.
+    pub fn formatted_clock(duration: Duration) -> String {
+        let secs = duration.as_secs();
+        let (hours, mins, secs) = (secs / 3600, (secs / 60) % 60, secs % 60);
+
+        if hours > 0 {
+            format!("{}:{:02}:{:02}", hours, mins, secs)
+        } else {
+            format!("{}:{:02}", mins, secs)
+        }
+    }
+}
+
+pub mod disk {
+    use std::ffi::CString;
+    use std::io;
+    use std::mem;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    /// Information about the filesystem that contains a path.
+    pub struct DiskStats {
+        pub available_bytes: u64,
+        pub is_tmpfs: bool,
+    }
+
+    impl DiskStats {
+        pub fn read<P>(path: P) -> io::Result<Self>
+        where
+            P: AsRef<Path>,
+        {
+            let path = CString::new(path.as_ref().as_os_str().as_bytes())
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+            let mut stats: libc::statfs = unsafe { mem::zeroed() };
+
+            if unsafe { libc::statfs(path.as_ptr(), &mut stats) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(Self {
+                available_bytes: stats.f_bavail * stats.f_bsize as u64,
+                is_tmpfs: is_tmpfs(&stats),
+            })
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn is_tmpfs(stats: &libc::statfs) -> bool {
+        stats.f_type == libc::TMPFS_MAGIC
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn is_tmpfs(stats: &libc::statfs) -> bool {
+        let name = unsafe { std::ffi::CStr::from_ptr(stats.f_fstypename.as_ptr()) };
+        name.to_bytes() == b"tmpfs"
+    }
+}
+
+pub mod stream {
+    use std::io::{self, Read};
+
+    /// Returns true if `left` and `right` have exactly the same content, reading both in chunks until they end.
+    pub fn equal<A, B>(left: &mut A, right: &mut B) -> io::Result<bool>
+    where
+        A: Read,
+        B: Read,
+    {
+        const BUFFER_SIZE: usize = 64 * 1024;
+
+        let mut left_buf = vec![0; BUFFER_SIZE];
+        let mut right_buf = vec![0; BUFFER_SIZE];
+
+        loop {
+            let left_len = read_full(left, &mut left_buf)?;
+            let right_len = read_full(right, &mut right_buf)?;
+
+            if left_buf[..left_len] != right_buf[..right_len] {
+                return Ok(false);
+            }
+
+            if left_len == 0 {
+                return Ok(true);
+            }
+        }
+    }
+
+    /// Read from `reader` until `buf` is full or the end of the stream has been reached.
+    ///
+    /// Returns the number of bytes read.
+    pub fn read_full<R>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize>
+    where
+        R: Read,
+    {
+        let mut total = 0;
+
+        while total < buf.len() {
+            match reader.read(&mut buf[total..]) {
+                Ok(0) => break,
+                Ok(read) => total += read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(total)
+    }
+}
+
+/// The bits of a Unix mode that say what kind of file it belongs to.
+///
+/// These are the same on every Unix, but `libc` only has them as a `mode_t`, which is narrower than the `u32` modes
+/// that zip archives and FUSE use on macOS.
+#[allow(dead_code)]
+pub mod file_type {
+    pub const MASK: u32 = 0o170_000;
+    pub const FIFO: u32 = 0o010_000;
+    pub const CHAR_DEVICE: u32 = 0o020_000;
+    pub const BLOCK_DEVICE: u32 = 0o060_000;
+    pub const REGULAR: u32 = 0o100_000;
+    pub const SYMLINK: u32 = 0o120_000;
+    pub const SOCKET: u32 = 0o140_000;
+}
+
+pub mod number {
+    use std::env;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    static SEPARATOR: AtomicU32 = AtomicU32::new(',' as u32);
+
+    /// Set the character used to separate groups of thousands.
+    pub fn set_separator(separator: char) {
+        SEPARATOR.store(separator as u32, Ordering::Relaxed);
+    }
+
+    fn separator() -> char {
+        std::char::from_u32(SEPARATOR.load(Ordering::Relaxed)).unwrap_or(',')
+    }
+
+    /// Guess the thousands separator from the user's locale.
+    ///
+    /// Falls back to a comma if the locale isn't set or isn't recognized.
+    pub fn locale_separator() -> char {
+        let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty());
+
+        let locale = match locale {
+            Some(locale) => locale,
+            None => return ',',
+        };
+
+        let language = locale.split(['_', '.']).next().unwrap_or_default();
+
+        match language {
+            "de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "el" | "ro" | "hr" | "sl"
+            | "sr" | "vi" => '.',
+            "fr" | "ru" | "pl" | "sv" | "fi" | "cs" | "sk" | "nb" | "no" | "uk" | "hu" | "bg"
+            | "et" | "lt" | "lv" => ' ',
+            _ => ',',
+        }
+    }
+
+    /// Format the given `value` with its digits separated into groups of thousands.
+    pub fn grouped(value: u64) -> String {
+        let digits = value.to_string();
+        let separator = separator();
+
+        let (head, rest) = digits.split_at(match digits.len() % 3 {
+            0 => digits.len().min(3),
+            len => len,
+        });
+
+        let mut result = String::with_capacity(digits.len() + digits.len() / 3);
+        result.push_str(head);
+
+        for group in rest.as_bytes().chunks(3) {
+            result.push(separator);
+            result.push_str(std::str::from_utf8(group).unwrap_or_default());
+        }
+
+        result
+    }
 }
@@ -0,0 +1,168 @@
+//! Mounts a fixture archive with the `vear` binary and checks what the filesystem does through real syscalls.
+//!
+//! These are skipped when FUSE filesystems can't be mounted, which is the case in most containers.
+
+#![cfg(all(feature = "mount", target_os = "linux"))]
+
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+const VEAR: &str = env!("CARGO_BIN_EXE_vear");
+
+const GREETING: &[u8] = b"Hello from inside of the archive\n";
+
+/// Content that compresses well, so reading it goes through decompression.
+fn nested_content() -> Vec<u8> {
+    (0..64 * 1024).map(|i| (i % 251) as u8).collect()
+}
+
+/// An archive mounted in a directory of its own, which is unmounted and removed when dropped.
+struct Mount {
+    dir: PathBuf,
+    mount_path: PathBuf,
+}
+
+impl Mount {
+    /// Write the fixture archive to a new directory and mount it there, or return None if FUSE can't be used.
+    fn fixture(name: &str) -> Option<Self> {
+        if !fuse_is_usable() {
+            eprintln!("skipping {}: FUSE filesystems can't be mounted here", name);
+            return None;
+        }
+
+        let dir = env::temp_dir().join(format!("vear-test-{}-{}", name, std::process::id()));
+        let archive_path = dir.join("fixture.zip");
+        let mount_path = dir.join("mount");
+
+        fs::create_dir_all(&mount_path).unwrap();
+        write_fixture(&archive_path).unwrap();
+
+        let status = Command::new(VEAR)
+            .args(["mount", "--detach", "--"])
+            .arg(&archive_path)
+            .arg(&mount_path)
+            .status()
+            .unwrap();
+
+        assert!(status.success(), "failed to mount the fixture archive");
+
+        Some(Self { dir, mount_path })
+    }
+
+    fn path(&self, path: &str) -> PathBuf {
+        self.mount_path.join(path)
+    }
+}
+
+impl Drop for Mount {
+    fn drop(&mut self) {
+        Command::new(VEAR)
+            .args(["unmount", "--force", "--"])
+            .arg(&self.mount_path)
+            .status()
+            .ok();
+
+        fs::remove_dir_all(&self.dir).ok();
+    }
+}
+
+/// Returns true if the FUSE device can be opened and there's a helper to mount with as a regular user.
+fn fuse_is_usable() -> bool {
+    let device = OpenOptions::new().read(true).write(true).open("/dev/fuse");
+
+    let has_helper = ["fusermount", "fusermount3"].iter().any(|helper| {
+        env::split_paths(&env::var_os("PATH").unwrap_or_default())
+            .any(|dir| dir.join(helper).is_file())
+    });
+
+    device.is_ok() && has_helper
+}
+
+fn write_fixture(path: &Path) -> zip::result::ZipResult<()> {
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    zip.start_file("greeting.txt", stored)?;
+    zip.write_all(GREETING)?;
+
+    zip.add_directory("dir/", stored)?;
+    zip.start_file("dir/nested.bin", deflated)?;
+    zip.write_all(&nested_content())?;
+
+    zip.finish()?;
+    Ok(())
+}
+
+fn assert_read_only<T>(result: io::Result<T>, what: &str) {
+    match result {
+        Ok(_) => panic!("{} succeeded on a read-only mount", what),
+        Err(err) => assert_eq!(
+            err.raw_os_error(),
+            Some(libc::EROFS),
+            "{} failed with the wrong error: {}",
+            what,
+            err
+        ),
+    }
+}
+
+#[test]
+fn reads_archive_content() {
+    let mount = match Mount::fixture("read") {
+        Some(mount) => mount,
+        None => return,
+    };
+
+    assert_eq!(fs::read(mount.path("greeting.txt")).unwrap(), GREETING);
+    assert_eq!(
+        fs::read(mount.path("dir/nested.bin")).unwrap(),
+        nested_content()
+    );
+
+    let mut names = fs::read_dir(&mount.mount_path)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+
+    names.sort_unstable();
+    assert_eq!(names, ["dir", "greeting.txt"]);
+
+    let metadata = fs::metadata(mount.path("dir/nested.bin")).unwrap();
+    assert!(metadata.is_file());
+    assert_eq!(metadata.len(), nested_content().len() as u64);
+}
+
+#[test]
+fn rejects_changes() {
+    let mount = match Mount::fixture("changes") {
+        Some(mount) => mount,
+        None => return,
+    };
+
+    assert_read_only(fs::create_dir(mount.path("new_dir")), "mkdir");
+    assert_read_only(fs::remove_file(mount.path("greeting.txt")), "unlink");
+    assert_read_only(fs::remove_dir(mount.path("dir")), "rmdir");
+
+    assert_read_only(
+        fs::rename(mount.path("greeting.txt"), mount.path("renamed.txt")),
+        "rename",
+    );
+
+    assert_read_only(
+        OpenOptions::new()
+            .write(true)
+            .open(mount.path("greeting.txt")),
+        "opening a file to write to",
+    );
+
+    assert_read_only(File::create(mount.path("created.txt")), "creating a file");
+
+    // Nothing should have changed after all of that
+    assert_eq!(fs::read(mount.path("greeting.txt")).unwrap(), GREETING);
+    assert!(!mount.path("renamed.txt").exists());
+}